@@ -291,3 +291,24 @@ def _verify_with_pyo3(vk_path: Path, public_inputs_path: Path, proof_path: Path)
         )
     except Exception:
         return False
+
+
+@pytest.mark.slow
+def test_unlinkability_v2_setup_prove_verify_in_process() -> None:
+    if os.environ.get("RUN_SLOW") != "1":
+        pytest.skip("RUN_SLOW not enabled")
+
+    pk_bytes, vk_bytes = unlinkability_py.setup_unlinkability_v2_bytes()
+    instance_bytes, public_inputs_bytes = (
+        unlinkability_py.make_unlinkability_instance_v2_bytes(
+            (7).to_bytes(32, "big"), (9).to_bytes(32, "big"), b"\x11" * 32
+        )
+    )
+    proof_bytes = unlinkability_py.prove_unlinkability_v2_bytes(pk_bytes, instance_bytes)
+    assert len(proof_bytes) == unlinkability_py.PROOF_SIZE_UNCOMPRESSED
+    assert unlinkability_py.verify_unlinkability_v2_bytes(
+        vk_bytes, public_inputs_bytes, proof_bytes
+    )
+
+    with pytest.raises(ValueError):
+        unlinkability_py.prove_unlinkability_v2_bytes(b"not a key", instance_bytes)
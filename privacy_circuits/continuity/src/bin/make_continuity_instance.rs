@@ -1,7 +1,9 @@
 use ark_bn254::Fr;
+use ark_ff::PrimeField;
 use continuity::schema::{build_instance_v1, build_instance_v2};
 use continuity::fr_from_fixed_bytes;
 use continuity::CONTINUITY_V2_DEFAULT_CTX_HASH;
+use membership::io::{parse_field_arg, save_witness, to_json_wrapped};
 use serde::Serialize;
 use std::env;
 use std::fs::File;
@@ -13,36 +15,43 @@ fn main() {
         Err(err) => {
             eprintln!("{err}");
             eprintln!(
-                "Usage: make_continuity_instance [--schema <v1|v2>] [--out-instance <path>] [--out-public-inputs <path>]"
+                "Usage: make_continuity_instance [--schema <v1|v2>] [--out-instance <path>] [--out-public-inputs <path>] [--save-witness <path>] [--format <bin|json>] [--identity <0xhex|base64>] [--r1 <0xhex|base64>] [--r2 <0xhex|base64>] [--ctx-hash <0xhex|base64>]"
             );
             std::process::exit(1);
         }
     };
 
-    let id = Fr::from(1u64);
-    let r1 = Fr::from(2u64);
-    let r2 = Fr::from(3u64);
+    let (id, r1, r2) = (args.identity, args.r1, args.r2);
 
     match args.schema {
         Schema::V1 => {
             let (instance, public_inputs) = build_instance_v1(id, r1, r2);
-            write_outputs(
-                &args.instance_out,
-                &args.public_inputs_out,
-                &instance,
-                &public_inputs,
-            );
+            write_outputs(&args, "v1", None, &instance, &public_inputs);
+            if let Err(err) = save_witness(
+                args.save_witness.as_deref(),
+                &[("id", &instance.id), ("r1", &instance.r1), ("r2", &instance.r2)],
+            ) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
         }
         Schema::V2 => {
-            let ctx_hash = fr_from_fixed_bytes("ctx_hash", &CONTINUITY_V2_DEFAULT_CTX_HASH)
-                .expect("default ctx_hash must be valid");
+            let ctx_hash = fr_from_fixed_bytes("ctx_hash", &args.ctx_hash)
+                .expect("ctx_hash is always 32 bytes");
             let (instance, public_inputs) = build_instance_v2(id, r1, r2, ctx_hash);
-            write_outputs(
-                &args.instance_out,
-                &args.public_inputs_out,
-                &instance,
-                &public_inputs,
-            );
+            write_outputs(&args, "v2", None, &instance, &public_inputs);
+            if let Err(err) = save_witness(
+                args.save_witness.as_deref(),
+                &[
+                    ("id", &instance.id),
+                    ("r1", &instance.r1),
+                    ("r2", &instance.r2),
+                    ("ctx_hash", &instance.ctx_hash),
+                ],
+            ) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
         }
     }
 }
@@ -51,12 +60,24 @@ struct Args {
     schema: Schema,
     instance_out: String,
     public_inputs_out: String,
+    save_witness: Option<String>,
+    format: OutputFormat,
+    identity: Fr,
+    r1: Fr,
+    r2: Fr,
+    ctx_hash: [u8; 32],
 }
 
 fn parse_args() -> Result<Args, String> {
     let mut schema = Schema::V1;
     let mut instance_out = "continuity_instance.bin".to_string();
     let mut public_inputs_out = "continuity_public_inputs.bin".to_string();
+    let mut save_witness = None;
+    let mut format = OutputFormat::Bin;
+    let mut identity = Fr::from(1u64);
+    let mut r1 = Fr::from(2u64);
+    let mut r2 = Fr::from(3u64);
+    let mut ctx_hash = None;
     let mut args = env::args().skip(1);
 
     while let Some(arg) = args.next() {
@@ -78,18 +99,71 @@ fn parse_args() -> Result<Args, String> {
                     .next()
                     .ok_or_else(|| "missing value for --out-public-inputs".to_string())?;
             }
+            "--save-witness" => {
+                save_witness = Some(
+                    args.next()
+                        .ok_or_else(|| "missing value for --save-witness".to_string())?,
+                );
+            }
+            "--format" => {
+                format = match args.next().as_deref() {
+                    Some("bin") => OutputFormat::Bin,
+                    Some("json") => OutputFormat::Json,
+                    _ => return Err("invalid format (expected bin or json)".to_string()),
+                };
+            }
+            "--identity" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "missing value for --identity".to_string())?;
+                identity = Fr::from_be_bytes_mod_order(&parse_field_arg("--identity", &value)?);
+            }
+            "--r1" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "missing value for --r1".to_string())?;
+                r1 = Fr::from_be_bytes_mod_order(&parse_field_arg("--r1", &value)?);
+            }
+            "--r2" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "missing value for --r2".to_string())?;
+                r2 = Fr::from_be_bytes_mod_order(&parse_field_arg("--r2", &value)?);
+            }
+            "--ctx-hash" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "missing value for --ctx-hash".to_string())?;
+                ctx_hash = Some(parse_field_arg("--ctx-hash", &value)?);
+            }
             "--help" | "-h" => return Err("help requested".to_string()),
             _ => return Err(format!("unknown argument: {arg}")),
         }
     }
 
+    if ctx_hash.is_some() && !matches!(schema, Schema::V2) {
+        return Err("--ctx-hash requires --schema v2".to_string());
+    }
+
     Ok(Args {
         schema,
         instance_out,
         public_inputs_out,
+        save_witness,
+        format,
+        identity,
+        r1,
+        r2,
+        ctx_hash: ctx_hash.unwrap_or(CONTINUITY_V2_DEFAULT_CTX_HASH),
     })
 }
 
+#[derive(Clone, Copy)]
+enum OutputFormat {
+    Bin,
+    Json,
+}
+
 #[derive(Clone, Copy)]
 enum Schema {
     V1,
@@ -97,17 +171,25 @@ enum Schema {
 }
 
 fn write_outputs<T: Serialize, U: Serialize>(
-    instance_out: &str,
-    public_inputs_out: &str,
+    args: &Args,
+    schema: &str,
+    depth: Option<usize>,
     instance: &T,
     public_inputs: &U,
 ) {
-    if let Err(err) = write_bincode(instance_out, instance) {
+    // Public inputs stay bincode in both formats; the verifiers read that form.
+    let written = match args.format {
+        OutputFormat::Bin => write_bincode(&args.instance_out, instance),
+        OutputFormat::Json => to_json_wrapped(schema, depth, public_inputs, instance).and_then(
+            |json| std::fs::write(&args.instance_out, json).map_err(|err| err.to_string()),
+        ),
+    };
+    if let Err(err) = written {
         eprintln!("failed to write instance: {err}");
         std::process::exit(1);
     }
 
-    if let Err(err) = write_bincode(public_inputs_out, public_inputs) {
+    if let Err(err) = write_bincode(&args.public_inputs_out, public_inputs) {
         eprintln!("failed to write public inputs: {err}");
         std::process::exit(1);
     }
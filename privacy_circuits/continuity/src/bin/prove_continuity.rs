@@ -1,28 +1,27 @@
-use ark_bn254::Bn254;
-use ark_groth16::ProvingKey;
-use ark_serialize::CanonicalDeserialize;
-use ark_serialize::CanonicalSerialize;
 use ark_std::rand::rngs::OsRng;
 use continuity::{
     prove_continuity, prove_continuity_v2, ContinuityInstanceV1, ContinuityInstanceV2,
+    ContinuityPublicInputsV1, ContinuityPublicInputsV2,
 };
+use membership::io::{read_proving_key_as, write_canonical, DEFAULT_MAX_PK_BYTES};
+use membership::{write_bundle, ProofBundle};
 use std::env;
 use std::fs;
-use std::fs::File;
-use std::io::{BufReader, BufWriter, Write};
 
 fn main() {
-    let (pk_path, instance_path, proof_out, schema) = match parse_args() {
-        Some(paths) => paths,
+    let args = match parse_args() {
+        Some(args) => args,
         None => {
             eprintln!(
-                "Usage: prove_continuity --pk <path> --instance <path> --proof-out <path> [--schema <v1|v2>]"
+                "Usage: prove_continuity --pk <path> --instance <path> --proof-out <path> [--bundle-out <path>] [--max-pk-bytes <n>] [--schema <v1|v2>] [--compressed]"
             );
             std::process::exit(1);
         }
     };
+    let Args { pk_path, instance_path, proof_out, bundle_out, schema, max_pk_bytes, compressed } =
+        args;
 
-    let pk = match read_proving_key(&pk_path) {
+    let pk = match read_proving_key_as(&pk_path, max_pk_bytes, compressed) {
         Ok(pk) => pk,
         Err(err) => {
             eprintln!("failed to read proving key: {err}");
@@ -32,7 +31,7 @@ fn main() {
 
     let mut rng = OsRng;
 
-    let proof = match schema {
+    let (proof, statement, public_inputs) = match schema {
         Schema::V1 => {
             let instance_bytes = match read_instance_v1(&instance_path) {
                 Ok(instance) => instance,
@@ -42,6 +41,7 @@ fn main() {
                 }
             };
 
+            let public_inputs = public_inputs_v1(&instance_bytes);
             let instance = match instance_bytes.into_instance() {
                 Ok(instance) => instance,
                 Err(err) => {
@@ -51,7 +51,7 @@ fn main() {
             };
 
             match prove_continuity(&pk, &instance, &mut rng) {
-                Ok(proof) => proof,
+                Ok(proof) => (proof, "continuity-v1", bincode::serialize(&public_inputs)),
                 Err(err) => {
                     eprintln!("proof generation failed: {err}");
                     std::process::exit(1);
@@ -67,6 +67,7 @@ fn main() {
                 }
             };
 
+            let public_inputs = public_inputs_v2(&instance_bytes);
             let instance = match instance_bytes.into_instance() {
                 Ok(instance) => instance,
                 Err(err) => {
@@ -76,7 +77,7 @@ fn main() {
             };
 
             match prove_continuity_v2(&pk, &instance, &mut rng) {
-                Ok(proof) => proof,
+                Ok(proof) => (proof, "continuity-v2", bincode::serialize(&public_inputs)),
                 Err(err) => {
                     eprintln!("proof generation failed: {err}");
                     std::process::exit(1);
@@ -85,23 +86,50 @@ fn main() {
         }
     };
 
-    if let Err(err) = write_serialized(&proof_out, &proof) {
+    if let Err(err) = write_canonical(&proof_out, &proof, compressed) {
         eprintln!("failed to write proof: {err}");
         std::process::exit(1);
     }
+
+    if let Some(path) = bundle_out {
+        let result = public_inputs
+            .map_err(|err| err.to_string())
+            .and_then(|inputs| ProofBundle::new(statement, inputs, &proof, &pk.vk, compressed))
+            .and_then(|bundle| write_bundle(&path, &bundle));
+        if let Err(err) = result {
+            eprintln!("failed to write bundle: {err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+struct Args {
+    pk_path: String,
+    instance_path: String,
+    proof_out: String,
+    bundle_out: Option<String>,
+    schema: Schema,
+    max_pk_bytes: u64,
+    compressed: bool,
 }
 
-fn parse_args() -> Option<(String, String, String, Schema)> {
+fn parse_args() -> Option<Args> {
     let mut pk_path = None;
     let mut instance_path = None;
     let mut proof_out = None;
+    let mut bundle_out = None;
+    let mut max_pk_bytes = DEFAULT_MAX_PK_BYTES;
     let mut schema = Schema::V1;
+    let mut compressed = false;
     let mut args = env::args().skip(1);
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--pk" => pk_path = args.next(),
             "--instance" => instance_path = args.next(),
             "--proof-out" => proof_out = args.next(),
+            "--bundle-out" => bundle_out = Some(args.next()?),
+            "--max-pk-bytes" => max_pk_bytes = args.next()?.parse().ok()?,
+            "--compressed" => compressed = true,
             "--schema" => {
                 schema = match args.next()?.as_str() {
                     "v1" => Schema::V1,
@@ -113,7 +141,15 @@ fn parse_args() -> Option<(String, String, String, Schema)> {
         }
     }
     match (pk_path, instance_path, proof_out) {
-        (Some(pk), Some(instance), Some(proof)) => Some((pk, instance, proof, schema)),
+        (Some(pk_path), Some(instance_path), Some(proof_out)) => Some(Args {
+            pk_path,
+            instance_path,
+            proof_out,
+            bundle_out,
+            schema,
+            max_pk_bytes,
+            compressed,
+        }),
         _ => None,
     }
 }
@@ -124,12 +160,6 @@ enum Schema {
     V2,
 }
 
-fn read_proving_key(path: &str) -> Result<ProvingKey<Bn254>, String> {
-    let file = File::open(path).map_err(|err| err.to_string())?;
-    let mut reader = BufReader::new(file);
-    ProvingKey::<Bn254>::deserialize_uncompressed(&mut reader).map_err(|err| err.to_string())
-}
-
 fn read_instance_v1(path: &str) -> Result<ContinuityInstanceV1, String> {
     let data = fs::read(path).map_err(|err| err.to_string())?;
     bincode::deserialize::<ContinuityInstanceV1>(&data).map_err(|err| err.to_string())
@@ -140,11 +170,24 @@ fn read_instance_v2(path: &str) -> Result<ContinuityInstanceV2, String> {
     bincode::deserialize::<ContinuityInstanceV2>(&data).map_err(|err| err.to_string())
 }
 
-fn write_serialized<T: CanonicalSerialize>(path: &str, value: &T) -> Result<(), String> {
-    let file = File::create(path).map_err(|err| err.to_string())?;
-    let mut writer = BufWriter::new(file);
-    value
-        .serialize_uncompressed(&mut writer)
-        .map_err(|err| err.to_string())?;
-    writer.flush().map_err(|err| err.to_string())
+// The public-inputs files make_continuity_instance writes alongside each instance.
+fn public_inputs_v1(instance: &ContinuityInstanceV1) -> ContinuityPublicInputsV1 {
+    ContinuityPublicInputsV1 {
+        schema_version: instance.schema_version,
+        c1_hash: instance.c1_hash,
+        c2_hash: instance.c2_hash,
+        domain_sep: instance.domain_sep,
+    }
+}
+
+fn public_inputs_v2(instance: &ContinuityInstanceV2) -> ContinuityPublicInputsV2 {
+    ContinuityPublicInputsV2 {
+        schema_version: instance.schema_version,
+        statement_type: instance.statement_type,
+        statement_version: instance.statement_version,
+        c1_hash: instance.c1_hash,
+        c2_hash: instance.c2_hash,
+        domain_sep: instance.domain_sep,
+        ctx_hash: instance.ctx_hash,
+    }
 }
@@ -1,25 +1,32 @@
-use ark_serialize::CanonicalSerialize;
 use ark_std::rand::rngs::OsRng;
-use continuity::{setup_continuity, setup_continuity_v2};
+use continuity::{
+    setup_continuity, setup_continuity_v2, setup_continuity_v2_with_seed,
+    setup_continuity_with_seed,
+};
+use membership::io::{key_output_paths, parse_seed_hex, write_canonical, write_params_spec};
+use membership::PoseidonParamsSpec;
 use std::env;
-use std::fs::File;
-use std::io::{BufWriter, Write};
 
 fn main() {
-    let (pk_out, vk_out, schema) = match parse_args() {
-        Some(paths) => paths,
+    let Args { pk_out, vk_out, schema, compressed, seed } = match parse_args() {
+        Some(args) => args,
         None => {
             eprintln!(
-                "Usage: setup_continuity --out-pk <path> --out-vk <path> [--schema <v1|v2>]"
+                "Usage: setup_continuity (--out-pk <path> --out-vk <path> | --out-dir <dir> --name <prefix>) [--schema <v1|v2>] [--compressed] [--seed <hex32>]"
             );
             std::process::exit(1);
         }
     };
 
-    let mut rng = OsRng;
-    let pk = match schema {
-        Schema::V1 => setup_continuity(&mut rng),
-        Schema::V2 => setup_continuity_v2(&mut rng),
+    if seed.is_some() {
+        // The seed recreates the setup trapdoor; seeded keys are for tests and CI.
+        eprintln!("warning: anyone with the --seed value can regenerate these keys; do not deploy");
+    }
+    let pk = match (schema, seed) {
+        (Schema::V1, Some(seed)) => setup_continuity_with_seed(seed),
+        (Schema::V1, None) => setup_continuity(&mut OsRng),
+        (Schema::V2, Some(seed)) => setup_continuity_v2_with_seed(seed),
+        (Schema::V2, None) => setup_continuity_v2(&mut OsRng),
     };
     let pk = match pk {
         Ok(pk) => pk,
@@ -30,25 +37,51 @@ fn main() {
     };
     let vk = pk.vk.clone();
 
-    if let Err(err) = write_serialized(&pk_out, &pk) {
+    if let Err(err) = write_canonical(&pk_out, &pk, compressed) {
         eprintln!("failed to write proving key: {err}");
         std::process::exit(1);
     }
-    if let Err(err) = write_serialized(&vk_out, &vk) {
+    if let Err(err) = write_canonical(&vk_out, &vk, compressed) {
         eprintln!("failed to write verifying key: {err}");
         std::process::exit(1);
     }
+    if let Err(err) = write_params_spec(&vk_out, &PoseidonParamsSpec::DEFAULT_SPEC) {
+        eprintln!("failed to write Poseidon parameter spec: {err}");
+        std::process::exit(1);
+    }
+}
+
+struct Args {
+    pk_out: String,
+    vk_out: String,
+    schema: Schema,
+    compressed: bool,
+    seed: Option<[u8; 32]>,
 }
 
-fn parse_args() -> Option<(String, String, Schema)> {
+fn parse_args() -> Option<Args> {
     let mut pk_out = None;
     let mut vk_out = None;
+    let mut out_dir = None;
+    let mut name = None;
     let mut schema = Schema::V1;
+    let mut compressed = false;
+    let mut seed = None;
     let mut args = env::args().skip(1);
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--out-pk" => pk_out = args.next(),
             "--out-vk" => vk_out = args.next(),
+            "--out-dir" => out_dir = args.next(),
+            "--name" => name = args.next(),
+            "--compressed" => compressed = true,
+            "--seed" => match parse_seed_hex(&args.next()?) {
+                Ok(bytes) => seed = Some(bytes),
+                Err(err) => {
+                    eprintln!("{err}");
+                    std::process::exit(1);
+                }
+            },
             "--schema" => {
                 schema = match args.next()?.as_str() {
                     "v1" => Schema::V1,
@@ -59,10 +92,18 @@ fn parse_args() -> Option<(String, String, Schema)> {
             _ => return None,
         }
     }
-    match (pk_out, vk_out) {
-        (Some(pk), Some(vk)) => Some((pk, vk, schema)),
-        _ => None,
-    }
+    let (pk_out, vk_out) = match (pk_out, vk_out, out_dir, name) {
+        (Some(pk), Some(vk), None, None) => (pk, vk),
+        (None, None, Some(dir), Some(name)) => match key_output_paths(&dir, &name) {
+            Ok(paths) => paths,
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        },
+        _ => return None,
+    };
+    Some(Args { pk_out, vk_out, schema, compressed, seed })
 }
 
 #[derive(Clone, Copy)]
@@ -70,12 +111,3 @@ enum Schema {
     V1,
     V2,
 }
-
-fn write_serialized<T: CanonicalSerialize>(path: &str, value: &T) -> Result<(), String> {
-    let file = File::create(path).map_err(|err| err.to_string())?;
-    let mut writer = BufWriter::new(file);
-    value
-        .serialize_uncompressed(&mut writer)
-        .map_err(|err| err.to_string())?;
-    writer.flush().map_err(|err| err.to_string())
-}
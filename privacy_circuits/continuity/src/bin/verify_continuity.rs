@@ -1,92 +1,58 @@
 use ark_bn254::Bn254;
 use ark_groth16::{Proof, VerifyingKey};
-use ark_serialize::CanonicalDeserialize;
+use continuity::schema::{ContinuityPublicInputs, ContinuityPublicInputsV2Data};
 use continuity::{
-    verify_continuity, verify_continuity_v2, ContinuityPublicInputsV1,
+    verify_continuity, verify_continuity_v2, vk_fingerprint_hex, ContinuityPublicInputsV1,
     ContinuityPublicInputsV2,
 };
+use membership::io::{
+    check_params_spec, decode_public_inputs, read_public_inputs_then_proof,
+    read_verifying_key_auto, DEFAULT_MAX_VK_BYTES,
+};
+use membership::{read_bundle, PoseidonParamsSpec, DEFAULT_MAX_BUNDLE_BYTES};
 use std::env;
-use std::fs;
-use std::fs::File;
-use std::io::BufReader;
 
 fn main() {
-    let (vk_path, inputs_path, proof_path, schema) = match parse_args() {
+    let (vk_path, source, schema) = match parse_args() {
         Some(paths) => paths,
         None => {
             eprintln!(
-                "Usage: verify_continuity --vk <path> --public-inputs <path> --proof <path> [--schema <v1|v2>]"
+                "Usage: verify_continuity --vk <path> (--public-inputs <path> --proof <path> | --bundle <path>) [--schema <v1|v2>]"
             );
             std::process::exit(1);
         }
     };
 
-    let vk = match read_verifying_key(&vk_path) {
+    let vk = match read_verifying_key_auto(&vk_path, DEFAULT_MAX_VK_BYTES) {
         Ok(vk) => vk,
         Err(err) => {
             eprintln!("failed to read verifying key: {err}");
             std::process::exit(1);
         }
     };
-
-    let proof = match read_proof(&proof_path) {
-        Ok(proof) => proof,
-        Err(err) => {
-            eprintln!("failed to read proof: {err}");
-            std::process::exit(1);
-        }
-    };
+    // Keys from a setup run with different Poseidon parameters cannot verify anything; say so
+    // up front instead of reporting every proof as invalid.
+    if let Err(err) = check_params_spec(&vk_path, &PoseidonParamsSpec::DEFAULT_SPEC) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
 
     let verified = match schema {
         Schema::V1 => {
-            let inputs_bytes = match read_public_inputs_v1(&inputs_path) {
-                Ok(inputs) => inputs,
-                Err(err) => {
-                    eprintln!("failed to read public inputs: {err}");
-                    std::process::exit(1);
-                }
-            };
-
-            let public_inputs = match inputs_bytes.into_public_inputs() {
-                Ok(inputs) => inputs,
-                Err(err) => {
-                    eprintln!("invalid public inputs: {err}");
-                    std::process::exit(1);
-                }
-            };
-
-            match verify_continuity(&vk, &public_inputs, &proof) {
-                Ok(result) => result,
-                Err(err) => {
-                    eprintln!("verification failed: {err}");
-                    std::process::exit(1);
-                }
-            }
+            let (public_inputs, proof) = read_source(&source, &vk, schema, decode_public_inputs_v1);
+            verify_continuity(&vk, &public_inputs, &proof)
         }
         Schema::V2 => {
-            let inputs_bytes = match read_public_inputs_v2(&inputs_path) {
-                Ok(inputs) => inputs,
-                Err(err) => {
-                    eprintln!("failed to read public inputs: {err}");
-                    std::process::exit(1);
-                }
-            };
-
-            let public_inputs = match inputs_bytes.into_public_inputs() {
-                Ok(inputs) => inputs,
-                Err(err) => {
-                    eprintln!("invalid public inputs: {err}");
-                    std::process::exit(1);
-                }
-            };
-
-            match verify_continuity_v2(&vk, &public_inputs, &proof) {
-                Ok(result) => result,
-                Err(err) => {
-                    eprintln!("verification failed: {err}");
-                    std::process::exit(1);
-                }
-            }
+            let (public_inputs, proof) = read_source(&source, &vk, schema, decode_public_inputs_v2);
+            verify_continuity_v2(&vk, &public_inputs, &proof)
+        }
+    };
+    let verified = match verified {
+        Ok(result) => result,
+        Err(err) => {
+            eprintln!("verification failed: {err}");
+            eprintln!("vk fingerprint: {}", vk_fingerprint_hex(&vk));
+            std::process::exit(1);
         }
     };
 
@@ -96,13 +62,20 @@ fn main() {
     }
 
     eprintln!("verification failed");
+    eprintln!("vk fingerprint: {}", vk_fingerprint_hex(&vk));
     std::process::exit(2);
 }
 
-fn parse_args() -> Option<(String, String, String, Schema)> {
+enum Source {
+    Files { inputs_path: String, proof_path: String },
+    Bundle(String),
+}
+
+fn parse_args() -> Option<(String, Source, Schema)> {
     let mut vk_path = None;
     let mut inputs_path = None;
     let mut proof_path = None;
+    let mut bundle = None;
     let mut schema = Schema::V1;
     let mut args = env::args().skip(1);
     while let Some(arg) = args.next() {
@@ -110,6 +83,9 @@ fn parse_args() -> Option<(String, String, String, Schema)> {
             "--vk" => vk_path = args.next(),
             "--public-inputs" => inputs_path = args.next(),
             "--proof" => proof_path = args.next(),
+            "--bundle" => bundle = args.next(),
+            // A no-op now that the key and proof encodings are detected.
+            "--compressed" => {}
             "--schema" => {
                 schema = match args.next()?.as_str() {
                     "v1" => Schema::V1,
@@ -120,10 +96,15 @@ fn parse_args() -> Option<(String, String, String, Schema)> {
             _ => return None,
         }
     }
-    match (vk_path, inputs_path, proof_path) {
-        (Some(vk), Some(inputs), Some(proof)) => Some((vk, inputs, proof, schema)),
-        _ => None,
-    }
+    let source = match (inputs_path, proof_path, bundle) {
+        (Some(inputs_path), Some(proof_path), None) => Source::Files {
+            inputs_path,
+            proof_path,
+        },
+        (None, None, Some(bundle)) => Source::Bundle(bundle),
+        _ => return None,
+    };
+    Some((vk_path?, source, schema))
 }
 
 #[derive(Clone, Copy)]
@@ -132,24 +113,59 @@ enum Schema {
     V2,
 }
 
-fn read_verifying_key(path: &str) -> Result<VerifyingKey<Bn254>, String> {
-    let file = File::open(path).map_err(|err| err.to_string())?;
-    let mut reader = BufReader::new(file);
-    VerifyingKey::<Bn254>::deserialize_uncompressed(&mut reader).map_err(|err| err.to_string())
+impl Schema {
+    fn statement(self) -> &'static str {
+        match self {
+            Schema::V1 => "continuity-v1",
+            Schema::V2 => "continuity-v2",
+        }
+    }
 }
 
-fn read_public_inputs_v1(path: &str) -> Result<ContinuityPublicInputsV1, String> {
-    let data = fs::read(path).map_err(|err| err.to_string())?;
-    bincode::deserialize::<ContinuityPublicInputsV1>(&data).map_err(|err| err.to_string())
+fn decode_public_inputs_v1(data: &[u8]) -> Result<ContinuityPublicInputs, String> {
+    decode_public_inputs(data, |bytes: ContinuityPublicInputsV1| {
+        bytes.into_public_inputs()
+    })
 }
 
-fn read_public_inputs_v2(path: &str) -> Result<ContinuityPublicInputsV2, String> {
-    let data = fs::read(path).map_err(|err| err.to_string())?;
-    bincode::deserialize::<ContinuityPublicInputsV2>(&data).map_err(|err| err.to_string())
+fn decode_public_inputs_v2(data: &[u8]) -> Result<ContinuityPublicInputsV2Data, String> {
+    decode_public_inputs(data, |bytes: ContinuityPublicInputsV2| {
+        bytes.into_public_inputs()
+    })
 }
 
-fn read_proof(path: &str) -> Result<Proof<Bn254>, String> {
-    let file = File::open(path).map_err(|err| err.to_string())?;
-    let mut reader = BufReader::new(file);
-    Proof::<Bn254>::deserialize_uncompressed(&mut reader).map_err(|err| err.to_string())
+// A bundle is checked against the VK fingerprint and --schema before anything
+// in it is decoded.
+fn read_source<T>(
+    source: &Source,
+    vk: &VerifyingKey<Bn254>,
+    schema: Schema,
+    decode: fn(&[u8]) -> Result<T, String>,
+) -> (T, Proof<Bn254>) {
+    match source {
+        Source::Files {
+            inputs_path,
+            proof_path,
+        } => match read_public_inputs_then_proof(inputs_path, proof_path, decode) {
+            Ok(loaded) => loaded,
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        },
+        Source::Bundle(path) => {
+            let opened = read_bundle(path, DEFAULT_MAX_BUNDLE_BYTES).and_then(|bundle| {
+                let (data, proof) = bundle.open(schema.statement(), vk)?;
+                Ok((decode(data)?, proof))
+            });
+            match opened {
+                Ok(opened) => opened,
+                Err(err) => {
+                    eprintln!("failed to read bundle: {err}");
+                    eprintln!("vk fingerprint: {}", vk_fingerprint_hex(vk));
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
 }
@@ -4,12 +4,17 @@ use ark_r1cs_std::prelude::*;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
 use ark_sponge::constraints::CryptographicSpongeVar;
 use ark_sponge::poseidon::constraints::PoseidonSpongeVar;
-use membership::poseidon_params;
+use ark_std::vec::Vec;
+use core::marker::PhantomData;
+use membership::{poseidon_params, CircuitHash, Poseidon};
 
 use crate::schema::{CONTINUITY_V1_DOMAIN_SEP, CONTINUITY_V2_DOMAIN_SEP};
 
 const DOMAIN_COMMITMENT: u64 = 1;
 
+// With `distinct` set the circuit also proves r1 != r2, so c1 and c2 are two
+// different commitments rather than one commitment presented twice. It adds a
+// constraint, so keys from a circuit with the flag off do not verify its proofs.
 #[derive(Clone, Debug, Default)]
 pub struct ContinuityCircuit<F: PrimeField> {
     pub c1_hash: Option<F>,
@@ -18,6 +23,24 @@ pub struct ContinuityCircuit<F: PrimeField> {
     pub id: Option<F>,
     pub r1: Option<F>,
     pub r2: Option<F>,
+    pub distinct: bool,
+}
+
+// `ContinuityCircuit` synthesized with the hash `H`. `ContinuityCircuit` itself is
+// this circuit with `H = Poseidon`.
+#[derive(Clone, Debug, Default)]
+pub struct ContinuityCircuitWithHash<F: PrimeField, H: CircuitHash = Poseidon> {
+    circuit: ContinuityCircuit<F>,
+    hash: PhantomData<H>,
+}
+
+impl<F: PrimeField, H: CircuitHash> ContinuityCircuitWithHash<F, H> {
+    pub fn new(circuit: ContinuityCircuit<F>) -> Self {
+        Self {
+            circuit,
+            hash: PhantomData,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -31,6 +54,17 @@ pub struct ContinuityCircuitV2<F: PrimeField> {
     pub r2: Option<F>,
 }
 
+// Continuity over a chain of rotations: every (r_i, c_i_hash) in `links` must
+// open to the same hidden `id` under `ctx_hash`. The public inputs are the c_i
+// in order, then domain_sep and ctx_hash, so a key is fixed to one chain length.
+#[derive(Clone, Debug, Default)]
+pub struct ContinuityCircuitNV2<F: PrimeField> {
+    pub links: Vec<(Option<F>, Option<F>)>,
+    pub domain_sep: Option<F>,
+    pub ctx_hash: Option<F>,
+    pub id: Option<F>,
+}
+
 fn poseidon_hash_var<F: PrimeField>(
     cs: ConstraintSystemRef<F>,
     params: &ark_sponge::poseidon::PoseidonConfig<F>,
@@ -44,7 +78,19 @@ fn poseidon_hash_var<F: PrimeField>(
 
 impl<F: PrimeField> ConstraintSynthesizer<F> for ContinuityCircuit<F> {
     fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
-        let params = poseidon_params::<F>();
+        self.synthesize::<Poseidon>(cs)
+    }
+}
+
+impl<F: PrimeField, H: CircuitHash> ConstraintSynthesizer<F> for ContinuityCircuitWithHash<F, H> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        self.circuit.synthesize::<H>(cs)
+    }
+}
+
+impl<F: PrimeField> ContinuityCircuit<F> {
+    fn synthesize<H: CircuitHash>(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let params = H::params::<F>();
 
         let c1_hash =
             FpVar::new_input(cs.clone(), || self.c1_hash.ok_or(SynthesisError::AssignmentMissing))?;
@@ -60,11 +106,21 @@ impl<F: PrimeField> ConstraintSynthesizer<F> for ContinuityCircuit<F> {
         let r2 =
             FpVar::new_witness(cs.clone(), || self.r2.ok_or(SynthesisError::AssignmentMissing))?;
 
+        if self.distinct {
+            // r1 - r2 is nonzero exactly when it has an inverse. Equal blindings get a
+            // zero witness so the system is unsatisfied rather than failing to build.
+            let diff_inv = FpVar::new_witness(cs.clone(), || {
+                let r1 = self.r1.ok_or(SynthesisError::AssignmentMissing)?;
+                let r2 = self.r2.ok_or(SynthesisError::AssignmentMissing)?;
+                Ok((r1 - r2).inverse().unwrap_or(F::ZERO))
+            })?;
+            (&r1 - &r2).mul_equals(&diff_inv, &FpVar::one())?;
+        }
+
         let domain_commitment = FpVar::constant(F::from(DOMAIN_COMMITMENT));
         let expected_c1 =
-            poseidon_hash_var(cs.clone(), &params, &[domain_commitment.clone(), id.clone(), r1])?;
-        let expected_c2 =
-            poseidon_hash_var(cs.clone(), &params, &[domain_commitment, id, r2])?;
+            H::hash_var(cs.clone(), &params, &[domain_commitment.clone(), id.clone(), r1])?;
+        let expected_c2 = H::hash_var(cs.clone(), &params, &[domain_commitment, id, r2])?;
 
         expected_c1.enforce_equal(&c1_hash)?;
         expected_c2.enforce_equal(&c2_hash)?;
@@ -122,3 +178,43 @@ impl<F: PrimeField> ConstraintSynthesizer<F> for ContinuityCircuitV2<F> {
         Ok(())
     }
 }
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for ContinuityCircuitNV2<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let params = poseidon_params::<F>();
+
+        let commitments = self
+            .links
+            .iter()
+            .map(|(_, c_hash)| {
+                FpVar::new_input(cs.clone(), || c_hash.ok_or(SynthesisError::AssignmentMissing))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let domain_sep = FpVar::new_input(cs.clone(), || {
+            self.domain_sep.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let ctx_hash =
+            FpVar::new_input(cs.clone(), || self.ctx_hash.ok_or(SynthesisError::AssignmentMissing))?;
+
+        let id =
+            FpVar::new_witness(cs.clone(), || self.id.ok_or(SynthesisError::AssignmentMissing))?;
+
+        let domain_commitment = FpVar::constant(F::from(DOMAIN_COMMITMENT));
+        for ((r, _), c_hash) in self.links.iter().zip(&commitments) {
+            let r = FpVar::new_witness(cs.clone(), || r.ok_or(SynthesisError::AssignmentMissing))?;
+            let expected = poseidon_hash_var(
+                cs.clone(),
+                &params,
+                &[domain_commitment.clone(), id.clone(), r, ctx_hash.clone()],
+            )?;
+            expected.enforce_equal(c_hash)?;
+        }
+
+        let domain_sep_const = FpVar::constant(F::from_be_bytes_mod_order(
+            &CONTINUITY_V2_DOMAIN_SEP,
+        ));
+        domain_sep.enforce_equal(&domain_sep_const)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use ark_std::string::{String, ToString};
+use core::fmt;
+use membership::FieldDecodeError;
+
+// Failures from the continuity schema parsers. Display keeps the wording of the former
+// string errors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ContinuityError {
+    VersionMismatch {
+        field: String,
+        expected: u16,
+        got: u16,
+    },
+    StatementMismatch {
+        field: &'static str,
+        expected: u16,
+        got: u16,
+    },
+    DomainSepMismatch {
+        field: String,
+    },
+    CommitmentMismatch {
+        field: &'static str,
+    },
+    PublishedMismatch {
+        field: &'static str,
+    },
+    Verification(String),
+    FieldDecode(FieldDecodeError),
+}
+
+impl fmt::Display for ContinuityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContinuityError::VersionMismatch {
+                field,
+                expected,
+                got,
+            } => write!(f, "{field}: schema_version mismatch (expected {expected}, got {got})"),
+            ContinuityError::StatementMismatch {
+                field,
+                expected,
+                got,
+            } => write!(f, "{field} mismatch (expected {expected}, got {got})"),
+            ContinuityError::DomainSepMismatch { field } => {
+                write!(f, "{field}: domain_sep mismatch")
+            }
+            ContinuityError::CommitmentMismatch { field } => {
+                write!(f, "{field} does not match commitment hash")
+            }
+            ContinuityError::PublishedMismatch { field } => {
+                write!(f, "{field} does not match the published commitment")
+            }
+            ContinuityError::Verification(reason) => f.write_str(reason),
+            ContinuityError::FieldDecode(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ContinuityError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ContinuityError::FieldDecode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<FieldDecodeError> for ContinuityError {
+    fn from(err: FieldDecodeError) -> Self {
+        ContinuityError::FieldDecode(err)
+    }
+}
+
+impl From<ContinuityError> for String {
+    fn from(err: ContinuityError) -> Self {
+        err.to_string()
+    }
+}
@@ -1,19 +1,42 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
 use ark_bn254::{Bn254, Fr};
 use ark_ff::PrimeField;
-use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_groth16::{
+    prepare_verifying_key, Groth16, PreparedVerifyingKey, Proof, ProvingKey, VerifyingKey,
+};
 use ark_relations::r1cs::SynthesisError;
-use ark_std::rand::RngCore;
+use ark_std::rand::rngs::StdRng;
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::{string::ToString, vec};
+use membership::FieldDecodeError;
 
-use crate::circuit::{ContinuityCircuit, ContinuityCircuitV2};
+use crate::circuit::{ContinuityCircuit, ContinuityCircuitNV2, ContinuityCircuitV2};
 use crate::schema::{
-    domain_sep_fr, domain_sep_v2_fr, ContinuityInstance, ContinuityInstanceV2Data,
-    ContinuityPublicInputs, ContinuityPublicInputsV2Data,
+    domain_sep_fr, domain_sep_v2_fr, ContinuityInstance, ContinuityInstanceNV2Data,
+    ContinuityInstanceV2Data, ContinuityPublicInputs, ContinuityPublicInputsNV2Data,
+    ContinuityPublicInputsV2Data,
 };
 
 pub mod circuit;
+pub mod error;
 pub mod schema;
 
-pub use membership::{commitment_hash, fr_to_fixed_bytes, poseidon_params};
+pub use error::ContinuityError;
+pub use membership::{
+    check_constant_form, commitment_hash, domain_sep_label, field_to_domain_label, fixed_bytes,
+    fixed_bytes_allow_empty, fr_to_fixed_bytes, poseidon_params, ConstantError,
+};
+#[cfg(feature = "std")]
+pub use membership::{
+    proof_from_bytes, vk_fingerprint, vk_fingerprint_hex, ProofError, VerifyOutcome,
+};
+#[cfg(feature = "std")]
+pub use membership::io::{
+    bincode_deserialize_capped, decode_canonical_auto, proof_serialized_size, read_file_capped,
+    DEFAULT_MAX_INSTANCE_BYTES, DEFAULT_MAX_PROOF_BYTES, DEFAULT_MAX_VK_BYTES,
+    PROOF_SIZE_COMPRESSED, PROOF_SIZE_UNCOMPRESSED,
+};
 pub use schema::commitment_hash_v2;
 pub use schema::{
     ContinuityInstanceV1, ContinuityInstanceV2, ContinuityPublicInputsV1,
@@ -23,13 +46,40 @@ pub use schema::{
     CONTINUITY_V2_DEFAULT_CTX_HASH, CONTINUITY_V2_DOMAIN_SEP,
 };
 
-pub fn fr_from_fixed_bytes(label: &str, bytes: &[u8; 32]) -> Result<Fr, String> {
+pub fn validate_constants() -> Result<(), ConstantError> {
+    check_constant_form(
+        "CONTINUITY_V1_DOMAIN_SEP",
+        &CONTINUITY_V1_DOMAIN_SEP,
+        "CONTINUITY_SNARK_V1",
+    )?;
+    check_constant_form(
+        "CONTINUITY_V2_DOMAIN_SEP",
+        &CONTINUITY_V2_DOMAIN_SEP,
+        "CONTINUITY_SNARK_V2",
+    )?;
+    check_constant_form(
+        "CONTINUITY_V2_DEFAULT_CTX_HASH",
+        &CONTINUITY_V2_DEFAULT_CTX_HASH,
+        "CONTINUITY_CTX_V2",
+    )
+}
+
+pub fn fr_from_fixed_bytes(label: &str, bytes: &[u8; 32]) -> Result<Fr, FieldDecodeError> {
     if bytes.is_empty() {
-        return Err(format!("{label}: empty field bytes"));
+        return Err(FieldDecodeError::new(label, "empty field bytes"));
     }
     Ok(Fr::from_be_bytes_mod_order(bytes))
 }
 
+// Public hashes compared by equality must use a single encoding; see
+// `membership::fr_from_bytes_canonical`.
+pub fn fr_from_fixed_bytes_canonical(
+    label: &str,
+    bytes: &[u8; 32],
+) -> Result<Fr, FieldDecodeError> {
+    membership::fr_from_bytes_canonical(label, bytes)
+}
+
 pub fn build_circuit(instance: &ContinuityInstance) -> ContinuityCircuit<Fr> {
     ContinuityCircuit::<Fr> {
         c1_hash: Some(instance.public_inputs.c1_hash),
@@ -38,6 +88,7 @@ pub fn build_circuit(instance: &ContinuityInstance) -> ContinuityCircuit<Fr> {
         id: Some(instance.witness.id),
         r1: Some(instance.witness.r1),
         r2: Some(instance.witness.r2),
+        distinct: false,
     }
 }
 
@@ -53,22 +104,60 @@ pub fn build_circuit_v2(instance: &ContinuityInstanceV2Data) -> ContinuityCircui
     }
 }
 
-pub fn setup_continuity<R: RngCore>(
-    rng: &mut R,
-) -> Result<ProvingKey<Bn254>, SynthesisError> {
+pub fn build_circuit_n_v2(instance: &ContinuityInstanceNV2Data) -> ContinuityCircuitNV2<Fr> {
+    let links = instance
+        .witness
+        .blindings
+        .iter()
+        .zip(&instance.public_inputs.commitments)
+        .map(|(r, c_hash)| (Some(*r), Some(*c_hash)))
+        .collect();
+    ContinuityCircuitNV2::<Fr> {
+        links,
+        domain_sep: Some(instance.public_inputs.domain_sep),
+        ctx_hash: Some(instance.public_inputs.ctx_hash),
+        id: Some(instance.witness.id),
+    }
+}
+
+fn setup_circuit(distinct: bool) -> ContinuityCircuit<Fr> {
     let params = poseidon_params::<Fr>();
     let zero = Fr::from(0u64);
     let commitment = commitment_hash(&params, zero, zero);
-    let domain_sep = domain_sep_fr();
-    let circuit = ContinuityCircuit::<Fr> {
+    ContinuityCircuit::<Fr> {
         c1_hash: Some(commitment),
         c2_hash: Some(commitment),
-        domain_sep: Some(domain_sep),
+        domain_sep: Some(domain_sep_fr()),
         id: Some(zero),
         r1: Some(zero),
         r2: Some(zero),
-    };
-    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+        distinct,
+    }
+}
+
+pub fn setup_continuity<R: RngCore>(
+    rng: &mut R,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(setup_circuit(false), rng)
+}
+
+// Keys for `prove_continuity_distinct`. Proofs are checked with `verify_continuity`;
+// the public inputs are unchanged.
+pub fn setup_continuity_distinct<R: RngCore>(
+    rng: &mut R,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(setup_circuit(true), rng)
+}
+
+// Reproducible keys for tests and CI; see `membership::setup_membership_with_seed`.
+pub fn setup_continuity_with_seed(seed: [u8; 32]) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    setup_continuity(&mut StdRng::from_seed(seed))
+}
+
+pub fn setup_continuity_v2_with_seed(
+    seed: [u8; 32],
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    setup_continuity_v2(&mut StdRng::from_seed(seed))
 }
 
 pub fn setup_continuity_v2<R: RngCore>(
@@ -91,6 +180,19 @@ pub fn setup_continuity_v2<R: RngCore>(
     Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
 }
 
+// The key only fits chains of exactly `links` commitments.
+pub fn setup_continuity_n_v2<R: RngCore>(
+    rng: &mut R,
+    links: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let zero = Fr::from(0u64);
+    let instance = schema::build_instance_n_v2(zero, &vec![zero; links], zero);
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(
+        build_circuit_n_v2(&instance),
+        rng,
+    )
+}
+
 pub fn prove_continuity<R: RngCore>(
     pk: &ProvingKey<Bn254>,
     instance: &ContinuityInstance,
@@ -100,6 +202,23 @@ pub fn prove_continuity<R: RngCore>(
     Groth16::<Bn254>::create_random_proof_with_reduction(circuit, pk, rng)
 }
 
+// Like `prove_continuity`, but the proof also shows r1 != r2. Equal blindings are
+// refused here since Groth16 would otherwise produce a proof that fails to verify.
+pub fn prove_continuity_distinct<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &ContinuityInstance,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    if instance.witness.r1 == instance.witness.r2 {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    let circuit = ContinuityCircuit {
+        distinct: true,
+        ..build_circuit(instance)
+    };
+    Groth16::<Bn254>::create_random_proof_with_reduction(circuit, pk, rng)
+}
+
 pub fn prove_continuity_v2<R: RngCore>(
     pk: &ProvingKey<Bn254>,
     instance: &ContinuityInstanceV2Data,
@@ -109,46 +228,158 @@ pub fn prove_continuity_v2<R: RngCore>(
     Groth16::<Bn254>::create_random_proof_with_reduction(circuit, pk, rng)
 }
 
+pub fn prove_continuity_n_v2<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &ContinuityInstanceNV2Data,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    if instance.witness.blindings.len() != instance.public_inputs.commitments.len() {
+        return Err(SynthesisError::AssignmentMissing);
+    }
+    let circuit = build_circuit_n_v2(instance);
+    Groth16::<Bn254>::create_random_proof_with_reduction(circuit, pk, rng)
+}
+
+// Holds the prepared verifying key for repeated `verify_continuity` checks.
+#[derive(Clone, Debug)]
+pub struct ContinuityVerifier {
+    pvk: PreparedVerifyingKey<Bn254>,
+}
+
+impl ContinuityVerifier {
+    pub fn new(vk: &VerifyingKey<Bn254>) -> Self {
+        Self {
+            pvk: prepare_verifying_key(vk),
+        }
+    }
+
+    pub fn verify(
+        &self,
+        public_inputs: &ContinuityPublicInputs,
+        proof: &Proof<Bn254>,
+    ) -> Result<bool, SynthesisError> {
+        let inputs = vec![
+            public_inputs.c1_hash,
+            public_inputs.c2_hash,
+            public_inputs.domain_sep,
+        ];
+        Groth16::<Bn254>::verify_proof(&self.pvk, proof, &inputs)
+    }
+
+    pub fn verify_v2(
+        &self,
+        public_inputs: &ContinuityPublicInputsV2Data,
+        proof: &Proof<Bn254>,
+    ) -> Result<bool, SynthesisError> {
+        let inputs = vec![
+            public_inputs.c1_hash,
+            public_inputs.c2_hash,
+            public_inputs.domain_sep,
+            public_inputs.ctx_hash,
+        ];
+        Groth16::<Bn254>::verify_proof(&self.pvk, proof, &inputs)
+    }
+}
+
 pub fn verify_continuity(
     vk: &VerifyingKey<Bn254>,
     public_inputs: &ContinuityPublicInputs,
     proof: &Proof<Bn254>,
 ) -> Result<bool, SynthesisError> {
-    let pvk = prepare_verifying_key(vk);
-    let inputs = vec![
-        public_inputs.c1_hash,
-        public_inputs.c2_hash,
-        public_inputs.domain_sep,
-    ];
-    Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)
+    ContinuityVerifier::new(vk).verify(public_inputs, proof)
 }
 
 pub fn verify_continuity_v2(
     vk: &VerifyingKey<Bn254>,
     public_inputs: &ContinuityPublicInputsV2Data,
     proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    ContinuityVerifier::new(vk).verify_v2(public_inputs, proof)
+}
+
+// A commitment count that does not match the key is rejected by Groth16 as a
+// malformed verifying key.
+pub fn verify_continuity_n_v2(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &ContinuityPublicInputsNV2Data,
+    proof: &Proof<Bn254>,
 ) -> Result<bool, SynthesisError> {
     let pvk = prepare_verifying_key(vk);
-    let inputs = vec![
-        public_inputs.c1_hash,
-        public_inputs.c2_hash,
-        public_inputs.domain_sep,
-        public_inputs.ctx_hash,
-    ];
+    let mut inputs = public_inputs.commitments.clone();
+    inputs.push(public_inputs.domain_sep);
+    inputs.push(public_inputs.ctx_hash);
     Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)
 }
+
+#[cfg(feature = "std")]
+pub fn verify_continuity_outcome(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: ContinuityPublicInputsV1,
+    proof: &Proof<Bn254>,
+) -> VerifyOutcome {
+    match public_inputs.into_public_inputs() {
+        Ok(inputs) => VerifyOutcome::from_verification(verify_continuity(vk, &inputs, proof)),
+        Err(err) => VerifyOutcome::SchemaError(err.to_string()),
+    }
+}
+
+#[cfg(feature = "std")]
+pub fn verify_continuity_v2_outcome(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: ContinuityPublicInputsV2,
+    proof: &Proof<Bn254>,
+) -> VerifyOutcome {
+    match public_inputs.into_public_inputs() {
+        Ok(inputs) => VerifyOutcome::from_verification(verify_continuity_v2(vk, &inputs, proof)),
+        Err(err) => VerifyOutcome::SchemaError(err.to_string()),
+    }
+}
+
+// Verifies only if the proof's c1/c2 are the externally published commitments.
+// Comparison is on canonical field encodings, so a non-reduced or otherwise
+// re-encoded copy of a published value does not match.
+pub fn verify_continuity_bound(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: ContinuityPublicInputsV1,
+    proof: &Proof<Bn254>,
+    expected_c1: [u8; 32],
+    expected_c2: [u8; 32],
+) -> Result<bool, ContinuityError> {
+    let inputs = public_inputs.into_public_inputs()?;
+    if fr_to_fixed_bytes(&inputs.c1_hash) != expected_c1 {
+        return Err(ContinuityError::PublishedMismatch { field: "c1_hash" });
+    }
+    if fr_to_fixed_bytes(&inputs.c2_hash) != expected_c2 {
+        return Err(ContinuityError::PublishedMismatch { field: "c2_hash" });
+    }
+    verify_continuity(vk, &inputs, proof)
+        .map_err(|err| ContinuityError::Verification(err.to_string()))
+}
 #[cfg(test)]
 mod tests {
     use super::{
-        commitment_hash, fr_from_fixed_bytes, fr_to_fixed_bytes, poseidon_params,
-        ContinuityInstanceV1, ContinuityPublicInputsV1, CONTINUITY_INSTANCE_VERSION_V1,
-        CONTINUITY_V1_DOMAIN_SEP,
+        build_circuit_v2, prove_continuity, prove_continuity_distinct, prove_continuity_n_v2,
+        prove_continuity_v2, setup_continuity, setup_continuity_distinct, setup_continuity_n_v2,
+        setup_continuity_v2, verify_continuity,
+        verify_continuity_bound,
+        verify_continuity_n_v2, verify_continuity_outcome, verify_continuity_v2,
+        verify_continuity_v2_outcome, ContinuityVerifier, VerifyOutcome,
+    };
+    use crate::schema::{
+        build_instance_n_v2, build_instance_v1, build_instance_v2, ContinuityInstanceV2,
+    };
+    use super::{
+        commitment_hash, domain_sep_label, field_to_domain_label, fr_from_fixed_bytes,
+        fr_to_fixed_bytes, poseidon_params, ContinuityError, ContinuityInstanceV1,
+        ContinuityPublicInputsV1, CONTINUITY_INSTANCE_VERSION_V1, CONTINUITY_V1_DOMAIN_SEP,
+        CONTINUITY_V2_DOMAIN_SEP,
     };
     use crate::circuit::{ContinuityCircuit, ContinuityCircuitV2};
     use crate::schema::{
         commitment_hash_v2, domain_sep_fr, domain_sep_v2_fr, ContinuityInstance,
     };
     use ark_bn254::{Bn254, Fr};
+    use ark_ff::{BigInteger, PrimeField};
     use ark_groth16::{prepare_verifying_key, Groth16};
     use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
     use ark_std::rand::{rngs::StdRng, SeedableRng};
@@ -170,6 +401,7 @@ mod tests {
             id: Some(id),
             r1: Some(r1),
             r2: Some(r2),
+            distinct: false,
         };
 
         assert!(circuit.generate_constraints(cs.clone()).is_ok());
@@ -193,12 +425,49 @@ mod tests {
             id: Some(id),
             r1: Some(r1),
             r2: Some(r2),
+            distinct: false,
         };
 
         assert!(circuit.generate_constraints(cs.clone()).is_ok());
         assert!(!cs.is_satisfied().unwrap());
     }
 
+    #[test]
+    fn continuity_distinct_rejects_equal_blindings() {
+        let params = poseidon_params::<Fr>();
+        let id = Fr::from(2u64);
+        let circuit = |r1: Fr, r2: Fr, distinct: bool| ContinuityCircuit::<Fr> {
+            c1_hash: Some(commitment_hash(&params, id, r1)),
+            c2_hash: Some(commitment_hash(&params, id, r2)),
+            domain_sep: Some(domain_sep_fr()),
+            id: Some(id),
+            r1: Some(r1),
+            r2: Some(r2),
+            distinct,
+        };
+        let satisfied = |circuit: ContinuityCircuit<Fr>| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+
+        let same = Fr::from(3u64);
+        assert!(satisfied(circuit(same, same, false)));
+        assert!(!satisfied(circuit(same, same, true)));
+        assert!(satisfied(circuit(same, Fr::from(4u64), true)));
+
+        let mut rng = StdRng::seed_from_u64(104);
+        let pk = setup_continuity_distinct(&mut rng).unwrap();
+        let (instance, _) = build_instance_v1(id, same, Fr::from(4u64));
+        let instance = instance.into_instance().unwrap();
+        let proof = prove_continuity_distinct(&pk, &instance, &mut rng).unwrap();
+        assert!(verify_continuity(&pk.vk, &instance.public_inputs, &proof).unwrap());
+
+        let (instance, _) = build_instance_v1(id, same, same);
+        let instance = instance.into_instance().unwrap();
+        assert!(prove_continuity_distinct(&pk, &instance, &mut rng).is_err());
+    }
+
     #[test]
     fn continuity_v2_domain_sep_tamper_fails() {
         let params = poseidon_params::<Fr>();
@@ -249,6 +518,23 @@ mod tests {
         assert!(!cs.is_satisfied().unwrap());
     }
 
+    #[test]
+    fn continuity_v2_rejects_unreduced_ctx_hash() {
+        let ctx_hash = Fr::from(9u64);
+        let (mut instance, mut public_inputs) =
+            build_instance_v2(Fr::from(2u64), Fr::from(3u64), Fr::from(4u64), ctx_hash);
+        let mut unreduced = ctx_hash.into_bigint();
+        assert!(!unreduced.add_with_carry(&Fr::MODULUS));
+        let unreduced: [u8; 32] = unreduced.to_bytes_be().try_into().unwrap();
+        instance.ctx_hash = unreduced;
+        public_inputs.ctx_hash = unreduced;
+
+        let err = public_inputs.into_public_inputs().unwrap_err().to_string();
+        assert!(err.contains("ctx_hash"));
+        let err = instance.into_instance().unwrap_err().to_string();
+        assert!(err.contains("ctx_hash"));
+    }
+
     #[test]
     fn continuity_instance_v1_validation_rejects_bad_domain_sep() {
         let mut instance = ContinuityInstanceV1 {
@@ -284,6 +570,7 @@ mod tests {
             id: Some(id),
             r1: Some(r1),
             r2: Some(r2),
+            distinct: false,
         };
 
         let mut rng = StdRng::seed_from_u64(42);
@@ -297,6 +584,27 @@ mod tests {
         assert!(Groth16::<Bn254>::verify_proof(&pvk, &proof, &inputs).unwrap());
     }
 
+    #[test]
+    fn continuity_v2_groth16_roundtrip() {
+        let id = Fr::from(11u64);
+        let ctx_hash = Fr::from(14u64);
+        let (instance, _) = build_instance_v2(id, Fr::from(12u64), Fr::from(13u64), ctx_hash);
+        let instance = instance.into_instance().unwrap();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        build_circuit_v2(&instance).generate_constraints(cs.clone()).unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        assert_eq!(cs.num_instance_variables(), 5);
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let pk = setup_continuity_v2(&mut rng).unwrap();
+        let proof = prove_continuity_v2(&pk, &instance, &mut rng).unwrap();
+        assert!(verify_continuity_v2(&pk.vk, &instance.public_inputs, &proof).unwrap());
+
+        let mut wrong_ctx = instance.public_inputs.clone();
+        wrong_ctx.ctx_hash = ctx_hash + Fr::from(1u64);
+        assert!(!verify_continuity_v2(&pk.vk, &wrong_ctx, &proof).unwrap());
+    }
+
     #[test]
     fn continuity_schema_roundtrip_public_inputs() {
         let params = poseidon_params::<Fr>();
@@ -347,4 +655,203 @@ mod tests {
         assert_eq!(instance.public_inputs.c1_hash, c1);
         assert_eq!(instance.public_inputs.c2_hash, c2);
     }
+
+    #[test]
+    fn continuity_bound_requires_published_commitments() {
+        let (instance, public_inputs) =
+            build_instance_v1(Fr::from(2u64), Fr::from(3u64), Fr::from(4u64));
+        let instance = instance.into_instance().unwrap();
+        let mut rng = StdRng::seed_from_u64(74);
+        let pk = setup_continuity(&mut rng).unwrap();
+        let proof = prove_continuity(&pk, &instance, &mut rng).unwrap();
+
+        let c1 = public_inputs.c1_hash;
+        let c2 = public_inputs.c2_hash;
+        assert!(verify_continuity_bound(&pk.vk, public_inputs.clone(), &proof, c1, c2).unwrap());
+
+        let mut other = c2;
+        other[31] ^= 0x01;
+        let err =
+            verify_continuity_bound(&pk.vk, public_inputs.clone(), &proof, c1, other).unwrap_err();
+        assert_eq!(err, ContinuityError::PublishedMismatch { field: "c2_hash" });
+        assert_eq!(err.to_string(), "c2_hash does not match the published commitment");
+        assert!(verify_continuity_bound(&pk.vk, public_inputs, &proof, c2, c1).is_err());
+    }
+
+    #[test]
+    fn continuity_outcomes() {
+        let id = Fr::from(2u64);
+        let r1 = Fr::from(3u64);
+        let r2 = Fr::from(4u64);
+        let mut rng = StdRng::seed_from_u64(64);
+
+        let (instance, public_inputs) = build_instance_v1(id, r1, r2);
+        let instance = instance.into_instance().unwrap();
+        let pk = setup_continuity(&mut rng).unwrap();
+        let proof = prove_continuity(&pk, &instance, &mut rng).unwrap();
+        assert_eq!(
+            verify_continuity_outcome(&pk.vk, public_inputs.clone(), &proof),
+            VerifyOutcome::Valid
+        );
+        let mut tampered = proof.clone();
+        tampered.a = -tampered.a;
+        assert_eq!(
+            verify_continuity_outcome(&pk.vk, public_inputs.clone(), &tampered),
+            VerifyOutcome::InvalidProof
+        );
+        let mut bad_domain = public_inputs;
+        bad_domain.domain_sep[0] ^= 0x01;
+        assert!(matches!(
+            verify_continuity_outcome(&pk.vk, bad_domain, &proof),
+            VerifyOutcome::SchemaError(_)
+        ));
+
+        let (instance, public_inputs) = build_instance_v2(id, r1, r2, Fr::from(5u64));
+        let instance = instance.into_instance().unwrap();
+        let pk = setup_continuity_v2(&mut rng).unwrap();
+        let proof = prove_continuity_v2(&pk, &instance, &mut rng).unwrap();
+        assert_eq!(
+            verify_continuity_v2_outcome(&pk.vk, public_inputs.clone(), &proof),
+            VerifyOutcome::Valid
+        );
+        let mut tampered = proof.clone();
+        tampered.a = -tampered.a;
+        assert_eq!(
+            verify_continuity_v2_outcome(&pk.vk, public_inputs.clone(), &tampered),
+            VerifyOutcome::InvalidProof
+        );
+        let mut bad_domain = public_inputs;
+        bad_domain.domain_sep[0] ^= 0x01;
+        assert!(matches!(
+            verify_continuity_v2_outcome(&pk.vk, bad_domain, &proof),
+            VerifyOutcome::SchemaError(_)
+        ));
+    }
+
+    fn golden_hex(value: &Fr) -> String {
+        fr_to_fixed_bytes(value)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    // Pinned public-input vectors for a fixed witness; see the membership golden test.
+    #[test]
+    fn golden_public_inputs_are_stable() {
+        let id = Fr::from(11u64);
+        let r1 = Fr::from(12u64);
+        let r2 = Fr::from(15u64);
+        let ctx_hash = Fr::from(13u64);
+
+        let (_, public_inputs) = build_instance_v1(id, r1, r2);
+        let inputs = public_inputs.into_public_inputs().unwrap();
+        let v1: Vec<String> = [inputs.c1_hash, inputs.c2_hash, inputs.domain_sep]
+            .iter()
+            .map(golden_hex)
+            .collect();
+        assert_eq!(
+            v1,
+            [
+                "053e9b766f06e0f84b083e31b7321768d2ff14b2941d40870bfe526e70d62736",
+                "21507edb3ff5a4311285a7437c2a0c71918c0c6be92e86be7adfeebc6b5a2e57",
+                "12eaffe1681cb51f9c09199cccbff9ee37224916e5a5eece1b7d69cb6f5f5f5e",
+            ]
+        );
+
+        let (_, public_inputs) = build_instance_v2(id, r1, r2, ctx_hash);
+        let inputs = public_inputs.into_public_inputs().unwrap();
+        let v2: Vec<String> =
+            [inputs.c1_hash, inputs.c2_hash, inputs.domain_sep, inputs.ctx_hash]
+                .iter()
+                .map(golden_hex)
+                .collect();
+        assert_eq!(
+            v2,
+            [
+                "0da29c15c61a0abc25bd190adc25ba316595922d02081aece11972bc974dbc72",
+                "079b68391da5a28a9df9d06474c306c88abac9aa70cf0ad863ddbe558d632371",
+                "12eaffe1681cb51f9c09199cccbff9ee37224a16e5a5eece1b7d69cb6f5f5f5e",
+                "000000000000000000000000000000000000000000000000000000000000000d",
+            ]
+        );
+    }
+
+    #[test]
+    fn domain_sep_label_renders_readable_prefix() {
+        let label = domain_sep_label(&CONTINUITY_V2_DOMAIN_SEP);
+        assert!(label.starts_with("CONTINUITY_SNARK_V2"));
+        assert_eq!(field_to_domain_label(&domain_sep_v2_fr()), label);
+
+        let mut bytes = CONTINUITY_V2_DOMAIN_SEP;
+        bytes[0] = 0x00;
+        bytes[31] = b'\\';
+        let label = domain_sep_label(&bytes);
+        assert!(label.starts_with("\\x00ONTINUITY_SNARK_V2"));
+        assert!(label.ends_with("\\x5c"));
+    }
+
+    #[test]
+    fn continuity_n_v2_links_four_commitments() {
+        let mut rng = StdRng::seed_from_u64(66);
+        let id = Fr::from(21u64);
+        let blindings: Vec<Fr> = (1..=4u64).map(Fr::from).collect();
+        let ctx_hash = Fr::from(9u64);
+        let instance = build_instance_n_v2(id, &blindings, ctx_hash);
+        assert_eq!(instance.public_inputs.commitments.len(), 4);
+
+        let pk = setup_continuity_n_v2(&mut rng, 4).unwrap();
+        assert_eq!(pk.vk.gamma_abc_g1.len(), 4 + 2 + 1);
+        let proof = prove_continuity_n_v2(&pk, &instance, &mut rng).unwrap();
+        assert!(verify_continuity_n_v2(&pk.vk, &instance.public_inputs, &proof).unwrap());
+
+        let mut reordered = instance.public_inputs.clone();
+        reordered.commitments.swap(1, 2);
+        assert!(!verify_continuity_n_v2(&pk.vk, &reordered, &proof).unwrap());
+
+        // A link opened under another id leaves the circuit unsatisfied.
+        let mut foreign = instance.clone();
+        foreign.public_inputs.commitments[3] =
+            commitment_hash_v2(&poseidon_params::<Fr>(), Fr::from(22u64), blindings[3], ctx_hash);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        super::build_circuit_n_v2(&foreign)
+            .generate_constraints(cs.clone())
+            .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+
+        let mut truncated = instance.public_inputs.clone();
+        truncated.commitments.pop();
+        assert!(verify_continuity_n_v2(&pk.vk, &truncated, &proof).is_err());
+    }
+
+    #[test]
+    fn continuity_instance_v2_json_round_trips_as_hex() {
+        let (instance, _) = build_instance_v2(
+            Fr::from(5u64),
+            Fr::from(6u64),
+            Fr::from(7u64),
+            Fr::from(8u64),
+        );
+        let json = instance.to_json().unwrap();
+        assert!(json.contains(&format!("\"0x{}\"", "00".repeat(31) + "05")));
+        assert_eq!(ContinuityInstanceV2::from_json(&json).unwrap(), instance);
+        assert!(ContinuityInstanceV2::from_json(&json.replace("0x", "0xff")).is_err());
+    }
+
+    #[test]
+    fn continuity_verifier_reuses_prepared_key() {
+        let mut rng = StdRng::seed_from_u64(75);
+        let pk = setup_continuity(&mut rng).unwrap();
+        let verifier = ContinuityVerifier::new(&pk.vk);
+        for (id, r1, r2) in [(5u64, 6u64, 7u64), (8, 9, 10)] {
+            let (instance, _) = build_instance_v1(Fr::from(id), Fr::from(r1), Fr::from(r2));
+            let instance = instance.into_instance().unwrap();
+            let proof = prove_continuity(&pk, &instance, &mut rng).unwrap();
+            assert!(verifier.verify(&instance.public_inputs, &proof).unwrap());
+
+            let mut swapped = instance.public_inputs.clone();
+            std::mem::swap(&mut swapped.c1_hash, &mut swapped.c2_hash);
+            assert!(!verifier.verify(&swapped, &proof).unwrap());
+            assert!(!verify_continuity(&pk.vk, &swapped, &proof).unwrap());
+        }
+    }
 }
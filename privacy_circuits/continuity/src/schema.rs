@@ -2,9 +2,11 @@ use ark_bn254::Fr;
 use ark_ff::PrimeField;
 use ark_sponge::poseidon::PoseidonSponge;
 use ark_sponge::CryptographicSponge;
+use ark_std::{string::ToString, vec::Vec};
 use serde::{Deserialize, Serialize};
 
-use crate::{fr_from_fixed_bytes, fr_to_fixed_bytes};
+use crate::error::ContinuityError;
+use crate::{fr_from_fixed_bytes, fr_from_fixed_bytes_canonical, fr_to_fixed_bytes};
 use membership::{commitment_hash, poseidon_params};
 
 pub const CONTINUITY_INSTANCE_VERSION_V1: u8 = 1;
@@ -37,28 +39,39 @@ pub struct ContinuityPublicInputsV1 {
     pub domain_sep: [u8; 32],
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ContinuityInstanceV2 {
     pub schema_version: u16,
     pub statement_type: u16,
     pub statement_version: u16,
+    #[serde(with = "membership::hex_serde::array")]
     pub id: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub r1: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub r2: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub c1_hash: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub c2_hash: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub domain_sep: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub ctx_hash: [u8; 32],
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ContinuityPublicInputsV2 {
     pub schema_version: u16,
     pub statement_type: u16,
     pub statement_version: u16,
+    #[serde(with = "membership::hex_serde::array")]
     pub c1_hash: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub c2_hash: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub domain_sep: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub ctx_hash: [u8; 32],
 }
 
@@ -69,7 +82,7 @@ pub struct ContinuityWitness {
     pub r2: Fr,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ContinuityPublicInputs {
     pub c1_hash: Fr,
     pub c2_hash: Fr,
@@ -89,7 +102,7 @@ pub struct ContinuityWitnessV2 {
     pub r2: Fr,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ContinuityPublicInputsV2Data {
     pub c1_hash: Fr,
     pub c2_hash: Fr,
@@ -103,6 +116,26 @@ pub struct ContinuityInstanceV2Data {
     pub witness: ContinuityWitnessV2,
 }
 
+#[derive(Clone, Debug)]
+pub struct ContinuityWitnessNV2 {
+    pub id: Fr,
+    pub blindings: Vec<Fr>,
+}
+
+// Commitment hashes in chain order, c1 first.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ContinuityPublicInputsNV2Data {
+    pub commitments: Vec<Fr>,
+    pub domain_sep: Fr,
+    pub ctx_hash: Fr,
+}
+
+#[derive(Clone, Debug)]
+pub struct ContinuityInstanceNV2Data {
+    pub public_inputs: ContinuityPublicInputsNV2Data,
+    pub witness: ContinuityWitnessNV2,
+}
+
 pub fn domain_sep_fr() -> Fr {
     Fr::from_be_bytes_mod_order(&CONTINUITY_V1_DOMAIN_SEP)
 }
@@ -111,88 +144,99 @@ pub fn domain_sep_v2_fr() -> Fr {
     Fr::from_be_bytes_mod_order(&CONTINUITY_V2_DOMAIN_SEP)
 }
 
-fn ensure_version(label: &str, version: u8) -> Result<(), String> {
+fn ensure_version(label: &str, version: u8) -> Result<(), ContinuityError> {
     if version != CONTINUITY_INSTANCE_VERSION_V1 {
-        return Err(format!(
-            "{label}: schema_version mismatch (expected {}, got {})",
-            CONTINUITY_INSTANCE_VERSION_V1, version
-        ));
+        return Err(ContinuityError::VersionMismatch {
+            field: label.to_string(),
+            expected: CONTINUITY_INSTANCE_VERSION_V1.into(),
+            got: version.into(),
+        });
     }
     Ok(())
 }
 
-fn ensure_domain_sep(label: &str, value: &[u8; 32]) -> Result<(), String> {
+fn ensure_domain_sep(label: &str, value: &[u8; 32]) -> Result<(), ContinuityError> {
     if value != &CONTINUITY_V1_DOMAIN_SEP {
-        return Err(format!("{label}: domain_sep mismatch"));
+        return Err(ContinuityError::DomainSepMismatch {
+            field: label.to_string(),
+        });
     }
     Ok(())
 }
 
-fn ensure_version_v2(label: &str, version: u16) -> Result<(), String> {
+fn ensure_version_v2(label: &str, version: u16) -> Result<(), ContinuityError> {
     if version != CONTINUITY_INSTANCE_VERSION_V2 {
-        return Err(format!(
-            "{label}: schema_version mismatch (expected {}, got {})",
-            CONTINUITY_INSTANCE_VERSION_V2, version
-        ));
+        return Err(ContinuityError::VersionMismatch {
+            field: label.to_string(),
+            expected: CONTINUITY_INSTANCE_VERSION_V2,
+            got: version,
+        });
     }
     Ok(())
 }
 
-fn ensure_statement_type_version(statement_type: u16, statement_version: u16) -> Result<(), String> {
+fn ensure_statement_type_version(
+    statement_type: u16,
+    statement_version: u16,
+) -> Result<(), ContinuityError> {
     if statement_type != CONTINUITY_STATEMENT_TYPE {
-        return Err(format!(
-            "statement_type mismatch (expected {}, got {})",
-            CONTINUITY_STATEMENT_TYPE, statement_type
-        ));
+        return Err(ContinuityError::StatementMismatch {
+            field: "statement_type",
+            expected: CONTINUITY_STATEMENT_TYPE,
+            got: statement_type,
+        });
     }
     if statement_version != CONTINUITY_STATEMENT_VERSION_V2 {
-        return Err(format!(
-            "statement_version mismatch (expected {}, got {})",
-            CONTINUITY_STATEMENT_VERSION_V2, statement_version
-        ));
+        return Err(ContinuityError::StatementMismatch {
+            field: "statement_version",
+            expected: CONTINUITY_STATEMENT_VERSION_V2,
+            got: statement_version,
+        });
     }
     Ok(())
 }
 
-fn ensure_domain_sep_v2(label: &str, value: &[u8; 32]) -> Result<(), String> {
+fn ensure_domain_sep_v2(label: &str, value: &[u8; 32]) -> Result<(), ContinuityError> {
     if value != &CONTINUITY_V2_DOMAIN_SEP {
-        return Err(format!("{label}: domain_sep mismatch"));
+        return Err(ContinuityError::DomainSepMismatch {
+            field: label.to_string(),
+        });
     }
     Ok(())
 }
 
 impl ContinuityPublicInputsV1 {
-    pub fn into_public_inputs(self) -> Result<ContinuityPublicInputs, String> {
+    pub fn into_public_inputs(self) -> Result<ContinuityPublicInputs, ContinuityError> {
         ensure_version("public_inputs.schema_version", self.schema_version)?;
         ensure_domain_sep("public_inputs.domain_sep", &self.domain_sep)?;
 
         Ok(ContinuityPublicInputs {
-            c1_hash: fr_from_fixed_bytes("c1_hash", &self.c1_hash)?,
-            c2_hash: fr_from_fixed_bytes("c2_hash", &self.c2_hash)?,
+            c1_hash: fr_from_fixed_bytes_canonical("c1_hash", &self.c1_hash)?,
+            c2_hash: fr_from_fixed_bytes_canonical("c2_hash", &self.c2_hash)?,
             domain_sep: fr_from_fixed_bytes("domain_sep", &self.domain_sep)?,
         })
     }
 }
 
 impl ContinuityInstanceV1 {
-    pub fn into_instance(self) -> Result<ContinuityInstance, String> {
+    pub fn into_instance(self) -> Result<ContinuityInstance, ContinuityError> {
         ensure_version("instance.schema_version", self.schema_version)?;
         ensure_domain_sep("instance.domain_sep", &self.domain_sep)?;
 
         let id = fr_from_fixed_bytes("id", &self.id)?;
         let r1 = fr_from_fixed_bytes("r1", &self.r1)?;
         let r2 = fr_from_fixed_bytes("r2", &self.r2)?;
-        let c1_hash = fr_from_fixed_bytes("c1_hash", &self.c1_hash)?;
-        let c2_hash = fr_from_fixed_bytes("c2_hash", &self.c2_hash)?;
+        let c1_hash = fr_from_fixed_bytes_canonical("c1_hash", &self.c1_hash)?;
+        let c2_hash = fr_from_fixed_bytes_canonical("c2_hash", &self.c2_hash)?;
 
         let params = poseidon_params::<Fr>();
         let expected_c1 = commitment_hash(&params, id, r1);
         if expected_c1 != c1_hash {
-            return Err("c1_hash does not match commitment hash".to_string());
+            return Err(ContinuityError::CommitmentMismatch { field: "c1_hash" });
         }
         let expected_c2 = commitment_hash(&params, id, r2);
         if expected_c2 != c2_hash {
-            return Err("c2_hash does not match commitment hash".to_string());
+            return Err(ContinuityError::CommitmentMismatch { field: "c2_hash" });
         }
 
         let public_inputs = ContinuityPublicInputs {
@@ -210,22 +254,22 @@ impl ContinuityInstanceV1 {
 }
 
 impl ContinuityPublicInputsV2 {
-    pub fn into_public_inputs(self) -> Result<ContinuityPublicInputsV2Data, String> {
+    pub fn into_public_inputs(self) -> Result<ContinuityPublicInputsV2Data, ContinuityError> {
         ensure_version_v2("public_inputs.schema_version", self.schema_version)?;
         ensure_statement_type_version(self.statement_type, self.statement_version)?;
         ensure_domain_sep_v2("public_inputs.domain_sep", &self.domain_sep)?;
 
         Ok(ContinuityPublicInputsV2Data {
-            c1_hash: fr_from_fixed_bytes("c1_hash", &self.c1_hash)?,
-            c2_hash: fr_from_fixed_bytes("c2_hash", &self.c2_hash)?,
+            c1_hash: fr_from_fixed_bytes_canonical("c1_hash", &self.c1_hash)?,
+            c2_hash: fr_from_fixed_bytes_canonical("c2_hash", &self.c2_hash)?,
             domain_sep: fr_from_fixed_bytes("domain_sep", &self.domain_sep)?,
-            ctx_hash: fr_from_fixed_bytes("ctx_hash", &self.ctx_hash)?,
+            ctx_hash: fr_from_fixed_bytes_canonical("ctx_hash", &self.ctx_hash)?,
         })
     }
 }
 
 impl ContinuityInstanceV2 {
-    pub fn into_instance(self) -> Result<ContinuityInstanceV2Data, String> {
+    pub fn into_instance(self) -> Result<ContinuityInstanceV2Data, ContinuityError> {
         ensure_version_v2("instance.schema_version", self.schema_version)?;
         ensure_statement_type_version(self.statement_type, self.statement_version)?;
         ensure_domain_sep_v2("instance.domain_sep", &self.domain_sep)?;
@@ -233,18 +277,18 @@ impl ContinuityInstanceV2 {
         let id = fr_from_fixed_bytes("id", &self.id)?;
         let r1 = fr_from_fixed_bytes("r1", &self.r1)?;
         let r2 = fr_from_fixed_bytes("r2", &self.r2)?;
-        let c1_hash = fr_from_fixed_bytes("c1_hash", &self.c1_hash)?;
-        let c2_hash = fr_from_fixed_bytes("c2_hash", &self.c2_hash)?;
-        let ctx_hash = fr_from_fixed_bytes("ctx_hash", &self.ctx_hash)?;
+        let c1_hash = fr_from_fixed_bytes_canonical("c1_hash", &self.c1_hash)?;
+        let c2_hash = fr_from_fixed_bytes_canonical("c2_hash", &self.c2_hash)?;
+        let ctx_hash = fr_from_fixed_bytes_canonical("ctx_hash", &self.ctx_hash)?;
 
         let params = poseidon_params::<Fr>();
         let expected_c1 = commitment_hash_v2(&params, id, r1, ctx_hash);
         if expected_c1 != c1_hash {
-            return Err("c1_hash does not match commitment hash".to_string());
+            return Err(ContinuityError::CommitmentMismatch { field: "c1_hash" });
         }
         let expected_c2 = commitment_hash_v2(&params, id, r2, ctx_hash);
         if expected_c2 != c2_hash {
-            return Err("c2_hash does not match commitment hash".to_string());
+            return Err(ContinuityError::CommitmentMismatch { field: "c2_hash" });
         }
 
         let public_inputs = ContinuityPublicInputsV2Data {
@@ -260,6 +304,17 @@ impl ContinuityInstanceV2 {
             witness,
         })
     }
+
+    // Pretty JSON with every byte field as a `0x`-prefixed hex string.
+    #[cfg(feature = "std")]
+    pub fn to_json(&self) -> Result<String, String> {
+        membership::hex_serde::to_json(self)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        membership::hex_serde::from_json(json)
+    }
 }
 
 pub fn build_instance_v1(id: Fr, r1: Fr, r2: Fr) -> (ContinuityInstanceV1, ContinuityPublicInputsV1) {
@@ -321,6 +376,26 @@ pub fn build_instance_v2(
     (instance, public_inputs)
 }
 
+// One commitment per blinding, all under the same id and ctx_hash.
+pub fn build_instance_n_v2(id: Fr, blindings: &[Fr], ctx_hash: Fr) -> ContinuityInstanceNV2Data {
+    let params = poseidon_params::<Fr>();
+    let commitments = blindings
+        .iter()
+        .map(|r| commitment_hash_v2(&params, id, *r, ctx_hash))
+        .collect();
+    ContinuityInstanceNV2Data {
+        public_inputs: ContinuityPublicInputsNV2Data {
+            commitments,
+            domain_sep: domain_sep_v2_fr(),
+            ctx_hash,
+        },
+        witness: ContinuityWitnessNV2 {
+            id,
+            blindings: blindings.to_vec(),
+        },
+    }
+}
+
 fn poseidon_hash_native(
     params: &ark_sponge::poseidon::PoseidonConfig<Fr>,
     inputs: &[Fr],
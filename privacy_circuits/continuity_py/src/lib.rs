@@ -1,20 +1,20 @@
 use ark_bn254::{Bn254, Fr};
 use ark_groth16::{Proof, VerifyingKey};
-use ark_serialize::CanonicalDeserialize;
 use continuity::{
-    commitment_hash, commitment_hash_v2, fr_from_fixed_bytes, fr_to_fixed_bytes,
-    verify_continuity, verify_continuity_v2 as verify_continuity_v2_inner,
+    bincode_deserialize_capped, commitment_hash, commitment_hash_v2, decode_canonical_auto,
+    fr_from_fixed_bytes, fr_to_fixed_bytes, proof_from_bytes, read_file_capped, verify_continuity,
+    verify_continuity_bound as verify_continuity_bound_inner,
+    verify_continuity_v2 as verify_continuity_v2_inner,
     ContinuityInstanceV1, ContinuityInstanceV2,
     ContinuityPublicInputsV1, ContinuityPublicInputsV2, CONTINUITY_INSTANCE_VERSION_V1,
     CONTINUITY_INSTANCE_VERSION_V2, CONTINUITY_STATEMENT_TYPE,
     CONTINUITY_STATEMENT_VERSION_V2, CONTINUITY_V1_DOMAIN_SEP, CONTINUITY_V2_DOMAIN_SEP,
+    DEFAULT_MAX_INSTANCE_BYTES, DEFAULT_MAX_PROOF_BYTES, DEFAULT_MAX_VK_BYTES,
 };
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::types::PyBytes;
-use std::fs;
-use std::fs::File;
-use std::io::BufReader;
+use serde::de::DeserializeOwned;
 
 #[pyfunction]
 fn make_continuity_instance_v1_bytes(
@@ -27,9 +27,12 @@ fn make_continuity_instance_v1_bytes(
     let r1_bytes = fixed_bytes("r1", r1)?;
     let r2_bytes = fixed_bytes("r2", r2)?;
 
-    let id_fr = fr_from_fixed_bytes("id", &id_bytes).map_err(PyValueError::new_err)?;
-    let r1_fr = fr_from_fixed_bytes("r1", &r1_bytes).map_err(PyValueError::new_err)?;
-    let r2_fr = fr_from_fixed_bytes("r2", &r2_bytes).map_err(PyValueError::new_err)?;
+    let id_fr = fr_from_fixed_bytes("id", &id_bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let r1_fr = fr_from_fixed_bytes("r1", &r1_bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let r2_fr = fr_from_fixed_bytes("r2", &r2_bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
 
     let params = continuity::poseidon_params::<Fr>();
     let c1 = commitment_hash(&params, id_fr, r1_fr);
@@ -76,12 +79,16 @@ fn make_continuity_instance_v2_bytes(
     let id_bytes = fixed_bytes("id", id)?;
     let r1_bytes = fixed_bytes("r1", r1)?;
     let r2_bytes = fixed_bytes("r2", r2)?;
-    let ctx_bytes = fixed_bytes("ctx_hash", ctx_hash)?;
+    let ctx_bytes = fixed_bytes_allow_empty("ctx_hash", ctx_hash)?;
 
-    let id_fr = fr_from_fixed_bytes("id", &id_bytes).map_err(PyValueError::new_err)?;
-    let r1_fr = fr_from_fixed_bytes("r1", &r1_bytes).map_err(PyValueError::new_err)?;
-    let r2_fr = fr_from_fixed_bytes("r2", &r2_bytes).map_err(PyValueError::new_err)?;
-    let ctx_fr = fr_from_fixed_bytes("ctx_hash", &ctx_bytes).map_err(PyValueError::new_err)?;
+    let id_fr = fr_from_fixed_bytes("id", &id_bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let r1_fr = fr_from_fixed_bytes("r1", &r1_bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let r2_fr = fr_from_fixed_bytes("r2", &r2_bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let ctx_fr = fr_from_fixed_bytes("ctx_hash", &ctx_bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
 
     let params = continuity::poseidon_params::<Fr>();
     let c1 = commitment_hash_v2(&params, id_fr, r1_fr, ctx_fr);
@@ -89,6 +96,8 @@ fn make_continuity_instance_v2_bytes(
 
     let c1_bytes = fixed_bytes_from_vec("c1_hash", fr_to_fixed_bytes(&c1))?;
     let c2_bytes = fixed_bytes_from_vec("c2_hash", fr_to_fixed_bytes(&c2))?;
+    // ctx_hash may be any 32 bytes; the instance carries the reduced element.
+    let ctx_bytes = fixed_bytes_from_vec("ctx_hash", fr_to_fixed_bytes(&ctx_fr))?;
 
     let public_inputs = ContinuityPublicInputsV2 {
         schema_version: CONTINUITY_INSTANCE_VERSION_V2,
@@ -131,7 +140,8 @@ fn verify_continuity_v1(
 ) -> PyResult<bool> {
     let vk = read_verifying_key(vk_path)?;
     let public_inputs_bytes = read_public_inputs_v1(public_inputs_path)?;
-    let public_inputs = public_inputs_bytes.into_public_inputs().map_err(PyValueError::new_err)?;
+    let public_inputs = public_inputs_bytes.into_public_inputs()
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
     let proof = read_proof(proof_path)?;
 
     verify_continuity(&vk, &public_inputs, &proof)
@@ -146,7 +156,8 @@ fn verify_continuity_v2(
 ) -> PyResult<bool> {
     let vk = read_verifying_key(vk_path)?;
     let public_inputs_bytes = read_public_inputs_v2(public_inputs_path)?;
-    let public_inputs = public_inputs_bytes.into_public_inputs().map_err(PyValueError::new_err)?;
+    let public_inputs = public_inputs_bytes.into_public_inputs()
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
     let proof = read_proof(proof_path)?;
 
     verify_continuity_v2_inner(&vk, &public_inputs, &proof)
@@ -160,9 +171,7 @@ fn verify_continuity_v1_bytes(
     proof_bytes: Vec<u8>,
 ) -> PyResult<bool> {
     let vk = deserialize_verifying_key(&vk_bytes)?;
-    let public_inputs: ContinuityPublicInputsV1 =
-        bincode::deserialize(&public_inputs_bytes)
-            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let public_inputs: ContinuityPublicInputsV1 = decode_bincode(&public_inputs_bytes)?;
     let inputs = public_inputs
         .into_public_inputs()
         .map_err(|err| PyValueError::new_err(err.to_string()))?;
@@ -172,25 +181,52 @@ fn verify_continuity_v1_bytes(
         .map_err(|err| PyValueError::new_err(err.to_string()))
 }
 
+// Decoding stays under the GIL so its errors surface as exceptions; the pairing
+// check runs on owned values with the GIL released.
 #[pyfunction]
 fn verify_continuity_v2_bytes(
+    py: Python<'_>,
     vk_bytes: Vec<u8>,
     public_inputs_bytes: Vec<u8>,
     proof_bytes: Vec<u8>,
 ) -> PyResult<bool> {
     let vk = deserialize_verifying_key(&vk_bytes)?;
-    let public_inputs: ContinuityPublicInputsV2 =
-        bincode::deserialize(&public_inputs_bytes)
-            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let public_inputs: ContinuityPublicInputsV2 = decode_bincode(&public_inputs_bytes)?;
     let inputs = public_inputs
         .into_public_inputs()
         .map_err(|err| PyValueError::new_err(err.to_string()))?;
     let proof = deserialize_proof(&proof_bytes)?;
 
-    verify_continuity_v2_inner(&vk, &inputs, &proof)
+    py.allow_threads(|| verify_continuity_v2_inner(&vk, &inputs, &proof))
         .map_err(|err| PyValueError::new_err(err.to_string()))
 }
 
+#[pyfunction]
+fn verify_continuity_bound(
+    vk_bytes: Vec<u8>,
+    public_inputs_bytes: Vec<u8>,
+    proof_bytes: Vec<u8>,
+    expected_c1: Vec<u8>,
+    expected_c2: Vec<u8>,
+) -> PyResult<bool> {
+    let vk = deserialize_verifying_key(&vk_bytes)?;
+    let public_inputs: ContinuityPublicInputsV1 = decode_bincode(&public_inputs_bytes)?;
+    let proof = deserialize_proof(&proof_bytes)?;
+    let expected_c1 = fixed_bytes("expected_c1", expected_c1)?;
+    let expected_c2 = fixed_bytes("expected_c2", expected_c2)?;
+
+    verify_continuity_bound_inner(&vk, public_inputs, &proof, expected_c1, expected_c2)
+        .map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+// SHA-256 of the uncompressed VK encoding; matches `sha256sum` of the setup's
+// vk file, so a failing proof can be checked against the key it was made for.
+#[pyfunction]
+fn vk_fingerprint(py: Python<'_>, vk_bytes: Vec<u8>) -> PyResult<Py<PyBytes>> {
+    let vk = deserialize_verifying_key(&vk_bytes)?;
+    Ok(PyBytes::new(py, &continuity::vk_fingerprint(&vk)).into())
+}
+
 #[pymodule]
 fn continuity_py(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(make_continuity_instance_v1_bytes, m)?)?;
@@ -199,24 +235,23 @@ fn continuity_py(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(verify_continuity_v1_bytes, m)?)?;
     m.add_function(wrap_pyfunction!(verify_continuity_v2, m)?)?;
     m.add_function(wrap_pyfunction!(verify_continuity_v2_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_continuity_bound, m)?)?;
+    m.add_function(wrap_pyfunction!(vk_fingerprint, m)?)?;
+    m.add("PROOF_SIZE_COMPRESSED", continuity::PROOF_SIZE_COMPRESSED)?;
+    m.add("PROOF_SIZE_UNCOMPRESSED", continuity::PROOF_SIZE_UNCOMPRESSED)?;
     Ok(())
 }
 
-fn fixed_bytes(label: &str, mut bytes: Vec<u8>) -> PyResult<[u8; 32]> {
-    if bytes.is_empty() {
-        return Err(PyValueError::new_err(format!("{label} must not be empty")));
-    }
-    if bytes.len() > 32 {
-        return Err(PyValueError::new_err(format!("{label} must be <= 32 bytes")));
-    }
-    if bytes.len() < 32 {
-        let mut padded = vec![0u8; 32 - bytes.len()];
-        padded.append(&mut bytes);
-        bytes = padded;
-    }
-    Ok(bytes
-        .try_into()
-        .map_err(|_| PyValueError::new_err("invalid length"))?)
+// id, r1/r2 and blinding must be supplied explicitly (b"" is an error, b"\x00" is
+// zero). ctx_hash goes through fixed_bytes_allow_empty so b"" means "no context".
+fn fixed_bytes(label: &str, bytes: Vec<u8>) -> PyResult<[u8; 32]> {
+    continuity::fixed_bytes(label, &bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+fn fixed_bytes_allow_empty(label: &str, bytes: Vec<u8>) -> PyResult<[u8; 32]> {
+    continuity::fixed_bytes_allow_empty(label, &bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))
 }
 
 fn fixed_bytes_from_vec(label: &str, bytes: Vec<u8>) -> PyResult<[u8; 32]> {
@@ -224,39 +259,38 @@ fn fixed_bytes_from_vec(label: &str, bytes: Vec<u8>) -> PyResult<[u8; 32]> {
 }
 
 fn read_verifying_key(path: &str) -> PyResult<VerifyingKey<Bn254>> {
-    let file = File::open(path).map_err(PyValueError::new_err)?;
-    let mut reader = BufReader::new(file);
-    VerifyingKey::<Bn254>::deserialize_uncompressed(&mut reader)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    let data = read_file_capped(path, DEFAULT_MAX_VK_BYTES).map_err(PyValueError::new_err)?;
+    deserialize_verifying_key(&data)
 }
 
 fn read_public_inputs_v1(path: &str) -> PyResult<ContinuityPublicInputsV1> {
-    let data = fs::read(path).map_err(PyValueError::new_err)?;
-    bincode::deserialize::<ContinuityPublicInputsV1>(&data)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    let data =
+        read_file_capped(path, DEFAULT_MAX_INSTANCE_BYTES).map_err(PyValueError::new_err)?;
+    decode_bincode(&data)
 }
 
 fn read_public_inputs_v2(path: &str) -> PyResult<ContinuityPublicInputsV2> {
-    let data = fs::read(path).map_err(PyValueError::new_err)?;
-    bincode::deserialize::<ContinuityPublicInputsV2>(&data)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    let data =
+        read_file_capped(path, DEFAULT_MAX_INSTANCE_BYTES).map_err(PyValueError::new_err)?;
+    decode_bincode(&data)
 }
 
 fn read_proof(path: &str) -> PyResult<Proof<Bn254>> {
-    let file = File::open(path).map_err(PyValueError::new_err)?;
-    let mut reader = BufReader::new(file);
-    Proof::<Bn254>::deserialize_uncompressed(&mut reader)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    let data = read_file_capped(path, DEFAULT_MAX_PROOF_BYTES).map_err(PyValueError::new_err)?;
+    decode_canonical_auto(&data).map_err(PyValueError::new_err)
 }
 
+// Public-input files are fixed-size, but a caller may hand over anything; the cap
+// keeps decoding from reading or allocating past DEFAULT_MAX_INSTANCE_BYTES.
+fn decode_bincode<T: DeserializeOwned>(data: &[u8]) -> PyResult<T> {
+    bincode_deserialize_capped(data, DEFAULT_MAX_INSTANCE_BYTES).map_err(PyValueError::new_err)
+}
+
+// Keys from either setup mode load; only bytes that decode in neither are refused.
 fn deserialize_verifying_key(bytes: &[u8]) -> PyResult<VerifyingKey<Bn254>> {
-    let mut reader = std::io::Cursor::new(bytes);
-    VerifyingKey::<Bn254>::deserialize_uncompressed(&mut reader)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    decode_canonical_auto(bytes).map_err(PyValueError::new_err)
 }
 
 fn deserialize_proof(bytes: &[u8]) -> PyResult<Proof<Bn254>> {
-    let mut reader = std::io::Cursor::new(bytes);
-    Proof::<Bn254>::deserialize_uncompressed(&mut reader)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    proof_from_bytes(bytes).map_err(|err| PyValueError::new_err(err.to_string()))
 }
@@ -0,0 +1,44 @@
+// Compares sponge rate 3 (the pinned parameters) against rate 5 for hashing 8
+// field elements: native time per hash and R1CS constraints per in-circuit hash.
+// Run with `cargo bench -p membership --bench poseidon_rate`.
+use ark_bn254::Fr;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_relations::r1cs::ConstraintSystem;
+use ark_sponge::constraints::CryptographicSpongeVar;
+use ark_sponge::poseidon::constraints::PoseidonSpongeVar;
+use ark_sponge::poseidon::PoseidonSponge;
+use ark_sponge::CryptographicSponge;
+use membership::poseidon_params_with;
+use std::hint::black_box;
+use std::time::Instant;
+
+const INPUTS: u64 = 8;
+const ITERATIONS: u32 = 2_000;
+
+fn main() {
+    let inputs: Vec<Fr> = (1..=INPUTS).map(Fr::from).collect();
+    println!("rate  ns/hash  constraints");
+    for rate in [3usize, 5] {
+        let params = poseidon_params_with::<Fr>(rate);
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            let mut sponge = PoseidonSponge::<Fr>::new(&params);
+            sponge.absorb(&black_box(&inputs));
+            black_box(sponge.squeeze_field_elements::<Fr>(1));
+        }
+        let per_hash = start.elapsed().as_nanos() / u128::from(ITERATIONS);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let vars: Vec<FpVar<Fr>> = inputs
+            .iter()
+            .map(|value| FpVar::new_witness(cs.clone(), || Ok(*value)).unwrap())
+            .collect();
+        let mut sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &params);
+        sponge.absorb(&vars).unwrap();
+        sponge.squeeze_field_elements(1).unwrap();
+
+        println!("{rate:<4}  {per_hash:<7}  {}", cs.num_constraints());
+    }
+}
@@ -0,0 +1,345 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ensure_depth, ensure_version_u16, fr_from_bytes_canonical, fr_to_array, merkle_root_var,
+    poseidon_hash_var, poseidon_params, MembershipError, MembershipInstance, MembershipWitness,
+    DOMAIN_COMMITMENT, DOMAIN_LEAF,
+};
+
+pub const AGGREGATE_MEMBERSHIP_INSTANCE_VERSION: u16 = 1;
+
+// N members of one tree proven together. Public inputs: [root, commitment_0, ...,
+// commitment_{N-1}]; each member's opening and path is a separate witness, so the
+// constraint count grows linearly in N while verification stays a single pairing
+// check. Keys are specific to (N, depth).
+#[derive(Clone, Debug, Default)]
+pub struct AggregateMembershipCircuit<F: PrimeField> {
+    pub root: Option<F>,
+    pub commitments: Vec<Option<F>>,
+    pub identity_scalars: Vec<Option<F>>,
+    pub blindings: Vec<Option<F>>,
+    pub expected_depth: usize,
+    pub merkle_paths: Vec<Vec<(Option<F>, Option<bool>)>>,
+}
+
+// Name used for the same circuit elsewhere: K openings against one root.
+pub type MembershipCircuitMulti<F> = AggregateMembershipCircuit<F>;
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for AggregateMembershipCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let count = self.commitments.len();
+        if count == 0
+            || self.identity_scalars.len() != count
+            || self.blindings.len() != count
+            || self.merkle_paths.len() != count
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        if self.expected_depth == 0
+            || self
+                .merkle_paths
+                .iter()
+                .any(|path| path.len() != self.expected_depth)
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let params = poseidon_params::<F>();
+
+        let root =
+            FpVar::new_input(cs.clone(), || self.root.ok_or(SynthesisError::AssignmentMissing))?;
+        let commitment_inputs = self
+            .commitments
+            .iter()
+            .map(|commitment| {
+                FpVar::new_input(cs.clone(), || {
+                    commitment.ok_or(SynthesisError::AssignmentMissing)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let domain_commitment = FpVar::constant(F::from(DOMAIN_COMMITMENT));
+        let domain_leaf = FpVar::constant(F::from(DOMAIN_LEAF));
+
+        let members = self
+            .identity_scalars
+            .into_iter()
+            .zip(self.blindings)
+            .zip(self.merkle_paths)
+            .zip(commitment_inputs);
+        for (((identity_scalar, blinding), merkle_path), commitment_input) in members {
+            let identity_scalar = FpVar::new_witness(cs.clone(), || {
+                identity_scalar.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let blinding = FpVar::new_witness(cs.clone(), || {
+                blinding.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+
+            let commitment = poseidon_hash_var(
+                cs.clone(),
+                &params,
+                &[domain_commitment.clone(), identity_scalar, blinding],
+            )?;
+            commitment.enforce_equal(&commitment_input)?;
+
+            let leaf = poseidon_hash_var(
+                cs.clone(),
+                &params,
+                &[domain_leaf.clone(), commitment, FpVar::zero()],
+            )?;
+            let computed_root = merkle_root_var(cs.clone(), &params, leaf, merkle_path)?;
+            computed_root.enforce_equal(&root)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct AggregateMembershipPublicInputs {
+    pub root: Fr,
+    pub commitments: Vec<Fr>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AggregateMembershipInstance {
+    pub public_inputs: AggregateMembershipPublicInputs,
+    pub witnesses: Vec<MembershipWitness>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AggregateMembershipPublicInputsBytes {
+    pub schema_version: u16,
+    pub depth: u32,
+    pub root: [u8; 32],
+    pub commitments: Vec<[u8; 32]>,
+}
+
+impl AggregateMembershipPublicInputsBytes {
+    pub fn from_instance(instance: &AggregateMembershipInstance) -> Self {
+        let depth = instance
+            .witnesses
+            .first()
+            .map_or(0, |witness| witness.merkle_path.len());
+        Self {
+            schema_version: AGGREGATE_MEMBERSHIP_INSTANCE_VERSION,
+            depth: depth as u32,
+            root: fr_to_array(&instance.public_inputs.root),
+            commitments: instance
+                .public_inputs
+                .commitments
+                .iter()
+                .map(fr_to_array)
+                .collect(),
+        }
+    }
+
+    pub fn into_public_inputs_with_depth(
+        self,
+    ) -> Result<(AggregateMembershipPublicInputs, usize), MembershipError> {
+        ensure_version_u16(
+            "public_inputs.schema_version",
+            self.schema_version,
+            AGGREGATE_MEMBERSHIP_INSTANCE_VERSION,
+        )?;
+        let depth = ensure_depth("public_inputs.depth", self.depth as usize)?;
+        if self.commitments.is_empty() {
+            return Err(MembershipError::EmptyField {
+                field: "public_inputs.commitments".to_string(),
+            });
+        }
+        let commitments = self
+            .commitments
+            .iter()
+            .map(|commitment| fr_from_bytes_canonical("commitment", commitment))
+            .collect::<Result<Vec<_>, _>>()?;
+        let inputs = AggregateMembershipPublicInputs {
+            root: fr_from_bytes_canonical("root", &self.root)?,
+            commitments,
+        };
+        Ok((inputs, depth))
+    }
+}
+
+// Combines single-member instances that share a root and depth, keeping their order.
+pub fn build_aggregate_instance(
+    instances: &[MembershipInstance],
+) -> Result<AggregateMembershipInstance, String> {
+    let first = instances
+        .first()
+        .ok_or_else(|| "at least one instance is required".to_string())?;
+    let root = first.public_inputs.root;
+    let depth = first.witness.merkle_path.len();
+    for (idx, instance) in instances.iter().enumerate() {
+        if instance.public_inputs.root != root {
+            return Err(format!("instance {idx}: root differs from instance 0"));
+        }
+        if instance.witness.merkle_path.len() != depth {
+            return Err(format!("instance {idx}: depth differs from instance 0"));
+        }
+    }
+    Ok(AggregateMembershipInstance {
+        public_inputs: AggregateMembershipPublicInputs {
+            root,
+            commitments: instances
+                .iter()
+                .map(|instance| instance.public_inputs.commitment)
+                .collect(),
+        },
+        witnesses: instances
+            .iter()
+            .map(|instance| instance.witness.clone())
+            .collect(),
+    })
+}
+
+pub fn aggregate_membership_field_inputs(
+    public_inputs: &AggregateMembershipPublicInputs,
+) -> Vec<Fr> {
+    let mut inputs = Vec::with_capacity(public_inputs.commitments.len() + 1);
+    inputs.push(public_inputs.root);
+    inputs.extend_from_slice(&public_inputs.commitments);
+    inputs
+}
+
+pub fn build_aggregate_circuit(
+    instance: &AggregateMembershipInstance,
+) -> AggregateMembershipCircuit<Fr> {
+    let witnesses = &instance.witnesses;
+    AggregateMembershipCircuit {
+        root: Some(instance.public_inputs.root),
+        commitments: instance.public_inputs.commitments.iter().copied().map(Some).collect(),
+        identity_scalars: witnesses.iter().map(|w| Some(w.identity_scalar)).collect(),
+        blindings: witnesses.iter().map(|w| Some(w.blinding)).collect(),
+        expected_depth: witnesses.first().map_or(0, |w| w.merkle_path.len()),
+        merkle_paths: witnesses
+            .iter()
+            .map(|w| {
+                w.merkle_path
+                    .iter()
+                    .map(|(sibling, is_left)| (Some(*sibling), Some(*is_left)))
+                    .collect()
+            })
+            .collect(),
+    }
+}
+
+pub fn setup_aggregate_membership<R: RngCore>(
+    rng: &mut R,
+    count: usize,
+    depth: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let zero = Fr::from(0u64);
+    let circuit = AggregateMembershipCircuit::<Fr> {
+        root: Some(zero),
+        commitments: vec![Some(zero); count],
+        identity_scalars: vec![Some(zero); count],
+        blindings: vec![Some(zero); count],
+        expected_depth: depth,
+        merkle_paths: vec![vec![(Some(zero), Some(false)); depth]; count],
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+pub fn prove_aggregate_membership<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &AggregateMembershipInstance,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    Groth16::<Bn254>::create_random_proof_with_reduction(
+        build_aggregate_circuit(instance),
+        pk,
+        rng,
+    )
+}
+
+pub fn verify_aggregate_membership(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &AggregateMembershipPublicInputs,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    let pvk = prepare_verifying_key(vk);
+    let inputs = aggregate_membership_field_inputs(public_inputs);
+    Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_aggregate_circuit, build_aggregate_instance, prove_aggregate_membership,
+        setup_aggregate_membership, verify_aggregate_membership,
+        AggregateMembershipPublicInputsBytes,
+    };
+    use crate::tree::PoseidonMerkleTree;
+    use crate::{
+        commitment_hash, poseidon_params, MembershipInstance, MembershipPublicInputs,
+        MembershipWitness,
+    };
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn aggregate_proof_covers_three_members() {
+        let params = poseidon_params::<Fr>();
+        let openings: Vec<(Fr, Fr)> = (0..4u64)
+            .map(|idx| (Fr::from(100 + idx), Fr::from(200 + idx)))
+            .collect();
+        let commitments: Vec<Fr> = openings
+            .iter()
+            .map(|(identity, blinding)| commitment_hash(&params, *identity, *blinding))
+            .collect();
+        let tree = PoseidonMerkleTree::build(&commitments, 2, &params).unwrap();
+        let instances: Vec<MembershipInstance> = [0usize, 2, 3]
+            .iter()
+            .map(|&idx| MembershipInstance {
+                public_inputs: MembershipPublicInputs {
+                    root: tree.root(),
+                    commitment: commitments[idx],
+                },
+                witness: MembershipWitness {
+                    identity_scalar: openings[idx].0,
+                    blinding: openings[idx].1,
+                    merkle_path: tree.path(idx).unwrap(),
+                },
+            })
+            .collect();
+        let instance = build_aggregate_instance(&instances).unwrap();
+
+        let mut rng = StdRng::seed_from_u64(93);
+        let pk = setup_aggregate_membership(&mut rng, 3, 2).unwrap();
+        assert_eq!(pk.vk.gamma_abc_g1.len(), 5);
+        let proof = prove_aggregate_membership(&pk, &instance, &mut rng).unwrap();
+        let (public_inputs, depth) = AggregateMembershipPublicInputsBytes::from_instance(&instance)
+            .into_public_inputs_with_depth()
+            .unwrap();
+        assert_eq!(depth, 2);
+        assert!(verify_aggregate_membership(&pk.vk, &public_inputs, &proof).unwrap());
+
+        let mut swapped = public_inputs.clone();
+        swapped.commitments.swap(0, 1);
+        assert!(!verify_aggregate_membership(&pk.vk, &swapped, &proof).unwrap());
+
+        // Member 1 presents member 0's path: its leaf no longer hashes to the root.
+        let mut wrong_path = instance.clone();
+        wrong_path.witnesses[1].merkle_path = tree.path(0).unwrap();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        build_aggregate_circuit(&wrong_path)
+            .generate_constraints(cs.clone())
+            .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+
+        // Swapping any single member for a non-member rejects the whole proof.
+        let outsider = commitment_hash(&params, Fr::from(999u64), Fr::from(998u64));
+        for idx in 0..3 {
+            let mut one_wrong = public_inputs.clone();
+            one_wrong.commitments[idx] = outsider;
+            assert!(!verify_aggregate_membership(&pk.vk, &one_wrong, &proof).unwrap());
+        }
+    }
+}
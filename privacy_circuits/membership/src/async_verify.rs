@@ -0,0 +1,68 @@
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::{Proof, VerifyingKey};
+use std::future::Future;
+
+use crate::{verify_membership_v2, MembershipPublicInputsV2Bytes};
+
+// Rejects proofs made against a root other than the one the registry currently
+// reports before paying for the pairing check.
+pub async fn verify_membership_v2_with_root_fetch<F, Fut>(
+    vk: &VerifyingKey<Bn254>,
+    inputs_bytes: &[u8],
+    proof: &Proof<Bn254>,
+    fetch_root: F,
+) -> Result<bool, String>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Fr, String>>,
+{
+    let inputs_bytes: MembershipPublicInputsV2Bytes = bincode::deserialize(inputs_bytes)
+        .map_err(|err| format!("failed to decode public inputs: {err}"))?;
+    let (public_inputs, _) = inputs_bytes.into_public_inputs_with_depth()?;
+
+    let authoritative_root = fetch_root().await?;
+    if public_inputs.root != authoritative_root {
+        return Err("root mismatch: proof is not against the current root".to_string());
+    }
+
+    verify_membership_v2(vk, &public_inputs, proof).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify_membership_v2_with_root_fetch;
+    use crate::test_support::{public_inputs_v2_bytes, sample_instance_v2};
+    use crate::{prove_membership_v2, setup_membership_with_depth_v2};
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::Proof;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[tokio::test]
+    async fn root_fetch_accepts_current_root_and_rejects_stale() {
+        let instance = sample_instance_v2(2);
+        let inputs_bytes = bincode::serialize(&public_inputs_v2_bytes(&instance)).unwrap();
+        let root = instance.public_inputs.root;
+
+        let mut rng = StdRng::seed_from_u64(58);
+        let pk = setup_membership_with_depth_v2(&mut rng, 2).unwrap();
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+
+        let verified =
+            verify_membership_v2_with_root_fetch(&pk.vk, &inputs_bytes, &proof, || async {
+                Ok(root)
+            })
+            .await;
+        assert_eq!(verified, Ok(true));
+
+        // A default proof would fail the pairing with Ok(false); an Err shows the
+        // root check short-circuited before verification.
+        let stale = verify_membership_v2_with_root_fetch(
+            &pk.vk,
+            &inputs_bytes,
+            &Proof::<Bn254>::default(),
+            || async { Ok(root + Fr::from(1u64)) },
+        )
+        .await;
+        assert!(stale.unwrap_err().contains("root mismatch"));
+    }
+}
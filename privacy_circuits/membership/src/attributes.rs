@@ -0,0 +1,345 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::bits::boolean::Boolean;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_sponge::poseidon::PoseidonConfig;
+use ark_std::rand::RngCore;
+
+use crate::{
+    merkle_root_var, poseidon_hash_native, poseidon_hash_var, poseidon_params, DOMAIN_ATTRIBUTES,
+    DOMAIN_LEAF,
+};
+
+// Commitment over an identity and `m` attributes, under its own domain so it can
+// never equal a plain two-value commitment.
+pub fn commitment_hash_with_attrs(
+    params: &PoseidonConfig<Fr>,
+    identity: Fr,
+    blinding: Fr,
+    attributes: &[Fr],
+) -> Fr {
+    let mut inputs = Vec::with_capacity(attributes.len() + 3);
+    inputs.extend([Fr::from(DOMAIN_ATTRIBUTES), identity, blinding]);
+    inputs.extend_from_slice(attributes);
+    poseidon_hash_native(params, &inputs)
+}
+
+// Membership of H(DOMAIN_ATTRIBUTES, identity, blinding, attr_0, ..., attr_{m-1})
+// with selective disclosure. Public inputs: [root, commitment, mask_0, ...,
+// mask_{m-1}, revealed_0, ..., revealed_{m-1}]. Where mask_i is set, attr_i must
+// equal revealed_i; where it is clear, revealed_i must be zero so a hidden slot
+// never carries a value a verifier could mistake for a disclosed one. The mask is
+// an input rather than a constant, so one key (per m and depth) serves every
+// disclosure choice.
+#[derive(Clone, Debug, Default)]
+pub struct MembershipCircuitWithAttrs<F: PrimeField> {
+    pub root: Option<F>,
+    pub commitment: Option<F>,
+    pub reveal_mask: Vec<Option<bool>>,
+    pub revealed_values: Vec<Option<F>>,
+    pub identity_scalar: Option<F>,
+    pub blinding: Option<F>,
+    pub attributes: Vec<Option<F>>,
+    pub expected_depth: usize,
+    pub merkle_path: Vec<(Option<F>, Option<bool>)>,
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for MembershipCircuitWithAttrs<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let count = self.attributes.len();
+        if self.reveal_mask.len() != count || self.revealed_values.len() != count {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        if self.expected_depth == 0 || self.merkle_path.len() != self.expected_depth {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let params = poseidon_params::<F>();
+
+        let root =
+            FpVar::new_input(cs.clone(), || self.root.ok_or(SynthesisError::AssignmentMissing))?;
+        let commitment_input = FpVar::new_input(cs.clone(), || {
+            self.commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let reveal_mask = self
+            .reveal_mask
+            .iter()
+            .map(|bit| {
+                Boolean::new_input(cs.clone(), || bit.ok_or(SynthesisError::AssignmentMissing))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let revealed_values = self
+            .revealed_values
+            .iter()
+            .map(|value| {
+                FpVar::new_input(cs.clone(), || value.ok_or(SynthesisError::AssignmentMissing))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let identity_scalar = FpVar::new_witness(cs.clone(), || {
+            self.identity_scalar.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|attribute| {
+                FpVar::new_witness(cs.clone(), || {
+                    attribute.ok_or(SynthesisError::AssignmentMissing)
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let zero = FpVar::zero();
+        let slots = attributes.iter().zip(&reveal_mask).zip(&revealed_values);
+        for ((attribute, reveal), revealed) in slots {
+            attribute.conditional_enforce_equal(revealed, reveal)?;
+            revealed.conditional_enforce_equal(&zero, &reveal.not())?;
+        }
+
+        let mut preimage = vec![
+            FpVar::constant(F::from(DOMAIN_ATTRIBUTES)),
+            identity_scalar,
+            blinding,
+        ];
+        preimage.extend(attributes);
+        let commitment = poseidon_hash_var(cs.clone(), &params, &preimage)?;
+        commitment.enforce_equal(&commitment_input)?;
+
+        let domain_leaf = FpVar::constant(F::from(DOMAIN_LEAF));
+        let leaf = poseidon_hash_var(cs.clone(), &params, &[domain_leaf, commitment, zero])?;
+        let computed_root = merkle_root_var(cs, &params, leaf, self.merkle_path)?;
+        computed_root.enforce_equal(&root)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MembershipWithAttrsPublicInputs {
+    pub root: Fr,
+    pub commitment: Fr,
+    pub reveal_mask: Vec<bool>,
+    pub revealed_values: Vec<Fr>,
+}
+
+impl MembershipWithAttrsPublicInputs {
+    // Discloses `attributes[i]` wherever `reveal_mask[i]` is set and zero elsewhere.
+    pub fn disclose(
+        root: Fr,
+        commitment: Fr,
+        attributes: &[Fr],
+        reveal_mask: &[bool],
+    ) -> Result<Self, String> {
+        if attributes.len() != reveal_mask.len() {
+            return Err(format!(
+                "reveal_mask has {} entries for {} attributes",
+                reveal_mask.len(),
+                attributes.len()
+            ));
+        }
+        let revealed_values = attributes
+            .iter()
+            .zip(reveal_mask)
+            .map(|(attribute, reveal)| if *reveal { *attribute } else { Fr::from(0u64) })
+            .collect();
+        Ok(Self {
+            root,
+            commitment,
+            reveal_mask: reveal_mask.to_vec(),
+            revealed_values,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MembershipWithAttrsWitness {
+    pub identity_scalar: Fr,
+    pub blinding: Fr,
+    pub attributes: Vec<Fr>,
+    pub merkle_path: Vec<(Fr, bool)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MembershipWithAttrsInstance {
+    pub public_inputs: MembershipWithAttrsPublicInputs,
+    pub witness: MembershipWithAttrsWitness,
+}
+
+pub fn membership_with_attrs_field_inputs(
+    public_inputs: &MembershipWithAttrsPublicInputs,
+) -> Vec<Fr> {
+    let mut inputs = Vec::with_capacity(2 + 2 * public_inputs.reveal_mask.len());
+    inputs.push(public_inputs.root);
+    inputs.push(public_inputs.commitment);
+    inputs.extend(public_inputs.reveal_mask.iter().map(|bit| Fr::from(*bit)));
+    inputs.extend_from_slice(&public_inputs.revealed_values);
+    inputs
+}
+
+pub fn build_circuit_with_attrs(
+    instance: &MembershipWithAttrsInstance,
+) -> MembershipCircuitWithAttrs<Fr> {
+    let public_inputs = &instance.public_inputs;
+    let witness = &instance.witness;
+    MembershipCircuitWithAttrs {
+        root: Some(public_inputs.root),
+        commitment: Some(public_inputs.commitment),
+        reveal_mask: public_inputs.reveal_mask.iter().copied().map(Some).collect(),
+        revealed_values: public_inputs.revealed_values.iter().copied().map(Some).collect(),
+        identity_scalar: Some(witness.identity_scalar),
+        blinding: Some(witness.blinding),
+        attributes: witness.attributes.iter().copied().map(Some).collect(),
+        expected_depth: witness.merkle_path.len(),
+        merkle_path: witness
+            .merkle_path
+            .iter()
+            .map(|(sibling, is_left)| (Some(*sibling), Some(*is_left)))
+            .collect(),
+    }
+}
+
+pub fn setup_membership_with_attrs<R: RngCore>(
+    rng: &mut R,
+    attribute_count: usize,
+    depth: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let zero = Fr::from(0u64);
+    let circuit = MembershipCircuitWithAttrs::<Fr> {
+        root: Some(zero),
+        commitment: Some(zero),
+        reveal_mask: vec![Some(false); attribute_count],
+        revealed_values: vec![Some(zero); attribute_count],
+        identity_scalar: Some(zero),
+        blinding: Some(zero),
+        attributes: vec![Some(zero); attribute_count],
+        expected_depth: depth,
+        merkle_path: vec![(Some(zero), Some(false)); depth],
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+pub fn prove_membership_with_attrs<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &MembershipWithAttrsInstance,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    Groth16::<Bn254>::create_random_proof_with_reduction(
+        build_circuit_with_attrs(instance),
+        pk,
+        rng,
+    )
+}
+
+pub fn verify_membership_with_attrs(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &MembershipWithAttrsPublicInputs,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    let pvk = prepare_verifying_key(vk);
+    let inputs = membership_with_attrs_field_inputs(public_inputs);
+    Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_circuit_with_attrs, commitment_hash_with_attrs, prove_membership_with_attrs,
+        setup_membership_with_attrs, verify_membership_with_attrs, MembershipWithAttrsInstance,
+        MembershipWithAttrsPublicInputs, MembershipWithAttrsWitness,
+    };
+    use crate::tree::PoseidonMerkleTree;
+    use crate::{commitment_hash, poseidon_params};
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    fn satisfied(instance: &MembershipWithAttrsInstance) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        build_circuit_with_attrs(instance)
+            .generate_constraints(cs.clone())
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn revealed_attributes_must_match_the_committed_ones() {
+        let params = poseidon_params::<Fr>();
+        let identity = Fr::from(61u64);
+        let blinding = Fr::from(62u64);
+        // Country code, birth year, account tier.
+        let attributes = vec![Fr::from(276u64), Fr::from(1990u64), Fr::from(3u64)];
+        let commitment = commitment_hash_with_attrs(&params, identity, blinding, &attributes);
+        assert_ne!(
+            commitment_hash_with_attrs(&params, identity, blinding, &[]),
+            commitment_hash(&params, identity, blinding)
+        );
+
+        let others = (0..3u64).map(|idx| commitment_hash(&params, Fr::from(idx), Fr::from(idx)));
+        let commitments: Vec<Fr> = others.chain([commitment]).collect();
+        let tree = PoseidonMerkleTree::build(&commitments, 2, &params).unwrap();
+        let witness = MembershipWithAttrsWitness {
+            identity_scalar: identity,
+            blinding,
+            attributes: attributes.clone(),
+            merkle_path: tree.path(3).unwrap(),
+        };
+        let mask = [true, false, true];
+        let instance = MembershipWithAttrsInstance {
+            public_inputs: MembershipWithAttrsPublicInputs::disclose(
+                tree.root(),
+                commitment,
+                &attributes,
+                &mask,
+            )
+            .unwrap(),
+            witness,
+        };
+        assert_eq!(
+            instance.public_inputs.revealed_values,
+            [Fr::from(276u64), Fr::from(0u64), Fr::from(3u64)]
+        );
+        assert!(satisfied(&instance));
+
+        let mut rng = StdRng::seed_from_u64(108);
+        let pk = setup_membership_with_attrs(&mut rng, 3, 2).unwrap();
+        assert_eq!(pk.vk.gamma_abc_g1.len(), 1 + 2 + 2 * 3);
+        let proof = prove_membership_with_attrs(&pk, &instance, &mut rng).unwrap();
+        let public_inputs = &instance.public_inputs;
+        assert!(verify_membership_with_attrs(&pk.vk, public_inputs, &proof).unwrap());
+
+        // A disclosed value other than the committed attribute.
+        let mut wrong_value = instance.clone();
+        wrong_value.public_inputs.revealed_values[0] = Fr::from(250u64);
+        assert!(!satisfied(&wrong_value));
+        let wrong_inputs = &wrong_value.public_inputs;
+        assert!(!verify_membership_with_attrs(&pk.vk, wrong_inputs, &proof).unwrap());
+
+        // Revealing the hidden attribute, or hiding a revealed one, changes the
+        // statement the proof was made for.
+        let mut more_revealed = public_inputs.clone();
+        more_revealed.reveal_mask[1] = true;
+        more_revealed.revealed_values[1] = attributes[1];
+        assert!(!verify_membership_with_attrs(&pk.vk, &more_revealed, &proof).unwrap());
+
+        // A hidden slot may not carry a value.
+        let mut leaked = instance.clone();
+        leaked.public_inputs.revealed_values[1] = attributes[1];
+        assert!(!satisfied(&leaked));
+
+        // Attributes that do not open the committed leaf.
+        let mut other_attributes = instance;
+        other_attributes.witness.attributes[1] = Fr::from(1991u64);
+        assert!(!satisfied(&other_attributes));
+
+        let short_mask = [true];
+        let root = tree.root();
+        assert!(
+            MembershipWithAttrsPublicInputs::disclose(root, commitment, &attributes, &short_mask)
+                .is_err()
+        );
+    }
+}
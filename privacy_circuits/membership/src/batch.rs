@@ -0,0 +1,106 @@
+use ark_bn254::{Bn254, Fr, G1Projective};
+use ark_ec::pairing::Pairing;
+use ark_ec::CurveGroup;
+use ark_ff::{Field, PrimeField};
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, VerifyingKey};
+use ark_relations::r1cs::SynthesisError;
+use ark_std::rand::rngs::OsRng;
+use ark_std::{UniformRand, Zero};
+
+use crate::MembershipPublicInputs;
+
+// Checks e(A, B) = e(alpha, beta) * e(L, gamma) * e(C, delta) for every proof at
+// once. Each equation is raised to a fresh random scalar before they are
+// multiplied together, so an invalid proof cannot be cancelled out by another
+// one, and the whole batch costs one multi-pairing and one exponentiation. An
+// empty batch is accepted.
+pub fn verify_membership_batch(
+    vk: &VerifyingKey<Bn254>,
+    batch: &[(MembershipPublicInputs, Proof<Bn254>)],
+) -> Result<bool, SynthesisError> {
+    if batch.is_empty() {
+        return Ok(true);
+    }
+    let pvk = prepare_verifying_key(vk);
+    let mut rng = OsRng;
+    let mut g1 = Vec::with_capacity(batch.len() + 2);
+    let mut g2 = Vec::with_capacity(batch.len() + 2);
+    let mut scalar_sum = Fr::zero();
+    let mut inputs_acc = G1Projective::zero();
+    let mut c_acc = G1Projective::zero();
+    for (public_inputs, proof) in batch {
+        let scalar = Fr::rand(&mut rng);
+        let prepared = Groth16::<Bn254>::prepare_inputs(
+            &pvk,
+            &[public_inputs.root, public_inputs.commitment],
+        )?;
+        scalar_sum += scalar;
+        inputs_acc += prepared * scalar;
+        c_acc += proof.c * scalar;
+        g1.push((proof.a * scalar).into_affine());
+        g2.push(<Bn254 as Pairing>::G2Prepared::from(proof.b));
+    }
+    g1.push(inputs_acc.into_affine());
+    g2.push(pvk.gamma_g2_neg_pc.clone());
+    g1.push(c_acc.into_affine());
+    g2.push(pvk.delta_g2_neg_pc.clone());
+
+    let product = Bn254::multi_pairing(g1, g2);
+    Ok(product.0 == pvk.alpha_g1_beta_g2.pow(scalar_sum.into_bigint()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify_membership_batch;
+    use crate::test_support::sample_instance;
+    use crate::{prove_membership, setup_membership_with_depth, verify_membership};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn batch_rejects_tampered_proof_in_the_middle() {
+        let mut rng = StdRng::seed_from_u64(58);
+        let pk = setup_membership_with_depth(&mut rng, 1).unwrap();
+        let mut batch = Vec::new();
+        for identity in 1..=5u64 {
+            let instance = sample_instance(identity);
+            let proof = prove_membership(&pk, &instance, &mut rng).unwrap();
+            assert!(verify_membership(&pk.vk, &instance.public_inputs, &proof).unwrap());
+            batch.push((instance.public_inputs, proof));
+        }
+        assert!(verify_membership_batch(&pk.vk, &batch).unwrap());
+        assert!(verify_membership_batch(&pk.vk, &[]).unwrap());
+
+        let mut tampered = batch.clone();
+        tampered[2].1.c = batch[3].1.c;
+        assert!(!verify_membership_batch(&pk.vk, &tampered).unwrap());
+
+        let mut swapped_inputs = batch.clone();
+        swapped_inputs[2].0 = batch[1].0.clone();
+        assert!(!verify_membership_batch(&pk.vk, &swapped_inputs).unwrap());
+    }
+
+    #[test]
+    fn one_bad_proof_fails_a_mixed_batch() {
+        let mut rng = StdRng::seed_from_u64(59);
+        let pk = setup_membership_with_depth(&mut rng, 1).unwrap();
+        let other_pk = setup_membership_with_depth(&mut rng, 1).unwrap();
+        let mut good = Vec::new();
+        for identity in 1..=4u64 {
+            let instance = sample_instance(identity);
+            let proof = prove_membership(&pk, &instance, &mut rng).unwrap();
+            good.push((instance.public_inputs, proof));
+        }
+        // A well-formed proof of a true statement, but under a different key.
+        let instance = sample_instance(5);
+        let bad_proof = prove_membership(&other_pk, &instance, &mut rng).unwrap();
+        assert!(!verify_membership(&pk.vk, &instance.public_inputs, &bad_proof).unwrap());
+        let bad = (instance.public_inputs, bad_proof);
+
+        assert!(verify_membership_batch(&pk.vk, &good).unwrap());
+        for position in 0..=good.len() {
+            let mut mixed = good.clone();
+            mixed.insert(position, bad.clone());
+            assert!(!verify_membership_batch(&pk.vk, &mixed).unwrap());
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use membership::diff_vks;
+use membership::io::{read_verifying_key_capped, DEFAULT_MAX_VK_BYTES};
+use std::env;
+
+fn main() {
+    let (a_path, b_path) = match parse_args() {
+        Some(paths) => paths,
+        None => {
+            eprintln!("Usage: diff_vks --a <vk> --b <vk>");
+            std::process::exit(1);
+        }
+    };
+
+    let a = match read_verifying_key_capped(&a_path, DEFAULT_MAX_VK_BYTES) {
+        Ok(vk) => vk,
+        Err(err) => {
+            eprintln!("failed to read {a_path}: {err}");
+            std::process::exit(1);
+        }
+    };
+    let b = match read_verifying_key_capped(&b_path, DEFAULT_MAX_VK_BYTES) {
+        Ok(vk) => vk,
+        Err(err) => {
+            eprintln!("failed to read {b_path}: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    let diff = diff_vks(&a, &b);
+    if diff.is_empty() {
+        println!("verifying keys are identical");
+        std::process::exit(0);
+    }
+    for component in diff {
+        println!("{component:?}");
+    }
+    std::process::exit(2);
+}
+
+fn parse_args() -> Option<(String, String)> {
+    let mut a_path = None;
+    let mut b_path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--a" => a_path = args.next(),
+            "--b" => b_path = args.next(),
+            _ => return None,
+        }
+    }
+    match (a_path, b_path) {
+        (Some(a), Some(b)) => Some((a, b)),
+        _ => None,
+    }
+}
@@ -0,0 +1,62 @@
+use membership::{export_r1cs, matrices_to_text, MembershipSchema, MERKLE_DEPTH};
+use std::env;
+use std::fs;
+
+fn main() {
+    let (schema, depth, out_path) = match parse_args() {
+        Some(args) => args,
+        None => {
+            eprintln!("Usage: dump_r1cs --out <path> [--depth <n>] [--schema <v0|v1|v2>]");
+            std::process::exit(1);
+        }
+    };
+
+    let cs = match export_r1cs(schema, depth) {
+        Ok(cs) => cs,
+        Err(err) => {
+            eprintln!("synthesis failed: {err}");
+            std::process::exit(1);
+        }
+    };
+    let matrices = match cs.to_matrices() {
+        Some(matrices) => matrices,
+        None => {
+            eprintln!("constraint matrices were not constructed");
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(err) = fs::write(&out_path, matrices_to_text(&matrices)) {
+        eprintln!("failed to write r1cs: {err}");
+        std::process::exit(1);
+    }
+    println!(
+        "constraints: {}, instance vars: {}, witness vars: {}",
+        matrices.num_constraints, matrices.num_instance_variables, matrices.num_witness_variables
+    );
+}
+
+fn parse_args() -> Option<(MembershipSchema, usize, String)> {
+    let mut schema = MembershipSchema::V0;
+    let mut depth = MERKLE_DEPTH;
+    let mut out_path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => out_path = args.next(),
+            "--depth" => {
+                depth = args.next()?.parse().ok()?;
+            }
+            "--schema" => {
+                schema = match args.next()?.as_str() {
+                    "v0" => MembershipSchema::V0,
+                    "v1" => MembershipSchema::V1,
+                    "v2" => MembershipSchema::V2,
+                    _ => return None,
+                };
+            }
+            _ => return None,
+        }
+    }
+    out_path.map(|out| (schema, depth, out))
+}
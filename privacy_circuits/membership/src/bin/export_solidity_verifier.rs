@@ -0,0 +1,74 @@
+use membership::io::{read_verifying_key_as, DEFAULT_MAX_VK_BYTES};
+use membership::{solidity_statement, solidity_verifier, SOLIDITY_STATEMENTS};
+use std::env;
+use std::fs;
+
+fn main() {
+    let args = match parse_args() {
+        Some(args) => args,
+        None => {
+            let names: Vec<&str> = SOLIDITY_STATEMENTS.iter().map(|s| s.name).collect();
+            eprintln!(
+                "Usage: export_solidity_verifier --vk <path> --out <path> [--statement <{}>] [--compressed]",
+                names.join("|")
+            );
+            std::process::exit(1);
+        }
+    };
+
+    let vk = match read_verifying_key_as(&args.vk_path, DEFAULT_MAX_VK_BYTES, args.compressed) {
+        Ok(vk) => vk,
+        Err(err) => {
+            eprintln!("failed to read verifying key: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    let source = match solidity_verifier(&vk, &args.statement) {
+        Ok(source) => source,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    };
+    if let Err(err) = fs::write(&args.out_path, source) {
+        eprintln!("failed to write {}: {err}", args.out_path);
+        std::process::exit(1);
+    }
+    println!(
+        "wrote {} verifier to {} (inputs: {})",
+        args.statement.name,
+        args.out_path,
+        args.statement.inputs.join(", ")
+    );
+}
+
+struct Args {
+    vk_path: String,
+    out_path: String,
+    statement: membership::SolidityStatement,
+    compressed: bool,
+}
+
+fn parse_args() -> Option<Args> {
+    let mut vk_path = None;
+    let mut out_path = None;
+    let mut statement = solidity_statement("membership-v2")?;
+    let mut compressed = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--vk" => vk_path = args.next(),
+            "--out" => out_path = args.next(),
+            "--statement" => statement = solidity_statement(&args.next()?)?,
+            "--compressed" => compressed = true,
+            _ => return None,
+        }
+    }
+    Some(Args {
+        vk_path: vk_path?,
+        out_path: out_path?,
+        statement,
+        compressed,
+    })
+}
@@ -1,11 +1,12 @@
 use ark_bn254::Fr;
 use ark_ff::PrimeField;
+use membership::io::{parse_field_arg, read_path_file, save_witness, to_json_wrapped};
 use membership::{
-    commitment_hash, fr_to_fixed_bytes, node_hash, poseidon_hash_leaf,
-    poseidon_hash_leaf_v2, poseidon_params, MembershipInstanceBytes,
+    commitment_hash, field_inputs_to_json, fr_to_array, fr_to_fixed_bytes,
+    membership_v2_field_inputs, node_hash, poseidon_hash_leaf, poseidon_hash_leaf_v2, poseidon_params, MembershipInstanceBytes,
     MembershipInstanceV1Bytes, MembershipInstanceV2Bytes, MembershipPublicInputsBytes,
     MembershipPublicInputsV1Bytes, MembershipPublicInputsV2Bytes, MembershipWitnessBytes,
-    MembershipWitnessV1Bytes, MembershipWitnessV2Bytes, MerklePathNodeBytes,
+    MembershipWitnessV1Bytes, MembershipWitnessV2Bytes, MerklePathNodeBytes, MerkleTree,
     MEMBERSHIP_INSTANCE_VERSION_V1, MEMBERSHIP_INSTANCE_VERSION_V2,
     MEMBERSHIP_STATEMENT_TYPE, MEMBERSHIP_STATEMENT_VERSION_V2, MEMBERSHIP_V2_DEFAULT_CTX_HASH,
     MEMBERSHIP_V2_DOMAIN_SEP, MERKLE_DEPTH,
@@ -23,28 +24,86 @@ fn main() {
         Err(err) => {
             eprintln!("{err}");
             eprintln!(
-                "Usage: make_membership_instance [--schema <v0|v1|v2>] [--depth <n>] [--out-instance <path>] [--out-public-inputs <path>]"
+                "Usage: make_membership_instance [--schema <v0|v1|v2>] [--depth <n>] [--path-file <csv|tsv>] [--out-instance <path>] [--out-public-inputs <path>] [--emit-field-inputs <path>] [--save-witness <path>] [--format <bin|json>] [--identity <0xhex|base64>] [--blinding <0xhex|base64>] [--ctx-hash <0xhex|base64>]"
             );
             std::process::exit(1);
         }
     };
 
+    let merkle_path = match args.path_file.as_deref().map(|path| read_path_file(path, args.depth)) {
+        Some(Ok(path)) => Some(path),
+        Some(Err(err)) => {
+            eprintln!("invalid --path-file: {err}");
+            std::process::exit(1);
+        }
+        None => None,
+    };
+
     match args.schema {
         Schema::V0 => {
-            let (instance_bytes, public_inputs_bytes) = build_legacy_instance();
-            write_outputs(&args.instance_out, &args.public_inputs_out, &instance_bytes, &public_inputs_bytes);
+            let (instance_bytes, public_inputs_bytes) =
+                build_legacy_instance(args.identity, args.blinding);
+            write_outputs(&args, "v0", Some(MERKLE_DEPTH), &instance_bytes, &public_inputs_bytes);
+            let witness = &instance_bytes.witness;
+            if let Err(err) = save_witness(
+                args.save_witness.as_deref(),
+                &[
+                    ("identity_scalar", &witness.identity_scalar),
+                    ("blinding", &witness.blinding),
+                ],
+            ) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
         }
         Schema::V1 => {
-            let (instance_bytes, public_inputs_bytes) = build_v1_instance(args.depth);
-            write_outputs(&args.instance_out, &args.public_inputs_out, &instance_bytes, &public_inputs_bytes);
+            let (instance_bytes, public_inputs_bytes) =
+                build_v1_instance(&args, merkle_path.as_deref());
+            write_outputs(&args, "v1", Some(args.depth), &instance_bytes, &public_inputs_bytes);
+            let witness = &instance_bytes.witness;
+            if let Err(err) = save_witness(
+                args.save_witness.as_deref(),
+                &[
+                    ("identity_scalar", &witness.identity_scalar),
+                    ("blinding", &witness.blinding),
+                ],
+            ) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
         }
         Schema::V2 => {
-            let (instance_bytes, public_inputs_bytes) = build_v2_instance(args.depth);
-            write_outputs(&args.instance_out, &args.public_inputs_out, &instance_bytes, &public_inputs_bytes);
+            let (instance_bytes, public_inputs_bytes) =
+                build_v2_instance(&args, merkle_path.as_deref());
+            write_outputs(&args, "v2", Some(args.depth), &instance_bytes, &public_inputs_bytes);
+            let witness = &instance_bytes.witness;
+            if let Err(err) = save_witness(
+                args.save_witness.as_deref(),
+                &[
+                    ("identity_scalar", &witness.identity_scalar),
+                    ("blinding", &witness.blinding),
+                    ("ctx_hash", &public_inputs_bytes.ctx_hash),
+                ],
+            ) {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+            if let Some(path) = &args.field_inputs_out {
+                if let Err(err) = write_field_inputs(path, public_inputs_bytes) {
+                    eprintln!("failed to write field inputs: {err}");
+                    std::process::exit(1);
+                }
+            }
         }
     }
 }
 
+#[derive(Clone, Copy)]
+enum OutputFormat {
+    Bin,
+    Json,
+}
+
 #[derive(Clone, Copy)]
 enum Schema {
     V0,
@@ -55,15 +114,29 @@ enum Schema {
 struct Args {
     schema: Schema,
     depth: usize,
+    path_file: Option<String>,
     instance_out: String,
     public_inputs_out: String,
+    field_inputs_out: Option<String>,
+    save_witness: Option<String>,
+    format: OutputFormat,
+    identity: Fr,
+    blinding: Fr,
+    ctx_hash: [u8; 32],
 }
 
 fn parse_args() -> Result<Args, String> {
     let mut schema = Schema::V0;
     let mut depth = DEFAULT_V1_DEPTH;
+    let mut path_file = None;
     let mut instance_out = "instance.bin".to_string();
     let mut public_inputs_out = "public_inputs.bin".to_string();
+    let mut field_inputs_out = None;
+    let mut save_witness = None;
+    let mut format = OutputFormat::Bin;
+    let mut identity = Fr::from(1u64);
+    let mut blinding = Fr::from(2u64);
+    let mut ctx_hash = None;
     let mut args = env::args().skip(1);
 
     while let Some(arg) = args.next() {
@@ -83,6 +156,12 @@ fn parse_args() -> Result<Args, String> {
                     .parse()
                     .map_err(|_| "invalid --depth value".to_string())?;
             }
+            "--path-file" => {
+                path_file = Some(
+                    args.next()
+                        .ok_or_else(|| "missing value for --path-file".to_string())?,
+                );
+            }
             "--out-instance" => {
                 instance_out = args
                     .next()
@@ -93,11 +172,60 @@ fn parse_args() -> Result<Args, String> {
                     .next()
                     .ok_or_else(|| "missing value for --out-public-inputs".to_string())?;
             }
+            "--emit-field-inputs" => {
+                field_inputs_out = Some(
+                    args.next()
+                        .ok_or_else(|| "missing value for --emit-field-inputs".to_string())?,
+                );
+            }
+            "--save-witness" => {
+                save_witness = Some(
+                    args.next()
+                        .ok_or_else(|| "missing value for --save-witness".to_string())?,
+                );
+            }
+            "--format" => {
+                format = match args.next().as_deref() {
+                    Some("bin") => OutputFormat::Bin,
+                    Some("json") => OutputFormat::Json,
+                    _ => return Err("invalid format (expected bin or json)".to_string()),
+                };
+            }
+            "--identity" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "missing value for --identity".to_string())?;
+                identity = Fr::from_be_bytes_mod_order(&parse_field_arg("--identity", &value)?);
+            }
+            "--blinding" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "missing value for --blinding".to_string())?;
+                blinding = Fr::from_be_bytes_mod_order(&parse_field_arg("--blinding", &value)?);
+            }
+            "--ctx-hash" => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| "missing value for --ctx-hash".to_string())?;
+                ctx_hash = Some(parse_field_arg("--ctx-hash", &value)?);
+            }
             "--help" | "-h" => return Err("help requested".to_string()),
             _ => return Err(format!("unknown argument: {arg}")),
         }
     }
 
+    if field_inputs_out.is_some() && !matches!(schema, Schema::V2) {
+        return Err("--emit-field-inputs requires --schema v2".to_string());
+    }
+
+    if ctx_hash.is_some() && !matches!(schema, Schema::V2) {
+        return Err("--ctx-hash requires --schema v2".to_string());
+    }
+
+    if path_file.is_some() && matches!(schema, Schema::V0) {
+        return Err("--path-file requires --schema v1 or v2".to_string());
+    }
+
     if matches!(schema, Schema::V0) {
         depth = MERKLE_DEPTH;
     } else if depth == 0 {
@@ -107,21 +235,37 @@ fn parse_args() -> Result<Args, String> {
     Ok(Args {
         schema,
         depth,
+        path_file,
         instance_out,
         public_inputs_out,
+        field_inputs_out,
+        save_witness,
+        format,
+        identity,
+        blinding,
+        ctx_hash: ctx_hash.unwrap_or(MEMBERSHIP_V2_DEFAULT_CTX_HASH),
     })
 }
 
-fn build_legacy_instance() -> (MembershipInstanceBytes, MembershipPublicInputsBytes) {
+fn build_legacy_instance(
+    identity: Fr,
+    blinding: Fr,
+) -> (MembershipInstanceBytes, MembershipPublicInputsBytes) {
     let params = poseidon_params::<Fr>();
-    let identity = Fr::from(1u64);
-    let blinding = Fr::from(2u64);
     let commitment = commitment_hash(&params, identity, blinding);
-    let leaf = poseidon_hash_leaf(&params, commitment);
-
     let sibling_commitment = commitment_hash(&params, Fr::from(3u64), Fr::from(4u64));
-    let sibling = poseidon_hash_leaf(&params, sibling_commitment);
-    let root = node_hash(&params, leaf, sibling);
+    let tree = MerkleTree::new(vec![commitment, sibling_commitment], &params)
+        .expect("two leaves always fit a depth-1 tree");
+    let root = tree.root();
+    let merkle_path = tree
+        .generate_path(0)
+        .expect("leaf 0 is present")
+        .into_iter()
+        .map(|(sibling, is_left)| MerklePathNodeBytes {
+            sibling: fr_to_fixed_bytes(&sibling),
+            is_left,
+        })
+        .collect();
 
     let instance_bytes = MembershipInstanceBytes {
         public_inputs: MembershipPublicInputsBytes {
@@ -131,10 +275,7 @@ fn build_legacy_instance() -> (MembershipInstanceBytes, MembershipPublicInputsBy
         witness: MembershipWitnessBytes {
             identity_scalar: fr_to_fixed_bytes(&identity),
             blinding: fr_to_fixed_bytes(&blinding),
-            merkle_path: vec![MerklePathNodeBytes {
-                sibling: fr_to_fixed_bytes(&sibling),
-                is_left: false,
-            }],
+            merkle_path,
         },
     };
 
@@ -146,10 +287,14 @@ fn build_legacy_instance() -> (MembershipInstanceBytes, MembershipPublicInputsBy
     (instance_bytes, public_inputs_bytes)
 }
 
-fn build_v1_instance(depth: usize) -> (MembershipInstanceV1Bytes, MembershipPublicInputsV1Bytes) {
+// Without `merkle_path` the siblings are synthetic; with it they come from
+// --path-file, already checked to hold `depth` entries.
+fn build_v1_instance(
+    args: &Args,
+    merkle_path: Option<&[(Fr, bool)]>,
+) -> (MembershipInstanceV1Bytes, MembershipPublicInputsV1Bytes) {
     let params = poseidon_params::<Fr>();
-    let identity = Fr::from(1u64);
-    let blinding = Fr::from(2u64);
+    let (depth, identity, blinding) = (args.depth, args.identity, args.blinding);
     let commitment = commitment_hash(&params, identity, blinding);
     let mut current = poseidon_hash_leaf(&params, commitment);
 
@@ -157,9 +302,10 @@ fn build_v1_instance(depth: usize) -> (MembershipInstanceV1Bytes, MembershipPubl
     let mut directions = Vec::with_capacity(depth);
 
     for idx in 0..depth {
-        let sibling_seed = Fr::from((idx as u64) + 10);
-        let sibling = node_hash(&params, commitment, sibling_seed);
-        let is_left = idx % 2 == 0;
+        let (sibling, is_left) = match merkle_path {
+            Some(path) => path[idx],
+            None => (node_hash(&params, commitment, Fr::from((idx as u64) + 10)), idx % 2 == 0),
+        };
         let (left, right) = if is_left {
             (sibling, current)
         } else {
@@ -194,22 +340,25 @@ fn build_v1_instance(depth: usize) -> (MembershipInstanceV1Bytes, MembershipPubl
     (instance, public_inputs)
 }
 
-fn build_v2_instance(depth: usize) -> (MembershipInstanceV2Bytes, MembershipPublicInputsV2Bytes) {
+fn build_v2_instance(
+    args: &Args,
+    merkle_path: Option<&[(Fr, bool)]>,
+) -> (MembershipInstanceV2Bytes, MembershipPublicInputsV2Bytes) {
     let params = poseidon_params::<Fr>();
-    let identity = Fr::from(1u64);
-    let blinding = Fr::from(2u64);
+    let (depth, identity, blinding) = (args.depth, args.identity, args.blinding);
     let commitment = commitment_hash(&params, identity, blinding);
     let domain_sep = Fr::from_be_bytes_mod_order(&MEMBERSHIP_V2_DOMAIN_SEP);
-    let ctx_hash = Fr::from_be_bytes_mod_order(&MEMBERSHIP_V2_DEFAULT_CTX_HASH);
+    let ctx_hash = Fr::from_be_bytes_mod_order(&args.ctx_hash);
     let mut current = poseidon_hash_leaf_v2(&params, domain_sep, ctx_hash, commitment);
 
     let mut siblings = Vec::with_capacity(depth);
     let mut directions = Vec::with_capacity(depth);
 
     for idx in 0..depth {
-        let sibling_seed = Fr::from((idx as u64) + 20);
-        let sibling = node_hash(&params, commitment, sibling_seed);
-        let is_left = idx % 2 == 0;
+        let (sibling, is_left) = match merkle_path {
+            Some(path) => path[idx],
+            None => (node_hash(&params, commitment, Fr::from((idx as u64) + 20)), idx % 2 == 0),
+        };
         let (left, right) = if is_left {
             (sibling, current)
         } else {
@@ -228,7 +377,8 @@ fn build_v2_instance(depth: usize) -> (MembershipInstanceV2Bytes, MembershipPubl
         root: fr_to_fixed_bytes(&current).try_into().unwrap(),
         commitment: fr_to_fixed_bytes(&commitment).try_into().unwrap(),
         domain_sep: MEMBERSHIP_V2_DOMAIN_SEP,
-        ctx_hash: MEMBERSHIP_V2_DEFAULT_CTX_HASH,
+        // --ctx-hash may be any 32 bytes; the instance carries the reduced element.
+        ctx_hash: fr_to_array(&ctx_hash),
     };
     let witness = MembershipWitnessV2Bytes {
         schema_version: MEMBERSHIP_INSTANCE_VERSION_V2,
@@ -249,17 +399,25 @@ fn build_v2_instance(depth: usize) -> (MembershipInstanceV2Bytes, MembershipPubl
 }
 
 fn write_outputs<T: Serialize, U: Serialize>(
-    instance_out: &str,
-    public_inputs_out: &str,
+    args: &Args,
+    schema: &str,
+    depth: Option<usize>,
     instance: &T,
     public_inputs: &U,
 ) {
-    if let Err(err) = write_bincode(instance_out, instance) {
+    // Public inputs stay bincode in both formats; the verifiers read that form.
+    let written = match args.format {
+        OutputFormat::Bin => write_bincode(&args.instance_out, instance),
+        OutputFormat::Json => to_json_wrapped(schema, depth, public_inputs, instance).and_then(
+            |json| std::fs::write(&args.instance_out, json).map_err(|err| err.to_string()),
+        ),
+    };
+    if let Err(err) = written {
         eprintln!("failed to write instance: {err}");
         std::process::exit(1);
     }
 
-    if let Err(err) = write_bincode(public_inputs_out, public_inputs) {
+    if let Err(err) = write_bincode(&args.public_inputs_out, public_inputs) {
         eprintln!("failed to write public inputs: {err}");
         std::process::exit(1);
     }
@@ -271,3 +429,9 @@ fn write_bincode<T: Serialize>(path: &str, value: &T) -> Result<(), String> {
     bincode::serialize_into(&mut writer, value).map_err(|err| err.to_string())?;
     writer.flush().map_err(|err| err.to_string())
 }
+
+fn write_field_inputs(path: &str, public_inputs: MembershipPublicInputsV2Bytes) -> Result<(), String> {
+    let (inputs, _) = public_inputs.into_public_inputs_with_depth()?;
+    let json = field_inputs_to_json(&membership_v2_field_inputs(&inputs));
+    std::fs::write(path, json).map_err(|err| err.to_string())
+}
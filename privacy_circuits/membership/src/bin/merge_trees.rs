@@ -0,0 +1,83 @@
+use ark_bn254::Fr;
+use ark_sponge::poseidon::PoseidonConfig;
+use membership::{merge_trees, poseidon_params, PoseidonMerkleTree, TreeSnapshotBytes};
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+
+fn main() {
+    let (a_path, b_path, out_path) = match parse_args() {
+        Some(paths) => paths,
+        None => {
+            eprintln!("Usage: merge_trees --a <snapshot> --b <snapshot> --out <snapshot>");
+            std::process::exit(1);
+        }
+    };
+
+    let params = poseidon_params::<Fr>();
+    let a = match read_tree(&a_path, &params) {
+        Ok(tree) => tree,
+        Err(err) => {
+            eprintln!("failed to read {a_path}: {err}");
+            std::process::exit(1);
+        }
+    };
+    let b = match read_tree(&b_path, &params) {
+        Ok(tree) => tree,
+        Err(err) => {
+            eprintln!("failed to read {b_path}: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    let merged = match merge_trees(&a, &b) {
+        Ok(tree) => tree,
+        Err(err) => {
+            eprintln!("merge failed: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    if let Err(err) = write_snapshot(&out_path, &merged.to_snapshot()) {
+        eprintln!("failed to write merged tree: {err}");
+        std::process::exit(1);
+    }
+    println!("leaves: {}", merged.len());
+    println!("root: {}", merged.root());
+}
+
+fn parse_args() -> Option<(String, String, String)> {
+    let mut a_path = None;
+    let mut b_path = None;
+    let mut out_path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--a" => a_path = args.next(),
+            "--b" => b_path = args.next(),
+            "--out" => out_path = args.next(),
+            _ => return None,
+        }
+    }
+    match (a_path, b_path, out_path) {
+        (Some(a), Some(b), Some(out)) => Some((a, b, out)),
+        _ => None,
+    }
+}
+
+fn read_tree(
+    path: &str,
+    params: &PoseidonConfig<Fr>,
+) -> Result<PoseidonMerkleTree, String> {
+    let data = fs::read(path).map_err(|err| err.to_string())?;
+    let snapshot =
+        bincode::deserialize::<TreeSnapshotBytes>(&data).map_err(|err| err.to_string())?;
+    PoseidonMerkleTree::from_snapshot(&snapshot, params)
+}
+
+fn write_snapshot(path: &str, snapshot: &TreeSnapshotBytes) -> Result<(), String> {
+    let file = File::create(path).map_err(|err| err.to_string())?;
+    let mut writer = BufWriter::new(file);
+    bincode::serialize_into(&mut writer, snapshot).map_err(|err| err.to_string())?;
+    writer.flush().map_err(|err| err.to_string())
+}
@@ -1,29 +1,40 @@
-use ark_bn254::Bn254;
-use ark_groth16::ProvingKey;
-use ark_serialize::CanonicalDeserialize;
-use ark_serialize::CanonicalSerialize;
 use ark_std::rand::rngs::OsRng;
+use ark_bn254::Bn254;
+use ark_groth16::{Proof, VerifyingKey};
+use membership::io::{read_proving_key_as, write_canonical, DEFAULT_MAX_PK_BYTES};
 use membership::{
-    prove_membership, prove_membership_v2, MembershipInstanceBytes,
-    MembershipInstanceV1Bytes, MembershipInstanceV2Bytes,
+    debug_check_membership, debug_check_membership_v2, field_to_domain_label, prove_membership,
+    prove_membership_v2, write_bundle, MembershipInstanceBytes,
+    MembershipInstanceV1Bytes, MembershipInstanceV2Bytes, ProofBundle,
 };
+use serde::Serialize;
 use std::env;
 use std::fs;
-use std::fs::File;
-use std::io::{BufReader, BufWriter, Write};
 
 fn main() {
-    let (pk_path, instance_path, proof_out, schema) = match parse_args() {
-        Some(paths) => paths,
+    let args = match parse_args() {
+        Some(args) => args,
         None => {
             eprintln!(
-                "Usage: prove_membership --pk <path> --instance <path> --proof-out <path> [--schema <v0|v1|v2>]"
+                "Usage: prove_membership --pk <path> --instance <path> --proof-out <path> [--bundle-out <path>] [--max-pk-bytes <n>] [--schema <v0|v1|v2>] [--debug] [--compressed]"
             );
             std::process::exit(1);
         }
     };
 
-    let pk = match read_proving_key(&pk_path) {
+    let Args {
+        pk_path,
+        instance_path,
+        proof_out,
+        bundle_out,
+        schema,
+        max_pk_bytes,
+        debug,
+        compressed,
+    } = args;
+
+    // The proof is written in the same encoding the proving key was read in.
+    let pk = match read_proving_key_as(&pk_path, max_pk_bytes, compressed) {
         Ok(pk) => pk,
         Err(err) => {
             eprintln!("failed to read proving key: {err}");
@@ -31,7 +42,7 @@ fn main() {
         }
     };
 
-    let instance = match schema {
+    let (instance, statement, public_inputs) = match schema {
         Schema::V0 => {
             let instance_bytes = match read_instance_v0(&instance_path) {
                 Ok(instance) => instance,
@@ -41,8 +52,9 @@ fn main() {
                 }
             };
 
+            let public_inputs = encode_public_inputs(&instance_bytes.public_inputs);
             match instance_bytes.into_instance() {
-                Ok(instance) => instance,
+                Ok(instance) => (instance, "membership-v0", public_inputs),
                 Err(err) => {
                     eprintln!("invalid instance: {err}");
                     std::process::exit(1);
@@ -58,8 +70,9 @@ fn main() {
                 }
             };
 
+            let public_inputs = encode_public_inputs(&instance_bytes.public_inputs);
             match instance_bytes.into_instance_with_depth() {
-                Ok((instance, _depth)) => instance,
+                Ok((instance, _depth)) => (instance, "membership-v1", public_inputs),
                 Err(err) => {
                     eprintln!("invalid instance: {err}");
                     std::process::exit(1);
@@ -75,6 +88,7 @@ fn main() {
                 }
             };
 
+            let public_inputs = encode_public_inputs(&instance_bytes.public_inputs);
             let instance = match instance_bytes.into_instance_with_depth() {
                 Ok((instance, _depth)) => instance,
                 Err(err) => {
@@ -83,6 +97,21 @@ fn main() {
                 }
             };
 
+            if debug {
+                eprintln!(
+                    "domain_sep: {}",
+                    field_to_domain_label(&instance.public_inputs.domain_sep)
+                );
+                eprintln!(
+                    "ctx_hash: {}",
+                    field_to_domain_label(&instance.public_inputs.ctx_hash)
+                );
+                if let Err(err) = debug_check_membership_v2(&instance) {
+                    eprintln!("witness check failed: {err}");
+                    std::process::exit(1);
+                }
+            }
+
             let mut rng = OsRng;
             let proof = match prove_membership_v2(&pk, &instance, &mut rng) {
                 Ok(proof) => proof,
@@ -92,14 +121,24 @@ fn main() {
                 }
             };
 
-            if let Err(err) = write_serialized(&proof_out, &proof) {
+            if let Err(err) = write_canonical(&proof_out, &proof, compressed) {
                 eprintln!("failed to write proof: {err}");
                 std::process::exit(1);
             }
+            if let Some(path) = bundle_out {
+                write_bundle_out(&path, "membership-v2", public_inputs, &proof, &pk.vk, compressed);
+            }
             return;
         }
     };
 
+    if debug {
+        if let Err(err) = debug_check_membership(&instance) {
+            eprintln!("witness check failed: {err}");
+            std::process::exit(1);
+        }
+    }
+
     let mut rng = OsRng;
     let proof = match prove_membership(&pk, &instance, &mut rng) {
         Ok(proof) => proof,
@@ -109,23 +148,73 @@ fn main() {
         }
     };
 
-    if let Err(err) = write_serialized(&proof_out, &proof) {
+    if let Err(err) = write_canonical(&proof_out, &proof, compressed) {
         eprintln!("failed to write proof: {err}");
         std::process::exit(1);
     }
+    if let Some(path) = bundle_out {
+        write_bundle_out(&path, statement, public_inputs, &proof, &pk.vk, compressed);
+    }
+}
+
+// The bundle carries the same bincode public-inputs file make_membership_instance
+// writes, so verify_membership --bundle parses it with the --public-inputs code.
+fn encode_public_inputs<T: Serialize>(public_inputs: &T) -> Vec<u8> {
+    match bincode::serialize(public_inputs) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("failed to encode public inputs: {err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn write_bundle_out(
+    path: &str,
+    statement: &str,
+    public_inputs: Vec<u8>,
+    proof: &Proof<Bn254>,
+    vk: &VerifyingKey<Bn254>,
+    compressed: bool,
+) {
+    let result = ProofBundle::new(statement, public_inputs, proof, vk, compressed)
+        .and_then(|bundle| write_bundle(path, &bundle));
+    if let Err(err) = result {
+        eprintln!("failed to write bundle: {err}");
+        std::process::exit(1);
+    }
+}
+
+struct Args {
+    pk_path: String,
+    instance_path: String,
+    proof_out: String,
+    bundle_out: Option<String>,
+    schema: Schema,
+    max_pk_bytes: u64,
+    debug: bool,
+    compressed: bool,
 }
 
-fn parse_args() -> Option<(String, String, String, Schema)> {
+fn parse_args() -> Option<Args> {
     let mut pk_path = None;
     let mut instance_path = None;
     let mut proof_out = None;
+    let mut bundle_out = None;
+    let mut max_pk_bytes = DEFAULT_MAX_PK_BYTES;
     let mut schema = Schema::V0;
+    let mut debug = false;
+    let mut compressed = false;
     let mut args = env::args().skip(1);
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--pk" => pk_path = args.next(),
             "--instance" => instance_path = args.next(),
             "--proof-out" => proof_out = args.next(),
+            "--bundle-out" => bundle_out = Some(args.next()?),
+            "--max-pk-bytes" => max_pk_bytes = args.next()?.parse().ok()?,
+            "--debug" => debug = true,
+            "--compressed" => compressed = true,
             "--schema" => {
                 schema = match args.next()?.as_str() {
                     "v0" => Schema::V0,
@@ -137,10 +226,16 @@ fn parse_args() -> Option<(String, String, String, Schema)> {
             _ => return None,
         }
     }
-    match (pk_path, instance_path, proof_out) {
-        (Some(pk), Some(instance), Some(proof)) => Some((pk, instance, proof, schema)),
-        _ => None,
-    }
+    Some(Args {
+        pk_path: pk_path?,
+        instance_path: instance_path?,
+        proof_out: proof_out?,
+        bundle_out,
+        schema,
+        max_pk_bytes,
+        debug,
+        compressed,
+    })
 }
 
 #[derive(Clone, Copy)]
@@ -150,12 +245,6 @@ enum Schema {
     V2,
 }
 
-fn read_proving_key(path: &str) -> Result<ProvingKey<Bn254>, String> {
-    let file = File::open(path).map_err(|err| err.to_string())?;
-    let mut reader = BufReader::new(file);
-    ProvingKey::<Bn254>::deserialize_uncompressed(&mut reader).map_err(|err| err.to_string())
-}
-
 fn read_instance_v0(path: &str) -> Result<MembershipInstanceBytes, String> {
     let data = fs::read(path).map_err(|err| err.to_string())?;
     bincode::deserialize::<MembershipInstanceBytes>(&data).map_err(|err| err.to_string())
@@ -170,12 +259,3 @@ fn read_instance_v2(path: &str) -> Result<MembershipInstanceV2Bytes, String> {
     let data = fs::read(path).map_err(|err| err.to_string())?;
     bincode::deserialize::<MembershipInstanceV2Bytes>(&data).map_err(|err| err.to_string())
 }
-
-fn write_serialized<T: CanonicalSerialize>(path: &str, value: &T) -> Result<(), String> {
-    let file = File::create(path).map_err(|err| err.to_string())?;
-    let mut writer = BufWriter::new(file);
-    value
-        .serialize_uncompressed(&mut writer)
-        .map_err(|err| err.to_string())?;
-    writer.flush().map_err(|err| err.to_string())
-}
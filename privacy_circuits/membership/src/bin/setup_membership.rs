@@ -1,25 +1,44 @@
-use ark_serialize::CanonicalSerialize;
 use ark_std::rand::rngs::OsRng;
-use membership::{setup_membership_with_depth, setup_membership_with_depth_v2, MERKLE_DEPTH};
+use membership::io::{key_output_paths, parse_seed_hex, write_canonical, write_params_spec};
+use membership::PoseidonParamsSpec;
+use membership::{
+    check_constraint_limit, setup_membership_with_depth, setup_membership_with_depth_v2,
+    setup_membership_with_seed, setup_membership_with_seed_v2, MembershipSchema,
+    DEFAULT_MAX_CONSTRAINTS, MERKLE_DEPTH,
+};
 use std::env;
-use std::fs::File;
-use std::io::{BufWriter, Write};
 
 fn main() {
-    let (pk_out, vk_out, depth, schema) = match parse_args() {
-        Some(paths) => paths,
+    let args = match parse_args() {
+        Some(args) => args,
         None => {
             eprintln!(
-                "Usage: setup_membership --pk-out <path> --vk-out <path> [--depth <n>] [--schema <v0|v1|v2>]"
+                "Usage: setup_membership (--pk-out <path> --vk-out <path> | --out-dir <dir> --name <prefix>) [--depth <n>] [--schema <v0|v1|v2>] [--max-constraints <n>] [--compressed] [--seed <hex32>]"
             );
             std::process::exit(1);
         }
     };
+    let Args { pk_out, vk_out, depth, schema, max_constraints, compressed, seed } = args;
 
-    let mut rng = OsRng;
-    let pk = match schema {
-        Schema::V2 => setup_membership_with_depth_v2(&mut rng, depth),
-        Schema::V0 | Schema::V1 => setup_membership_with_depth(&mut rng, depth),
+    // V0 keys come from the V1 circuit at the requested depth.
+    let circuit_schema = match schema {
+        Schema::V0 | Schema::V1 => MembershipSchema::V1,
+        Schema::V2 => MembershipSchema::V2,
+    };
+    if let Err(err) = check_constraint_limit(circuit_schema, depth, max_constraints) {
+        eprintln!("setup rejected: {err}");
+        std::process::exit(1);
+    }
+
+    if seed.is_some() {
+        // The seed recreates the setup trapdoor; seeded keys are for tests and CI.
+        eprintln!("warning: anyone with the --seed value can regenerate these keys; do not deploy");
+    }
+    let pk = match (schema, seed) {
+        (Schema::V2, Some(seed)) => setup_membership_with_seed_v2(seed, depth),
+        (Schema::V2, None) => setup_membership_with_depth_v2(&mut OsRng, depth),
+        (Schema::V0 | Schema::V1, Some(seed)) => setup_membership_with_seed(seed, depth),
+        (Schema::V0 | Schema::V1, None) => setup_membership_with_depth(&mut OsRng, depth),
     };
     let pk = match pk {
         Ok(pk) => pk,
@@ -30,29 +49,61 @@ fn main() {
     };
     let vk = pk.vk.clone();
 
-    if let Err(err) = write_serialized(&pk_out, &pk) {
+    if let Err(err) = write_canonical(&pk_out, &pk, compressed) {
         eprintln!("failed to write proving key: {err}");
         std::process::exit(1);
     }
-    if let Err(err) = write_serialized(&vk_out, &vk) {
+    if let Err(err) = write_canonical(&vk_out, &vk, compressed) {
         eprintln!("failed to write verifying key: {err}");
         std::process::exit(1);
     }
+    if let Err(err) = write_params_spec(&vk_out, &PoseidonParamsSpec::DEFAULT_SPEC) {
+        eprintln!("failed to write Poseidon parameter spec: {err}");
+        std::process::exit(1);
+    }
 }
 
-fn parse_args() -> Option<(String, String, usize, Schema)> {
+struct Args {
+    pk_out: String,
+    vk_out: String,
+    depth: usize,
+    schema: Schema,
+    max_constraints: usize,
+    compressed: bool,
+    seed: Option<[u8; 32]>,
+}
+
+fn parse_args() -> Option<Args> {
     let mut pk_out = None;
     let mut vk_out = None;
+    let mut out_dir = None;
+    let mut name = None;
     let mut depth = MERKLE_DEPTH;
     let mut schema = Schema::V0;
+    let mut max_constraints = DEFAULT_MAX_CONSTRAINTS;
+    let mut compressed = false;
+    let mut seed = None;
     let mut args = env::args().skip(1);
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--pk-out" => pk_out = args.next(),
             "--vk-out" => vk_out = args.next(),
+            "--out-dir" => out_dir = args.next(),
+            "--name" => name = args.next(),
+            "--compressed" => compressed = true,
+            "--seed" => match parse_seed_hex(&args.next()?) {
+                Ok(bytes) => seed = Some(bytes),
+                Err(err) => {
+                    eprintln!("{err}");
+                    std::process::exit(1);
+                }
+            },
             "--depth" => {
                 depth = args.next()?.parse().ok()?;
             }
+            "--max-constraints" => {
+                max_constraints = args.next()?.parse().ok()?;
+            }
             "--schema" => {
                 schema = match args.next()?.as_str() {
                     "v0" => Schema::V0,
@@ -64,10 +115,18 @@ fn parse_args() -> Option<(String, String, usize, Schema)> {
             _ => return None,
         }
     }
-    match (pk_out, vk_out) {
-        (Some(pk), Some(vk)) => Some((pk, vk, depth, schema)),
-        _ => None,
-    }
+    let (pk_out, vk_out) = match (pk_out, vk_out, out_dir, name) {
+        (Some(pk), Some(vk), None, None) => (pk, vk),
+        (None, None, Some(dir), Some(name)) => match key_output_paths(&dir, &name) {
+            Ok(paths) => paths,
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        },
+        _ => return None,
+    };
+    Some(Args { pk_out, vk_out, depth, schema, max_constraints, compressed, seed })
 }
 
 #[derive(Clone, Copy)]
@@ -76,12 +135,3 @@ enum Schema {
     V1,
     V2,
 }
-
-fn write_serialized<T: CanonicalSerialize>(path: &str, value: &T) -> Result<(), String> {
-    let file = File::create(path).map_err(|err| err.to_string())?;
-    let mut writer = BufWriter::new(file);
-    value
-        .serialize_uncompressed(&mut writer)
-        .map_err(|err| err.to_string())?;
-    writer.flush().map_err(|err| err.to_string())
-}
@@ -1,133 +1,200 @@
 use ark_bn254::Bn254;
 use ark_groth16::{Proof, VerifyingKey};
-use ark_serialize::CanonicalDeserialize;
+use membership::io::{
+    check_params_spec, decode_public_inputs, read_public_inputs_then_proof,
+    read_verifying_key_auto, DEFAULT_MAX_VK_BYTES,
+};
 use membership::{
-    verify_membership, verify_membership_v2, MembershipPublicInputsBytes,
-    MembershipPublicInputsV1Bytes, MembershipPublicInputsV2Bytes,
+    read_bundle, verify_membership, verify_membership_v2, vk_fingerprint_hex,
+    MembershipPublicInputs, MembershipPublicInputsBytes, MembershipPublicInputsV1Bytes,
+    MembershipPublicInputsV2, MembershipPublicInputsV2Bytes, PoseidonParamsSpec,
+    DEFAULT_MAX_BUNDLE_BYTES,
 };
 use std::env;
 use std::fs;
-use std::fs::File;
-use std::io::BufReader;
+use std::io::{self, Write};
+use std::path::Path;
 
 fn main() {
-    let (vk_path, inputs_path, proof_path, schema) = match parse_args() {
-        Some(paths) => paths,
+    let args = match parse_args() {
+        Some(args) => args,
         None => {
             eprintln!(
-                "Usage: verify_membership --vk <path> --public-inputs <path> --proof <path> [--schema <v0|v1|v2>]"
+                "Usage: verify_membership --vk <path> (--public-inputs <path> --proof <path> | --bundle <path> | --dir <path>) [--schema <v0|v1|v2>]"
             );
             std::process::exit(1);
         }
     };
 
-    let vk = match read_verifying_key(&vk_path) {
+    let vk = match read_verifying_key_auto(&args.vk_path, DEFAULT_MAX_VK_BYTES) {
         Ok(vk) => vk,
         Err(err) => {
             eprintln!("failed to read verifying key: {err}");
             std::process::exit(1);
         }
     };
+    // Keys from a setup run with different Poseidon parameters cannot verify anything; say so
+    // up front instead of reporting every proof as invalid.
+    if let Err(err) = check_params_spec(&args.vk_path, &PoseidonParamsSpec::DEFAULT_SPEC) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
 
-    let proof = match read_proof(&proof_path) {
-        Ok(proof) => proof,
-        Err(err) => {
-            eprintln!("failed to read proof: {err}");
-            std::process::exit(1);
+    let result = match args.mode {
+        Mode::Single { inputs_path, proof_path } => {
+            verify_one(&vk, args.schema, &inputs_path, &proof_path)
         }
-    };
-
-    let verified = match schema {
-        Schema::V0 => {
-            let inputs_bytes = match read_public_inputs_v0(&inputs_path) {
-                Ok(inputs) => inputs,
+        Mode::Bundle(path) => verify_bundle(&vk, args.schema, &path),
+        Mode::Dir(dir) => {
+            let code = match verify_dir(&vk, &dir, args.schema, &mut io::stdout()) {
+                Ok(code) => code,
                 Err(err) => {
-                    eprintln!("failed to read public inputs: {err}");
-                    std::process::exit(1);
+                    eprintln!("{err}");
+                    1
                 }
             };
-
-            let public_inputs = match inputs_bytes.into_public_inputs() {
-                Ok(inputs) => inputs,
-                Err(err) => {
-                    eprintln!("invalid public inputs: {err}");
-                    std::process::exit(1);
-                }
-            };
-
-            match verify_membership(&vk, &public_inputs, &proof) {
-                Ok(result) => result,
-                Err(err) => {
-                    eprintln!("verification failed: {err}");
-                    std::process::exit(1);
-                }
+            if code == 2 {
+                eprintln!("vk fingerprint: {}", vk_fingerprint_hex(&vk));
             }
+            std::process::exit(code);
         }
-        Schema::V1 => {
-            let inputs_bytes = match read_public_inputs_v1(&inputs_path) {
-                Ok(inputs) => inputs,
-                Err(err) => {
-                    eprintln!("failed to read public inputs: {err}");
-                    std::process::exit(1);
-                }
-            };
+    };
 
-            let (public_inputs, _depth) = match inputs_bytes.into_public_inputs_with_depth() {
-                Ok((inputs, depth)) => (inputs, depth),
-                Err(err) => {
-                    eprintln!("invalid public inputs: {err}");
-                    std::process::exit(1);
-                }
-            };
+    match result {
+        Ok(true) => {
+            println!("verified");
+            std::process::exit(0);
+        }
+        Ok(false) => {
+            eprintln!("verification failed");
+            eprintln!("vk fingerprint: {}", vk_fingerprint_hex(&vk));
+            std::process::exit(2);
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            eprintln!("vk fingerprint: {}", vk_fingerprint_hex(&vk));
+            std::process::exit(1);
+        }
+    }
+}
 
-            match verify_membership(&vk, &public_inputs, &proof) {
-                Ok(result) => result,
-                Err(err) => {
-                    eprintln!("verification failed: {err}");
-                    std::process::exit(1);
-                }
-            }
+// Ok(false) is a proof that does not verify; Err is anything that stopped the
+// check from running (unreadable or malformed files, wrong input count).
+fn verify_one(
+    vk: &VerifyingKey<Bn254>,
+    schema: Schema,
+    inputs_path: &str,
+    proof_path: &str,
+) -> Result<bool, String> {
+    let (public_inputs, proof) = read_public_inputs_then_proof(inputs_path, proof_path, |data| {
+        PublicInputs::decode(schema, data)
+    })?;
+    public_inputs.verify(vk, &proof)
+}
+
+// The bundle's statement must match --schema and its fingerprint the --vk, both
+// checked before the proof is decoded.
+fn verify_bundle(vk: &VerifyingKey<Bn254>, schema: Schema, path: &str) -> Result<bool, String> {
+    let bundle = read_bundle(path, DEFAULT_MAX_BUNDLE_BYTES)
+        .map_err(|err| format!("failed to read bundle: {err}"))?;
+    let (data, proof) = bundle.open(schema.statement(), vk)?;
+    PublicInputs::decode(schema, data)
+        .map_err(|err| format!("failed to read public inputs: {err}"))?
+        .verify(vk, &proof)
+}
+
+enum PublicInputs {
+    V1(MembershipPublicInputs),
+    V2(MembershipPublicInputsV2),
+}
+
+impl PublicInputs {
+    fn decode(schema: Schema, data: &[u8]) -> Result<Self, String> {
+        match schema {
+            Schema::V0 => decode_public_inputs_v0(data).map(Self::V1),
+            Schema::V1 => decode_public_inputs_v1(data).map(|(inputs, _depth)| Self::V1(inputs)),
+            Schema::V2 => decode_public_inputs_v2(data).map(|(inputs, _depth)| Self::V2(inputs)),
         }
-        Schema::V2 => {
-            let inputs_bytes = match read_public_inputs_v2(&inputs_path) {
-                Ok(inputs) => inputs,
-                Err(err) => {
-                    eprintln!("failed to read public inputs: {err}");
-                    std::process::exit(1);
-                }
-            };
+    }
 
-            let (public_inputs, _depth) = match inputs_bytes.into_public_inputs_with_depth() {
-                Ok((inputs, depth)) => (inputs, depth),
-                Err(err) => {
-                    eprintln!("invalid public inputs: {err}");
-                    std::process::exit(1);
-                }
-            };
+    fn verify(&self, vk: &VerifyingKey<Bn254>, proof: &Proof<Bn254>) -> Result<bool, String> {
+        match self {
+            Self::V1(inputs) => verify_membership(vk, inputs, proof),
+            Self::V2(inputs) => verify_membership_v2(vk, inputs, proof),
+        }
+        .map_err(|err| format!("verification failed: {err}"))
+    }
+}
 
-            match verify_membership_v2(&vk, &public_inputs, &proof) {
-                Ok(result) => result,
-                Err(err) => {
-                    eprintln!("verification failed: {err}");
-                    std::process::exit(1);
-                }
+// Audits every `<name>.proof` in `dir` against `<name>.public_inputs` and writes a
+// row per proof plus a total. Returns the exit code: 0 when every proof verified,
+// 2 when any failed or lacked its public inputs. A directory without proofs is an
+// error so an empty audit is never reported as a pass.
+fn verify_dir(
+    vk: &VerifyingKey<Bn254>,
+    dir: &str,
+    schema: Schema,
+    out: &mut dyn Write,
+) -> Result<i32, String> {
+    let entries = fs::read_dir(dir).map_err(|err| format!("failed to read {dir}: {err}"))?;
+    let mut names = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|err| err.to_string())?.path();
+        if path.extension().and_then(|ext| ext.to_str()) == Some("proof") {
+            if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                names.push(stem.to_string());
             }
         }
-    };
+    }
+    if names.is_empty() {
+        return Err(format!("no *.proof files in {dir}"));
+    }
+    names.sort();
 
-    if verified {
-        println!("verified");
-        std::process::exit(0);
+    let write_err = |err: io::Error| err.to_string();
+    let width = names.iter().map(String::len).max().unwrap_or(0).max("proof".len());
+    writeln!(out, "{:<width$}  result", "proof").map_err(write_err)?;
+    let mut passed = 0;
+    for name in &names {
+        let inputs_path = Path::new(dir).join(format!("{name}.public_inputs"));
+        let proof_path = Path::new(dir).join(format!("{name}.proof"));
+        let result = if inputs_path.is_file() {
+            verify_one(vk, schema, &inputs_path.to_string_lossy(), &proof_path.to_string_lossy())
+        } else {
+            Err(format!("missing {name}.public_inputs"))
+        };
+        let status = match result {
+            Ok(true) => {
+                passed += 1;
+                "ok".to_string()
+            }
+            Ok(false) => "FAILED".to_string(),
+            Err(err) => format!("FAILED ({err})"),
+        };
+        writeln!(out, "{name:<width$}  {status}").map_err(write_err)?;
     }
+    writeln!(out, "{passed}/{} proofs verified", names.len()).map_err(write_err)?;
+    Ok(if passed == names.len() { 0 } else { 2 })
+}
 
-    eprintln!("verification failed");
-    std::process::exit(2);
+struct Args {
+    vk_path: String,
+    schema: Schema,
+    mode: Mode,
 }
 
-fn parse_args() -> Option<(String, String, String, Schema)> {
+enum Mode {
+    Single { inputs_path: String, proof_path: String },
+    Bundle(String),
+    Dir(String),
+}
+
+fn parse_args() -> Option<Args> {
     let mut vk_path = None;
     let mut inputs_path = None;
     let mut proof_path = None;
+    let mut bundle = None;
+    let mut dir = None;
     let mut schema = Schema::V0;
     let mut args = env::args().skip(1);
     while let Some(arg) = args.next() {
@@ -135,6 +202,8 @@ fn parse_args() -> Option<(String, String, String, Schema)> {
             "--vk" => vk_path = args.next(),
             "--public-inputs" => inputs_path = args.next(),
             "--proof" => proof_path = args.next(),
+            "--bundle" => bundle = args.next(),
+            "--dir" => dir = args.next(),
             "--schema" => {
                 schema = match args.next()?.as_str() {
                     "v0" => Schema::V0,
@@ -146,10 +215,20 @@ fn parse_args() -> Option<(String, String, String, Schema)> {
             _ => return None,
         }
     }
-    match (vk_path, inputs_path, proof_path) {
-        (Some(vk), Some(inputs), Some(proof)) => Some((vk, inputs, proof, schema)),
-        _ => None,
-    }
+    let mode = match (inputs_path, proof_path, bundle, dir) {
+        (Some(inputs_path), Some(proof_path), None, None) => Mode::Single {
+            inputs_path,
+            proof_path,
+        },
+        (None, None, Some(bundle), None) => Mode::Bundle(bundle),
+        (None, None, None, Some(dir)) => Mode::Dir(dir),
+        _ => return None,
+    };
+    Some(Args {
+        vk_path: vk_path?,
+        schema,
+        mode,
+    })
 }
 
 #[derive(Clone, Copy)]
@@ -159,29 +238,106 @@ enum Schema {
     V2,
 }
 
-fn read_verifying_key(path: &str) -> Result<VerifyingKey<Bn254>, String> {
-    let file = File::open(path).map_err(|err| err.to_string())?;
-    let mut reader = BufReader::new(file);
-    VerifyingKey::<Bn254>::deserialize_uncompressed(&mut reader).map_err(|err| err.to_string())
+impl Schema {
+    fn statement(self) -> &'static str {
+        match self {
+            Schema::V0 => "membership-v0",
+            Schema::V1 => "membership-v1",
+            Schema::V2 => "membership-v2",
+        }
+    }
 }
 
-fn read_public_inputs_v0(path: &str) -> Result<MembershipPublicInputsBytes, String> {
-    let data = fs::read(path).map_err(|err| err.to_string())?;
-    bincode::deserialize::<MembershipPublicInputsBytes>(&data).map_err(|err| err.to_string())
+fn decode_public_inputs_v0(data: &[u8]) -> Result<MembershipPublicInputs, String> {
+    decode_public_inputs(data, |bytes: MembershipPublicInputsBytes| {
+        bytes.into_public_inputs()
+    })
 }
 
-fn read_public_inputs_v1(path: &str) -> Result<MembershipPublicInputsV1Bytes, String> {
-    let data = fs::read(path).map_err(|err| err.to_string())?;
-    bincode::deserialize::<MembershipPublicInputsV1Bytes>(&data).map_err(|err| err.to_string())
+fn decode_public_inputs_v1(data: &[u8]) -> Result<(MembershipPublicInputs, usize), String> {
+    decode_public_inputs(data, |bytes: MembershipPublicInputsV1Bytes| {
+        bytes.into_public_inputs_with_depth()
+    })
 }
 
-fn read_public_inputs_v2(path: &str) -> Result<MembershipPublicInputsV2Bytes, String> {
-    let data = fs::read(path).map_err(|err| err.to_string())?;
-    bincode::deserialize::<MembershipPublicInputsV2Bytes>(&data).map_err(|err| err.to_string())
+fn decode_public_inputs_v2(data: &[u8]) -> Result<(MembershipPublicInputsV2, usize), String> {
+    decode_public_inputs(data, |bytes: MembershipPublicInputsV2Bytes| {
+        bytes.into_public_inputs_with_depth()
+    })
 }
 
-fn read_proof(path: &str) -> Result<Proof<Bn254>, String> {
-    let file = File::open(path).map_err(|err| err.to_string())?;
-    let mut reader = BufReader::new(file);
-    Proof::<Bn254>::deserialize_uncompressed(&mut reader).map_err(|err| err.to_string())
+#[cfg(test)]
+mod tests {
+    use super::{verify_dir, Schema};
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use membership::test_support::sample_instance;
+    use membership::{
+        fr_to_fixed_bytes, prove_membership, setup_membership, MembershipInstance,
+        MembershipPublicInputsBytes,
+    };
+    use std::fs;
+
+    #[test]
+    fn verify_dir_reports_each_proof_and_fails_on_any_bad_one() {
+        let dir = std::env::temp_dir().join(format!("verify-dir-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut rng = StdRng::seed_from_u64(69);
+        let pk = setup_membership(&mut rng).unwrap();
+
+        let write_pair = |name: &str, instance: &MembershipInstance, rng: &mut StdRng| {
+            let proof = prove_membership(&pk, instance, rng).unwrap();
+            let mut proof_bytes = Vec::new();
+            proof.serialize_uncompressed(&mut proof_bytes).unwrap();
+            fs::write(dir.join(format!("{name}.proof")), proof_bytes).unwrap();
+            let inputs = MembershipPublicInputsBytes {
+                root: fr_to_fixed_bytes(&instance.public_inputs.root),
+                commitment: fr_to_fixed_bytes(&instance.public_inputs.commitment),
+            };
+            fs::write(
+                dir.join(format!("{name}.public_inputs")),
+                bincode::serialize(&inputs).unwrap(),
+            )
+            .unwrap();
+        };
+        write_pair("alice", &sample_instance(1), &mut rng);
+        write_pair("bob", &sample_instance(3), &mut rng);
+        let dir_str = dir.to_string_lossy().into_owned();
+
+        let mut out = Vec::new();
+        assert_eq!(verify_dir(&pk.vk, &dir_str, Schema::V0, &mut out).unwrap(), 0);
+        let summary = String::from_utf8(out).unwrap();
+        assert!(summary.contains("alice  ok"));
+        assert!(summary.ends_with("2/2 proofs verified\n"));
+
+        // bob's proof now answers for carol's inputs, and dave has no inputs.
+        fs::copy(dir.join("bob.proof"), dir.join("carol.proof")).unwrap();
+        let carol = sample_instance(5);
+        let carol_inputs = MembershipPublicInputsBytes {
+            root: fr_to_fixed_bytes(&carol.public_inputs.root),
+            commitment: fr_to_fixed_bytes(&carol.public_inputs.commitment),
+        };
+        fs::write(dir.join("carol.public_inputs"), bincode::serialize(&carol_inputs).unwrap())
+            .unwrap();
+        fs::copy(dir.join("alice.proof"), dir.join("dave.proof")).unwrap();
+
+        let mut out = Vec::new();
+        assert_eq!(verify_dir(&pk.vk, &dir_str, Schema::V0, &mut out).unwrap(), 2);
+        let summary = String::from_utf8(out).unwrap();
+        let rows: Vec<&str> = summary.lines().collect();
+        assert_eq!(
+            rows,
+            [
+                "proof  result",
+                "alice  ok",
+                "bob    ok",
+                "carol  FAILED",
+                "dave   FAILED (missing dave.public_inputs)",
+                "2/4 proofs verified",
+            ]
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(verify_dir(&pk.vk, &dir_str, Schema::V0, &mut Vec::new()).is_err());
+    }
 }
@@ -0,0 +1,297 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ensure_depth, ensure_version_u16, fr_from_bytes_canonical, fr_to_array, merkle_root_var,
+    poseidon_hash_var, poseidon_params, MembershipError, MembershipWitness, DOMAIN_COMMITMENT,
+    DOMAIN_LEAF,
+};
+
+pub const BLOCKLIST_MEMBERSHIP_INSTANCE_VERSION: u16 = 1;
+
+// V1 membership plus "the committed identity is none of these". Public inputs:
+// [root, commitment, blocked_0, ..., blocked_{N-1}]. Each inequality is enforced as
+// (identity - blocked_i) * inv_i = 1 with inv_i a witness, which has no solution
+// when identity == blocked_i. Keys are specific to (depth, N).
+#[derive(Clone, Debug, Default)]
+pub struct MembershipWithBlocklistCircuit<F: PrimeField> {
+    pub root: Option<F>,
+    pub commitment: Option<F>,
+    pub blocked: Vec<Option<F>>,
+    pub identity_scalar: Option<F>,
+    pub blinding: Option<F>,
+    pub expected_depth: usize,
+    pub merkle_path: Vec<(Option<F>, Option<bool>)>,
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for MembershipWithBlocklistCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        if self.expected_depth == 0 || self.merkle_path.len() != self.expected_depth {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let params = poseidon_params::<F>();
+
+        let root =
+            FpVar::new_input(cs.clone(), || self.root.ok_or(SynthesisError::AssignmentMissing))?;
+        let commitment_input = FpVar::new_input(cs.clone(), || {
+            self.commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blocked = self
+            .blocked
+            .iter()
+            .map(|value| {
+                FpVar::new_input(cs.clone(), || value.ok_or(SynthesisError::AssignmentMissing))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let identity_scalar = FpVar::new_witness(cs.clone(), || {
+            self.identity_scalar.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        for (blocked_var, blocked_value) in blocked.iter().zip(&self.blocked) {
+            // A blocked identity has no inverse; zero keeps synthesis going and
+            // leaves the constraint unsatisfied.
+            let inverse = FpVar::new_witness(cs.clone(), || {
+                let identity = self.identity_scalar.ok_or(SynthesisError::AssignmentMissing)?;
+                let blocked = blocked_value.ok_or(SynthesisError::AssignmentMissing)?;
+                Ok((identity - blocked).inverse().unwrap_or(F::zero()))
+            })?;
+            let difference = &identity_scalar - blocked_var;
+            difference.mul_equals(&inverse, &FpVar::one())?;
+        }
+
+        let commitment = poseidon_hash_var(
+            cs.clone(),
+            &params,
+            &[FpVar::constant(F::from(DOMAIN_COMMITMENT)), identity_scalar, blinding],
+        )?;
+        commitment.enforce_equal(&commitment_input)?;
+
+        let leaf = poseidon_hash_var(
+            cs.clone(),
+            &params,
+            &[FpVar::constant(F::from(DOMAIN_LEAF)), commitment, FpVar::zero()],
+        )?;
+        let computed_root = merkle_root_var(cs, &params, leaf, self.merkle_path)?;
+        computed_root.enforce_equal(&root)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BlocklistMembershipPublicInputs {
+    pub root: Fr,
+    pub commitment: Fr,
+    pub blocked: Vec<Fr>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BlocklistMembershipInstance {
+    pub public_inputs: BlocklistMembershipPublicInputs,
+    pub witness: MembershipWitness,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BlocklistMembershipPublicInputsBytes {
+    pub schema_version: u16,
+    pub depth: u32,
+    pub root: [u8; 32],
+    pub commitment: [u8; 32],
+    pub blocked: Vec<[u8; 32]>,
+}
+
+impl BlocklistMembershipPublicInputsBytes {
+    pub fn from_instance(instance: &BlocklistMembershipInstance) -> Self {
+        let public_inputs = &instance.public_inputs;
+        Self {
+            schema_version: BLOCKLIST_MEMBERSHIP_INSTANCE_VERSION,
+            depth: instance.witness.merkle_path.len() as u32,
+            root: fr_to_array(&public_inputs.root),
+            commitment: fr_to_array(&public_inputs.commitment),
+            blocked: public_inputs.blocked.iter().map(fr_to_array).collect(),
+        }
+    }
+
+    pub fn into_public_inputs_with_depth(
+        self,
+    ) -> Result<(BlocklistMembershipPublicInputs, usize), MembershipError> {
+        ensure_version_u16(
+            "public_inputs.schema_version",
+            self.schema_version,
+            BLOCKLIST_MEMBERSHIP_INSTANCE_VERSION,
+        )?;
+        let depth = ensure_depth("public_inputs.depth", self.depth as usize)?;
+        let blocked = self
+            .blocked
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| fr_from_bytes_canonical(&format!("blocked[{idx}]"), value))
+            .collect::<Result<Vec<_>, _>>()?;
+        let inputs = BlocklistMembershipPublicInputs {
+            root: fr_from_bytes_canonical("root", &self.root)?,
+            commitment: fr_from_bytes_canonical("commitment", &self.commitment)?,
+            blocked,
+        };
+        Ok((inputs, depth))
+    }
+}
+
+pub fn blocklist_membership_field_inputs(
+    public_inputs: &BlocklistMembershipPublicInputs,
+) -> Vec<Fr> {
+    let mut inputs = Vec::with_capacity(public_inputs.blocked.len() + 2);
+    inputs.push(public_inputs.root);
+    inputs.push(public_inputs.commitment);
+    inputs.extend_from_slice(&public_inputs.blocked);
+    inputs
+}
+
+pub fn build_blocklist_circuit(
+    instance: &BlocklistMembershipInstance,
+) -> MembershipWithBlocklistCircuit<Fr> {
+    let witness = &instance.witness;
+    MembershipWithBlocklistCircuit {
+        root: Some(instance.public_inputs.root),
+        commitment: Some(instance.public_inputs.commitment),
+        blocked: instance.public_inputs.blocked.iter().copied().map(Some).collect(),
+        identity_scalar: Some(witness.identity_scalar),
+        blinding: Some(witness.blinding),
+        expected_depth: witness.merkle_path.len(),
+        merkle_path: witness
+            .merkle_path
+            .iter()
+            .map(|(sibling, is_left)| (Some(*sibling), Some(*is_left)))
+            .collect(),
+    }
+}
+
+pub fn setup_membership_with_blocklist<R: RngCore>(
+    rng: &mut R,
+    depth: usize,
+    blocked_count: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let zero = Fr::from(0u64);
+    let circuit = MembershipWithBlocklistCircuit::<Fr> {
+        root: Some(zero),
+        commitment: Some(zero),
+        blocked: vec![Some(Fr::from(1u64)); blocked_count],
+        identity_scalar: Some(zero),
+        blinding: Some(zero),
+        expected_depth: depth,
+        merkle_path: vec![(Some(zero), Some(false)); depth],
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+pub fn prove_membership_with_blocklist<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &BlocklistMembershipInstance,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    Groth16::<Bn254>::create_random_proof_with_reduction(
+        build_blocklist_circuit(instance),
+        pk,
+        rng,
+    )
+}
+
+pub fn verify_membership_with_blocklist(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &BlocklistMembershipPublicInputs,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    let pvk = prepare_verifying_key(vk);
+    let inputs = blocklist_membership_field_inputs(public_inputs);
+    Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_blocklist_circuit, prove_membership_with_blocklist, setup_membership_with_blocklist,
+        verify_membership_with_blocklist, BlocklistMembershipInstance,
+        BlocklistMembershipPublicInputs, BlocklistMembershipPublicInputsBytes,
+    };
+    use crate::tree::PoseidonMerkleTree;
+    use crate::{commitment_hash, poseidon_params, MembershipError, MembershipWitness};
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    fn satisfied(instance: &BlocklistMembershipInstance) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        build_blocklist_circuit(instance)
+            .generate_constraints(cs.clone())
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn blocked_identities_and_non_members_are_rejected() {
+        let params = poseidon_params::<Fr>();
+        let openings: Vec<(Fr, Fr)> = (0..3u64)
+            .map(|idx| (Fr::from(300 + idx), Fr::from(400 + idx)))
+            .collect();
+        let commitments: Vec<Fr> = openings
+            .iter()
+            .map(|(identity, blinding)| commitment_hash(&params, *identity, *blinding))
+            .collect();
+        let tree = PoseidonMerkleTree::build(&commitments, 2, &params).unwrap();
+        let blocked = vec![Fr::from(301u64), Fr::from(999u64)];
+        let instance_for = |idx: usize| BlocklistMembershipInstance {
+            public_inputs: BlocklistMembershipPublicInputs {
+                root: tree.root(),
+                commitment: commitments[idx],
+                blocked: blocked.clone(),
+            },
+            witness: MembershipWitness {
+                identity_scalar: openings[idx].0,
+                blinding: openings[idx].1,
+                merkle_path: tree.path(idx).unwrap(),
+            },
+        };
+
+        let allowed = instance_for(0);
+        let mut rng = StdRng::seed_from_u64(98);
+        let pk = setup_membership_with_blocklist(&mut rng, 2, blocked.len()).unwrap();
+        assert_eq!(pk.vk.gamma_abc_g1.len(), 5);
+        let proof = prove_membership_with_blocklist(&pk, &allowed, &mut rng).unwrap();
+        let (public_inputs, depth) = BlocklistMembershipPublicInputsBytes::from_instance(&allowed)
+            .into_public_inputs_with_depth()
+            .unwrap();
+        assert_eq!(depth, 2);
+        assert!(verify_membership_with_blocklist(&pk.vk, &public_inputs, &proof).unwrap());
+
+        let mut other_blocklist = public_inputs.clone();
+        other_blocklist.blocked[1] = Fr::from(998u64);
+        assert!(!verify_membership_with_blocklist(&pk.vk, &other_blocklist, &proof).unwrap());
+
+        // A blocked entry at or past the modulus is rejected, not reduced.
+        let mut non_canonical = BlocklistMembershipPublicInputsBytes::from_instance(&allowed);
+        non_canonical.blocked[1] = [0xff; 32];
+        assert!(non_canonical.into_public_inputs_with_depth().is_err());
+        let mut no_depth = BlocklistMembershipPublicInputsBytes::from_instance(&allowed);
+        no_depth.depth = 0;
+        let err = no_depth.into_public_inputs_with_depth().unwrap_err();
+        assert!(matches!(err, MembershipError::ZeroDepth { .. }));
+
+        // Member 1 is in the tree but its identity (301) is blocked.
+        assert!(!satisfied(&instance_for(1)));
+
+        // An opening that is not in the tree cannot reach the root.
+        let mut outsider = instance_for(2);
+        outsider.witness.identity_scalar = Fr::from(500u64);
+        outsider.public_inputs.commitment =
+            commitment_hash(&params, Fr::from(500u64), openings[2].1);
+        assert!(!satisfied(&outsider));
+    }
+}
@@ -0,0 +1,142 @@
+use ark_bn254::Bn254;
+use ark_groth16::{Proof, VerifyingKey};
+use ark_serialize::CanonicalSerialize;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::io::{decode_canonical, read_file_capped, DEFAULT_MAX_PROOF_BYTES};
+use crate::vk_fingerprint;
+
+pub const PROOF_BUNDLE_VERSION: u16 = 1;
+// A bundle holds one proof plus a few dozen bytes of framing and public inputs.
+pub const DEFAULT_MAX_BUNDLE_BYTES: u64 = 1 << 16;
+
+// One proof with everything needed to check it except the verifying key:
+// `statement` names the circuit and schema in the SOLIDITY_STATEMENTS style
+// ("membership-v2"), `public_inputs` is the bincode public-inputs file the verify
+// binaries read, and `proof` is in the encoding `compressed` says. The VK is
+// referenced by its SHA-256 fingerprint so a bundle checked against the wrong key
+// is rejected before any pairing work.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofBundle {
+    pub bundle_version: u16,
+    pub statement: String,
+    pub compressed: bool,
+    #[serde(with = "crate::hex_serde::array")]
+    pub vk_fingerprint: [u8; 32],
+    #[serde(with = "crate::hex_serde::bytes")]
+    pub public_inputs: Vec<u8>,
+    #[serde(with = "crate::hex_serde::bytes")]
+    pub proof: Vec<u8>,
+}
+
+impl ProofBundle {
+    pub fn new(
+        statement: &str,
+        public_inputs: Vec<u8>,
+        proof: &Proof<Bn254>,
+        vk: &VerifyingKey<Bn254>,
+        compressed: bool,
+    ) -> Result<Self, String> {
+        let mut proof_bytes = Vec::new();
+        if compressed {
+            proof.serialize_compressed(&mut proof_bytes)
+        } else {
+            proof.serialize_uncompressed(&mut proof_bytes)
+        }
+        .map_err(|err| err.to_string())?;
+        Ok(Self {
+            bundle_version: PROOF_BUNDLE_VERSION,
+            statement: statement.to_string(),
+            compressed,
+            vk_fingerprint: vk_fingerprint(vk),
+            public_inputs,
+            proof: proof_bytes,
+        })
+    }
+
+    // Checks the statement and VK fingerprint, then decodes the proof. The public
+    // inputs are returned undecoded for the statement's own parser.
+    pub fn open(
+        &self,
+        statement: &str,
+        vk: &VerifyingKey<Bn254>,
+    ) -> Result<(&[u8], Proof<Bn254>), String> {
+        if self.statement != statement {
+            return Err(format!("bundle is for {}, expected {statement}", self.statement));
+        }
+        if self.vk_fingerprint != vk_fingerprint(vk) {
+            return Err("bundle was made for a different verifying key".to_string());
+        }
+        if self.proof.len() as u64 > DEFAULT_MAX_PROOF_BYTES {
+            return Err(format!("bundle proof is {} bytes", self.proof.len()));
+        }
+        let proof = decode_canonical(&self.proof, self.compressed)
+            .map_err(|err| format!("invalid bundle proof: {err}"))?;
+        Ok((&self.public_inputs, proof))
+    }
+}
+
+pub fn write_bundle(path: &str, bundle: &ProofBundle) -> Result<(), String> {
+    let bytes = bincode::serialize(bundle).map_err(|err| err.to_string())?;
+    fs::write(path, bytes).map_err(|err| format!("failed to write {path}: {err}"))
+}
+
+pub fn read_bundle(path: &str, max_bytes: u64) -> Result<ProofBundle, String> {
+    let data = read_file_capped(path, max_bytes)?;
+    let bundle: ProofBundle =
+        bincode::deserialize(&data).map_err(|err| format!("{path}: {err}"))?;
+    if bundle.bundle_version != PROOF_BUNDLE_VERSION {
+        return Err(format!(
+            "{path}: unsupported bundle version {} (expected {PROOF_BUNDLE_VERSION})",
+            bundle.bundle_version
+        ));
+    }
+    Ok(bundle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_bundle, write_bundle, ProofBundle, DEFAULT_MAX_BUNDLE_BYTES};
+    use crate::test_support::{public_inputs_v2_bytes, sample_instance_v2};
+    use crate::{
+        prove_membership_v2, setup_membership_with_depth_v2, verify_membership_v2,
+        MembershipPublicInputsV2Bytes,
+    };
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn bundle_round_trips_and_rejects_a_foreign_vk() {
+        let instance = sample_instance_v2(2);
+        let mut rng = StdRng::seed_from_u64(103);
+        let pk = setup_membership_with_depth_v2(&mut rng, 2).unwrap();
+        let other = setup_membership_with_depth_v2(&mut rng, 2).unwrap();
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+        let inputs = bincode::serialize(&public_inputs_v2_bytes(&instance)).unwrap();
+
+        let path = std::env::temp_dir().join(format!("bundle-{}.bin", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        for compressed in [false, true] {
+            let bundle =
+                ProofBundle::new("membership-v2", inputs.clone(), &proof, &pk.vk, compressed)
+                    .unwrap();
+            write_bundle(&path, &bundle).unwrap();
+            let read = read_bundle(&path, DEFAULT_MAX_BUNDLE_BYTES).unwrap();
+            assert_eq!(read, bundle);
+
+            let (inputs_bytes, decoded) = read.open("membership-v2", &pk.vk).unwrap();
+            let parsed: MembershipPublicInputsV2Bytes = bincode::deserialize(inputs_bytes).unwrap();
+            let (parsed, _) = parsed.into_public_inputs_with_depth().unwrap();
+            assert!(verify_membership_v2(&pk.vk, &parsed, &decoded).unwrap());
+
+            assert!(read.open("membership-v2", &other.vk).is_err());
+            assert!(read.open("membership-v1", &pk.vk).is_err());
+        }
+
+        let mut bundle = read_bundle(&path, DEFAULT_MAX_BUNDLE_BYTES).unwrap();
+        bundle.bundle_version += 1;
+        write_bundle(&path, &bundle).unwrap();
+        assert!(read_bundle(&path, DEFAULT_MAX_BUNDLE_BYTES).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
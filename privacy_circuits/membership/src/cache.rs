@@ -0,0 +1,159 @@
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, VerifyingKey};
+use ark_serialize::CanonicalSerialize;
+use lru::LruCache;
+use sha2::{Digest, Sha256};
+use std::num::NonZeroUsize;
+
+use crate::fr_to_fixed_bytes;
+
+pub fn public_inputs_digest(public_inputs: &[Fr]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update((public_inputs.len() as u64).to_be_bytes());
+    for value in public_inputs {
+        hasher.update(fr_to_fixed_bytes(value));
+    }
+    hasher.finalize().into()
+}
+
+pub fn proof_digest(proof: &Proof<Bn254>) -> Result<[u8; 32], String> {
+    let mut bytes = Vec::new();
+    proof
+        .serialize_compressed(&mut bytes)
+        .map_err(|err| err.to_string())?;
+    Ok(Sha256::digest(&bytes).into())
+}
+
+// SHA-256 of the uncompressed VK encoding, i.e. `sha256sum` of a VK file written
+// by the setup binaries without --compressed. Keys from different setups or depths
+// differ here, which tells a bad proof apart from a verifying key that does not
+// belong to the proving key.
+pub fn vk_fingerprint(vk: &VerifyingKey<Bn254>) -> [u8; 32] {
+    let mut bytes = Vec::with_capacity(vk.uncompressed_size());
+    vk.serialize_uncompressed(&mut bytes)
+        .expect("verifying key serializes into a Vec");
+    Sha256::digest(&bytes).into()
+}
+
+pub fn vk_fingerprint_hex(vk: &VerifyingKey<Bn254>) -> String {
+    vk_fingerprint(vk)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+// Memoizes verification results by (public inputs digest, proof digest). The key
+// does not cover the verifying key: call `clear` whenever the VK is rotated, or a
+// result computed under the old key will be served for the new one.
+pub struct VerificationCache {
+    entries: LruCache<([u8; 32], [u8; 32]), bool>,
+    verifications: usize,
+}
+
+impl VerificationCache {
+    pub fn new(capacity: usize) -> Result<Self, String> {
+        let capacity = NonZeroUsize::new(capacity)
+            .ok_or_else(|| "cache capacity must be > 0".to_string())?;
+        Ok(Self {
+            entries: LruCache::new(capacity),
+            verifications: 0,
+        })
+    }
+
+    pub fn verify(
+        &mut self,
+        vk: &VerifyingKey<Bn254>,
+        public_inputs: &[Fr],
+        proof: &Proof<Bn254>,
+    ) -> Result<bool, String> {
+        let key = (public_inputs_digest(public_inputs), proof_digest(proof)?);
+        if let Some(verified) = self.entries.get(&key) {
+            return Ok(*verified);
+        }
+
+        self.verifications += 1;
+        let pvk = prepare_verifying_key(vk);
+        let verified = Groth16::<Bn254>::verify_proof(&pvk, proof, public_inputs)
+            .map_err(|err| err.to_string())?;
+        self.entries.put(key, verified);
+        Ok(verified)
+    }
+
+    // Number of verifications that missed the cache and ran the pairing check.
+    pub fn verifications(&self) -> usize {
+        self.verifications
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{vk_fingerprint, vk_fingerprint_hex, VerificationCache};
+    use crate::io::write_uncompressed;
+    use crate::test_support::sample_instance_v2;
+    use crate::{membership_v2_field_inputs, prove_membership_v2, setup_membership_with_depth_v2};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn repeated_verification_is_served_from_cache() {
+        let instance = sample_instance_v2(2);
+        let mut rng = StdRng::seed_from_u64(70);
+        let pk = setup_membership_with_depth_v2(&mut rng, 2).unwrap();
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+        let inputs = membership_v2_field_inputs(&instance.public_inputs);
+
+        let mut cache = VerificationCache::new(4).unwrap();
+        assert!(cache.verify(&pk.vk, &inputs, &proof).unwrap());
+        assert_eq!(cache.verifications(), 1);
+        assert!(cache.verify(&pk.vk, &inputs, &proof).unwrap());
+        assert_eq!(cache.verifications(), 1);
+
+        let mut tampered = inputs.clone();
+        tampered[1] += ark_bn254::Fr::from(1u64);
+        assert!(!cache.verify(&pk.vk, &tampered, &proof).unwrap());
+        assert!(!cache.verify(&pk.vk, &tampered, &proof).unwrap());
+        assert_eq!(cache.verifications(), 2);
+        assert_eq!(cache.len(), 2);
+
+        cache.clear();
+        assert!(cache.verify(&pk.vk, &inputs, &proof).unwrap());
+        assert_eq!(cache.verifications(), 3);
+        assert!(VerificationCache::new(0).is_err());
+    }
+
+    #[test]
+    fn vk_fingerprint_matches_file_hash_and_separates_setups() {
+        let mut rng = StdRng::seed_from_u64(71);
+        let depth_two = setup_membership_with_depth_v2(&mut rng, 2).unwrap().vk;
+        let depth_two_again = setup_membership_with_depth_v2(&mut rng, 2).unwrap().vk;
+        let depth_three = setup_membership_with_depth_v2(&mut rng, 3).unwrap().vk;
+
+        let fingerprint = vk_fingerprint(&depth_two);
+        assert_eq!(fingerprint, vk_fingerprint(&depth_two.clone()));
+        assert_ne!(fingerprint, vk_fingerprint(&depth_two_again));
+        assert_ne!(fingerprint, vk_fingerprint(&depth_three));
+
+        let path = std::env::temp_dir().join(format!("vk-fingerprint-{}.bin", std::process::id()));
+        let path = path.to_string_lossy().into_owned();
+        write_uncompressed(&path, &depth_two).unwrap();
+        let file_hash: [u8; 32] = Sha256::digest(std::fs::read(&path).unwrap()).into();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(file_hash, fingerprint);
+
+        let hex = vk_fingerprint_hex(&depth_two);
+        assert_eq!(hex.len(), 64);
+        assert!(hex.starts_with(&format!("{:02x}", fingerprint[0])));
+    }
+}
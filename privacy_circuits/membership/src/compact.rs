@@ -0,0 +1,153 @@
+use ark_bn254::Fr;
+
+use crate::tree::MAX_TREE_DEPTH;
+use crate::MembershipWitness;
+
+// Witness with empty-subtree siblings elided. Bit `level` of `empty_levels` is set
+// when the sibling at that level equals the empty-subtree hash for that level.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompactWitness {
+    pub identity_scalar: Fr,
+    pub blinding: Fr,
+    pub depth: u32,
+    pub empty_levels: u64,
+    pub siblings: Vec<Fr>,
+    pub directions: Vec<bool>,
+}
+
+pub fn compact_witness(witness: &MembershipWitness, empty_hashes: &[Fr]) -> CompactWitness {
+    let mut empty_levels = 0u64;
+    let mut siblings = Vec::new();
+    let mut directions = Vec::with_capacity(witness.merkle_path.len());
+    for (level, (sibling, is_left)) in witness.merkle_path.iter().enumerate() {
+        let is_empty = level < MAX_TREE_DEPTH && empty_hashes.get(level) == Some(sibling);
+        if is_empty {
+            empty_levels |= 1 << level;
+        } else {
+            siblings.push(*sibling);
+        }
+        directions.push(*is_left);
+    }
+    CompactWitness {
+        identity_scalar: witness.identity_scalar,
+        blinding: witness.blinding,
+        depth: witness.merkle_path.len() as u32,
+        empty_levels,
+        siblings,
+        directions,
+    }
+}
+
+pub fn expand_witness(
+    compact: &CompactWitness,
+    empty_hashes: &[Fr],
+) -> Result<MembershipWitness, String> {
+    let depth = compact.depth as usize;
+    if depth > MAX_TREE_DEPTH {
+        return Err(format!(
+            "compact witness depth {} exceeds maximum {}",
+            depth, MAX_TREE_DEPTH
+        ));
+    }
+    if compact.directions.len() != depth {
+        return Err(format!(
+            "directions length mismatch: expected {}, got {}",
+            depth,
+            compact.directions.len()
+        ));
+    }
+    if compact.empty_levels >> depth != 0 {
+        return Err("empty_levels has bits set beyond depth".to_string());
+    }
+    let empty_count = compact.empty_levels.count_ones() as usize;
+    if compact.siblings.len() + empty_count != depth {
+        return Err(format!(
+            "siblings length mismatch: expected {}, got {}",
+            depth - empty_count,
+            compact.siblings.len()
+        ));
+    }
+
+    let mut stored = compact.siblings.iter();
+    let mut merkle_path = Vec::with_capacity(depth);
+    for (level, is_left) in compact.directions.iter().enumerate() {
+        let sibling = if compact.empty_levels & (1 << level) != 0 {
+            *empty_hashes
+                .get(level)
+                .ok_or_else(|| format!("missing empty hash for level {}", level))?
+        } else {
+            *stored.next().expect("sibling count checked above")
+        };
+        merkle_path.push((sibling, *is_left));
+    }
+
+    Ok(MembershipWitness {
+        identity_scalar: compact.identity_scalar,
+        blinding: compact.blinding,
+        merkle_path,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compact_witness, expand_witness};
+    use crate::tree::{empty_subtree_hashes, PoseidonMerkleTree};
+    use crate::{
+        commitment_hash, poseidon_params, prove_membership, setup_membership_with_depth,
+        verify_membership, MembershipInstance, MembershipPublicInputs, MembershipWitness,
+    };
+    use ark_bn254::Fr;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn compact_witness_roundtrips_and_proves() {
+        let params = poseidon_params::<Fr>();
+        let depth = 6;
+        let identity = Fr::from(5u64);
+        let blinding = Fr::from(6u64);
+        let commitments = vec![
+            commitment_hash(&params, Fr::from(1u64), Fr::from(2u64)),
+            commitment_hash(&params, identity, blinding),
+        ];
+        let tree = PoseidonMerkleTree::build(&commitments, depth, &params).unwrap();
+        let empty_hashes = empty_subtree_hashes(&params, depth);
+
+        let witness = MembershipWitness {
+            identity_scalar: identity,
+            blinding,
+            merkle_path: tree.path(1).unwrap(),
+        };
+        let compact = compact_witness(&witness, &empty_hashes);
+        assert_eq!(compact.siblings.len(), 1);
+        assert_eq!(compact.empty_levels.count_ones() as usize, depth - 1);
+
+        let expanded = expand_witness(&compact, &empty_hashes).unwrap();
+        assert_eq!(expanded, witness);
+
+        let instance = MembershipInstance {
+            public_inputs: MembershipPublicInputs {
+                root: tree.root(),
+                commitment: commitments[1],
+            },
+            witness: expanded,
+        };
+        let mut rng = StdRng::seed_from_u64(57);
+        let pk = setup_membership_with_depth(&mut rng, depth).unwrap();
+        let proof = prove_membership(&pk, &instance, &mut rng).unwrap();
+        assert!(verify_membership(&pk.vk, &instance.public_inputs, &proof).unwrap());
+    }
+
+    #[test]
+    fn expand_witness_rejects_inconsistent_counts() {
+        let params = poseidon_params::<Fr>();
+        let empty_hashes = empty_subtree_hashes(&params, 4);
+        let witness = MembershipWitness {
+            identity_scalar: Fr::from(1u64),
+            blinding: Fr::from(2u64),
+            merkle_path: vec![(Fr::from(9u64), false), (empty_hashes[1], true)],
+        };
+        let mut compact = compact_witness(&witness, &empty_hashes);
+        compact.siblings.push(Fr::from(3u64));
+        assert!(expand_witness(&compact, &empty_hashes).is_err());
+    }
+}
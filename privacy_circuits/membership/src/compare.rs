@@ -0,0 +1,184 @@
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::bits::boolean::Boolean;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+
+// Shared comparison gadgets for range, ordering and non-membership circuits.
+// Comparisons go through a bit decomposition, which is only sound while
+// differences of two in-range values cannot wrap around the modulus, so widths
+// are capped two bits below the field size.
+pub fn max_compare_bits<F: PrimeField>() -> usize {
+    F::MODULUS_BIT_SIZE as usize - 2
+}
+
+fn check_bit_width<F: PrimeField>(bit_width: usize) -> Result<(), SynthesisError> {
+    if bit_width == 0 || bit_width > max_compare_bits::<F>() {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    Ok(())
+}
+
+// Enforces 0 <= v < 2^bit_width by witnessing `bit_width` bits that recompose to v.
+pub fn enforce_in_range<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    v: &FpVar<F>,
+    bit_width: usize,
+) -> Result<(), SynthesisError> {
+    check_bit_width::<F>(bit_width)?;
+    let value_bits = v.value().map(|value| value.into_bigint().to_bits_le());
+    let bits = (0..bit_width)
+        .map(|idx| {
+            Boolean::new_witness(cs.clone(), || {
+                value_bits
+                    .as_ref()
+                    .map(|bits| bits[idx])
+                    .map_err(|_| SynthesisError::AssignmentMissing)
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Boolean::le_bits_to_fp_var(&bits)?.enforce_equal(v)
+}
+
+// Enforces a < b for a, b < 2^bit_width, via b - a - 1 being in range.
+pub fn enforce_less_than<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+    bit_width: usize,
+) -> Result<(), SynthesisError> {
+    check_bit_width::<F>(bit_width)?;
+    enforce_in_range(cs.clone(), a, bit_width)?;
+    enforce_in_range(cs.clone(), b, bit_width)?;
+    let gap = b - a - FpVar::one();
+    enforce_in_range(cs, &gap, bit_width)
+}
+
+// Enforces a < b over the whole field, comparing the canonical bit decompositions
+// from the top bit down. Costlier than `enforce_less_than` but needs no bound on
+// the operands, so values near the modulus compare correctly.
+pub fn enforce_less_than_full<F: PrimeField>(
+    a: &FpVar<F>,
+    b: &FpVar<F>,
+) -> Result<(), SynthesisError> {
+    let mut less = Boolean::FALSE;
+    let mut equal = Boolean::TRUE;
+    for (a_bit, b_bit) in a.to_bits_le()?.iter().zip(b.to_bits_le()?).rev() {
+        let smaller_here = a_bit.not().and(&b_bit)?;
+        less = less.or(&equal.and(&smaller_here)?)?;
+        equal = equal.and(&a_bit.is_eq(&b_bit)?)?;
+    }
+    less.enforce_equal(&Boolean::TRUE)
+}
+
+fn check_native_bit_width(bit_width: usize) -> Result<(), String> {
+    let max = max_compare_bits::<Fr>();
+    if bit_width == 0 || bit_width > max {
+        return Err(format!("bit_width must be between 1 and {max}, got {bit_width}"));
+    }
+    Ok(())
+}
+
+pub fn in_range_native(v: Fr, bit_width: usize) -> Result<bool, String> {
+    check_native_bit_width(bit_width)?;
+    Ok(v.into_bigint().num_bits() as usize <= bit_width)
+}
+
+pub fn less_than_native(a: Fr, b: Fr, bit_width: usize) -> Result<bool, String> {
+    check_native_bit_width(bit_width)?;
+    if !in_range_native(a, bit_width)? || !in_range_native(b, bit_width)? {
+        return Ok(false);
+    }
+    Ok(a.into_bigint() < b.into_bigint())
+}
+
+pub fn less_than_full_native(a: Fr, b: Fr) -> bool {
+    a.into_bigint() < b.into_bigint()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        enforce_in_range, enforce_less_than, enforce_less_than_full, in_range_native,
+        less_than_full_native, less_than_native, max_compare_bits,
+    };
+    use ark_bn254::Fr;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+
+    fn in_range_holds(v: u64, bit_width: usize) -> Result<bool, SynthesisError> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let v = FpVar::new_witness(cs.clone(), || Ok(Fr::from(v)))?;
+        enforce_in_range(cs.clone(), &v, bit_width)?;
+        cs.is_satisfied()
+    }
+
+    fn less_than_holds(a: u64, b: u64, bit_width: usize) -> Result<bool, SynthesisError> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let a = FpVar::new_witness(cs.clone(), || Ok(Fr::from(a)))?;
+        let b = FpVar::new_witness(cs.clone(), || Ok(Fr::from(b)))?;
+        enforce_less_than(cs.clone(), &a, &b, bit_width)?;
+        cs.is_satisfied()
+    }
+
+    fn less_than_full_holds(a: Fr, b: Fr) -> Result<bool, SynthesisError> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let a = FpVar::new_witness(cs.clone(), || Ok(a))?;
+        let b = FpVar::new_witness(cs.clone(), || Ok(b))?;
+        enforce_less_than_full(&a, &b)?;
+        cs.is_satisfied()
+    }
+
+    #[test]
+    fn gadgets_accept_and_reject_boundaries() {
+        assert!(in_range_holds(0, 8).unwrap());
+        assert!(in_range_holds(255, 8).unwrap());
+        assert!(!in_range_holds(256, 8).unwrap());
+
+        assert!(less_than_holds(0, 255, 8).unwrap());
+        assert!(less_than_holds(254, 255, 8).unwrap());
+        assert!(!less_than_holds(255, 255, 8).unwrap());
+        assert!(!less_than_holds(200, 100, 8).unwrap());
+        assert!(!less_than_holds(1, 256, 8).unwrap());
+
+        let too_wide = max_compare_bits::<Fr>() + 1;
+        assert!(in_range_holds(1, too_wide).is_err());
+        assert!(less_than_holds(1, 2, too_wide).is_err());
+        assert!(in_range_holds(1, 0).is_err());
+    }
+
+    #[test]
+    fn native_counterparts_match_gadgets() {
+        for (a, b) in [(0u64, 255u64), (254, 255), (255, 255), (200, 100), (1, 256)] {
+            assert_eq!(
+                less_than_native(Fr::from(a), Fr::from(b), 8).unwrap(),
+                less_than_holds(a, b, 8).unwrap()
+            );
+        }
+        assert!(in_range_native(Fr::from(255u64), 8).unwrap());
+        assert!(!in_range_native(Fr::from(256u64), 8).unwrap());
+        assert!(!in_range_native(-Fr::from(1u64), 252).unwrap());
+        assert!(in_range_native(Fr::from(1u64), max_compare_bits::<Fr>() + 1).is_err());
+    }
+
+    #[test]
+    fn full_width_less_than_orders_the_whole_field() {
+        let top = -Fr::from(1u64);
+        let cases = [
+            (Fr::from(0u64), Fr::from(1u64)),
+            (Fr::from(1u64), Fr::from(0u64)),
+            (Fr::from(7u64), Fr::from(7u64)),
+            (Fr::from(u64::MAX), top),
+            (top, Fr::from(u64::MAX)),
+            (top - Fr::from(1u64), top),
+            (top, top),
+        ];
+        for (a, b) in cases {
+            assert_eq!(less_than_full_holds(a, b).unwrap(), less_than_full_native(a, b));
+        }
+        assert!(less_than_full_holds(Fr::from(0u64), top).unwrap());
+        assert!(!less_than_full_holds(top, Fr::from(0u64)).unwrap());
+    }
+}
@@ -0,0 +1,192 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::rand::RngCore;
+
+use crate::{
+    merkle_root_var, poseidon_hash_var, poseidon_params, verify_membership, MembershipInstance,
+    MembershipPublicInputs, DOMAIN_LEAF,
+};
+
+// Same statement as MembershipCircuit, but the commitment domain is a constant
+// fixed at setup. Keys generated for one domain reject commitments from another.
+#[derive(Clone, Debug, Default)]
+pub struct MembershipCircuitDomainCommitment<F: PrimeField> {
+    pub commitment_domain: F,
+    pub root: Option<F>,
+    pub commitment: Option<F>,
+    pub identity_scalar: Option<F>,
+    pub blinding: Option<F>,
+    pub expected_depth: usize,
+    pub merkle_path: Vec<(Option<F>, Option<bool>)>,
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for MembershipCircuitDomainCommitment<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        if self.expected_depth == 0 || self.merkle_path.len() != self.expected_depth {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let params = poseidon_params::<F>();
+
+        let root =
+            FpVar::new_input(cs.clone(), || self.root.ok_or(SynthesisError::AssignmentMissing))?;
+        let commitment_input = FpVar::new_input(cs.clone(), || {
+            self.commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let identity_scalar = FpVar::new_witness(cs.clone(), || {
+            self.identity_scalar.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let domain_commitment = FpVar::constant(self.commitment_domain);
+        let domain_leaf = FpVar::constant(F::from(DOMAIN_LEAF));
+
+        let commitment = poseidon_hash_var(
+            cs.clone(),
+            &params,
+            &[domain_commitment, identity_scalar, blinding],
+        )?;
+        commitment.enforce_equal(&commitment_input)?;
+
+        let leaf =
+            poseidon_hash_var(cs.clone(), &params, &[domain_leaf, commitment, FpVar::zero()])?;
+        let computed_root = merkle_root_var(cs, &params, leaf, self.merkle_path)?;
+        computed_root.enforce_equal(&root)?;
+        Ok(())
+    }
+}
+
+pub fn setup_membership_domain_commitment<R: RngCore>(
+    rng: &mut R,
+    depth: usize,
+    commitment_domain: Fr,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let zero = Fr::from(0u64);
+    let circuit = MembershipCircuitDomainCommitment::<Fr> {
+        commitment_domain,
+        root: Some(zero),
+        commitment: Some(zero),
+        identity_scalar: Some(zero),
+        blinding: Some(zero),
+        expected_depth: depth,
+        merkle_path: vec![(Some(zero), Some(false)); depth],
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+pub fn build_domain_commitment_circuit(
+    instance: &MembershipInstance,
+    commitment_domain: Fr,
+) -> MembershipCircuitDomainCommitment<Fr> {
+    MembershipCircuitDomainCommitment {
+        commitment_domain,
+        root: Some(instance.public_inputs.root),
+        commitment: Some(instance.public_inputs.commitment),
+        identity_scalar: Some(instance.witness.identity_scalar),
+        blinding: Some(instance.witness.blinding),
+        expected_depth: instance.witness.merkle_path.len(),
+        merkle_path: instance
+            .witness
+            .merkle_path
+            .iter()
+            .map(|(sibling, is_left)| (Some(*sibling), Some(*is_left)))
+            .collect(),
+    }
+}
+
+pub fn prove_membership_domain_commitment<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &MembershipInstance,
+    commitment_domain: Fr,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    let circuit = build_domain_commitment_circuit(instance, commitment_domain);
+    Groth16::<Bn254>::create_random_proof_with_reduction(circuit, pk, rng)
+}
+
+pub fn verify_membership_domain_commitment(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &MembershipPublicInputs,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    verify_membership(vk, public_inputs, proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_domain_commitment_circuit, prove_membership_domain_commitment,
+        setup_membership_domain_commitment, verify_membership_domain_commitment,
+    };
+    use crate::{
+        commitment_hash, commitment_hash_domain, leaf_hash, node_hash, poseidon_params,
+        MembershipInstance, MembershipPublicInputs, MembershipWitness,
+    };
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn domain_commitments_differ_and_bind_to_setup_domain() {
+        let params = poseidon_params::<Fr>();
+        let identity = Fr::from(31u64);
+        let blinding = Fr::from(32u64);
+        let domain_a = Fr::from(1001u64);
+        let domain_b = Fr::from(1002u64);
+
+        let commitment_a = commitment_hash_domain(&params, domain_a, identity, blinding);
+        let commitment_b = commitment_hash_domain(&params, domain_b, identity, blinding);
+        assert_ne!(commitment_a, commitment_b);
+        assert_eq!(
+            commitment_hash_domain(&params, Fr::from(1u64), identity, blinding),
+            commitment_hash(&params, identity, blinding)
+        );
+
+        let sibling = leaf_hash(&params, Fr::from(33u64));
+        let root = node_hash(&params, leaf_hash(&params, commitment_a), sibling);
+        let instance = MembershipInstance {
+            public_inputs: MembershipPublicInputs {
+                root,
+                commitment: commitment_a,
+            },
+            witness: MembershipWitness {
+                identity_scalar: identity,
+                blinding,
+                merkle_path: vec![(sibling, false)],
+            },
+        };
+
+        // The witness opens `commitment_a` only under domain A.
+        for (domain, expected) in [(domain_a, true), (domain_b, false)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            build_domain_commitment_circuit(&instance, domain)
+                .generate_constraints(cs.clone())
+                .unwrap();
+            assert_eq!(cs.is_satisfied().unwrap(), expected);
+        }
+
+        // Both keys come from the same randomness, so the domain constant is the only
+        // difference between them.
+        let mut setup_rng = StdRng::seed_from_u64(62);
+        let pk_a = setup_membership_domain_commitment(&mut setup_rng, 1, domain_a).unwrap();
+        let mut setup_rng = StdRng::seed_from_u64(62);
+        let pk_b = setup_membership_domain_commitment(&mut setup_rng, 1, domain_b).unwrap();
+        let mut rng = StdRng::seed_from_u64(63);
+        let proof =
+            prove_membership_domain_commitment(&pk_a, &instance, domain_a, &mut rng).unwrap();
+
+        assert!(
+            verify_membership_domain_commitment(&pk_a.vk, &instance.public_inputs, &proof)
+                .unwrap()
+        );
+        assert!(
+            !verify_membership_domain_commitment(&pk_b.vk, &instance.public_inputs, &proof)
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,272 @@
+use ark_std::string::{String, ToString};
+use core::fmt;
+
+use crate::PoseidonParamsSpec;
+
+// A single field that could not be read as a scalar. Shared with the other
+// statement crates, which decode their public inputs through membership.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldDecodeError {
+    pub field: String,
+    pub reason: String,
+}
+
+impl FieldDecodeError {
+    pub fn new(field: &str, reason: impl Into<String>) -> Self {
+        Self {
+            field: field.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for FieldDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.reason)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FieldDecodeError {}
+
+impl From<FieldDecodeError> for String {
+    fn from(err: FieldDecodeError) -> Self {
+        err.to_string()
+    }
+}
+
+// Failures from the membership schema parsers. Display keeps the wording of the
+// former string errors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MembershipError {
+    VersionMismatch {
+        field: String,
+        expected: u16,
+        got: u16,
+    },
+    StatementMismatch {
+        field: &'static str,
+        expected: u16,
+        got: u16,
+    },
+    DomainSepMismatch {
+        field: String,
+    },
+    LengthMismatch {
+        field: String,
+        expected: usize,
+        got: usize,
+    },
+    DepthMismatch {
+        field: String,
+        expected: usize,
+        got: usize,
+    },
+    ZeroDepth {
+        field: String,
+    },
+    EmptyField {
+        field: String,
+    },
+    TooLong {
+        field: String,
+        max: usize,
+    },
+    InvalidPoseidonSpec(PoseidonParamsSpec),
+    ValueNotInSet,
+    CommitmentMismatch,
+    RootMismatch,
+    FieldDecode(FieldDecodeError),
+}
+
+impl fmt::Display for MembershipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MembershipError::VersionMismatch {
+                field,
+                expected,
+                got,
+            } => write!(f, "{field}: version mismatch (expected {expected}, got {got})"),
+            MembershipError::StatementMismatch {
+                field,
+                expected,
+                got,
+            } => write!(f, "{field} mismatch (expected {expected}, got {got})"),
+            MembershipError::DomainSepMismatch { field } => {
+                write!(f, "{field}: domain_sep mismatch")
+            }
+            MembershipError::LengthMismatch {
+                field,
+                expected,
+                got,
+            } => write!(f, "{field} length mismatch: expected {expected}, got {got}"),
+            MembershipError::DepthMismatch {
+                field,
+                expected,
+                got,
+            } => write!(f, "{field} mismatch: expected {expected}, got {got}"),
+            MembershipError::ZeroDepth { field } => write!(f, "{field} must be > 0"),
+            MembershipError::EmptyField { field } => write!(f, "{field} must not be empty"),
+            MembershipError::TooLong { field, max } => write!(f, "{field} must be <= {max} bytes"),
+            MembershipError::InvalidPoseidonSpec(spec) => {
+                write!(f, "invalid poseidon spec {spec:?}")
+            }
+            MembershipError::ValueNotInSet => write!(f, "value is not in the allowed set"),
+            MembershipError::CommitmentMismatch => {
+                write!(f, "commitment does not match the witness opening")
+            }
+            MembershipError::RootMismatch => {
+                write!(f, "root does not match the root recomputed from the witness path")
+            }
+            MembershipError::FieldDecode(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MembershipError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MembershipError::FieldDecode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<FieldDecodeError> for MembershipError {
+    fn from(err: FieldDecodeError) -> Self {
+        MembershipError::FieldDecode(err)
+    }
+}
+
+// Lets the string-returning helpers and binaries keep using `?` on the parsers.
+impl From<MembershipError> for String {
+    fn from(err: MembershipError) -> Self {
+        err.to_string()
+    }
+}
+
+// A shipped constant that fails the startup self-test: the form checks of
+// `check_constant_form`, a collision in `check_domain_seps_distinct`, or a drifted
+// Poseidon configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConstantError {
+    Length {
+        label: String,
+        got: usize,
+    },
+    InvalidByte {
+        label: String,
+        byte: u8,
+        offset: usize,
+    },
+    MissingPrefix {
+        label: String,
+        prefix: String,
+    },
+    MissingPadding {
+        label: String,
+    },
+    ReducesToZero {
+        label: String,
+    },
+    Collision {
+        label: String,
+        other: String,
+    },
+    PoseidonParamsHash,
+}
+
+impl fmt::Display for ConstantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstantError::Length { label, got } => {
+                write!(f, "{label} must be {} bytes, got {got}", crate::FIELD_BYTES)
+            }
+            ConstantError::InvalidByte {
+                label,
+                byte,
+                offset,
+            } => write!(f, "{label} has invalid byte 0x{byte:02x} at offset {offset}"),
+            ConstantError::MissingPrefix { label, prefix } => {
+                write!(f, "{label} must start with {prefix}")
+            }
+            ConstantError::MissingPadding { label } => {
+                write!(f, "{label} must be right-padded with '_'")
+            }
+            ConstantError::ReducesToZero { label } => {
+                write!(f, "{label} reduces to zero in the field")
+            }
+            ConstantError::Collision { label, other } => {
+                write!(f, "{label} and {other} reduce to the same field element")
+            }
+            ConstantError::PoseidonParamsHash => {
+                write!(f, "poseidon parameter hash does not match the pinned value")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConstantError {}
+
+impl From<ConstantError> for String {
+    fn from(err: ConstantError) -> Self {
+        err.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FieldDecodeError, MembershipError};
+    use crate::fr_to_fixed_bytes;
+    use crate::test_support::{instance_v2_bytes, public_inputs_v2_bytes, sample_instance_v2};
+    use ark_bn254::Fr;
+
+    #[test]
+    fn parser_failures_can_be_matched_by_cause() {
+        let inputs = public_inputs_v2_bytes(&sample_instance_v2(2));
+
+        let mut bad = inputs.clone();
+        bad.schema_version += 1;
+        let err = bad.into_public_inputs_with_depth().unwrap_err();
+        assert!(matches!(
+            err,
+            MembershipError::VersionMismatch { expected, got, .. } if got == expected + 1
+        ));
+
+        let mut bad = inputs.clone();
+        bad.domain_sep[0] ^= 1;
+        let err = bad.into_public_inputs_with_depth().unwrap_err();
+        assert!(matches!(err, MembershipError::DomainSepMismatch { .. }));
+        assert_eq!(err.to_string(), "public_inputs.domain_sep: domain_sep mismatch");
+
+        let mut bad = inputs;
+        bad.root = [0xff; 32];
+        let err = bad.into_public_inputs_with_depth().unwrap_err();
+        assert!(matches!(
+            &err,
+            MembershipError::FieldDecode(FieldDecodeError { field, .. }) if field == "root"
+        ));
+        assert!(std::error::Error::source(&err).is_some());
+    }
+
+    #[test]
+    fn checked_parse_rejects_a_root_the_path_does_not_reach() {
+        let instance = sample_instance_v2(3);
+        let bytes = instance_v2_bytes(&instance);
+        let (checked, depth) = bytes.clone().into_instance_checked().unwrap();
+        assert_eq!(depth, 3);
+        assert_eq!(checked.public_inputs.root, instance.public_inputs.root);
+
+        let mut bad = bytes.clone();
+        bad.witness.merkle_directions[1] ^= true;
+        assert!(bad.clone().into_instance_with_depth().is_ok());
+        assert_eq!(bad.into_instance_checked().unwrap_err(), MembershipError::RootMismatch);
+
+        let mut bad = bytes;
+        bad.witness.blinding = fr_to_fixed_bytes(&Fr::from(7u64));
+        let err = bad.into_instance_checked().unwrap_err();
+        assert_eq!(err, MembershipError::CommitmentMismatch);
+    }
+}
@@ -0,0 +1,102 @@
+use ark_bn254::Bn254;
+use ark_groth16::{Proof, VerifyingKey};
+use ark_relations::r1cs::SynthesisError;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{
+    ensure_domain_sep, fr_from_bytes, fr_from_bytes_canonical, verify_membership_v2,
+    MembershipError, MembershipPublicInputsV2,
+};
+
+#[derive(Debug)]
+pub enum FieldMapError {
+    MissingField(&'static str),
+    InvalidField(MembershipError),
+    Synthesis(SynthesisError),
+}
+
+impl fmt::Display for FieldMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldMapError::MissingField(name) => write!(f, "missing field: {name}"),
+            FieldMapError::InvalidField(err) => write!(f, "invalid field: {err}"),
+            FieldMapError::Synthesis(err) => write!(f, "verification failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for FieldMapError {}
+
+fn field<'a>(
+    fields: &'a HashMap<String, [u8; 32]>,
+    name: &'static str,
+) -> Result<&'a [u8; 32], FieldMapError> {
+    fields.get(name).ok_or(FieldMapError::MissingField(name))
+}
+
+// Public inputs keyed by name, as web frameworks hand JSON objects over. Keys
+// other than the four V2 inputs are ignored.
+pub fn membership_v2_inputs_from_map(
+    fields: &HashMap<String, [u8; 32]>,
+) -> Result<MembershipPublicInputsV2, FieldMapError> {
+    let root = field(fields, "root")?;
+    let commitment = field(fields, "commitment")?;
+    let domain_sep = field(fields, "domain_sep")?;
+    let ctx_hash = field(fields, "ctx_hash")?;
+    ensure_domain_sep("domain_sep", domain_sep).map_err(FieldMapError::InvalidField)?;
+
+    let invalid = |err| FieldMapError::InvalidField(MembershipError::FieldDecode(err));
+    Ok(MembershipPublicInputsV2 {
+        root: fr_from_bytes_canonical("root", root).map_err(invalid)?,
+        commitment: fr_from_bytes_canonical("commitment", commitment).map_err(invalid)?,
+        domain_sep: fr_from_bytes("domain_sep", domain_sep).map_err(invalid)?,
+        ctx_hash: fr_from_bytes_canonical("ctx_hash", ctx_hash).map_err(invalid)?,
+    })
+}
+
+pub fn verify_membership_v2_from_map(
+    vk: &VerifyingKey<Bn254>,
+    fields: &HashMap<String, [u8; 32]>,
+    proof: &Proof<Bn254>,
+) -> Result<bool, FieldMapError> {
+    let public_inputs = membership_v2_inputs_from_map(fields)?;
+    verify_membership_v2(vk, &public_inputs, proof).map_err(FieldMapError::Synthesis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_membership_v2_from_map, FieldMapError};
+    use crate::test_support::{public_inputs_v2_bytes, sample_instance_v2};
+    use crate::{prove_membership_v2, setup_membership_with_depth_v2};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use std::collections::HashMap;
+
+    #[test]
+    fn named_fields_verify_and_missing_ones_are_reported() {
+        let mut rng = StdRng::seed_from_u64(97);
+        let pk = setup_membership_with_depth_v2(&mut rng, 2).unwrap();
+        let instance = sample_instance_v2(2);
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+
+        let inputs = public_inputs_v2_bytes(&instance);
+        let mut fields: HashMap<String, [u8; 32]> = [
+            ("root", inputs.root),
+            ("commitment", inputs.commitment),
+            ("domain_sep", inputs.domain_sep),
+            ("ctx_hash", inputs.ctx_hash),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value))
+        .collect();
+        assert!(verify_membership_v2_from_map(&pk.vk, &fields, &proof).unwrap());
+
+        fields.insert("root".to_string(), inputs.commitment);
+        assert!(!verify_membership_v2_from_map(&pk.vk, &fields, &proof).unwrap());
+
+        fields.remove("ctx_hash");
+        let err = verify_membership_v2_from_map(&pk.vk, &fields, &proof).unwrap_err();
+        assert!(matches!(err, FieldMapError::MissingField("ctx_hash")));
+        assert_eq!(err.to_string(), "missing field: ctx_hash");
+    }
+}
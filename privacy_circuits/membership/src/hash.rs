@@ -0,0 +1,100 @@
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_relations::r1cs::{ConstraintSystemRef, SynthesisError};
+use ark_sponge::poseidon::PoseidonConfig;
+use core::fmt;
+
+use crate::{poseidon_hash_native, poseidon_hash_var, poseidon_params};
+
+// Hash used by the circuits for commitments, leaves, nodes and tags. A circuit
+// parameterized over `H` must get the same digest from `hash` natively as from
+// `hash_var` in-circuit, otherwise honest witnesses stop satisfying it. Swapping
+// the hash changes every key and public value, so it is a new statement version.
+pub trait CircuitHash: Clone + fmt::Debug + Default {
+    type Params<F: PrimeField>;
+
+    fn params<F: PrimeField>() -> Self::Params<F>;
+
+    fn hash(params: &Self::Params<Fr>, inputs: &[Fr]) -> Fr;
+
+    fn hash_var<F: PrimeField>(
+        cs: ConstraintSystemRef<F>,
+        params: &Self::Params<F>,
+        inputs: &[FpVar<F>],
+    ) -> Result<FpVar<F>, SynthesisError>;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Poseidon;
+
+impl CircuitHash for Poseidon {
+    type Params<F: PrimeField> = PoseidonConfig<F>;
+
+    fn params<F: PrimeField>() -> PoseidonConfig<F> {
+        poseidon_params::<F>()
+    }
+
+    fn hash(params: &PoseidonConfig<Fr>, inputs: &[Fr]) -> Fr {
+        poseidon_hash_native(params, inputs)
+    }
+
+    fn hash_var<F: PrimeField>(
+        cs: ConstraintSystemRef<F>,
+        params: &PoseidonConfig<F>,
+        inputs: &[FpVar<F>],
+    ) -> Result<FpVar<F>, SynthesisError> {
+        poseidon_hash_var(cs, params, inputs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CircuitHash, Poseidon};
+    use crate::{
+        build_circuit, commitment_hash, leaf_hash, node_hash, poseidon_params, prove_membership,
+        setup_membership_with_depth, verify_membership, MembershipCircuitWithHash,
+        MembershipInstance, MembershipPublicInputs, MembershipWitness, DOMAIN_COMMITMENT,
+    };
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn default_hash_circuit_proves_and_verifies_as_before() {
+        let params = poseidon_params::<Fr>();
+        let identity_scalar = Fr::from(3u64);
+        let blinding = Fr::from(4u64);
+        let commitment = commitment_hash(&params, identity_scalar, blinding);
+        let inputs = [Fr::from(DOMAIN_COMMITMENT), identity_scalar, blinding];
+        assert_eq!(Poseidon::hash(&Poseidon::params::<Fr>(), &inputs), commitment);
+
+        let sibling = leaf_hash(&params, Fr::from(5u64));
+        let instance = MembershipInstance {
+            public_inputs: MembershipPublicInputs {
+                root: node_hash(&params, sibling, leaf_hash(&params, commitment)),
+                commitment,
+            },
+            witness: MembershipWitness {
+                identity_scalar,
+                blinding,
+                merkle_path: vec![(sibling, true)],
+            },
+        };
+
+        let default_cs = ConstraintSystem::<Fr>::new_ref();
+        build_circuit(&instance)
+            .generate_constraints(default_cs.clone())
+            .unwrap();
+        let explicit = MembershipCircuitWithHash::<Fr, Poseidon>::new(build_circuit(&instance));
+        let explicit_cs = ConstraintSystem::<Fr>::new_ref();
+        explicit.generate_constraints(explicit_cs.clone()).unwrap();
+        assert!(default_cs.is_satisfied().unwrap());
+        assert_eq!(default_cs.num_constraints(), explicit_cs.num_constraints());
+
+        let mut rng = StdRng::seed_from_u64(59);
+        let pk = setup_membership_with_depth(&mut rng, 1).unwrap();
+        let proof = prove_membership(&pk, &instance, &mut rng).unwrap();
+        assert!(verify_membership(&pk.vk, &instance.public_inputs, &proof).unwrap());
+    }
+}
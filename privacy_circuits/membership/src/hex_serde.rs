@@ -0,0 +1,215 @@
+use ark_std::{format, string::String, vec::Vec};
+use core::fmt;
+#[cfg(feature = "std")]
+use serde::de::DeserializeOwned;
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+// Serde adapters for the byte fields of the *Bytes schemas. Human-readable formats
+// (JSON) get `0x`-prefixed lowercase hex strings; bincode and other binary formats
+// keep the encoding the fields had before, so existing instance files still load.
+// Reading JSON also accepts bare hex and arrays of byte values.
+
+#[cfg(feature = "std")]
+pub fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|err| err.to_string())
+}
+
+#[cfg(feature = "std")]
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, String> {
+    serde_json::from_str(json).map_err(|err| err.to_string())
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("0x{hex}")
+}
+
+fn decode(value: &str) -> Result<Vec<u8>, String> {
+    let hex = value.strip_prefix("0x").unwrap_or(value);
+    if hex.len() % 2 == 1 || !hex.is_ascii() {
+        return Err(format!("invalid hex string {value:?}"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|idx| {
+            u8::from_str_radix(&hex[idx..idx + 2], 16)
+                .map_err(|_| format!("invalid hex string {value:?}"))
+        })
+        .collect()
+}
+
+struct HexVisitor;
+
+impl<'de> Visitor<'de> for HexVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a hex string or a byte array")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Vec<u8>, E> {
+        decode(value).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(value.to_vec())
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut out = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(byte) = seq.next_element::<u8>()? {
+            out.push(byte);
+        }
+        Ok(out)
+    }
+}
+
+struct HexRef<'a>(&'a [u8]);
+
+impl Serialize for HexRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&encode(self.0))
+    }
+}
+
+struct Hex(Vec<u8>);
+
+impl<'de> Deserialize<'de> for Hex {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(HexVisitor).map(Hex)
+    }
+}
+
+// `[u8; 32]` fields; binary formats keep serde's 32-element tuple.
+pub mod array {
+    use super::{encode, Hex};
+    use ark_std::format;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&encode(value))
+        } else {
+            value.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        if !deserializer.is_human_readable() {
+            return <[u8; 32]>::deserialize(deserializer);
+        }
+        let Hex(bytes) = Hex::deserialize(deserializer)?;
+        let len = bytes.len();
+        bytes
+            .try_into()
+            .map_err(|_| D::Error::custom(format!("expected 32 bytes, got {len}")))
+    }
+}
+
+// `Vec<u8>` fields; binary formats keep the `serde_bytes` encoding.
+pub mod bytes {
+    use super::{encode, Hex};
+    use ark_std::vec::Vec;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&encode(value))
+        } else {
+            serde_bytes::serialize(value, serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        if deserializer.is_human_readable() {
+            Hex::deserialize(deserializer).map(|Hex(bytes)| bytes)
+        } else {
+            serde_bytes::deserialize(deserializer)
+        }
+    }
+}
+
+// `Vec<Vec<u8>>` fields such as Merkle siblings; binary formats keep the plain
+// nested sequence.
+pub mod bytes_vec {
+    use super::{Hex, HexRef};
+    use ark_std::vec::Vec;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &[Vec<u8>], serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_seq(value.iter().map(|bytes| HexRef(bytes.as_slice())))
+        } else {
+            value.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Vec<u8>>, D::Error> {
+        if deserializer.is_human_readable() {
+            let items: Vec<Hex> = Vec::deserialize(deserializer)?;
+            Ok(items.into_iter().map(|Hex(bytes)| bytes).collect())
+        } else {
+            Vec::deserialize(deserializer)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_support::{public_inputs_v2_bytes, sample_instance_v2};
+    use crate::{
+        fr_to_fixed_bytes, MembershipInstanceV2Bytes, MembershipWitnessV2Bytes,
+        MEMBERSHIP_INSTANCE_VERSION_V2,
+    };
+
+    #[test]
+    fn membership_v2_bytes_json_round_trips_as_hex() {
+        let instance = sample_instance_v2(3);
+        let witness = &instance.witness;
+        let bytes = MembershipInstanceV2Bytes {
+            schema_version: MEMBERSHIP_INSTANCE_VERSION_V2,
+            public_inputs: public_inputs_v2_bytes(&instance),
+            witness: MembershipWitnessV2Bytes {
+                schema_version: MEMBERSHIP_INSTANCE_VERSION_V2,
+                depth: 3,
+                identity_scalar: fr_to_fixed_bytes(&witness.identity_scalar),
+                blinding: fr_to_fixed_bytes(&witness.blinding),
+                merkle_siblings: witness
+                    .merkle_path
+                    .iter()
+                    .map(|(sibling, _)| fr_to_fixed_bytes(sibling))
+                    .collect(),
+                merkle_directions: witness.merkle_path.iter().map(|(_, left)| *left).collect(),
+            },
+        };
+
+        let json = bytes.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let root = value["public_inputs"]["root"].as_str().unwrap();
+        assert_eq!(root.len(), 66);
+        assert!(root.starts_with("0x"));
+        assert!(value["witness"]["merkle_siblings"][2].as_str().unwrap().starts_with("0x"));
+        assert_eq!(MembershipInstanceV2Bytes::from_json(&json).unwrap(), bytes);
+
+        // Bare hex and byte arrays are accepted on input.
+        let bare = json.replace("\"0x", "\"");
+        assert_eq!(MembershipInstanceV2Bytes::from_json(&bare).unwrap(), bytes);
+        let mut value = value;
+        value["public_inputs"]["root"] = serde_json::json!(bytes.public_inputs.root.to_vec());
+        let from_array = MembershipInstanceV2Bytes::from_json(&value.to_string()).unwrap();
+        assert_eq!(from_array, bytes);
+        value["public_inputs"]["root"] = serde_json::json!("0xabcd");
+        assert!(MembershipInstanceV2Bytes::from_json(&value.to_string()).is_err());
+
+        // bincode keeps the raw layout: 2 + 2 + 2 + 4 header bytes and four fields.
+        let encoded = bincode::serialize(&bytes.public_inputs).unwrap();
+        assert_eq!(encoded.len(), 10 + 4 * 32);
+        let decoded: MembershipInstanceV2Bytes =
+            bincode::deserialize(&bincode::serialize(&bytes).unwrap()).unwrap();
+        assert_eq!(decoded, bytes);
+    }
+}
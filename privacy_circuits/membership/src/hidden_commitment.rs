@@ -0,0 +1,210 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ensure_depth, ensure_version_u16, fr_from_bytes_canonical, fr_to_fixed_bytes, merkle_root_var,
+    poseidon_hash_var, poseidon_params, MembershipError, MembershipInstance, DOMAIN_COMMITMENT,
+    DOMAIN_LEAF,
+};
+
+pub const MEMBERSHIP_INSTANCE_VERSION_V3: u16 = 3;
+
+// Membership with the commitment kept private: only the root is public, so
+// proofs from the same member cannot be linked through a shared commitment.
+// The circuit still proves an opening (identity, blinding) of some leaf.
+#[derive(Clone, Debug, Default)]
+pub struct MembershipCircuitHiddenCommitment<F: PrimeField> {
+    pub root: Option<F>,
+    pub identity_scalar: Option<F>,
+    pub blinding: Option<F>,
+    pub expected_depth: usize,
+    pub merkle_path: Vec<(Option<F>, Option<bool>)>,
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for MembershipCircuitHiddenCommitment<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        if self.expected_depth == 0 || self.merkle_path.len() != self.expected_depth {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let params = poseidon_params::<F>();
+
+        let root =
+            FpVar::new_input(cs.clone(), || self.root.ok_or(SynthesisError::AssignmentMissing))?;
+        let identity_scalar = FpVar::new_witness(cs.clone(), || {
+            self.identity_scalar.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let domain_commitment = FpVar::constant(F::from(DOMAIN_COMMITMENT));
+        let domain_leaf = FpVar::constant(F::from(DOMAIN_LEAF));
+
+        let commitment = poseidon_hash_var(
+            cs.clone(),
+            &params,
+            &[domain_commitment, identity_scalar, blinding],
+        )?;
+        let leaf =
+            poseidon_hash_var(cs.clone(), &params, &[domain_leaf, commitment, FpVar::zero()])?;
+        let computed_root = merkle_root_var(cs, &params, leaf, self.merkle_path)?;
+        computed_root.enforce_equal(&root)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MembershipPublicInputsV3 {
+    pub root: Fr,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MembershipPublicInputsV3Bytes {
+    pub schema_version: u16,
+    pub depth: u32,
+    pub root: [u8; 32],
+}
+
+impl MembershipPublicInputsV3Bytes {
+    pub fn from_instance(instance: &MembershipInstance) -> Self {
+        let mut root = [0u8; 32];
+        root.copy_from_slice(&fr_to_fixed_bytes(&instance.public_inputs.root));
+        Self {
+            schema_version: MEMBERSHIP_INSTANCE_VERSION_V3,
+            depth: instance.witness.merkle_path.len() as u32,
+            root,
+        }
+    }
+
+    pub fn into_public_inputs_with_depth(
+        self,
+    ) -> Result<(MembershipPublicInputsV3, usize), MembershipError> {
+        ensure_version_u16(
+            "public_inputs.schema_version",
+            self.schema_version,
+            MEMBERSHIP_INSTANCE_VERSION_V3,
+        )?;
+        let depth = ensure_depth("public_inputs.depth", self.depth as usize)?;
+        let inputs = MembershipPublicInputsV3 {
+            root: fr_from_bytes_canonical("root", &self.root)?,
+        };
+        Ok((inputs, depth))
+    }
+}
+
+pub fn membership_v3_field_inputs(public_inputs: &MembershipPublicInputsV3) -> Vec<Fr> {
+    vec![public_inputs.root]
+}
+
+pub fn build_circuit_hidden_commitment(
+    instance: &MembershipInstance,
+) -> MembershipCircuitHiddenCommitment<Fr> {
+    MembershipCircuitHiddenCommitment {
+        root: Some(instance.public_inputs.root),
+        identity_scalar: Some(instance.witness.identity_scalar),
+        blinding: Some(instance.witness.blinding),
+        expected_depth: instance.witness.merkle_path.len(),
+        merkle_path: instance
+            .witness
+            .merkle_path
+            .iter()
+            .map(|(sibling, is_left)| (Some(*sibling), Some(*is_left)))
+            .collect(),
+    }
+}
+
+pub fn setup_membership_hidden_commitment<R: RngCore>(
+    rng: &mut R,
+    depth: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let zero = Fr::from(0u64);
+    let circuit = MembershipCircuitHiddenCommitment::<Fr> {
+        root: Some(zero),
+        identity_scalar: Some(zero),
+        blinding: Some(zero),
+        expected_depth: depth,
+        merkle_path: vec![(Some(zero), Some(false)); depth],
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+// The instance's public commitment is ignored; it never reaches the proof.
+pub fn prove_membership_hidden_commitment<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &MembershipInstance,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    Groth16::<Bn254>::create_random_proof_with_reduction(
+        build_circuit_hidden_commitment(instance),
+        pk,
+        rng,
+    )
+}
+
+pub fn verify_membership_hidden_commitment(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &MembershipPublicInputsV3,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    let pvk = prepare_verifying_key(vk);
+    Groth16::<Bn254>::verify_proof(&pvk, proof, &membership_v3_field_inputs(public_inputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        membership_v3_field_inputs, prove_membership_hidden_commitment,
+        setup_membership_hidden_commitment, verify_membership_hidden_commitment,
+        MembershipPublicInputsV3Bytes,
+    };
+    use crate::{
+        commitment_hash, leaf_hash, node_hash, poseidon_params, MembershipInstance,
+        MembershipPublicInputs, MembershipWitness,
+    };
+    use ark_bn254::Fr;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn hidden_commitment_proof_verifies_against_root_only() {
+        let params = poseidon_params::<Fr>();
+        let identity = Fr::from(61u64);
+        let blinding = Fr::from(62u64);
+        let commitment = commitment_hash(&params, identity, blinding);
+        let sibling = leaf_hash(&params, Fr::from(63u64));
+        let instance = MembershipInstance {
+            public_inputs: MembershipPublicInputs {
+                root: node_hash(&params, sibling, leaf_hash(&params, commitment)),
+                commitment,
+            },
+            witness: MembershipWitness {
+                identity_scalar: identity,
+                blinding,
+                merkle_path: vec![(sibling, true)],
+            },
+        };
+
+        let mut rng = StdRng::seed_from_u64(86);
+        let pk = setup_membership_hidden_commitment(&mut rng, 1).unwrap();
+        assert_eq!(pk.vk.gamma_abc_g1.len(), 2);
+        let proof = prove_membership_hidden_commitment(&pk, &instance, &mut rng).unwrap();
+
+        let (public_inputs, depth) = MembershipPublicInputsV3Bytes::from_instance(&instance)
+            .into_public_inputs_with_depth()
+            .unwrap();
+        assert_eq!(depth, 1);
+        let field_inputs = membership_v3_field_inputs(&public_inputs);
+        assert_eq!(field_inputs, vec![instance.public_inputs.root]);
+        assert!(!field_inputs.contains(&commitment));
+        assert!(verify_membership_hidden_commitment(&pk.vk, &public_inputs, &proof).unwrap());
+
+        let mut wrong_root = public_inputs.clone();
+        wrong_root.root += Fr::from(1u64);
+        assert!(!verify_membership_hidden_commitment(&pk.vk, &wrong_root, &proof).unwrap());
+    }
+}
@@ -0,0 +1,928 @@
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::{Proof, ProvingKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Read, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+use crate::{fixed_bytes, fr_from_bytes_canonical, proof_from_bytes, PoseidonParamsSpec};
+
+// Defaults sized well above the largest artifacts the setup binaries produce for
+// MAX_TREE_DEPTH; override per-call where a deployment needs more.
+pub const DEFAULT_MAX_PK_BYTES: u64 = 1 << 30;
+pub const DEFAULT_MAX_VK_BYTES: u64 = 1 << 20;
+pub const DEFAULT_MAX_PROOF_BYTES: u64 = 1 << 12;
+// Instance and public-input files stay a few KiB even with 256 siblings.
+pub const DEFAULT_MAX_INSTANCE_BYTES: u64 = 1 << 16;
+
+// A BN254 Groth16 proof is always A (G1), B (G2) and C (G1): 32 + 64 + 32 bytes
+// compressed, twice that uncompressed. Lets callers size buffers and reject
+// proofs of the wrong length before deserializing them.
+pub const PROOF_SIZE_COMPRESSED: usize = 128;
+pub const PROOF_SIZE_UNCOMPRESSED: usize = 256;
+
+pub fn proof_serialized_size(compressed: bool) -> usize {
+    if compressed {
+        PROOF_SIZE_COMPRESSED
+    } else {
+        PROOF_SIZE_UNCOMPRESSED
+    }
+}
+
+// Rejects files over `max_bytes` from their metadata before allocating, then reads
+// at most `max_bytes + 1` so a file growing underneath us is still caught.
+pub fn read_file_capped(path: &str, max_bytes: u64) -> Result<Vec<u8>, String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    let len = file.metadata().map_err(|err| err.to_string())?.len();
+    if len > max_bytes {
+        return Err(format!(
+            "{path}: file is {len} bytes, exceeds cap of {max_bytes} bytes"
+        ));
+    }
+    let mut data = Vec::with_capacity(len as usize);
+    file.take(max_bytes + 1)
+        .read_to_end(&mut data)
+        .map_err(|err| err.to_string())?;
+    if data.len() as u64 > max_bytes {
+        return Err(format!("{path}: file exceeds cap of {max_bytes} bytes"));
+    }
+    Ok(data)
+}
+
+// `bincode::deserialize` with the same encoding, but decoding stops once
+// `max_bytes` have been consumed, so a crafted length prefix cannot drive an
+// allocation past the cap.
+pub fn bincode_deserialize_capped<T: DeserializeOwned>(
+    data: &[u8],
+    max_bytes: u64,
+) -> Result<T, String> {
+    if data.len() as u64 > max_bytes {
+        return Err(format!(
+            "data is {} bytes, exceeds cap of {max_bytes} bytes",
+            data.len()
+        ));
+    }
+    bincode::options()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(max_bytes)
+        .deserialize(data)
+        .map_err(|err| err.to_string())
+}
+
+pub fn read_proving_key_capped(path: &str, max_bytes: u64) -> Result<ProvingKey<Bn254>, String> {
+    read_proving_key_as(path, max_bytes, false)
+}
+
+pub fn read_verifying_key_capped(
+    path: &str,
+    max_bytes: u64,
+) -> Result<VerifyingKey<Bn254>, String> {
+    read_verifying_key_as(path, max_bytes, false)
+}
+
+// Accepts either proof encoding, told apart by length.
+pub fn read_proof_capped(path: &str, max_bytes: u64) -> Result<Proof<Bn254>, String> {
+    let data = read_file_capped(path, max_bytes)?;
+    proof_from_bytes(&data).map_err(|err| format!("{path}: {err}"))
+}
+
+pub fn read_proving_key_as(
+    path: &str,
+    max_bytes: u64,
+    compressed: bool,
+) -> Result<ProvingKey<Bn254>, String> {
+    let data = read_file_capped(path, max_bytes)?;
+    decode_canonical(&data, compressed).map_err(|err| format!("{path}: {err}"))
+}
+
+pub fn read_verifying_key_as(
+    path: &str,
+    max_bytes: u64,
+    compressed: bool,
+) -> Result<VerifyingKey<Bn254>, String> {
+    let data = read_file_capped(path, max_bytes)?;
+    decode_canonical(&data, compressed).map_err(|err| format!("{path}: {err}"))
+}
+
+// Unlike `read_proof_capped`, only the requested encoding is accepted.
+pub fn read_proof_as(
+    path: &str,
+    max_bytes: u64,
+    compressed: bool,
+) -> Result<Proof<Bn254>, String> {
+    let data = read_file_capped(path, max_bytes)?;
+    decode_canonical(&data, compressed).map_err(|err| format!("{path}: {err}"))
+}
+
+// For keys and proofs whose mode is not known up front: uncompressed is tried
+// first, then compressed, and the file is rejected only if neither decodes.
+pub fn read_verifying_key_auto(
+    path: &str,
+    max_bytes: u64,
+) -> Result<VerifyingKey<Bn254>, String> {
+    let data = read_file_capped(path, max_bytes)?;
+    decode_canonical_auto(&data).map_err(|err| format!("{path}: {err}"))
+}
+
+pub fn read_proof_auto(path: &str, max_bytes: u64) -> Result<Proof<Bn254>, String> {
+    let data = read_file_capped(path, max_bytes)?;
+    decode_canonical_auto(&data).map_err(|err| format!("{path}: {err}"))
+}
+
+pub fn decode_canonical_auto<T: CanonicalDeserialize>(data: &[u8]) -> Result<T, String> {
+    decode_exact(data, false).or_else(|uncompressed| {
+        decode_exact(data, true).map_err(|compressed| {
+            format!(
+                "not a valid encoding in either mode (uncompressed: {uncompressed}; \
+                 compressed: {compressed})"
+            )
+        })
+    })
+}
+
+// arkworks output carries no marker for its encoding, so data is decoded only in
+// the mode asked for and must be consumed exactly. If the other mode would have
+// decoded it, the error names the flag to change instead of reporting bad bytes.
+pub fn decode_canonical<T: CanonicalDeserialize>(
+    data: &[u8],
+    compressed: bool,
+) -> Result<T, String> {
+    decode_exact(data, compressed).map_err(|err| {
+        match decode_exact::<T>(data, !compressed) {
+            Ok(_) if compressed => "data is uncompressed; rerun without --compressed".to_string(),
+            Ok(_) => "data is compressed; rerun with --compressed".to_string(),
+            Err(_) => err,
+        }
+    })
+}
+
+fn decode_exact<T: CanonicalDeserialize>(data: &[u8], compressed: bool) -> Result<T, String> {
+    let mut reader = data;
+    let value = if compressed {
+        T::deserialize_compressed(&mut reader)
+    } else {
+        T::deserialize_uncompressed(&mut reader)
+    }
+    .map_err(|err| err.to_string())?;
+    if !reader.is_empty() {
+        return Err(format!("{} trailing bytes after encoded value", reader.len()));
+    }
+    Ok(value)
+}
+
+pub const SWAPPED_ARGUMENTS_HINT: &str =
+    "the --public-inputs file looks like a proof; did you swap the arguments?";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlobKind {
+    LikelyProof,
+    LikelyPublicInputs,
+}
+
+// A blob counts as a proof only if it decodes as exactly one uncompressed,
+// on-curve Groth16 proof with no trailing bytes; everything else is assumed to be
+// bincode public inputs.
+pub fn classify_blob(data: &[u8]) -> BlobKind {
+    let mut reader = data;
+    match Proof::<Bn254>::deserialize_uncompressed(&mut reader) {
+        Ok(_) if reader.is_empty() => BlobKind::LikelyProof,
+        _ => BlobKind::LikelyPublicInputs,
+    }
+}
+
+// Decodes and converts a public-inputs blob. On failure at either step, a blob
+// that is really a proof gets SWAPPED_ARGUMENTS_HINT instead of the bincode or
+// schema error, since bincode happily reads the leading bytes of a proof.
+pub fn decode_public_inputs<T: DeserializeOwned, U, E: Into<String>>(
+    data: &[u8],
+    convert: impl FnOnce(T) -> Result<U, E>,
+) -> Result<U, String> {
+    bincode::deserialize::<T>(data)
+        .map_err(|err| err.to_string())
+        .and_then(|value| convert(value).map_err(Into::into))
+        .map_err(|err| match classify_blob(data) {
+            BlobKind::LikelyProof => SWAPPED_ARGUMENTS_HINT.to_string(),
+            BlobKind::LikelyPublicInputs => err,
+        })
+}
+
+// For the verify binaries' `--public-inputs <path> --proof <path>` form. Public
+// inputs are decoded before the proof is read so that swapped paths are reported
+// as such rather than as a bad proof.
+pub fn read_public_inputs_then_proof<U>(
+    inputs_path: &str,
+    proof_path: &str,
+    decode: impl FnOnce(&[u8]) -> Result<U, String>,
+) -> Result<(U, Proof<Bn254>), String> {
+    let public_inputs = read_file_capped(inputs_path, DEFAULT_MAX_INSTANCE_BYTES)
+        .and_then(|data| decode(&data))
+        .map_err(|err| format!("failed to read public inputs: {err}"))?;
+    let proof = read_proof_auto(proof_path, DEFAULT_MAX_PROOF_BYTES)
+        .map_err(|err| format!("failed to read proof: {err}"))?;
+    Ok((public_inputs, proof))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn from_hex_32(label: &str, hex: &str) -> Result<[u8; 32], String> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return Err(format!("{label}: expected 64 hex characters"));
+    }
+    let mut out = [0u8; 32];
+    for (idx, byte) in out.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * idx..2 * idx + 2], 16)
+            .map_err(|_| format!("{label}: invalid hex"))?;
+    }
+    Ok(out)
+}
+
+// Merkle path exported as one `sibling_hex,is_left` row per level, leaf first.
+// Commas or tabs separate the columns; a leading `0x`, blank lines, `#` comments
+// and a `sibling...` header row are accepted. Siblings must be canonical field
+// elements and the row count must equal `depth`.
+pub fn read_path_file(path: &str, depth: usize) -> Result<Vec<(Fr, bool)>, String> {
+    let data = fs::read_to_string(path).map_err(|err| format!("failed to read {path}: {err}"))?;
+    parse_path_rows(&data, depth)
+}
+
+pub fn parse_path_rows(data: &str, depth: usize) -> Result<Vec<(Fr, bool)>, String> {
+    let mut path = Vec::with_capacity(depth);
+    for (idx, line) in data.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let label = format!("path row {}", idx + 1);
+        let columns: Vec<&str> = line.split([',', '\t']).map(str::trim).collect();
+        let [sibling_hex, is_left] = columns[..] else {
+            return Err(format!("{label}: expected 2 columns, got {}", columns.len()));
+        };
+        if path.is_empty() && sibling_hex.starts_with("sibling") {
+            continue;
+        }
+        let hex = sibling_hex.strip_prefix("0x").unwrap_or(sibling_hex);
+        let sibling = fr_from_bytes_canonical(&label, &from_hex_32(&label, hex)?)?;
+        let is_left = match is_left.to_ascii_lowercase().as_str() {
+            "1" | "true" => true,
+            "0" | "false" => false,
+            other => return Err(format!("{label}: invalid is_left value {other:?}")),
+        };
+        path.push((sibling, is_left));
+    }
+    if path.len() != depth {
+        return Err(format!("path file has {} rows, expected depth {depth}", path.len()));
+    }
+    Ok(path)
+}
+
+// `--seed` for the setup binaries: exactly 32 bytes as 64 hex characters, with an
+// optional `0x` prefix.
+pub fn parse_seed_hex(value: &str) -> Result<[u8; 32], String> {
+    from_hex_32("--seed", value.strip_prefix("0x").unwrap_or(value))
+}
+
+// A field value given on the command line: `0x`-prefixed hex or standard base64
+// (padding optional), at most 32 bytes, left-padded big-endian like the Python
+// `fixed_bytes` helper.
+pub fn parse_field_arg(label: &str, value: &str) -> Result<[u8; 32], String> {
+    let bytes = match value.strip_prefix("0x") {
+        Some(hex) => decode_hex(label, hex)?,
+        None => decode_base64(label, value)?,
+    };
+    if bytes.len() > 32 {
+        return Err(format!("{label}: {} bytes is more than the 32-byte maximum", bytes.len()));
+    }
+    Ok(fixed_bytes(label, &bytes)?)
+}
+
+fn decode_hex(label: &str, hex: &str) -> Result<Vec<u8>, String> {
+    if hex.len() % 2 == 1 || !hex.is_ascii() {
+        return Err(format!("{label}: invalid hex"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|idx| {
+            u8::from_str_radix(&hex[idx..idx + 2], 16).map_err(|_| format!("{label}: invalid hex"))
+        })
+        .collect()
+}
+
+fn decode_base64(label: &str, value: &str) -> Result<Vec<u8>, String> {
+    let invalid = || format!("{label}: expected 0x-prefixed hex or base64");
+    let data = value.trim_end_matches('=');
+    if data.len() % 4 == 1 {
+        return Err(invalid());
+    }
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0u32);
+    for ch in data.bytes() {
+        let sextet = match ch {
+            b'A'..=b'Z' => ch - b'A',
+            b'a'..=b'z' => ch - b'a' + 26,
+            b'0'..=b'9' => ch - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return Err(invalid()),
+        };
+        acc = (acc << 6) | u32::from(sextet);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+            acc &= (1 << bits) - 1;
+        }
+    }
+    if acc != 0 {
+        return Err(invalid());
+    }
+    Ok(out)
+}
+
+pub fn write_uncompressed<T: CanonicalSerialize>(path: &str, value: &T) -> Result<(), String> {
+    write_canonical(path, value, false)
+}
+
+pub fn write_canonical<T: CanonicalSerialize>(
+    path: &str,
+    value: &T,
+    compressed: bool,
+) -> Result<(), String> {
+    let file = File::create(path).map_err(|err| err.to_string())?;
+    let mut writer = BufWriter::new(file);
+    if compressed {
+        value.serialize_compressed(&mut writer)
+    } else {
+        value.serialize_uncompressed(&mut writer)
+    }
+    .map_err(|err| err.to_string())?;
+    writer.flush().map_err(|err| err.to_string())
+}
+
+// The setup binaries record the Poseidon spec a key was generated under in
+// `<vk path>.poseidon`, and the verify binaries compare it with the spec they hash
+// with. A VK without the file predates it and is accepted as the default spec.
+pub fn params_spec_path(vk_path: &str) -> String {
+    format!("{vk_path}.poseidon")
+}
+
+pub fn write_params_spec(vk_path: &str, spec: &PoseidonParamsSpec) -> Result<(), String> {
+    let path = params_spec_path(vk_path);
+    fs::write(&path, spec.to_bytes()).map_err(|err| format!("failed to write {path}: {err}"))
+}
+
+pub fn check_params_spec(vk_path: &str, expected: &PoseidonParamsSpec) -> Result<(), String> {
+    let path = params_spec_path(vk_path);
+    let data = match fs::read(&path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(format!("failed to read {path}: {err}")),
+    };
+    let recorded = PoseidonParamsSpec::from_bytes(&data).map_err(|err| format!("{path}: {err}"))?;
+    if recorded != *expected {
+        return Err(format!(
+            "verifying key was set up with poseidon spec {recorded:?}, expected {expected:?}"
+        ));
+    }
+    Ok(())
+}
+
+// Resolves the `--out-dir <dir> --name <prefix>` form of the setup binaries to
+// `<dir>/<prefix>_pk.bin` and `<dir>/<prefix>_vk.bin`, creating `dir` if needed.
+pub fn key_output_paths(out_dir: &str, name: &str) -> Result<(String, String), String> {
+    if name.is_empty() || name.contains(['/', '\\']) {
+        return Err(format!("invalid key name prefix: {name:?}"));
+    }
+    fs::create_dir_all(out_dir).map_err(|err| format!("failed to create {out_dir}: {err}"))?;
+    let dir = std::path::Path::new(out_dir);
+    let path = |suffix: &str| {
+        dir.join(format!("{name}_{suffix}.bin"))
+            .to_string_lossy()
+            .into_owned()
+    };
+    Ok((path("pk"), path("vk")))
+}
+
+// Writes the secret opening of an instance (identity, blinding(s), ctx) as a JSON
+// object of 32-byte big-endian hex strings. Anyone holding this file can open the
+// commitment, so it is created owner-only where the platform allows.
+pub fn write_witness_json(path: &str, values: &[(&str, &[u8])]) -> Result<(), String> {
+    let mut object = serde_json::Map::new();
+    for (label, value) in values {
+        let fixed = fixed_bytes(label, value)?;
+        object.insert(label.to_string(), serde_json::Value::String(to_hex(&fixed)));
+    }
+    let json = serde_json::to_string_pretty(&object).map_err(|err| err.to_string())?;
+    write_owner_only(path, json.as_bytes())
+}
+
+// For files holding witness secrets. On unix the file is created with mode 0o600,
+// and an existing file is narrowed to it before being overwritten.
+pub fn write_owner_only(path: &str, bytes: &[u8]) -> Result<(), String> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path).map_err(|err| err.to_string())?;
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))
+        .map_err(|err| err.to_string())?;
+    file.write_all(bytes).map_err(|err| err.to_string())
+}
+
+// `--save-witness` for the make_*_instance binaries: nothing without a path,
+// otherwise `write_witness_json` and a reminder on stderr that the file is secret.
+pub fn save_witness(path: Option<&str>, values: &[(&str, &[u8])]) -> Result<(), String> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    write_witness_json(path, values).map_err(|err| format!("failed to write witness: {err}"))?;
+    eprintln!("warning: {path} holds the secret opening of the commitment; keep it private");
+    Ok(())
+}
+
+pub fn read_witness_json(path: &str) -> Result<BTreeMap<String, [u8; 32]>, String> {
+    let data = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let object: BTreeMap<String, String> =
+        serde_json::from_str(&data).map_err(|err| err.to_string())?;
+    object
+        .into_iter()
+        .map(|(label, hex)| {
+            let bytes = from_hex_32(&label, &hex)?;
+            Ok((label, bytes))
+        })
+        .collect()
+}
+
+// Self-documenting JSON for instance files: `{"schema", "depth", "public_inputs",
+// "instance"}` with every 32-byte field as a hex string and the version and
+// statement fields left as integers. `depth` is omitted for statements without a
+// tree.
+pub fn to_json_wrapped<P: Serialize, I: Serialize>(
+    schema: &str,
+    depth: Option<usize>,
+    public_inputs: &P,
+    instance: &I,
+) -> Result<String, String> {
+    let mut object = serde_json::Map::new();
+    object.insert("schema".to_string(), Value::String(schema.to_string()));
+    if let Some(depth) = depth {
+        object.insert("depth".to_string(), Value::from(depth));
+    }
+    let public_inputs = serde_json::to_value(public_inputs).map_err(|err| err.to_string())?;
+    let instance = serde_json::to_value(instance).map_err(|err| err.to_string())?;
+    object.insert("public_inputs".to_string(), bytes_to_hex(public_inputs));
+    object.insert("instance".to_string(), bytes_to_hex(instance));
+    serde_json::to_string_pretty(&object).map_err(|err| err.to_string())
+}
+
+// Reads the `instance` of a wrapped file written for `expected_schema`; the
+// `public_inputs` copy is informational and not parsed.
+pub fn from_json_wrapped<T: DeserializeOwned>(
+    json: &str,
+    expected_schema: &str,
+) -> Result<T, String> {
+    let mut object: serde_json::Map<String, Value> =
+        serde_json::from_str(json).map_err(|err| err.to_string())?;
+    match object.get("schema") {
+        Some(Value::String(schema)) if schema == expected_schema => {}
+        Some(Value::String(schema)) => {
+            return Err(format!("schema mismatch: expected {expected_schema}, got {schema}"));
+        }
+        _ => return Err("missing schema".to_string()),
+    }
+    let instance = object
+        .remove("instance")
+        .ok_or_else(|| "missing instance".to_string())?;
+    serde_json::from_value(hex_to_bytes(instance)?).map_err(|err| err.to_string())
+}
+
+// serde_json writes byte arrays as arrays of numbers; those of field-element
+// length become hex strings.
+fn bytes_to_hex(value: Value) -> Value {
+    match value {
+        Value::Array(items) => {
+            let bytes: Option<Vec<u8>> = items
+                .iter()
+                .map(|item| item.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+                .collect();
+            match bytes {
+                Some(bytes) if bytes.len() == 32 => Value::String(to_hex(&bytes)),
+                _ => Value::Array(items.into_iter().map(bytes_to_hex).collect()),
+            }
+        }
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, field)| (key, bytes_to_hex(field)))
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn hex_to_bytes(value: Value) -> Result<Value, String> {
+    match value {
+        Value::String(hex) => {
+            let hex = hex.strip_prefix("0x").unwrap_or(&hex);
+            Ok(Value::from(from_hex_32("instance", hex)?.to_vec()))
+        }
+        Value::Array(items) => Ok(Value::Array(
+            items
+                .into_iter()
+                .map(hex_to_bytes)
+                .collect::<Result<_, _>>()?,
+        )),
+        Value::Object(fields) => Ok(Value::Object(
+            fields
+                .into_iter()
+                .map(|(key, field)| Ok((key, hex_to_bytes(field)?)))
+                .collect::<Result<_, String>>()?,
+        )),
+        other => Ok(other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        bincode_deserialize_capped, check_params_spec, classify_blob, decode_public_inputs,
+        from_json_wrapped, key_output_paths, params_spec_path, parse_field_arg,
+        proof_serialized_size, read_file_capped, read_path_file, read_proof_as, read_proof_auto,
+        read_proof_capped, read_proving_key_capped, read_public_inputs_then_proof,
+        read_verifying_key_as, read_verifying_key_auto, read_verifying_key_capped,
+        read_witness_json, save_witness, to_json_wrapped, write_canonical, write_params_spec,
+        write_uncompressed, BlobKind, DEFAULT_MAX_INSTANCE_BYTES, DEFAULT_MAX_PK_BYTES,
+        DEFAULT_MAX_VK_BYTES, PROOF_SIZE_COMPRESSED, PROOF_SIZE_UNCOMPRESSED,
+        SWAPPED_ARGUMENTS_HINT,
+    };
+    use crate::test_support::{public_inputs_v2_bytes, sample_instance_v2};
+    use crate::{
+        commitment_hash, fr_to_fixed_bytes, poseidon_params, prove_membership_v2,
+        root_from_witness, setup_membership_with_depth_v2, verify_membership_v2,
+        MembershipInstanceV2Bytes, MembershipPublicInputsV2Bytes, MembershipWitness,
+        MembershipWitnessV2Bytes, PoseidonMerkleTree, PoseidonParamsSpec,
+        MEMBERSHIP_INSTANCE_VERSION_V2, MEMBERSHIP_STATEMENT_TYPE,
+    };
+    use ark_bn254::Fr;
+    use ark_ff::PrimeField;
+    use ark_bn254::Bn254;
+    use ark_groth16::Proof;
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use std::fs;
+
+    fn temp_path(name: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("membership-io-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn capped_reads_accept_under_and_reject_over() {
+        let path = temp_path("blob.bin");
+        fs::write(&path, vec![7u8; 100]).unwrap();
+        assert_eq!(read_file_capped(&path, 100).unwrap().len(), 100);
+        assert_eq!(read_file_capped(&path, 101).unwrap().len(), 100);
+        assert!(read_file_capped(&path, 99).unwrap_err().contains("exceeds cap"));
+
+        let proof_path = temp_path("proof.bin");
+        let mut bytes = Vec::new();
+        Proof::<Bn254>::default()
+            .serialize_uncompressed(&mut bytes)
+            .unwrap();
+        fs::write(&proof_path, &bytes).unwrap();
+        let size = bytes.len() as u64;
+        assert!(read_proof_capped(&proof_path, size).is_ok());
+        assert!(read_proof_capped(&proof_path, size - 1).is_err());
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&proof_path).unwrap();
+    }
+
+    #[test]
+    fn capped_bincode_rejects_a_huge_length_prefix() {
+        let siblings = vec![vec![7u8; 32]; 4];
+        let bytes = bincode::serialize(&siblings).unwrap();
+        let decoded: Vec<Vec<u8>> =
+            bincode_deserialize_capped(&bytes, DEFAULT_MAX_INSTANCE_BYTES).unwrap();
+        assert_eq!(decoded, siblings);
+        assert!(bincode_deserialize_capped::<Vec<Vec<u8>>>(&bytes, 16).is_err());
+
+        // One sibling claiming u32::MAX bytes: bincode must stop at the cap rather
+        // than trust the prefix.
+        let mut crafted = 1u64.to_le_bytes().to_vec();
+        crafted.extend_from_slice(&u64::from(u32::MAX).to_le_bytes());
+        crafted.extend_from_slice(&[0u8; 64]);
+        let err = bincode_deserialize_capped::<Vec<Vec<u8>>>(&crafted, DEFAULT_MAX_INSTANCE_BYTES);
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn params_spec_sidecar_flags_a_key_from_another_spec() {
+        let vk_path = temp_path("spec_vk.bin");
+        let default = PoseidonParamsSpec::DEFAULT_SPEC;
+        assert!(check_params_spec(&vk_path, &default).is_ok());
+
+        write_params_spec(&vk_path, &default).unwrap();
+        assert!(check_params_spec(&vk_path, &default).is_ok());
+
+        let wider = PoseidonParamsSpec { rate: 4, ..default };
+        write_params_spec(&vk_path, &wider).unwrap();
+        let err = check_params_spec(&vk_path, &default).unwrap_err();
+        assert!(err.contains("rate: 4"), "{err}");
+
+        fs::write(params_spec_path(&vk_path), [0u8; 31]).unwrap();
+        assert!(check_params_spec(&vk_path, &default).is_err());
+        fs::remove_file(params_spec_path(&vk_path)).unwrap();
+    }
+
+    #[test]
+    fn saved_witness_reopens_instance_commitment() {
+        let instance = sample_instance_v2(2);
+        let path = temp_path("witness.json");
+        let identity = fr_to_fixed_bytes(&instance.witness.identity_scalar);
+        let blinding = fr_to_fixed_bytes(&instance.witness.blinding);
+        let ctx_hash = fr_to_fixed_bytes(&instance.public_inputs.ctx_hash);
+        let values: [(&str, &[u8]); 3] = [
+            ("identity_scalar", &identity),
+            ("blinding", &blinding),
+            ("ctx_hash", &ctx_hash),
+        ];
+        let _ = fs::remove_file(&path);
+        save_witness(None, &values).unwrap();
+        assert!(fs::metadata(&path).is_err());
+        save_witness(Some(&path), &values).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+            // A rewrite narrows a file that was created world-readable.
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+            save_witness(Some(&path), &values).unwrap();
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let saved = read_witness_json(&path).unwrap();
+        assert_eq!(saved.len(), 3);
+        let params = poseidon_params::<Fr>();
+        let commitment = commitment_hash(
+            &params,
+            Fr::from_be_bytes_mod_order(&saved["identity_scalar"]),
+            Fr::from_be_bytes_mod_order(&saved["blinding"]),
+        );
+        assert_eq!(commitment, instance.public_inputs.commitment);
+        assert_eq!(saved["ctx_hash"].to_vec(), ctx_hash);
+
+        fs::write(&path, r#"{"blinding": "zz"}"#).unwrap();
+        assert!(read_witness_json(&path).is_err());
+    }
+
+    #[test]
+    fn proof_size_constants_match_a_serialized_proof() {
+        let instance = sample_instance_v2(1);
+        let mut rng = StdRng::seed_from_u64(60);
+        let pk = setup_membership_with_depth_v2(&mut rng, 1).unwrap();
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+
+        let mut compressed = Vec::new();
+        proof.serialize_compressed(&mut compressed).unwrap();
+        let mut uncompressed = Vec::new();
+        proof.serialize_uncompressed(&mut uncompressed).unwrap();
+        assert_eq!(compressed.len(), PROOF_SIZE_COMPRESSED);
+        assert_eq!(uncompressed.len(), PROOF_SIZE_UNCOMPRESSED);
+        assert_eq!(proof_serialized_size(true), proof.compressed_size());
+        assert_eq!(proof_serialized_size(false), proof.uncompressed_size());
+    }
+
+    #[test]
+    fn compressed_artifacts_only_load_in_compressed_mode() {
+        let instance = sample_instance_v2(1);
+        let mut rng = StdRng::seed_from_u64(70);
+        let pk = setup_membership_with_depth_v2(&mut rng, 1).unwrap();
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+        let vk_path = temp_path("compressed_vk.bin");
+        let proof_path = temp_path("compressed_proof.bin");
+        write_canonical(&vk_path, &pk.vk, true).unwrap();
+        write_canonical(&proof_path, &proof, true).unwrap();
+        assert_eq!(fs::metadata(&proof_path).unwrap().len(), PROOF_SIZE_COMPRESSED as u64);
+
+        let vk = read_verifying_key_as(&vk_path, DEFAULT_MAX_VK_BYTES, true).unwrap();
+        let read_back = read_proof_as(&proof_path, 1 << 12, true).unwrap();
+        assert!(verify_membership_v2(&vk, &instance.public_inputs, &read_back).unwrap());
+
+        let err = read_verifying_key_capped(&vk_path, DEFAULT_MAX_VK_BYTES).unwrap_err();
+        assert!(err.ends_with("data is compressed; rerun with --compressed"), "{err}");
+        let err = read_proof_as(&proof_path, 1 << 12, false).unwrap_err();
+        assert!(err.ends_with("data is compressed; rerun with --compressed"), "{err}");
+
+        write_canonical(&proof_path, &proof, false).unwrap();
+        let err = read_proof_as(&proof_path, 1 << 12, true).unwrap_err();
+        assert!(err.ends_with("data is uncompressed; rerun without --compressed"), "{err}");
+    }
+
+    #[test]
+    fn auto_readers_accept_either_encoding() {
+        let instance = sample_instance_v2(1);
+        let mut rng = StdRng::seed_from_u64(106);
+        let pk = setup_membership_with_depth_v2(&mut rng, 1).unwrap();
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+        let vk_path = temp_path("auto_vk.bin");
+        let proof_path = temp_path("auto_proof.bin");
+        for compressed in [false, true] {
+            write_canonical(&vk_path, &pk.vk, compressed).unwrap();
+            write_canonical(&proof_path, &proof, compressed).unwrap();
+            let vk = read_verifying_key_auto(&vk_path, DEFAULT_MAX_VK_BYTES).unwrap();
+            let read_back = read_proof_auto(&proof_path, 1 << 12).unwrap();
+            assert_eq!(vk, pk.vk);
+            assert!(verify_membership_v2(&vk, &instance.public_inputs, &read_back).unwrap());
+        }
+
+        fs::write(&vk_path, [7u8; 40]).unwrap();
+        let err = read_verifying_key_auto(&vk_path, DEFAULT_MAX_VK_BYTES).unwrap_err();
+        assert!(err.contains("not a valid encoding in either mode"), "{err}");
+        fs::remove_file(&vk_path).unwrap();
+        fs::remove_file(&proof_path).unwrap();
+    }
+
+    #[test]
+    fn swapped_proof_and_public_inputs_are_detected() {
+        let instance = sample_instance_v2(1);
+        let mut rng = StdRng::seed_from_u64(82);
+        let pk = setup_membership_with_depth_v2(&mut rng, 1).unwrap();
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+        let mut proof_bytes = Vec::new();
+        proof.serialize_uncompressed(&mut proof_bytes).unwrap();
+        let inputs_bytes = bincode::serialize(&public_inputs_v2_bytes(&instance)).unwrap();
+
+        assert_eq!(classify_blob(&proof_bytes), BlobKind::LikelyProof);
+        assert_eq!(classify_blob(&inputs_bytes), BlobKind::LikelyPublicInputs);
+
+        let decode = |data: &[u8]| {
+            decode_public_inputs(data, |bytes: MembershipPublicInputsV2Bytes| {
+                bytes.into_public_inputs_with_depth()
+            })
+        };
+        let (decoded, depth) = decode(&inputs_bytes).unwrap();
+        assert_eq!(decoded.commitment, instance.public_inputs.commitment);
+        assert_eq!(depth, 1);
+        assert_eq!(decode(&proof_bytes).unwrap_err(), SWAPPED_ARGUMENTS_HINT);
+        assert_ne!(
+            decode(&inputs_bytes[..10]).unwrap_err(),
+            SWAPPED_ARGUMENTS_HINT
+        );
+
+        let inputs_path = temp_path("swap_public_inputs.bin");
+        let proof_path = temp_path("swap_proof.bin");
+        fs::write(&inputs_path, &inputs_bytes).unwrap();
+        fs::write(&proof_path, &proof_bytes).unwrap();
+        let (decoded, read_back) =
+            read_public_inputs_then_proof(&inputs_path, &proof_path, decode).unwrap();
+        assert_eq!(decoded.0, instance.public_inputs);
+        assert_eq!(read_back, proof);
+        let err = read_public_inputs_then_proof(&proof_path, &inputs_path, decode).unwrap_err();
+        assert_eq!(
+            err,
+            format!("failed to read public inputs: {SWAPPED_ARGUMENTS_HINT}")
+        );
+        fs::remove_file(&inputs_path).unwrap();
+        fs::remove_file(&proof_path).unwrap();
+    }
+
+    #[test]
+    fn out_dir_mode_writes_a_matching_key_pair() {
+        let out_dir = temp_path("keys/nested");
+        let _ = fs::remove_dir_all(&out_dir);
+        let (pk_path, vk_path) = key_output_paths(&out_dir, "membership_v2").unwrap();
+        assert!(pk_path.ends_with("membership_v2_pk.bin"));
+        assert!(vk_path.ends_with("membership_v2_vk.bin"));
+        assert!(key_output_paths(&out_dir, "../escape").is_err());
+        assert!(key_output_paths(&out_dir, "").is_err());
+
+        let mut rng = StdRng::seed_from_u64(92);
+        let pk = setup_membership_with_depth_v2(&mut rng, 1).unwrap();
+        write_uncompressed(&pk_path, &pk).unwrap();
+        write_uncompressed(&vk_path, &pk.vk).unwrap();
+        assert!(fs::metadata(&pk_path).unwrap().is_file());
+        assert!(fs::metadata(&vk_path).unwrap().is_file());
+
+        let pk = read_proving_key_capped(&pk_path, DEFAULT_MAX_PK_BYTES).unwrap();
+        let vk = read_verifying_key_capped(&vk_path, DEFAULT_MAX_VK_BYTES).unwrap();
+        let instance = sample_instance_v2(1);
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+        assert!(verify_membership_v2(&vk, &instance.public_inputs, &proof).unwrap());
+
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+
+    #[test]
+    fn wrapped_json_instance_round_trips_and_proves() {
+        let instance = sample_instance_v2(2);
+        let public_inputs = public_inputs_v2_bytes(&instance);
+        let witness = &instance.witness;
+        let instance_bytes = MembershipInstanceV2Bytes {
+            schema_version: MEMBERSHIP_INSTANCE_VERSION_V2,
+            public_inputs: public_inputs.clone(),
+            witness: MembershipWitnessV2Bytes {
+                schema_version: MEMBERSHIP_INSTANCE_VERSION_V2,
+                depth: 2,
+                identity_scalar: fr_to_fixed_bytes(&witness.identity_scalar),
+                blinding: fr_to_fixed_bytes(&witness.blinding),
+                merkle_siblings: witness
+                    .merkle_path
+                    .iter()
+                    .map(|(sibling, _)| fr_to_fixed_bytes(sibling))
+                    .collect(),
+                merkle_directions: witness.merkle_path.iter().map(|(_, left)| *left).collect(),
+            },
+        };
+
+        let json = to_json_wrapped("v2", Some(2), &public_inputs, &instance_bytes).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["schema"], "v2");
+        assert_eq!(value["depth"], 2);
+        assert_eq!(value["public_inputs"]["statement_type"], MEMBERSHIP_STATEMENT_TYPE);
+        let root_hex = value["public_inputs"]["root"].as_str().unwrap();
+        assert_eq!(root_hex.len(), 66);
+        assert!(root_hex.starts_with("0x"));
+        let sibling_hex = value["instance"]["witness"]["merkle_siblings"][1].as_str().unwrap();
+        assert_eq!(sibling_hex.len(), 66);
+
+        let parsed: MembershipInstanceV2Bytes = from_json_wrapped(&json, "v2").unwrap();
+        let (parsed, depth) = parsed.into_instance_with_depth().unwrap();
+        assert_eq!(depth, 2);
+        assert_eq!(parsed.public_inputs.root, instance.public_inputs.root);
+        let mut rng = StdRng::seed_from_u64(95);
+        let pk = setup_membership_with_depth_v2(&mut rng, 2).unwrap();
+        let proof = prove_membership_v2(&pk, &parsed, &mut rng).unwrap();
+        assert!(verify_membership_v2(&pk.vk, &parsed.public_inputs, &proof).unwrap());
+
+        assert!(from_json_wrapped::<MembershipInstanceV2Bytes>(&json, "v1").is_err());
+    }
+
+    #[test]
+    fn csv_path_file_reproduces_tree_root() {
+        let params = poseidon_params::<Fr>();
+        let identity_scalar = Fr::from(1u64);
+        let blinding = Fr::from(2u64);
+        let mut commitments: Vec<Fr> = (10..18u64).map(Fr::from).collect();
+        commitments[5] = commitment_hash(&params, identity_scalar, blinding);
+        let tree = PoseidonMerkleTree::build(&commitments, 3, &params).unwrap();
+
+        let mut csv = "sibling_hex,is_left\n".to_string();
+        for (sibling, is_left) in tree.path(5).unwrap() {
+            let hex: String = fr_to_fixed_bytes(&sibling)
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect();
+            csv.push_str(&format!("{hex},{}\n", u8::from(is_left)));
+        }
+        let path = temp_path("path.csv");
+        fs::write(&path, &csv).unwrap();
+
+        let merkle_path = read_path_file(&path, 3).unwrap();
+        let witness = MembershipWitness {
+            identity_scalar,
+            blinding,
+            merkle_path,
+        };
+        assert_eq!(root_from_witness(&params, &witness), tree.root());
+
+        let err = read_path_file(&path, 4).unwrap_err();
+        assert_eq!(err, "path file has 3 rows, expected depth 4");
+        fs::write(&path, csv.replace(",1\n", "\t1\n").replacen("sibling_hex", "#", 1)).unwrap();
+        assert_eq!(read_path_file(&path, 3).unwrap(), witness.merkle_path);
+        fs::write(&path, format!("{},0\n", "ff".repeat(32))).unwrap();
+        assert!(read_path_file(&path, 1).unwrap_err().contains("canonical"));
+    }
+
+    #[test]
+    fn field_args_accept_hex_and_base64_and_pad_big_endian() {
+        let mut five = [0u8; 32];
+        five[31] = 5;
+        assert_eq!(parse_field_arg("--identity", "0x05").unwrap(), five);
+        assert_eq!(parse_field_arg("--identity", "BQ==").unwrap(), five);
+        assert_eq!(parse_field_arg("--identity", "BQ").unwrap(), five);
+        assert_eq!(parse_field_arg("--blinding", "AQID").unwrap()[29..], [1, 2, 3]);
+        let full = format!("0x{}", "ab".repeat(32));
+        assert_eq!(parse_field_arg("--ctx-hash", &full).unwrap(), [0xab; 32]);
+
+        let err = parse_field_arg("--identity", &format!("0x{}", "11".repeat(33))).unwrap_err();
+        assert_eq!(err, "--identity: 33 bytes is more than the 32-byte maximum");
+        assert!(parse_field_arg("--identity", &"A".repeat(48)).is_err());
+        assert!(parse_field_arg("--identity", "0x").is_err());
+        assert!(parse_field_arg("--identity", "0x123").is_err());
+        assert!(parse_field_arg("--identity", "BR==").is_err());
+        assert!(parse_field_arg("--identity", "12 34").is_err());
+    }
+}
@@ -1,6 +1,12 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+// Helpers shared with the std-only statement modules go unused without them.
+#![cfg_attr(not(feature = "std"), allow(dead_code))]
+
 use ark_bn254::{Bn254, Fr};
 use ark_ff::{BigInteger, PrimeField};
-use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_groth16::{
+    prepare_verifying_key, Groth16, PreparedVerifyingKey, Proof, ProvingKey, VerifyingKey,
+};
 use ark_r1cs_std::bits::boolean::Boolean;
 use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::prelude::*;
@@ -9,13 +15,28 @@ use ark_sponge::constraints::CryptographicSpongeVar;
 use ark_sponge::poseidon::constraints::PoseidonSpongeVar;
 use ark_sponge::poseidon::{find_poseidon_ark_and_mds, PoseidonConfig, PoseidonSponge};
 use ark_sponge::CryptographicSponge;
-use ark_std::rand::RngCore;
+use ark_std::rand::rngs::StdRng;
+use ark_std::rand::{RngCore, SeedableRng};
+use ark_std::{format, string::{String, ToString}, vec, vec::Vec};
+use core::marker::PhantomData;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+#[cfg(feature = "std")]
+use std::any::Any;
+#[cfg(feature = "std")]
+use std::str::FromStr;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
 
 const POSEIDON_RATE: usize = 3;
-const DOMAIN_COMMITMENT: u64 = 1;
-const DOMAIN_LEAF: u64 = 2;
-const DOMAIN_NODE: u64 = 3;
+pub const DOMAIN_COMMITMENT: u64 = 1;
+pub const DOMAIN_LEAF: u64 = 2;
+pub const DOMAIN_NODE: u64 = 3;
+pub const DOMAIN_SLOT: u64 = 4;
+pub const DOMAIN_SCHNORR: u64 = 5;
+pub const DOMAIN_ATTRIBUTES: u64 = 6;
 const FIELD_BYTES: usize = 32;
 pub const MERKLE_DEPTH: usize = 1;
 pub const MEMBERSHIP_INSTANCE_VERSION_V1: u8 = 1;
@@ -26,34 +47,515 @@ pub const MEMBERSHIP_V2_DOMAIN_SEP: [u8; 32] =
     *b"SNARK_MEMBERSHIP_V2_____________";
 pub const MEMBERSHIP_V2_DEFAULT_CTX_HASH: [u8; 32] =
     *b"MEMBERSHIP_CTX_V2_______________";
+// SHA-256 over the BN254 Poseidon config returned by `poseidon_params`. Any change
+// to rounds, rate, alpha or the generated ARK/MDS invalidates existing commitments.
+pub const POSEIDON_PARAMS_HASH: [u8; 32] = [
+    0x95, 0xcc, 0xd2, 0x80, 0x37, 0x5b, 0xd8, 0x86,
+    0xdb, 0xbc, 0x93, 0x7a, 0x7f, 0x1d, 0x80, 0xce,
+    0x44, 0xc0, 0x37, 0x8e, 0x7c, 0x1a, 0x2c, 0x9d,
+    0x65, 0x3f, 0x0a, 0x79, 0x64, 0x76, 0x1f, 0xb6,
+];
+
+#[cfg(feature = "std")]
+pub mod aggregate;
+#[cfg(feature = "std")]
+pub mod attributes;
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub mod batch;
+#[cfg(feature = "std")]
+pub mod blocklist;
+#[cfg(feature = "std")]
+pub mod bundle;
+#[cfg(feature = "std")]
+pub mod cache;
+#[cfg(feature = "std")]
+pub mod compact;
+#[cfg(feature = "std")]
+pub mod compare;
+#[cfg(feature = "std")]
+pub mod domain_commitment;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod field_map;
+pub mod hash;
+pub mod hex_serde;
+#[cfg(feature = "std")]
+pub mod hidden_commitment;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "std")]
+pub mod multi_depth;
+#[cfg(feature = "std")]
+pub mod non_membership;
+#[cfg(feature = "std")]
+pub mod outcome;
+#[cfg(feature = "std")]
+pub mod padded;
+#[cfg(feature = "std")]
+pub mod proof_points;
+#[cfg(feature = "std")]
+pub mod r1cs;
+#[cfg(feature = "std")]
+pub mod request;
+#[cfg(feature = "std")]
+pub mod schnorr;
+#[cfg(feature = "std")]
+pub mod secret;
+pub mod set_membership;
+#[cfg(feature = "std")]
+pub mod signed;
+#[cfg(feature = "std")]
+pub mod slotted;
+#[cfg(feature = "std")]
+pub mod solidity;
+#[cfg(feature = "std")]
+pub mod threshold;
+#[cfg(feature = "std")]
+pub mod tree;
+#[cfg(feature = "std")]
+pub mod vk_diff;
+
+#[cfg(feature = "std")]
+pub use aggregate::{
+    aggregate_membership_field_inputs, build_aggregate_circuit, build_aggregate_instance,
+    prove_aggregate_membership, setup_aggregate_membership, verify_aggregate_membership,
+    AggregateMembershipCircuit, AggregateMembershipInstance, AggregateMembershipPublicInputs,
+    AggregateMembershipPublicInputsBytes, MembershipCircuitMulti,
+    AGGREGATE_MEMBERSHIP_INSTANCE_VERSION,
+};
+#[cfg(feature = "std")]
+pub use attributes::{
+    build_circuit_with_attrs, commitment_hash_with_attrs, membership_with_attrs_field_inputs,
+    prove_membership_with_attrs, setup_membership_with_attrs, verify_membership_with_attrs,
+    MembershipCircuitWithAttrs, MembershipWithAttrsInstance, MembershipWithAttrsPublicInputs,
+    MembershipWithAttrsWitness,
+};
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+pub use batch::verify_membership_batch;
+#[cfg(feature = "std")]
+pub use blocklist::{
+    blocklist_membership_field_inputs, build_blocklist_circuit, prove_membership_with_blocklist,
+    setup_membership_with_blocklist, verify_membership_with_blocklist,
+    BlocklistMembershipInstance, BlocklistMembershipPublicInputs,
+    BlocklistMembershipPublicInputsBytes, MembershipWithBlocklistCircuit,
+    BLOCKLIST_MEMBERSHIP_INSTANCE_VERSION,
+};
+#[cfg(feature = "std")]
+pub use bundle::{
+    read_bundle, write_bundle, ProofBundle, DEFAULT_MAX_BUNDLE_BYTES, PROOF_BUNDLE_VERSION,
+};
+#[cfg(feature = "std")]
+pub use cache::{
+    proof_digest, public_inputs_digest, vk_fingerprint, vk_fingerprint_hex, VerificationCache,
+};
+#[cfg(feature = "std")]
+pub use compact::{compact_witness, expand_witness, CompactWitness};
+#[cfg(feature = "std")]
+pub use compare::{
+    enforce_in_range, enforce_less_than, enforce_less_than_full, in_range_native,
+    less_than_full_native, less_than_native, max_compare_bits,
+};
+#[cfg(feature = "std")]
+pub use domain_commitment::{
+    build_domain_commitment_circuit, prove_membership_domain_commitment,
+    setup_membership_domain_commitment, verify_membership_domain_commitment,
+    MembershipCircuitDomainCommitment,
+};
+pub use error::{ConstantError, FieldDecodeError, MembershipError};
+#[cfg(feature = "std")]
+pub use field_map::{
+    membership_v2_inputs_from_map, verify_membership_v2_from_map, FieldMapError,
+};
+pub use hash::{CircuitHash, Poseidon};
+#[cfg(feature = "std")]
+pub use hidden_commitment::{
+    build_circuit_hidden_commitment, membership_v3_field_inputs,
+    prove_membership_hidden_commitment, setup_membership_hidden_commitment,
+    verify_membership_hidden_commitment, MembershipCircuitHiddenCommitment,
+    MembershipPublicInputsV3, MembershipPublicInputsV3Bytes, MEMBERSHIP_INSTANCE_VERSION_V3,
+};
+#[cfg(feature = "std")]
+pub use multi_depth::{verify_membership_multi_depth, MultiDepthVerifyError};
+#[cfg(feature = "std")]
+pub use non_membership::{
+    build_non_membership_circuit, build_non_membership_instance, build_sorted_tree,
+    prove_non_membership, setup_non_membership, verify_non_membership, NonMembershipCircuit,
+    NonMembershipInstance, NonMembershipPublicInputs, NonMembershipWitness,
+};
+#[cfg(feature = "std")]
+pub use outcome::{
+    verify_membership_outcome, verify_membership_v1_outcome, verify_membership_v2_outcome,
+    VerifyOutcome,
+};
+#[cfg(feature = "std")]
+pub use padded::{
+    padded_root, prove_membership_padded, setup_membership_padded, verify_membership_padded,
+    MembershipCircuitPadded,
+};
+#[cfg(feature = "std")]
+pub use proof_points::{
+    proof_from_bytes, validate_proof_points, verify_membership_v2_strict, ProofError, ProofPoint,
+};
+#[cfg(feature = "std")]
+pub use r1cs::{
+    check_constraint_limit, debug_check_membership, debug_check_membership_v2,
+    estimate_constraints, export_r1cs, matrices_to_text, MembershipSchema,
+    DEFAULT_MAX_CONSTRAINTS,
+};
+#[cfg(feature = "std")]
+pub use request::{
+    membership_v2_instance_for_request, verify_membership_v2_for_request, ProofRequest,
+};
+#[cfg(feature = "std")]
+pub use schnorr::{
+    build_circuit_schnorr, prove_membership_schnorr, schnorr_public_key, setup_membership_schnorr,
+    sign_ctx_hash, verify_membership_schnorr, verify_schnorr, MembershipCircuitV3,
+    SchnorrSignature,
+};
+#[cfg(feature = "std")]
+pub use secret::membership_instance_v2_bytes_zeroizing;
+pub use set_membership::{
+    build_set_membership_circuit, build_set_membership_instance, prove_set_membership,
+    set_membership_field_inputs, setup_set_membership, verify_set_membership,
+    SetMembershipCircuit, SetMembershipInstance, SetMembershipInstanceBytes,
+    SetMembershipPublicInputs, SetMembershipPublicInputsBytes, SetMembershipWitness,
+    SET_MEMBERSHIP_DOMAIN_SEP, SET_MEMBERSHIP_INSTANCE_VERSION,
+};
+#[cfg(feature = "std")]
+pub use signed::{
+    build_circuit_signed, prove_membership_signed, setup_membership_signed,
+    verify_membership_signed, MembershipCircuitSigned,
+};
+#[cfg(feature = "std")]
+pub use slotted::{
+    build_circuit_slotted, prove_membership_slotted, setup_membership_slotted, slot_for_identity,
+    verify_membership_slotted, MembershipCircuitSlotted,
+};
+#[cfg(feature = "std")]
+pub use solidity::{
+    solidity_proof_words, solidity_statement, solidity_verifier, SolidityStatement,
+    SOLIDITY_STATEMENTS,
+};
+#[cfg(feature = "std")]
+pub use threshold::{
+    build_threshold_circuit, prove_threshold_membership, setup_threshold_membership,
+    threshold_membership_field_inputs, verify_threshold_membership, ThresholdBranchWitness,
+    ThresholdMembershipCircuit, ThresholdMembershipInstance, ThresholdMembershipPublicInputs,
+    ThresholdMembershipWitness,
+};
+#[cfg(feature = "std")]
+pub use tree::{
+    empty_subtree_hashes, merge_trees, plain_inclusion_proof, registry_entries, registry_entry,
+    root_set_diff, verify_plain_inclusion, IncrementalMerkleTree, MerkleTree, PlainInclusionProof,
+    PoseidonMerkleTree, RegistryEntry, SetDiff, TreeSnapshotBytes,
+};
+#[cfg(feature = "std")]
+pub use vk_diff::{diff_vks, VkComponent};
+
+#[cfg(feature = "async")]
+pub mod async_verify;
+#[cfg(feature = "rayon")]
+pub mod parallel;
+#[cfg(feature = "rayon")]
+pub use parallel::prove_membership_many;
 
 #[cfg(test)]
 mod poseidon_merkle_tests;
+// Sample instances for the unit tests and the binaries' tests; not a supported API.
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub mod test_support;
+
+#[cfg(feature = "std")]
+static FR_POSEIDON_PARAMS: OnceLock<PoseidonConfig<Fr>> = OnceLock::new();
+#[cfg(test)]
+static POSEIDON_DERIVATIONS: core::sync::atomic::AtomicUsize =
+    core::sync::atomic::AtomicUsize::new(0);
+
+// The BN254 config is derived once per process and cloned out; deriving the
+// ARK/MDS matrices dominates native hashing and constraint generation. Other
+// fields still derive on every call.
+#[cfg(feature = "std")]
+pub fn poseidon_params<F: PrimeField>() -> PoseidonConfig<F> {
+    let cached: &dyn Any = FR_POSEIDON_PARAMS.get_or_init(derive_poseidon_params::<Fr>);
+    match cached.downcast_ref::<PoseidonConfig<F>>() {
+        Some(params) => params.clone(),
+        None => derive_poseidon_params::<F>(),
+    }
+}
 
+// Without `std` there is no `OnceLock`, so every call derives the config.
+#[cfg(not(feature = "std"))]
 pub fn poseidon_params<F: PrimeField>() -> PoseidonConfig<F> {
-    let full_rounds = 8u64;
-    let partial_rounds = 56u64;
-    let alpha = 5u64;
+    derive_poseidon_params::<F>()
+}
+
+fn derive_poseidon_params<F: PrimeField>() -> PoseidonConfig<F> {
+    #[cfg(test)]
+    POSEIDON_DERIVATIONS.fetch_add(1, core::sync::atomic::Ordering::SeqCst);
+    poseidon_params_with::<F>(POSEIDON_RATE)
+}
+
+// The numbers a Poseidon config is derived from; the ARK/MDS matrices follow from
+// them and the field. Every circuit in the workspace uses `DEFAULT_SPEC`, which is
+// what `POSEIDON_PARAMS_HASH` pins. Other specs are for benchmarking only: keys
+// and commitments made under one are incompatible with the default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PoseidonParamsSpec {
+    pub rate: usize,
+    pub full_rounds: usize,
+    pub partial_rounds: usize,
+    pub alpha: u64,
+}
+
+pub const POSEIDON_PARAMS_SPEC_BYTES: usize = 32;
+
+impl PoseidonParamsSpec {
+    pub const DEFAULT_SPEC: Self = Self {
+        rate: POSEIDON_RATE,
+        full_rounds: 8,
+        partial_rounds: 56,
+        alpha: 5,
+    };
+
+    // Four big-endian u64s: rate, full rounds, partial rounds, alpha.
+    pub fn to_bytes(&self) -> [u8; POSEIDON_PARAMS_SPEC_BYTES] {
+        let mut out = [0u8; POSEIDON_PARAMS_SPEC_BYTES];
+        let values = [
+            self.rate as u64,
+            self.full_rounds as u64,
+            self.partial_rounds as u64,
+            self.alpha,
+        ];
+        for (chunk, value) in out.chunks_mut(8).zip(values) {
+            chunk.copy_from_slice(&value.to_be_bytes());
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MembershipError> {
+        if bytes.len() != POSEIDON_PARAMS_SPEC_BYTES {
+            return Err(MembershipError::LengthMismatch {
+                field: "poseidon spec".to_string(),
+                expected: POSEIDON_PARAMS_SPEC_BYTES,
+                got: bytes.len(),
+            });
+        }
+        let word = |idx: usize| {
+            let mut value = [0u8; 8];
+            value.copy_from_slice(&bytes[8 * idx..8 * idx + 8]);
+            u64::from_be_bytes(value)
+        };
+        let spec = Self {
+            rate: word(0) as usize,
+            full_rounds: word(1) as usize,
+            partial_rounds: word(2) as usize,
+            alpha: word(3),
+        };
+        if spec.rate == 0 || spec.full_rounds == 0 || spec.alpha < 2 {
+            return Err(MembershipError::InvalidPoseidonSpec(spec));
+        }
+        Ok(spec)
+    }
+}
+
+// Derives a fresh (uncached) config from `spec`, with capacity 1 as everywhere
+// else. `poseidon_params` is this at `DEFAULT_SPEC`, cached.
+pub fn poseidon_params_from_spec<F: PrimeField>(spec: PoseidonParamsSpec) -> PoseidonConfig<F> {
     let (ark, mds) = find_poseidon_ark_and_mds::<F>(
         F::MODULUS_BIT_SIZE as u64,
-        POSEIDON_RATE,
-        full_rounds,
-        partial_rounds,
+        spec.rate,
+        spec.full_rounds as u64,
+        spec.partial_rounds as u64,
         0,
     );
 
     PoseidonConfig::new(
-        full_rounds as usize,
-        partial_rounds as usize,
-        alpha,
+        spec.full_rounds,
+        spec.partial_rounds,
+        spec.alpha,
         mds,
         ark,
-        POSEIDON_RATE,
+        spec.rate,
         1,
     )
 }
 
-fn poseidon_hash_var<F: PrimeField>(
+// Derives a fresh (uncached) config with the given sponge rate and the same
+// rounds, alpha and capacity as `poseidon_params`. The ARK/MDS matrices are
+// generated for `rate`, so the config is only self-consistent at that rate. Only
+// `POSEIDON_RATE` matches the pinned parameter hash and the circuits' keys.
+pub fn poseidon_params_with<F: PrimeField>(rate: usize) -> PoseidonConfig<F> {
+    poseidon_params_from_spec(PoseidonParamsSpec {
+        rate,
+        ..PoseidonParamsSpec::DEFAULT_SPEC
+    })
+}
+
+pub fn poseidon_params_hash() -> [u8; 32] {
+    let params = poseidon_params::<Fr>();
+    let mut hasher = Sha256::new();
+    for value in [
+        params.full_rounds as u64,
+        params.partial_rounds as u64,
+        params.alpha,
+        params.rate as u64,
+        params.capacity as u64,
+    ] {
+        hasher.update(value.to_be_bytes());
+    }
+    for matrix in [&params.ark, &params.mds] {
+        hasher.update((matrix.len() as u64).to_be_bytes());
+        for row in matrix.iter() {
+            hasher.update((row.len() as u64).to_be_bytes());
+            for value in row {
+                hasher.update(fr_to_fixed_bytes(value));
+            }
+        }
+    }
+    hasher.finalize().into()
+}
+
+pub fn check_poseidon_params_hash() -> Result<(), ConstantError> {
+    if poseidon_params_hash() != POSEIDON_PARAMS_HASH {
+        return Err(ConstantError::PoseidonParamsHash);
+    }
+    Ok(())
+}
+
+// Domain-sep and ctx constants are 32 ASCII bytes of `[A-Z0-9_]`, starting with
+// their statement tag and right-padded with underscores.
+pub fn check_constant_form(label: &str, value: &[u8], prefix: &str) -> Result<(), ConstantError> {
+    if value.len() != FIELD_BYTES {
+        return Err(ConstantError::Length {
+            label: label.to_string(),
+            got: value.len(),
+        });
+    }
+    if let Some(pos) = value
+        .iter()
+        .position(|b| !(b.is_ascii_uppercase() || b.is_ascii_digit() || *b == b'_'))
+    {
+        return Err(ConstantError::InvalidByte {
+            label: label.to_string(),
+            byte: value[pos],
+            offset: pos,
+        });
+    }
+    if !value.starts_with(prefix.as_bytes()) {
+        return Err(ConstantError::MissingPrefix {
+            label: label.to_string(),
+            prefix: prefix.to_string(),
+        });
+    }
+    if value[FIELD_BYTES - 1] != b'_' {
+        return Err(ConstantError::MissingPadding {
+            label: label.to_string(),
+        });
+    }
+    Ok(())
+}
+
+// Byte strings up to this length are always canonical field elements: 2^248 is
+// below the BN254 scalar modulus. Every 32-byte `[A-Z0-9_]` constant is above it
+// (any first byte >= 0x31 is), so the shipped domain separators all reach the
+// field through `from_be_bytes_mod_order`. That is consistent, since schemas
+// compare the raw bytes and circuits and native code reduce the same way, but it
+// means separation only holds while the reduced values stay distinct and nonzero.
+// Domain-sep fields are therefore never decoded with `fr_from_bytes_canonical`.
+pub const DOMAIN_SEP_CANONICAL_BYTES: usize = 31;
+
+pub fn domain_sep_is_canonical(value: &[u8]) -> bool {
+    value.len() <= FIELD_BYTES && fr_from_bytes_canonical("domain_sep", value).is_ok()
+}
+
+// Fails if a domain separator reduces to zero or to the same field element as
+// another one in `constants`, which would merge two statements' transcripts.
+pub fn check_domain_seps_distinct(
+    constants: &[(&str, &[u8; 32])],
+) -> Result<(), ConstantError> {
+    let mut seen: Vec<(&str, Fr)> = Vec::with_capacity(constants.len());
+    for (label, value) in constants {
+        let reduced = Fr::from_be_bytes_mod_order(*value);
+        if reduced == Fr::from(0u64) {
+            return Err(ConstantError::ReducesToZero {
+                label: label.to_string(),
+            });
+        }
+        if let Some((other, _)) = seen.iter().find(|(_, existing)| *existing == reduced) {
+            return Err(ConstantError::Collision {
+                label: label.to_string(),
+                other: other.to_string(),
+            });
+        }
+        seen.push((label, reduced));
+    }
+    Ok(())
+}
+
+// Renders a 32-byte domain separator for logs: printable ASCII is kept as-is and
+// every other byte (and `\`) is written as a `\xNN` escape.
+pub fn domain_sep_label(bytes: &[u8; FIELD_BYTES]) -> String {
+    let mut out = String::with_capacity(FIELD_BYTES);
+    for &byte in bytes {
+        if (byte == b' ' || byte.is_ascii_graphic()) && byte != b'\\' {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("\\x{byte:02x}"));
+        }
+    }
+    out
+}
+
+// ASCII constants are wider than the modulus, so `from_be_bytes_mod_order` folds
+// them. Lifting the canonical value by multiples of the modulus recovers the
+// original bytes when one of the candidates is printable; otherwise the
+// canonical encoding is rendered.
+pub fn field_to_domain_label(fr: &Fr) -> String {
+    let canonical = fr.into_bigint();
+    let mut value = canonical;
+    loop {
+        let bytes = value.to_bytes_be();
+        if bytes.iter().all(|b| *b == b' ' || b.is_ascii_graphic()) {
+            let mut fixed = [0u8; FIELD_BYTES];
+            fixed.copy_from_slice(&bytes);
+            return domain_sep_label(&fixed);
+        }
+        if value.add_with_carry(&Fr::MODULUS) {
+            break;
+        }
+    }
+    let mut fixed = [0u8; FIELD_BYTES];
+    fixed.copy_from_slice(&canonical.to_bytes_be());
+    domain_sep_label(&fixed)
+}
+
+pub fn validate_constants() -> Result<(), ConstantError> {
+    check_constant_form(
+        "MEMBERSHIP_V2_DOMAIN_SEP",
+        &MEMBERSHIP_V2_DOMAIN_SEP,
+        "SNARK_MEMBERSHIP_V2",
+    )?;
+    check_constant_form(
+        "MEMBERSHIP_V2_DEFAULT_CTX_HASH",
+        &MEMBERSHIP_V2_DEFAULT_CTX_HASH,
+        "MEMBERSHIP_CTX_V2",
+    )?;
+    check_constant_form(
+        "SET_MEMBERSHIP_DOMAIN_SEP",
+        &SET_MEMBERSHIP_DOMAIN_SEP,
+        "SNARK_SET_MEMBERSHIP_V1",
+    )?;
+    check_domain_seps_distinct(&[
+        ("MEMBERSHIP_V2_DOMAIN_SEP", &MEMBERSHIP_V2_DOMAIN_SEP),
+        ("SET_MEMBERSHIP_DOMAIN_SEP", &SET_MEMBERSHIP_DOMAIN_SEP),
+    ])?;
+    check_poseidon_params_hash()
+}
+
+// In-circuit counterpart of `poseidon_hash_native`: absorbs `inputs` and squeezes
+// one element.
+pub fn poseidon_hash_var<F: PrimeField>(
     cs: ConstraintSystemRef<F>,
     params: &PoseidonConfig<F>,
     inputs: &[FpVar<F>],
@@ -64,6 +566,31 @@ fn poseidon_hash_var<F: PrimeField>(
     Ok(output.remove(0))
 }
 
+// Walks a witnessed (sibling, is_left) path up from `leaf` using node hashing and
+// returns the recomputed root.
+pub fn merkle_root_var<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    params: &PoseidonConfig<F>,
+    leaf: FpVar<F>,
+    merkle_path: Vec<(Option<F>, Option<bool>)>,
+) -> Result<FpVar<F>, SynthesisError> {
+    let domain_node = FpVar::constant(F::from(DOMAIN_NODE));
+    let mut current = leaf;
+    for (sibling_value, is_left_value) in merkle_path {
+        let sibling = FpVar::new_witness(cs.clone(), || {
+            sibling_value.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let is_left = Boolean::new_witness(cs.clone(), || {
+            is_left_value.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let left = is_left.select(&sibling, &current)?;
+        let right = is_left.select(&current, &sibling)?;
+        current = poseidon_hash_var(cs.clone(), params, &[domain_node.clone(), left, right])?;
+    }
+    Ok(current)
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct MerklePathNodeBytes {
     #[serde(with = "serde_bytes")]
@@ -123,45 +650,50 @@ pub struct MembershipInstanceV1Bytes {
     pub witness: MembershipWitnessV1Bytes,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MembershipWitnessV2Bytes {
     pub schema_version: u16,
     pub depth: u32,
-    #[serde(with = "serde_bytes")]
+    #[serde(with = "crate::hex_serde::bytes")]
     pub identity_scalar: Vec<u8>,
-    #[serde(with = "serde_bytes")]
+    #[serde(with = "crate::hex_serde::bytes")]
     pub blinding: Vec<u8>,
+    #[serde(with = "crate::hex_serde::bytes_vec")]
     pub merkle_siblings: Vec<Vec<u8>>,
     pub merkle_directions: Vec<bool>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MembershipPublicInputsV2Bytes {
     pub schema_version: u16,
     pub statement_type: u16,
     pub statement_version: u16,
     pub depth: u32,
+    #[serde(with = "crate::hex_serde::array")]
     pub root: [u8; 32],
+    #[serde(with = "crate::hex_serde::array")]
     pub commitment: [u8; 32],
+    #[serde(with = "crate::hex_serde::array")]
     pub domain_sep: [u8; 32],
+    #[serde(with = "crate::hex_serde::array")]
     pub ctx_hash: [u8; 32],
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct MembershipInstanceV2Bytes {
     pub schema_version: u16,
     pub public_inputs: MembershipPublicInputsV2Bytes,
     pub witness: MembershipWitnessV2Bytes,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct MembershipWitness {
     pub identity_scalar: Fr,
     pub blinding: Fr,
     pub merkle_path: Vec<(Fr, bool)>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct MembershipPublicInputs {
     pub root: Fr,
     pub commitment: Fr,
@@ -180,7 +712,7 @@ pub struct MembershipWitnessV2 {
     pub merkle_path: Vec<(Fr, bool)>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct MembershipPublicInputsV2 {
     pub root: Fr,
     pub commitment: Fr,
@@ -194,37 +726,47 @@ pub struct MembershipInstanceV2 {
     pub witness: MembershipWitnessV2,
 }
 
-fn fr_from_bytes(label: &str, bytes: &[u8]) -> Result<Fr, String> {
+fn fr_from_bytes(label: &str, bytes: &[u8]) -> Result<Fr, FieldDecodeError> {
     if bytes.is_empty() {
-        return Err(format!("{}: empty field bytes", label));
+        return Err(FieldDecodeError::new(label, "empty field bytes"));
     }
     if bytes.len() > FIELD_BYTES {
-        return Err(format!(
-            "{}: expected at most {} bytes, got {}",
+        return Err(FieldDecodeError::new(
             label,
-            FIELD_BYTES,
-            bytes.len()
+            format!("expected at most {} bytes, got {}", FIELD_BYTES, bytes.len()),
         ));
     }
     Ok(Fr::from_be_bytes_mod_order(bytes))
 }
 
-fn ensure_version(label: &str, version: u8, expected: u8) -> Result<(), String> {
-    if version != expected {
-        return Err(format!(
-            "{}: version mismatch (expected {}, got {})",
-            label, expected, version
+// Strict form of `fr_from_bytes` for public values that are compared by equality
+// (roots, commitments, tags): an integer >= the scalar modulus is rejected instead
+// of reduced, so every element has exactly one accepted encoding. Labels such as
+// domain_sep and ctx_hash, and hash preimages, stay on the reducing variant.
+pub fn fr_from_bytes_canonical(label: &str, bytes: &[u8]) -> Result<Fr, FieldDecodeError> {
+    let value = fr_from_bytes(label, bytes)?;
+    let mut padded = [0u8; FIELD_BYTES];
+    padded[FIELD_BYTES - bytes.len()..].copy_from_slice(bytes);
+    if fr_to_fixed_bytes(&value)[..] != padded[..] {
+        return Err(FieldDecodeError::new(
+            label,
+            "not a canonical field element (>= modulus)",
         ));
     }
-    Ok(())
+    Ok(value)
+}
+
+fn ensure_version(label: &str, version: u8, expected: u8) -> Result<(), MembershipError> {
+    ensure_version_u16(label, version.into(), expected.into())
 }
 
-fn ensure_version_u16(label: &str, version: u16, expected: u16) -> Result<(), String> {
+fn ensure_version_u16(label: &str, version: u16, expected: u16) -> Result<(), MembershipError> {
     if version != expected {
-        return Err(format!(
-            "{}: version mismatch (expected {}, got {})",
-            label, expected, version
-        ));
+        return Err(MembershipError::VersionMismatch {
+            field: label.to_string(),
+            expected,
+            got: version,
+        });
     }
     Ok(())
 }
@@ -232,25 +774,60 @@ fn ensure_version_u16(label: &str, version: u16, expected: u16) -> Result<(), St
 fn ensure_statement_type_version(
     statement_type: u16,
     statement_version: u16,
-) -> Result<(), String> {
+) -> Result<(), MembershipError> {
     if statement_type != MEMBERSHIP_STATEMENT_TYPE {
-        return Err(format!(
-            "statement_type mismatch (expected {}, got {})",
-            MEMBERSHIP_STATEMENT_TYPE, statement_type
-        ));
+        return Err(MembershipError::StatementMismatch {
+            field: "statement_type",
+            expected: MEMBERSHIP_STATEMENT_TYPE,
+            got: statement_type,
+        });
     }
     if statement_version != MEMBERSHIP_STATEMENT_VERSION_V2 {
-        return Err(format!(
-            "statement_version mismatch (expected {}, got {})",
-            MEMBERSHIP_STATEMENT_VERSION_V2, statement_version
-        ));
+        return Err(MembershipError::StatementMismatch {
+            field: "statement_version",
+            expected: MEMBERSHIP_STATEMENT_VERSION_V2,
+            got: statement_version,
+        });
     }
     Ok(())
 }
 
-fn ensure_domain_sep(label: &str, domain_sep: &[u8; 32]) -> Result<(), String> {
+fn ensure_domain_sep(label: &str, domain_sep: &[u8; 32]) -> Result<(), MembershipError> {
     if domain_sep != &MEMBERSHIP_V2_DOMAIN_SEP {
-        return Err(format!("{label}: domain_sep mismatch"));
+        return Err(MembershipError::DomainSepMismatch {
+            field: label.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn ensure_depth(label: &str, depth: usize) -> Result<usize, MembershipError> {
+    if depth == 0 {
+        return Err(MembershipError::ZeroDepth {
+            field: label.to_string(),
+        });
+    }
+    Ok(depth)
+}
+
+fn ensure_len(label: &str, len: usize, expected: usize) -> Result<(), MembershipError> {
+    if len != expected {
+        return Err(MembershipError::LengthMismatch {
+            field: label.to_string(),
+            expected,
+            got: len,
+        });
+    }
+    Ok(())
+}
+
+fn ensure_witness_depth(label: &str, depth: u32, expected: usize) -> Result<(), MembershipError> {
+    if depth as usize != expected {
+        return Err(MembershipError::DepthMismatch {
+            field: label.to_string(),
+            expected,
+            got: depth as usize,
+        });
     }
     Ok(())
 }
@@ -259,6 +836,31 @@ fn membership_v2_domain_sep_fr() -> Fr {
     Fr::from_be_bytes_mod_order(&MEMBERSHIP_V2_DOMAIN_SEP)
 }
 
+// Left-pads big-endian input to 32 bytes. Empty input is rejected so a missing
+// argument is never read as zero; callers wanting zero pass explicit zero bytes.
+pub fn fixed_bytes(label: &str, bytes: &[u8]) -> Result<[u8; 32], MembershipError> {
+    if bytes.is_empty() {
+        return Err(MembershipError::EmptyField {
+            field: label.to_string(),
+        });
+    }
+    fixed_bytes_allow_empty(label, bytes)
+}
+
+// Like `fixed_bytes`, but empty input maps to the zero field element. Only used
+// for optional context inputs (ctx_hash), never for identity or blinding values.
+pub fn fixed_bytes_allow_empty(label: &str, bytes: &[u8]) -> Result<[u8; 32], MembershipError> {
+    if bytes.len() > FIELD_BYTES {
+        return Err(MembershipError::TooLong {
+            field: label.to_string(),
+            max: FIELD_BYTES,
+        });
+    }
+    let mut fixed = [0u8; 32];
+    fixed[FIELD_BYTES - bytes.len()..].copy_from_slice(bytes);
+    Ok(fixed)
+}
+
 pub fn fr_to_fixed_bytes(value: &Fr) -> Vec<u8> {
     let mut bytes = value.into_bigint().to_bytes_be();
     if bytes.len() < FIELD_BYTES {
@@ -269,6 +871,22 @@ pub fn fr_to_fixed_bytes(value: &Fr) -> Vec<u8> {
     bytes
 }
 
+// Array form of `fr_to_fixed_bytes` for the `[u8; 32]` fields of the byte schemas.
+pub fn fr_to_array(value: &Fr) -> [u8; 32] {
+    let mut out = [0u8; FIELD_BYTES];
+    out.copy_from_slice(&fr_to_fixed_bytes(value));
+    out
+}
+
+// 128-bit identities map to the field exactly (2^128 < r), so no high bits are lost.
+pub fn fr_from_u64_pair(hi: u64, lo: u64) -> Fr {
+    Fr::from(hi) * Fr::from(u128::from(u64::MAX) + 1) + Fr::from(lo)
+}
+
+pub fn fr_from_u128(value: u128) -> Fr {
+    fr_from_u64_pair((value >> 64) as u64, value as u64)
+}
+
 fn poseidon_hash_native(params: &PoseidonConfig<Fr>, inputs: &[Fr]) -> Fr {
     let mut sponge = PoseidonSponge::<Fr>::new(params);
     sponge.absorb(&inputs);
@@ -276,10 +894,23 @@ fn poseidon_hash_native(params: &PoseidonConfig<Fr>, inputs: &[Fr]) -> Fr {
 }
 
 pub fn commitment_hash(params: &PoseidonConfig<Fr>, identity: Fr, blinding: Fr) -> Fr {
-    poseidon_hash_native(
-        params,
-        &[Fr::from(DOMAIN_COMMITMENT), identity, blinding],
-    )
+    commitment_hash_domain(params, Fr::from(DOMAIN_COMMITMENT), identity, blinding)
+}
+
+pub fn commitment_hash_domain(
+    params: &PoseidonConfig<Fr>,
+    domain: Fr,
+    identity: Fr,
+    blinding: Fr,
+) -> Fr {
+    poseidon_hash_native(params, &[domain, identity, blinding])
+}
+
+// Checks an opening revealed out-of-band (no proof) against a published
+// commitment. This is the same relation the membership circuits enforce.
+pub fn verify_opening(identity: Fr, blinding: Fr, claimed_commitment: [u8; 32]) -> bool {
+    let params = poseidon_params::<Fr>();
+    fr_to_fixed_bytes(&commitment_hash(&params, identity, blinding)) == claimed_commitment
 }
 
 pub fn leaf_hash(params: &PoseidonConfig<Fr>, commitment: Fr) -> Fr {
@@ -313,23 +944,49 @@ pub fn poseidon_hash_leaf_v2(
     poseidon_hash_native(params, &[domain_sep, ctx_hash, commitment])
 }
 
+fn root_from_leaf(params: &PoseidonConfig<Fr>, leaf: Fr, merkle_path: &[(Fr, bool)]) -> Fr {
+    merkle_path.iter().fold(leaf, |current, (sibling, is_left)| {
+        if *is_left {
+            node_hash(params, *sibling, current)
+        } else {
+            node_hash(params, current, *sibling)
+        }
+    })
+}
+
+pub fn root_from_witness(params: &PoseidonConfig<Fr>, witness: &MembershipWitness) -> Fr {
+    let commitment = commitment_hash(params, witness.identity_scalar, witness.blinding);
+    root_from_leaf(params, leaf_hash(params, commitment), &witness.merkle_path)
+}
+
+pub fn root_from_witness_v2(
+    params: &PoseidonConfig<Fr>,
+    witness: &MembershipWitnessV2,
+    domain_sep: Fr,
+    ctx_hash: Fr,
+) -> Fr {
+    let commitment = commitment_hash(params, witness.identity_scalar, witness.blinding);
+    let leaf = poseidon_hash_leaf_v2(params, domain_sep, ctx_hash, commitment);
+    root_from_leaf(params, leaf, &witness.merkle_path)
+}
+
 impl MembershipPublicInputsBytes {
-    pub fn into_public_inputs(self) -> Result<MembershipPublicInputs, String> {
+    pub fn into_public_inputs(self) -> Result<MembershipPublicInputs, MembershipError> {
         Ok(MembershipPublicInputs {
-            root: fr_from_bytes("root", &self.root)?,
-            commitment: fr_from_bytes("commitment", &self.commitment)?,
+            root: fr_from_bytes_canonical("root", &self.root)?,
+            commitment: fr_from_bytes_canonical("commitment", &self.commitment)?,
         })
     }
 }
 
 impl MembershipWitnessBytes {
-    pub fn into_witness(self) -> Result<MembershipWitness, String> {
-        if self.merkle_path.len() != MERKLE_DEPTH {
-            return Err(format!(
-                "merkle_path length mismatch: expected {}, got {}",
-                MERKLE_DEPTH,
-                self.merkle_path.len()
-            ));
+    pub fn into_witness(self) -> Result<MembershipWitness, MembershipError> {
+        // The depth is whatever the path length is; `build_circuit` takes it from
+        // the parsed path, so keys must come from `setup_membership_with_depth`.
+        if self.merkle_path.is_empty() {
+            return Err(MembershipError::EmptyField {
+                field: "merkle_path".to_string(),
+            });
         }
         let mut path = Vec::with_capacity(self.merkle_path.len());
         for (idx, node) in self.merkle_path.into_iter().enumerate() {
@@ -346,7 +1003,7 @@ impl MembershipWitnessBytes {
 }
 
 impl MembershipInstanceBytes {
-    pub fn into_instance(self) -> Result<MembershipInstance, String> {
+    pub fn into_instance(self) -> Result<MembershipInstance, MembershipError> {
         Ok(MembershipInstance {
             public_inputs: self.public_inputs.into_public_inputs()?,
             witness: self.witness.into_witness()?,
@@ -357,43 +1014,23 @@ impl MembershipInstanceBytes {
 impl MembershipPublicInputsV1Bytes {
     pub fn into_public_inputs_with_depth(
         self,
-    ) -> Result<(MembershipPublicInputs, usize), String> {
+    ) -> Result<(MembershipPublicInputs, usize), MembershipError> {
         ensure_version("public_inputs.version", self.version, MEMBERSHIP_INSTANCE_VERSION_V1)?;
-        let depth = self.depth as usize;
-        if depth == 0 {
-            return Err("public_inputs.depth must be > 0".to_string());
-        }
+        let depth = ensure_depth("public_inputs.depth", self.depth as usize)?;
         let inputs = MembershipPublicInputs {
-            root: fr_from_bytes("root", &self.root)?,
-            commitment: fr_from_bytes("commitment", &self.commitment)?,
+            root: fr_from_bytes_canonical("root", &self.root)?,
+            commitment: fr_from_bytes_canonical("commitment", &self.commitment)?,
         };
         Ok((inputs, depth))
     }
 }
 
 impl MembershipWitnessV1Bytes {
-    pub fn into_witness(self, expected_depth: usize) -> Result<MembershipWitness, String> {
+    pub fn into_witness(self, expected_depth: usize) -> Result<MembershipWitness, MembershipError> {
         ensure_version("witness.version", self.version, MEMBERSHIP_INSTANCE_VERSION_V1)?;
-        if self.depth as usize != expected_depth {
-            return Err(format!(
-                "witness.depth mismatch: expected {}, got {}",
-                expected_depth, self.depth
-            ));
-        }
-        if self.merkle_siblings.len() != expected_depth {
-            return Err(format!(
-                "merkle_siblings length mismatch: expected {}, got {}",
-                expected_depth,
-                self.merkle_siblings.len()
-            ));
-        }
-        if self.merkle_directions.len() != expected_depth {
-            return Err(format!(
-                "merkle_directions length mismatch: expected {}, got {}",
-                expected_depth,
-                self.merkle_directions.len()
-            ));
-        }
+        ensure_witness_depth("witness.depth", self.depth, expected_depth)?;
+        ensure_len("merkle_siblings", self.merkle_siblings.len(), expected_depth)?;
+        ensure_len("merkle_directions", self.merkle_directions.len(), expected_depth)?;
 
         let mut path = Vec::with_capacity(expected_depth);
         for (idx, sibling_bytes) in self.merkle_siblings.into_iter().enumerate() {
@@ -411,16 +1048,11 @@ impl MembershipWitnessV1Bytes {
 }
 
 impl MembershipInstanceV1Bytes {
-    pub fn into_instance_with_depth(self) -> Result<(MembershipInstance, usize), String> {
+    pub fn into_instance_with_depth(self) -> Result<(MembershipInstance, usize), MembershipError> {
         ensure_version("instance.version", self.version, MEMBERSHIP_INSTANCE_VERSION_V1)?;
         let (public_inputs, expected_depth) =
             self.public_inputs.into_public_inputs_with_depth()?;
-        if self.witness.depth as usize != expected_depth {
-            return Err(format!(
-                "instance.depth mismatch: public_inputs {}, witness {}",
-                expected_depth, self.witness.depth
-            ));
-        }
+        ensure_witness_depth("instance.depth", self.witness.depth, expected_depth)?;
         let instance = MembershipInstance {
             public_inputs,
             witness: self.witness.into_witness(expected_depth)?,
@@ -432,7 +1064,7 @@ impl MembershipInstanceV1Bytes {
 impl MembershipPublicInputsV2Bytes {
     pub fn into_public_inputs_with_depth(
         self,
-    ) -> Result<(MembershipPublicInputsV2, usize), String> {
+    ) -> Result<(MembershipPublicInputsV2, usize), MembershipError> {
         ensure_version_u16(
             "public_inputs.schema_version",
             self.schema_version,
@@ -441,48 +1073,31 @@ impl MembershipPublicInputsV2Bytes {
         ensure_statement_type_version(self.statement_type, self.statement_version)?;
         ensure_domain_sep("public_inputs.domain_sep", &self.domain_sep)?;
 
-        let depth = self.depth as usize;
-        if depth == 0 {
-            return Err("public_inputs.depth must be > 0".to_string());
-        }
+        let depth = ensure_depth("public_inputs.depth", self.depth as usize)?;
 
         let inputs = MembershipPublicInputsV2 {
-            root: fr_from_bytes("root", &self.root)?,
-            commitment: fr_from_bytes("commitment", &self.commitment)?,
+            root: fr_from_bytes_canonical("root", &self.root)?,
+            commitment: fr_from_bytes_canonical("commitment", &self.commitment)?,
             domain_sep: fr_from_bytes("domain_sep", &self.domain_sep)?,
-            ctx_hash: fr_from_bytes("ctx_hash", &self.ctx_hash)?,
+            ctx_hash: fr_from_bytes_canonical("ctx_hash", &self.ctx_hash)?,
         };
         Ok((inputs, depth))
     }
 }
 
 impl MembershipWitnessV2Bytes {
-    pub fn into_witness(self, expected_depth: usize) -> Result<MembershipWitnessV2, String> {
+    pub fn into_witness(
+        self,
+        expected_depth: usize,
+    ) -> Result<MembershipWitnessV2, MembershipError> {
         ensure_version_u16(
             "witness.schema_version",
             self.schema_version,
             MEMBERSHIP_INSTANCE_VERSION_V2,
         )?;
-        if self.depth as usize != expected_depth {
-            return Err(format!(
-                "witness.depth mismatch: expected {}, got {}",
-                expected_depth, self.depth
-            ));
-        }
-        if self.merkle_siblings.len() != expected_depth {
-            return Err(format!(
-                "merkle_siblings length mismatch: expected {}, got {}",
-                expected_depth,
-                self.merkle_siblings.len()
-            ));
-        }
-        if self.merkle_directions.len() != expected_depth {
-            return Err(format!(
-                "merkle_directions length mismatch: expected {}, got {}",
-                expected_depth,
-                self.merkle_directions.len()
-            ));
-        }
+        ensure_witness_depth("witness.depth", self.depth, expected_depth)?;
+        ensure_len("merkle_siblings", self.merkle_siblings.len(), expected_depth)?;
+        ensure_len("merkle_directions", self.merkle_directions.len(), expected_depth)?;
 
         let mut path = Vec::with_capacity(expected_depth);
         for (idx, sibling_bytes) in self.merkle_siblings.into_iter().enumerate() {
@@ -500,7 +1115,9 @@ impl MembershipWitnessV2Bytes {
 }
 
 impl MembershipInstanceV2Bytes {
-    pub fn into_instance_with_depth(self) -> Result<(MembershipInstanceV2, usize), String> {
+    pub fn into_instance_with_depth(
+        self,
+    ) -> Result<(MembershipInstanceV2, usize), MembershipError> {
         ensure_version_u16(
             "instance.schema_version",
             self.schema_version,
@@ -508,18 +1125,44 @@ impl MembershipInstanceV2Bytes {
         )?;
         let (public_inputs, expected_depth) =
             self.public_inputs.into_public_inputs_with_depth()?;
-        if self.witness.depth as usize != expected_depth {
-            return Err(format!(
-                "instance.depth mismatch: public_inputs {}, witness {}",
-                expected_depth, self.witness.depth
-            ));
-        }
+        ensure_witness_depth("instance.depth", self.witness.depth, expected_depth)?;
         let instance = MembershipInstanceV2 {
             public_inputs,
             witness: self.witness.into_witness(expected_depth)?,
         };
         Ok((instance, expected_depth))
     }
+
+    // `into_instance_with_depth` plus the checks the circuit would make, so an
+    // instance whose opening or path does not reach its public root is reported
+    // here instead of failing at proving time as Unsatisfiable.
+    pub fn into_instance_checked(self) -> Result<(MembershipInstanceV2, usize), MembershipError> {
+        let (instance, depth) = self.into_instance_with_depth()?;
+        let params = poseidon_params::<Fr>();
+        let inputs = &instance.public_inputs;
+        let witness = &instance.witness;
+        if commitment_hash(&params, witness.identity_scalar, witness.blinding) != inputs.commitment
+        {
+            return Err(MembershipError::CommitmentMismatch);
+        }
+        let leaf =
+            poseidon_hash_leaf_v2(&params, inputs.domain_sep, inputs.ctx_hash, inputs.commitment);
+        if root_from_leaf(&params, leaf, &witness.merkle_path) != inputs.root {
+            return Err(MembershipError::RootMismatch);
+        }
+        Ok((instance, depth))
+    }
+
+    // Pretty JSON with every byte field as a `0x`-prefixed hex string.
+    #[cfg(feature = "std")]
+    pub fn to_json(&self) -> Result<String, String> {
+        hex_serde::to_json(self)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        hex_serde::from_json(json)
+    }
 }
 
 pub fn build_circuit(instance: &MembershipInstance) -> MembershipCircuit<Fr> {
@@ -562,32 +1205,27 @@ pub fn setup_membership<R: RngCore>(
     setup_membership_with_depth(rng, MERKLE_DEPTH)
 }
 
-pub fn setup_membership_with_depth<R: RngCore>(
-    rng: &mut R,
-    depth: usize,
-) -> Result<ProvingKey<Bn254>, SynthesisError> {
+// Placeholder assignments used wherever only the circuit shape matters (setup,
+// constraint export).
+fn placeholder_circuit(depth: usize) -> MembershipCircuit<Fr> {
     let zero = Fr::from(0u64);
-    let circuit = MembershipCircuit::<Fr> {
+    MembershipCircuit::<Fr> {
         root: Some(zero),
         commitment: Some(zero),
         identity_scalar: Some(zero),
         blinding: Some(zero),
         expected_depth: depth,
         merkle_path: vec![(Some(zero), Some(false)); depth],
-    };
-    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+    }
 }
 
-pub fn setup_membership_with_depth_v2<R: RngCore>(
-    rng: &mut R,
-    depth: usize,
-) -> Result<ProvingKey<Bn254>, SynthesisError> {
+fn placeholder_circuit_v2(depth: usize) -> MembershipCircuitV2<Fr> {
     let params = poseidon_params::<Fr>();
     let zero = Fr::from(0u64);
     let commitment = commitment_hash(&params, zero, zero);
     let domain_sep = membership_v2_domain_sep_fr();
     let ctx_hash = Fr::from(0u64);
-    let circuit = MembershipCircuitV2::<Fr> {
+    MembershipCircuitV2::<Fr> {
         root: Some(commitment),
         commitment: Some(commitment),
         domain_sep: Some(domain_sep),
@@ -596,8 +1234,38 @@ pub fn setup_membership_with_depth_v2<R: RngCore>(
         blinding: Some(zero),
         expected_depth: depth,
         merkle_path: vec![(Some(commitment), Some(false)); depth],
-    };
-    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+    }
+}
+
+pub fn setup_membership_with_depth<R: RngCore>(
+    rng: &mut R,
+    depth: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(placeholder_circuit(depth), rng)
+}
+
+pub fn setup_membership_with_depth_v2<R: RngCore>(
+    rng: &mut R,
+    depth: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(placeholder_circuit_v2(depth), rng)
+}
+
+// Same seed, same keys: for tests and CI that need to regenerate a recorded key
+// pair. Anyone holding the seed can recompute the setup trapdoor and forge
+// proofs, so production keys must come from OsRng or a ceremony.
+pub fn setup_membership_with_seed(
+    seed: [u8; 32],
+    depth: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    setup_membership_with_depth(&mut StdRng::from_seed(seed), depth)
+}
+
+pub fn setup_membership_with_seed_v2(
+    seed: [u8; 32],
+    depth: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    setup_membership_with_depth_v2(&mut StdRng::from_seed(seed), depth)
 }
 
 pub fn prove_membership<R: RngCore>(
@@ -618,14 +1286,66 @@ pub fn prove_membership_v2<R: RngCore>(
     Groth16::<Bn254>::create_random_proof_with_reduction(circuit, pk, rng)
 }
 
+// Prepares the verifying key once so a service checking many proofs against the
+// same key does not redo it per call. `verify_membership` builds one per call.
+#[derive(Clone, Debug)]
+pub struct MembershipVerifier {
+    pvk: PreparedVerifyingKey<Bn254>,
+}
+
+impl MembershipVerifier {
+    pub fn new(vk: &VerifyingKey<Bn254>) -> Self {
+        Self {
+            pvk: prepare_verifying_key(vk),
+        }
+    }
+
+    pub fn verify(
+        &self,
+        public_inputs: &MembershipPublicInputs,
+        proof: &Proof<Bn254>,
+    ) -> Result<bool, SynthesisError> {
+        let inputs = vec![public_inputs.root, public_inputs.commitment];
+        Groth16::<Bn254>::verify_proof(&self.pvk, proof, &inputs)
+    }
+
+    pub fn verify_v2(
+        &self,
+        public_inputs: &MembershipPublicInputsV2,
+        proof: &Proof<Bn254>,
+    ) -> Result<bool, SynthesisError> {
+        let inputs = vec![
+            public_inputs.root,
+            public_inputs.commitment,
+            public_inputs.domain_sep,
+            public_inputs.ctx_hash,
+        ];
+        Groth16::<Bn254>::verify_proof(&self.pvk, proof, &inputs)
+    }
+}
+
 pub fn verify_membership(
     vk: &VerifyingKey<Bn254>,
     public_inputs: &MembershipPublicInputs,
     proof: &Proof<Bn254>,
 ) -> Result<bool, SynthesisError> {
+    MembershipVerifier::new(vk).verify(public_inputs, proof)
+}
+
+// `verify_membership` plus the wall-clock time spent in `Groth16::verify_proof`,
+// for services that track verification latency. Preparing the key and inputs is
+// not counted.
+#[cfg(feature = "std")]
+pub fn verify_membership_timed(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &MembershipPublicInputs,
+    proof: &Proof<Bn254>,
+) -> Result<(bool, Duration), SynthesisError> {
     let pvk = prepare_verifying_key(vk);
     let inputs = vec![public_inputs.root, public_inputs.commitment];
-    Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)
+    let start = Instant::now();
+    let valid = Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)?;
+    Ok((valid, start.elapsed()))
 }
 
 pub fn verify_membership_v2(
@@ -633,15 +1353,126 @@ pub fn verify_membership_v2(
     public_inputs: &MembershipPublicInputsV2,
     proof: &Proof<Bn254>,
 ) -> Result<bool, SynthesisError> {
-    let pvk = prepare_verifying_key(vk);
-    let inputs = vec![
+    MembershipVerifier::new(vk).verify_v2(public_inputs, proof)
+}
+
+// V2 verification from root and commitment alone, with domain_sep and ctx_hash
+// filled from MEMBERSHIP_V2_DOMAIN_SEP and MEMBERSHIP_V2_DEFAULT_CTX_HASH. Only
+// valid for deployments proving under the default ctx_hash; any other context
+// makes every proof fail here.
+pub fn verify_membership_v2_partial(
+    vk: &VerifyingKey<Bn254>,
+    root: Fr,
+    commitment: Fr,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    let public_inputs = MembershipPublicInputsV2 {
+        root,
+        commitment,
+        domain_sep: membership_v2_domain_sep_fr(),
+        ctx_hash: Fr::from_be_bytes_mod_order(&MEMBERSHIP_V2_DEFAULT_CTX_HASH),
+    };
+    verify_membership_v2(vk, &public_inputs, proof)
+}
+
+pub fn membership_v2_field_inputs(public_inputs: &MembershipPublicInputsV2) -> Vec<Fr> {
+    vec![
         public_inputs.root,
         public_inputs.commitment,
         public_inputs.domain_sep,
         public_inputs.ctx_hash,
-    ];
-    Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)
+    ]
 }
+
+#[cfg(feature = "std")]
+// Values are written as canonical decimal integers. `Fr`'s own `Display` renders zero as
+// an empty string, which `from_str` then refuses, so it is not used here.
+pub fn field_inputs_to_json(inputs: &[Fr]) -> String {
+    let values: Vec<String> = inputs
+        .iter()
+        .map(|value| value.into_bigint().to_string())
+        .collect();
+    serde_json::to_string(&values).expect("string array serializes")
+}
+
+#[cfg(feature = "std")]
+pub fn field_inputs_from_json(inputs_json: &str) -> Result<Vec<Fr>, String> {
+    let values: Vec<String> = serde_json::from_str(inputs_json)
+        .map_err(|err| format!("invalid field inputs json: {err}"))?;
+    values
+        .iter()
+        .enumerate()
+        .map(|(idx, value)| {
+            // `from_str` reduces values at or past the modulus; only the canonical spelling
+            // of the parsed element is accepted.
+            Fr::from_str(value)
+                .ok()
+                .filter(|parsed| parsed.into_bigint().to_string() == *value)
+                .ok_or_else(|| format!("inputs[{}]: invalid field element", idx))
+        })
+        .collect()
+}
+
+pub fn expected_public_inputs(vk: &VerifyingKey<Bn254>) -> usize {
+    vk.gamma_abc_g1.len().saturating_sub(1)
+}
+
+pub fn vk_matches_instance(vk: &VerifyingKey<Bn254>, instance_public_inputs: &[Fr]) -> bool {
+    expected_public_inputs(vk) == instance_public_inputs.len()
+}
+
+#[cfg(feature = "std")]
+pub fn verify_membership_v2_from_json_inputs(
+    vk: &VerifyingKey<Bn254>,
+    inputs_json: &str,
+    proof: &Proof<Bn254>,
+) -> Result<bool, String> {
+    let inputs = field_inputs_from_json(inputs_json)?;
+    if !vk_matches_instance(vk, &inputs) {
+        return Err(format!(
+            "public input count mismatch: verifying key expects {}, got {}",
+            expected_public_inputs(vk),
+            inputs.len()
+        ));
+    }
+    let pvk = prepare_verifying_key(vk);
+    Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs).map_err(|err| err.to_string())
+}
+
+// Rejects a proof whose root is not one of `allowed_roots` (canonical 32-byte
+// encodings, e.g. the current and previous epoch roots) before the pairing check.
+#[cfg(feature = "std")]
+pub fn verify_membership_v2_root_allowlist(
+    vk: &VerifyingKey<Bn254>,
+    inputs_bytes: &[u8],
+    proof: &Proof<Bn254>,
+    allowed_roots: &[[u8; 32]],
+) -> Result<bool, String> {
+    verify_with_root_allowlist(inputs_bytes, allowed_roots, |public_inputs| {
+        verify_membership_v2(vk, public_inputs, proof)
+    })
+}
+
+#[cfg(feature = "std")]
+fn verify_with_root_allowlist<V>(
+    inputs_bytes: &[u8],
+    allowed_roots: &[[u8; 32]],
+    verify: V,
+) -> Result<bool, String>
+where
+    V: FnOnce(&MembershipPublicInputsV2) -> Result<bool, SynthesisError>,
+{
+    let inputs_bytes: MembershipPublicInputsV2Bytes = bincode::deserialize(inputs_bytes)
+        .map_err(|err| format!("failed to decode public inputs: {err}"))?;
+    let (public_inputs, _) = inputs_bytes.into_public_inputs_with_depth()?;
+
+    let root = fr_to_fixed_bytes(&public_inputs.root);
+    if !allowed_roots.iter().any(|allowed| allowed[..] == root[..]) {
+        return Err("root not in allowlist".to_string());
+    }
+    verify(&public_inputs).map_err(|err| err.to_string())
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct MembershipCircuit<F: PrimeField> {
     pub root: Option<F>,
@@ -653,12 +1484,41 @@ pub struct MembershipCircuit<F: PrimeField> {
     pub merkle_path: Vec<(Option<F>, Option<bool>)>,
 }
 
+// `MembershipCircuit` synthesized with the hash `H`. `MembershipCircuit` itself is
+// this circuit with `H = Poseidon`.
+#[derive(Clone, Debug, Default)]
+pub struct MembershipCircuitWithHash<F: PrimeField, H: CircuitHash = Poseidon> {
+    circuit: MembershipCircuit<F>,
+    hash: PhantomData<H>,
+}
+
+impl<F: PrimeField, H: CircuitHash> MembershipCircuitWithHash<F, H> {
+    pub fn new(circuit: MembershipCircuit<F>) -> Self {
+        Self {
+            circuit,
+            hash: PhantomData,
+        }
+    }
+}
+
 impl<F: PrimeField> ConstraintSynthesizer<F> for MembershipCircuit<F> {
     fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        self.synthesize::<Poseidon>(cs)
+    }
+}
+
+impl<F: PrimeField, H: CircuitHash> ConstraintSynthesizer<F> for MembershipCircuitWithHash<F, H> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        self.circuit.synthesize::<H>(cs)
+    }
+}
+
+impl<F: PrimeField> MembershipCircuit<F> {
+    fn synthesize<H: CircuitHash>(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
         if self.expected_depth == 0 || self.merkle_path.len() != self.expected_depth {
             return Err(SynthesisError::Unsatisfiable);
         }
-        let params = poseidon_params::<F>();
+        let params = H::params::<F>();
 
         let root =
             FpVar::new_input(cs.clone(), || self.root.ok_or(SynthesisError::AssignmentMissing))?;
@@ -677,15 +1537,14 @@ impl<F: PrimeField> ConstraintSynthesizer<F> for MembershipCircuit<F> {
         let domain_node = FpVar::constant(F::from(DOMAIN_NODE));
         let zero = FpVar::zero();
 
-        let commitment = poseidon_hash_var(
+        let commitment = H::hash_var(
             cs.clone(),
             &params,
             &[domain_commitment, identity_scalar, blinding],
         )?;
         commitment.enforce_equal(&commitment_input)?;
 
-        let mut current =
-            poseidon_hash_var(cs.clone(), &params, &[domain_leaf, commitment, zero])?;
+        let mut current = H::hash_var(cs.clone(), &params, &[domain_leaf, commitment, zero])?;
         for (sibling_value, is_left_value) in self.merkle_path {
             let sibling = FpVar::new_witness(cs.clone(), || {
                 sibling_value.ok_or(SynthesisError::AssignmentMissing)
@@ -696,7 +1555,7 @@ impl<F: PrimeField> ConstraintSynthesizer<F> for MembershipCircuit<F> {
 
             let left = is_left.select(&sibling, &current)?;
             let right = is_left.select(&current, &sibling)?;
-            current = poseidon_hash_var(cs.clone(), &params, &[domain_node.clone(), left, right])?;
+            current = H::hash_var(cs.clone(), &params, &[domain_node.clone(), left, right])?;
         }
 
         current.enforce_equal(&root)?;
@@ -779,17 +1638,35 @@ impl<F: PrimeField> ConstraintSynthesizer<F> for MembershipCircuitV2<F> {
 #[cfg(test)]
 mod tests {
     use super::{
-        commitment_hash, fr_to_fixed_bytes, leaf_hash, node_hash, poseidon_hash_leaf_v2,
-        poseidon_params, membership_v2_domain_sep_fr, MembershipCircuit,
-        MembershipCircuitV2, MembershipInstanceBytes, MembershipInstanceV1Bytes,
-        MembershipPublicInputsBytes, MembershipPublicInputsV1Bytes, MembershipWitnessBytes,
-        MembershipWitnessV1Bytes, MerklePathNodeBytes, MEMBERSHIP_INSTANCE_VERSION_V1,
-        MERKLE_DEPTH,
+        commitment_hash, fr_to_fixed_bytes, leaf_hash, membership_v2_domain_sep_fr, node_hash,
+        poseidon_hash_leaf_v2, poseidon_params, MembershipCircuit, MembershipCircuitV2,
+        MembershipInstanceBytes, MembershipInstanceV1Bytes, MembershipPublicInputsBytes,
+        MembershipPublicInputsV1Bytes, MembershipWitnessBytes, MembershipWitnessV1Bytes,
+        MerklePathNodeBytes, MEMBERSHIP_INSTANCE_VERSION_V1, MERKLE_DEPTH,
+    };
+    use super::{
+        field_inputs_from_json, field_inputs_to_json, membership_v2_field_inputs, prove_membership,
+        prove_membership_v2, root_from_witness, root_from_witness_v2, setup_membership_with_depth,
+        setup_membership_with_depth_v2, verify_membership, verify_membership_timed,
+        verify_membership_v2, verify_membership_v2_from_json_inputs, verify_membership_v2_partial,
+        verify_membership_v2_root_allowlist, verify_opening, verify_with_root_allowlist,
+        vk_matches_instance, MembershipPublicInputsV2, MembershipVerifier, MembershipWitness,
+        MembershipWitnessV2, MEMBERSHIP_V2_DEFAULT_CTX_HASH,
+    };
+    use super::{
+        fixed_bytes, fixed_bytes_allow_empty, fr_from_bytes_canonical, fr_from_u128,
+        fr_from_u64_pair,
     };
+    use crate::test_support::{
+        public_inputs_v2_bytes, sample_instance, sample_instance_v2, sample_instance_v2_with_ctx,
+    };
+    use crate::tree::PoseidonMerkleTree;
     use ark_bn254::{Bn254, Fr};
+    use ark_ff::{BigInteger, PrimeField};
     use ark_groth16::{prepare_verifying_key, Groth16};
     use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
     use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use std::time::Duration;
 
     #[test]
     fn membership_circuit_accepts_valid_path() {
@@ -908,6 +1785,74 @@ mod tests {
         assert_eq!(instance.witness.merkle_path.len(), MERKLE_DEPTH);
     }
 
+    #[test]
+    fn canonical_decoding_rejects_reduced_encodings() {
+        let modulus = Fr::MODULUS.to_bytes_be();
+        let max = fr_to_fixed_bytes(&-Fr::from(1u64));
+        assert_eq!(fr_from_bytes_canonical("x", &max).unwrap(), -Fr::from(1u64));
+        assert_eq!(fr_from_bytes_canonical("x", &[5u8]).unwrap(), Fr::from(5u64));
+        assert!(fr_from_bytes_canonical("x", &modulus).is_err());
+        assert!(fr_from_bytes_canonical("x", &[0xff; 32]).is_err());
+
+        // The modulus reduces to zero, so without the check it would alias 0x00..00.
+        let mut inputs = public_inputs_v2_bytes(&sample_instance_v2(1));
+        inputs.commitment = modulus.try_into().unwrap();
+        let err = inputs.into_public_inputs_with_depth().unwrap_err().to_string();
+        assert!(err.contains("commitment: not a canonical field element"));
+
+        // ctx_hash is a public input too: ctx and ctx + p must not both be accepted.
+        let instance = sample_instance_v2(1);
+        let mut unreduced = instance.public_inputs.ctx_hash.into_bigint();
+        assert!(!unreduced.add_with_carry(&Fr::MODULUS));
+        let mut inputs = public_inputs_v2_bytes(&instance);
+        inputs.ctx_hash = unreduced.to_bytes_be().try_into().unwrap();
+        let err = inputs.into_public_inputs_with_depth().unwrap_err().to_string();
+        assert!(err.contains("ctx_hash: not a canonical field element"));
+    }
+
+    #[test]
+    fn membership_v0_instance_bytes_accept_any_depth() {
+        let params = poseidon_params::<Fr>();
+        let identity = Fr::from(9u64);
+        let blinding = Fr::from(10u64);
+        let commitments: Vec<Fr> = (0..6u64)
+            .map(|idx| commitment_hash(&params, Fr::from(idx), Fr::from(idx + 50)))
+            .chain(std::iter::once(commitment_hash(&params, identity, blinding)))
+            .collect();
+        let tree = PoseidonMerkleTree::build(&commitments, 4, &params).unwrap();
+        let merkle_path = tree
+            .path(6)
+            .unwrap()
+            .into_iter()
+            .map(|(sibling, is_left)| MerklePathNodeBytes {
+                sibling: fr_to_fixed_bytes(&sibling),
+                is_left,
+            })
+            .collect();
+        let instance_bytes = MembershipInstanceBytes {
+            public_inputs: MembershipPublicInputsBytes {
+                root: fr_to_fixed_bytes(&tree.root()),
+                commitment: fr_to_fixed_bytes(&commitments[6]),
+            },
+            witness: MembershipWitnessBytes {
+                identity_scalar: fr_to_fixed_bytes(&identity),
+                blinding: fr_to_fixed_bytes(&blinding),
+                merkle_path,
+            },
+        };
+
+        let mut empty_path = instance_bytes.clone();
+        empty_path.witness.merkle_path.clear();
+        assert!(empty_path.into_instance().is_err());
+
+        let instance = instance_bytes.into_instance().unwrap();
+        assert_eq!(instance.witness.merkle_path.len(), 4);
+        let mut rng = StdRng::seed_from_u64(96);
+        let pk = setup_membership_with_depth(&mut rng, 4).unwrap();
+        let proof = prove_membership(&pk, &instance, &mut rng).unwrap();
+        assert!(verify_membership(&pk.vk, &instance.public_inputs, &proof).unwrap());
+    }
+
     #[test]
     fn membership_v1_witness_length_mismatch_fails() {
         let witness = MembershipWitnessV1Bytes {
@@ -919,7 +1864,7 @@ mod tests {
             merkle_directions: vec![false, true],
         };
 
-        let err = witness.into_witness(2).unwrap_err();
+        let err = witness.into_witness(2).unwrap_err().to_string();
         assert!(err.contains("merkle_siblings length mismatch"));
     }
 
@@ -948,7 +1893,7 @@ mod tests {
             witness,
         };
 
-        let err = instance.into_instance_with_depth().unwrap_err();
+        let err = instance.into_instance_with_depth().unwrap_err().to_string();
         assert!(err.contains("instance.depth mismatch"));
     }
 
@@ -1068,4 +2013,366 @@ mod tests {
         assert!(circuit.generate_constraints(cs.clone()).is_ok());
         assert!(!cs.is_satisfied().unwrap());
     }
+
+    #[test]
+    fn timed_verification_matches_untimed_result() {
+        let instance = sample_instance(11);
+        let mut rng = StdRng::seed_from_u64(96);
+        let pk = setup_membership_with_depth(&mut rng, 1).unwrap();
+        let proof = prove_membership(&pk, &instance, &mut rng).unwrap();
+
+        let inputs = &instance.public_inputs;
+        let (valid, elapsed) = verify_membership_timed(&pk.vk, inputs, &proof).unwrap();
+        assert!(valid);
+        assert!(elapsed > Duration::ZERO);
+        assert_eq!(valid, verify_membership(&pk.vk, inputs, &proof).unwrap());
+
+        let mut wrong = instance.public_inputs.clone();
+        wrong.commitment += Fr::from(1u64);
+        let (valid, elapsed) = verify_membership_timed(&pk.vk, &wrong, &proof).unwrap();
+        assert!(!valid);
+        assert!(elapsed > Duration::ZERO);
+        assert_eq!(valid, verify_membership(&pk.vk, &wrong, &proof).unwrap());
+    }
+
+    #[test]
+    fn membership_verifier_reuses_prepared_key() {
+        let mut rng = StdRng::seed_from_u64(97);
+        let pk = setup_membership_with_depth(&mut rng, 1).unwrap();
+        let verifier = MembershipVerifier::new(&pk.vk);
+        for identity in [20u64, 30, 40] {
+            let instance = sample_instance(identity);
+            let proof = prove_membership(&pk, &instance, &mut rng).unwrap();
+            assert!(verifier.verify(&instance.public_inputs, &proof).unwrap());
+
+            let mut wrong = instance.public_inputs.clone();
+            wrong.root += Fr::from(1u64);
+            assert!(!verifier.verify(&wrong, &proof).unwrap());
+            assert!(!verify_membership(&pk.vk, &wrong, &proof).unwrap());
+        }
+    }
+
+    #[test]
+    fn membership_v2_verifies_from_json_field_inputs() {
+        let instance = sample_instance_v2(2);
+        let mut rng = StdRng::seed_from_u64(77);
+        let pk = setup_membership_with_depth_v2(&mut rng, 2).unwrap();
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+
+        let mut inputs = membership_v2_field_inputs(&instance.public_inputs);
+        let json = field_inputs_to_json(&inputs);
+        assert!(verify_membership_v2_from_json_inputs(&pk.vk, &json, &proof).unwrap());
+
+        inputs[3] += Fr::from(1u64);
+        let tampered = field_inputs_to_json(&inputs);
+        assert!(!verify_membership_v2_from_json_inputs(&pk.vk, &tampered, &proof).unwrap());
+
+        let short = field_inputs_to_json(&inputs[..3]);
+        assert!(verify_membership_v2_from_json_inputs(&pk.vk, &short, &proof).is_err());
+        assert!(verify_membership_v2_from_json_inputs(&pk.vk, "[\"abc\"]", &proof).is_err());
+    }
+
+    #[test]
+    fn field_inputs_json_round_trips_zero_and_rejects_unreduced_values() {
+        let inputs = [Fr::from(0u64), Fr::from(1u64), -Fr::from(1u64)];
+        let json = field_inputs_to_json(&inputs);
+        assert_eq!(field_inputs_from_json(&json).unwrap(), inputs);
+
+        let modulus = Fr::MODULUS.to_string();
+        let mut past_modulus = Fr::MODULUS;
+        past_modulus.add_with_carry(&Fr::from(5u64).into_bigint());
+        for unreduced in [modulus, past_modulus.to_string()] {
+            let json = format!("[\"{unreduced}\"]");
+            assert!(field_inputs_from_json(&json).is_err());
+        }
+        assert!(field_inputs_from_json("[\"\"]").is_err());
+        assert!(field_inputs_from_json("[\"007\"]").is_err());
+    }
+
+    #[test]
+    fn membership_v2_partial_matches_full_verification() {
+        let default_ctx = Fr::from_be_bytes_mod_order(&MEMBERSHIP_V2_DEFAULT_CTX_HASH);
+        let mut rng = StdRng::seed_from_u64(76);
+        let pk = setup_membership_with_depth_v2(&mut rng, 2).unwrap();
+
+        let instance = sample_instance_v2_with_ctx(2, default_ctx);
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+        let inputs = &instance.public_inputs;
+        assert!(verify_membership_v2(&pk.vk, inputs, &proof).unwrap());
+        assert!(
+            verify_membership_v2_partial(&pk.vk, inputs.root, inputs.commitment, &proof).unwrap()
+        );
+        assert!(!verify_membership_v2_partial(
+            &pk.vk,
+            inputs.root,
+            inputs.commitment + Fr::from(1u64),
+            &proof
+        )
+        .unwrap());
+
+        // A proof under a non-default ctx_hash only verifies with the full vector.
+        let instance = sample_instance_v2(2);
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+        let inputs = &instance.public_inputs;
+        assert!(verify_membership_v2(&pk.vk, inputs, &proof).unwrap());
+        assert!(
+            !verify_membership_v2_partial(&pk.vk, inputs.root, inputs.commitment, &proof).unwrap()
+        );
+    }
+
+    #[test]
+    fn root_allowlist_rejects_unknown_root_before_pairing() {
+        let instance = sample_instance_v2(2);
+        let inputs_bytes = bincode::serialize(&public_inputs_v2_bytes(&instance)).unwrap();
+        let mut rng = StdRng::seed_from_u64(79);
+        let pk = setup_membership_with_depth_v2(&mut rng, 2).unwrap();
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+
+        let root: [u8; 32] = fr_to_fixed_bytes(&instance.public_inputs.root)
+            .try_into()
+            .unwrap();
+        let previous = [7u8; 32];
+        assert!(
+            verify_membership_v2_root_allowlist(&pk.vk, &inputs_bytes, &proof, &[previous, root])
+                .unwrap()
+        );
+
+        let mut pairings = 0;
+        let err = verify_with_root_allowlist(&inputs_bytes, &[previous], |public_inputs| {
+            pairings += 1;
+            verify_membership_v2(&pk.vk, public_inputs, &proof)
+        })
+        .unwrap_err();
+        assert_eq!(err, "root not in allowlist");
+        assert_eq!(pairings, 0);
+
+        assert!(verify_with_root_allowlist(&inputs_bytes, &[root], |public_inputs| {
+            pairings += 1;
+            verify_membership_v2(&pk.vk, public_inputs, &proof)
+        })
+        .unwrap());
+        assert_eq!(pairings, 1);
+    }
+
+    #[test]
+    fn fr_from_u128_keeps_high_bits() {
+        assert_eq!(fr_from_u128(1u128 << 64), fr_from_u64_pair(1, 0));
+        assert_ne!(fr_from_u128(1u128 << 64), Fr::from(0u64));
+        for value in [0u64, 1, 42, u64::MAX] {
+            assert_eq!(fr_from_u128(value as u128), Fr::from(value));
+            assert_eq!(fr_from_u64_pair(0, value), Fr::from(value));
+        }
+        let value = (7u128 << 64) | 9;
+        assert_eq!(fr_from_u128(value), Fr::from(value));
+        assert_eq!(fr_from_u128(u128::MAX), fr_from_u64_pair(u64::MAX, u64::MAX));
+        let two_pow_64 = fr_from_u64_pair(1, 0);
+        assert_eq!(fr_from_u128(u128::MAX) + Fr::from(1u64), two_pow_64 * two_pow_64);
+    }
+
+    #[test]
+    fn fixed_bytes_rejects_empty_but_accepts_zero() {
+        for label in ["id", "blinding"] {
+            let err = fixed_bytes(label, &[]).unwrap_err();
+            assert_eq!(err.to_string(), format!("{label} must not be empty"));
+            assert_eq!(fixed_bytes(label, &[0u8]).unwrap(), [0u8; 32]);
+            assert_eq!(fixed_bytes(label, &[0u8; 32]).unwrap(), [0u8; 32]);
+        }
+        // ctx_hash goes through the allow-empty rule the Python bindings use, where b""
+        // means "no context" and encodes the same as an explicit zero.
+        for ctx_hash in [&[][..], &[0u8][..], &[0u8; 32][..]] {
+            let fixed = fixed_bytes_allow_empty("ctx_hash", ctx_hash).unwrap();
+            assert_eq!(fixed, [0u8; 32]);
+        }
+
+        let mut expected = [0u8; 32];
+        expected[31] = 7;
+        assert_eq!(fixed_bytes("id", &[7u8]).unwrap(), expected);
+        assert_eq!(fixed_bytes_allow_empty("ctx_hash", &[7u8]).unwrap(), expected);
+        assert!(fixed_bytes("id", &[1u8; 33]).is_err());
+        assert!(fixed_bytes_allow_empty("ctx_hash", &[1u8; 33]).is_err());
+    }
+
+    #[test]
+    fn vk_matches_instance_checks_input_count() {
+        let instance = sample_instance_v2(1);
+        let mut rng = StdRng::seed_from_u64(61);
+        let pk = setup_membership_with_depth_v2(&mut rng, 1).unwrap();
+
+        let inputs = membership_v2_field_inputs(&instance.public_inputs);
+        assert_eq!(inputs.len(), 4);
+        assert!(vk_matches_instance(&pk.vk, &inputs));
+        assert!(!vk_matches_instance(&pk.vk, &inputs[..2]));
+    }
+
+    #[test]
+    fn root_from_witness_matches_built_roots() {
+        let params = poseidon_params::<Fr>();
+        let identity = Fr::from(51u64);
+        let blinding = Fr::from(52u64);
+        let commitments = vec![
+            commitment_hash(&params, Fr::from(1u64), Fr::from(2u64)),
+            commitment_hash(&params, Fr::from(3u64), Fr::from(4u64)),
+            commitment_hash(&params, identity, blinding),
+        ];
+        let tree = PoseidonMerkleTree::build(&commitments, 3, &params).unwrap();
+        let witness = MembershipWitness {
+            identity_scalar: identity,
+            blinding,
+            merkle_path: tree.path(2).unwrap(),
+        };
+        assert_eq!(root_from_witness(&params, &witness), tree.root());
+
+        let instance = sample_instance_v2(3);
+        let inputs = &instance.public_inputs;
+        assert_eq!(
+            root_from_witness_v2(&params, &instance.witness, inputs.domain_sep, inputs.ctx_hash),
+            inputs.root
+        );
+    }
+
+    #[test]
+    fn verify_opening_matches_circuit_commitment_check() {
+        let params = poseidon_params::<Fr>();
+        let instance = sample_instance_v2(2);
+        let witness = &instance.witness;
+        let commitment: [u8; 32] = fr_to_fixed_bytes(&instance.public_inputs.commitment)
+            .try_into()
+            .unwrap();
+        let wrong_blinding = witness.blinding + Fr::from(1u64);
+
+        assert!(verify_opening(witness.identity_scalar, witness.blinding, commitment));
+        assert!(!verify_opening(witness.identity_scalar, wrong_blinding, commitment));
+
+        // Root follows the opened commitment, so only the commitment check can fail.
+        let sibling = leaf_hash(&params, Fr::from(7u64));
+        let circuit_accepts = |blinding: Fr| {
+            let identity = witness.identity_scalar;
+            let leaf = leaf_hash(&params, commitment_hash(&params, identity, blinding));
+            let circuit = MembershipCircuit::<Fr> {
+                root: Some(node_hash(&params, leaf, sibling)),
+                commitment: Some(instance.public_inputs.commitment),
+                identity_scalar: Some(identity),
+                blinding: Some(blinding),
+                expected_depth: 1,
+                merkle_path: vec![(Some(sibling), Some(false))],
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            cs.is_satisfied().unwrap()
+        };
+        assert!(circuit_accepts(witness.blinding));
+        assert!(!circuit_accepts(wrong_blinding));
+    }
+
+    fn golden_hex(value: &Fr) -> String {
+        fr_to_fixed_bytes(value)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    // Pinned outputs for a fixed witness. A change to the Poseidon parameters, the
+    // domain constants or the public-input order must show up here; only update
+    // these values together with a schema version bump.
+    #[test]
+    fn golden_hashes_and_public_inputs_are_stable() {
+        let params = poseidon_params::<Fr>();
+        let identity = Fr::from(11u64);
+        let blinding = Fr::from(12u64);
+        let ctx_hash = Fr::from(13u64);
+        let sibling = Fr::from(14u64);
+        let domain_sep = membership_v2_domain_sep_fr();
+
+        let commitment = commitment_hash(&params, identity, blinding);
+        let leaf = leaf_hash(&params, commitment);
+        let leaf_v2 = poseidon_hash_leaf_v2(&params, domain_sep, ctx_hash, commitment);
+        let root = node_hash(&params, leaf, sibling);
+        let root_v2 = node_hash(&params, sibling, leaf_v2);
+        let witness_v2 = MembershipWitnessV2 {
+            identity_scalar: identity,
+            blinding,
+            merkle_path: vec![(sibling, true)],
+        };
+        assert_eq!(root_from_witness_v2(&params, &witness_v2, domain_sep, ctx_hash), root_v2);
+
+        let commitment_hex = "053e9b766f06e0f84b083e31b7321768d2ff14b2941d40870bfe526e70d62736";
+        let root_v2_hex = "26e80fcc16e16a87f1da102eee5665c4bd75b7e49c932fb16a656d996b11c93a";
+        assert_eq!(golden_hex(&commitment), commitment_hex);
+        assert_eq!(
+            golden_hex(&leaf),
+            "287e8d5abc8075fa7e6e5483292ff926a029498f914d5607facb3580ee004783"
+        );
+        assert_eq!(
+            golden_hex(&leaf_v2),
+            "06725bb74859fbee6db48b5ab19c870f807978ed862359c554e88a14b66459e4"
+        );
+        assert_eq!(
+            golden_hex(&root),
+            "07d3ad782599ad3b90c2a54bc59873bc159589cc616af9ec1dfb0f18526cabc0"
+        );
+        assert_eq!(golden_hex(&root_v2), root_v2_hex);
+
+        let field_inputs_v2: Vec<String> = membership_v2_field_inputs(&MembershipPublicInputsV2 {
+            root: root_v2,
+            commitment,
+            domain_sep,
+            ctx_hash,
+        })
+        .iter()
+        .map(golden_hex)
+        .collect();
+        assert_eq!(
+            field_inputs_v2,
+            [
+                root_v2_hex,
+                commitment_hex,
+                "22e9f2df6a2dad1b94f1ff9bd1c6f0f337224a16e5a5eece1b7d69cb6f5f5f5e",
+                "000000000000000000000000000000000000000000000000000000000000000d",
+            ]
+        );
+    }
+
+    #[test]
+    fn domain_seps_reduce_to_distinct_nonzero_elements() {
+        use super::{
+            check_domain_seps_distinct, domain_sep_is_canonical, validate_constants,
+            DOMAIN_SEP_CANONICAL_BYTES, MEMBERSHIP_V2_DOMAIN_SEP, SET_MEMBERSHIP_DOMAIN_SEP,
+        };
+
+        validate_constants().unwrap();
+        // The shipped 32-byte constants exceed the modulus; shorter labels do not.
+        assert!(!domain_sep_is_canonical(&MEMBERSHIP_V2_DOMAIN_SEP));
+        assert!(!domain_sep_is_canonical(&SET_MEMBERSHIP_DOMAIN_SEP));
+        assert!(domain_sep_is_canonical(&[0xff; DOMAIN_SEP_CANONICAL_BYTES]));
+        assert!(domain_sep_is_canonical(&MEMBERSHIP_V2_DOMAIN_SEP[1..]));
+
+        let modulus: [u8; 32] = Fr::MODULUS.to_bytes_be().try_into().unwrap();
+        let mut five = [0u8; 32];
+        five[31] = 5;
+        let mut modulus_plus_five = modulus;
+        modulus_plus_five[31] += 5;
+        let err = check_domain_seps_distinct(&[("A", &five), ("B", &modulus_plus_five)]);
+        assert_eq!(err.unwrap_err().to_string(), "B and A reduce to the same field element");
+        let err = check_domain_seps_distinct(&[("ZERO", &modulus)]);
+        assert_eq!(err.unwrap_err().to_string(), "ZERO reduces to zero in the field");
+    }
+
+    #[test]
+    fn seeded_setup_reproduces_the_same_keys() {
+        use super::{setup_membership_with_seed, setup_membership_with_seed_v2};
+        use crate::io::parse_seed_hex;
+        use crate::vk_fingerprint;
+
+        let seed = parse_seed_hex(&format!("0x{}", "07".repeat(32))).unwrap();
+        assert_eq!(seed, [7u8; 32]);
+        assert!(parse_seed_hex("07").is_err());
+
+        let first = setup_membership_with_seed_v2(seed, 2).unwrap();
+        let second = setup_membership_with_seed_v2(seed, 2).unwrap();
+        assert_eq!(vk_fingerprint(&first.vk), vk_fingerprint(&second.vk));
+        let other = setup_membership_with_seed_v2([8u8; 32], 2).unwrap();
+        assert_ne!(vk_fingerprint(&first.vk), vk_fingerprint(&other.vk));
+        let v1 = setup_membership_with_seed(seed, 2).unwrap();
+        assert_eq!(v1.vk.gamma_abc_g1.len(), 3);
+    }
 }
@@ -0,0 +1,83 @@
+use ark_bn254::Bn254;
+use ark_groth16::{Proof, VerifyingKey};
+use ark_relations::r1cs::SynthesisError;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{verify_membership_v2, MembershipError, MembershipPublicInputsV2Bytes};
+
+#[derive(Debug)]
+pub enum MultiDepthVerifyError {
+    NoVkForDepth(u32),
+    InvalidPublicInputs(MembershipError),
+    Synthesis(SynthesisError),
+}
+
+impl fmt::Display for MultiDepthVerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MultiDepthVerifyError::NoVkForDepth(depth) => {
+                write!(f, "no verifying key for depth {depth}")
+            }
+            MultiDepthVerifyError::InvalidPublicInputs(err) => {
+                write!(f, "invalid public inputs: {err}")
+            }
+            MultiDepthVerifyError::Synthesis(err) => write!(f, "verification failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for MultiDepthVerifyError {}
+
+// Each depth has its own key pair, so a registry that was re-setup at a larger
+// depth keeps the old VK around until proofs made against it have drained. The
+// depth in the public inputs picks the key.
+pub fn verify_membership_multi_depth(
+    vks: &HashMap<u32, VerifyingKey<Bn254>>,
+    inputs_bytes: &MembershipPublicInputsV2Bytes,
+    proof: &Proof<Bn254>,
+) -> Result<bool, MultiDepthVerifyError> {
+    let vk = vks
+        .get(&inputs_bytes.depth)
+        .ok_or(MultiDepthVerifyError::NoVkForDepth(inputs_bytes.depth))?;
+    let (public_inputs, _) = inputs_bytes
+        .clone()
+        .into_public_inputs_with_depth()
+        .map_err(MultiDepthVerifyError::InvalidPublicInputs)?;
+    verify_membership_v2(vk, &public_inputs, proof).map_err(MultiDepthVerifyError::Synthesis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_membership_multi_depth, MultiDepthVerifyError};
+    use crate::test_support::{public_inputs_v2_bytes, sample_instance_v2};
+    use crate::{prove_membership_v2, setup_membership_with_depth_v2};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use std::collections::HashMap;
+
+    #[test]
+    fn proofs_for_each_registered_depth_verify_through_one_call() {
+        let mut rng = StdRng::seed_from_u64(95);
+        let mut vks = HashMap::new();
+        let mut cases = Vec::new();
+        for depth in [4usize, 8] {
+            let pk = setup_membership_with_depth_v2(&mut rng, depth).unwrap();
+            let instance = sample_instance_v2(depth);
+            let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+            vks.insert(depth as u32, pk.vk);
+            cases.push((public_inputs_v2_bytes(&instance), proof));
+        }
+
+        for (inputs_bytes, proof) in &cases {
+            assert!(verify_membership_multi_depth(&vks, inputs_bytes, proof).unwrap());
+        }
+
+        let (mut inputs_bytes, proof) = cases.remove(0);
+        inputs_bytes.depth = 8;
+        assert!(!verify_membership_multi_depth(&vks, &inputs_bytes, &proof).unwrap());
+        inputs_bytes.depth = 6;
+        let err = verify_membership_multi_depth(&vks, &inputs_bytes, &proof).unwrap_err();
+        assert!(matches!(err, MultiDepthVerifyError::NoVkForDepth(6)));
+        assert_eq!(err.to_string(), "no verifying key for depth 6");
+    }
+}
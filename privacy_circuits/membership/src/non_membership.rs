@@ -0,0 +1,296 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_sponge::poseidon::PoseidonConfig;
+use ark_std::rand::RngCore;
+
+use crate::compare::enforce_less_than_full;
+use crate::tree::PoseidonMerkleTree;
+use crate::{poseidon_hash_var, poseidon_params, DOMAIN_LEAF, DOMAIN_NODE};
+
+// "query is not in the set" against a sorted tree. Public inputs: [root,
+// query_commitment]. The witness opens two leaves `low` and `high` at adjacent
+// indices i and i + 1 with low < query < high as integers; both paths use the
+// ordinary leaf and node hashes, so the root is a normal membership root. The
+// statement only means absence if the root's leaves are strictly increasing,
+// which the verifier takes on trust from whoever publishes the root; build it
+// with `build_sorted_tree`. Keys are specific to the depth.
+#[derive(Clone, Debug, Default)]
+pub struct NonMembershipCircuit<F: PrimeField> {
+    pub root: Option<F>,
+    pub query_commitment: Option<F>,
+    pub low: Option<F>,
+    pub high: Option<F>,
+    pub expected_depth: usize,
+    pub low_path: Vec<(Option<F>, Option<bool>)>,
+    pub high_path: Vec<(Option<F>, Option<bool>)>,
+}
+
+// Root and leaf index for `leaf` under `merkle_path`. is_left marks a right child,
+// so the direction bits read leaf-first are the index in little-endian.
+fn root_and_index_var<F: PrimeField>(
+    cs: ConstraintSystemRef<F>,
+    params: &PoseidonConfig<F>,
+    leaf: FpVar<F>,
+    merkle_path: Vec<(Option<F>, Option<bool>)>,
+) -> Result<(FpVar<F>, FpVar<F>), SynthesisError> {
+    let domain_node = FpVar::constant(F::from(DOMAIN_NODE));
+    let mut current = leaf;
+    let mut index = FpVar::zero();
+    let mut weight = F::one();
+    for (sibling_value, is_left_value) in merkle_path {
+        let sibling = FpVar::new_witness(cs.clone(), || {
+            sibling_value.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let is_left = Boolean::new_witness(cs.clone(), || {
+            is_left_value.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let left = is_left.select(&sibling, &current)?;
+        let right = is_left.select(&current, &sibling)?;
+        current = poseidon_hash_var(cs.clone(), params, &[domain_node.clone(), left, right])?;
+        index += FpVar::from(is_left) * weight;
+        weight.double_in_place();
+    }
+    Ok((current, index))
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for NonMembershipCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        if self.expected_depth == 0
+            || self.low_path.len() != self.expected_depth
+            || self.high_path.len() != self.expected_depth
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let params = poseidon_params::<F>();
+
+        let root =
+            FpVar::new_input(cs.clone(), || self.root.ok_or(SynthesisError::AssignmentMissing))?;
+        let query = FpVar::new_input(cs.clone(), || {
+            self.query_commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let low =
+            FpVar::new_witness(cs.clone(), || self.low.ok_or(SynthesisError::AssignmentMissing))?;
+        let high =
+            FpVar::new_witness(cs.clone(), || self.high.ok_or(SynthesisError::AssignmentMissing))?;
+
+        let domain_leaf = FpVar::constant(F::from(DOMAIN_LEAF));
+        let low_leaf = poseidon_hash_var(
+            cs.clone(),
+            &params,
+            &[domain_leaf.clone(), low.clone(), FpVar::zero()],
+        )?;
+        let high_leaf =
+            poseidon_hash_var(cs.clone(), &params, &[domain_leaf, high.clone(), FpVar::zero()])?;
+        let (low_root, low_index) =
+            root_and_index_var(cs.clone(), &params, low_leaf, self.low_path)?;
+        let (high_root, high_index) =
+            root_and_index_var(cs.clone(), &params, high_leaf, self.high_path)?;
+        low_root.enforce_equal(&root)?;
+        high_root.enforce_equal(&root)?;
+        high_index.enforce_equal(&(low_index + F::one()))?;
+
+        enforce_less_than_full(&low, &query)?;
+        enforce_less_than_full(&query, &high)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NonMembershipPublicInputs {
+    pub root: Fr,
+    pub query_commitment: Fr,
+}
+
+#[derive(Clone, Debug)]
+pub struct NonMembershipWitness {
+    pub low: Fr,
+    pub high: Fr,
+    pub low_path: Vec<(Fr, bool)>,
+    pub high_path: Vec<(Fr, bool)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NonMembershipInstance {
+    pub public_inputs: NonMembershipPublicInputs,
+    pub witness: NonMembershipWitness,
+}
+
+// Sorts the commitments and brackets them with 0 and p - 1, so every value not in
+// the set, including ones below the smallest or above the largest commitment,
+// has an adjacent pair around it. Padding slots hash like a zero commitment and
+// follow p - 1, so they never bracket anything. Duplicates and the two sentinel
+// values themselves are rejected.
+pub fn build_sorted_tree(
+    commitments: &[Fr],
+    depth: usize,
+    params: &PoseidonConfig<Fr>,
+) -> Result<PoseidonMerkleTree, String> {
+    let low_sentinel = Fr::from(0u64);
+    let high_sentinel = -Fr::from(1u64);
+    let mut leaves = commitments.to_vec();
+    leaves.sort();
+    if leaves.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err("sorted tree commitments must be distinct".to_string());
+    }
+    if leaves.iter().any(|leaf| *leaf == low_sentinel || *leaf == high_sentinel) {
+        return Err("commitments 0 and p - 1 are reserved as sentinels".to_string());
+    }
+    leaves.insert(0, low_sentinel);
+    leaves.push(high_sentinel);
+    PoseidonMerkleTree::build(&leaves, depth, params)
+}
+
+pub fn build_non_membership_instance(
+    tree: &PoseidonMerkleTree,
+    query_commitment: Fr,
+) -> Result<NonMembershipInstance, String> {
+    let leaves = tree.commitments();
+    if leaves.windows(2).any(|pair| pair[0] >= pair[1]) {
+        return Err("tree leaves are not strictly increasing".to_string());
+    }
+    if leaves.contains(&query_commitment) {
+        return Err("query commitment is a member of the tree".to_string());
+    }
+    let index = leaves
+        .windows(2)
+        .position(|pair| pair[0] < query_commitment && query_commitment < pair[1])
+        .ok_or_else(|| "query commitment is outside the sentinel range".to_string())?;
+    Ok(NonMembershipInstance {
+        public_inputs: NonMembershipPublicInputs {
+            root: tree.root(),
+            query_commitment,
+        },
+        witness: NonMembershipWitness {
+            low: leaves[index],
+            high: leaves[index + 1],
+            low_path: tree.path(index)?,
+            high_path: tree.path(index + 1)?,
+        },
+    })
+}
+
+fn optional_path(path: &[(Fr, bool)]) -> Vec<(Option<Fr>, Option<bool>)> {
+    path.iter()
+        .map(|(sibling, is_left)| (Some(*sibling), Some(*is_left)))
+        .collect()
+}
+
+pub fn build_non_membership_circuit(instance: &NonMembershipInstance) -> NonMembershipCircuit<Fr> {
+    let witness = &instance.witness;
+    NonMembershipCircuit {
+        root: Some(instance.public_inputs.root),
+        query_commitment: Some(instance.public_inputs.query_commitment),
+        low: Some(witness.low),
+        high: Some(witness.high),
+        expected_depth: witness.low_path.len(),
+        low_path: optional_path(&witness.low_path),
+        high_path: optional_path(&witness.high_path),
+    }
+}
+
+pub fn setup_non_membership<R: RngCore>(
+    rng: &mut R,
+    depth: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let zero = Fr::from(0u64);
+    let circuit = NonMembershipCircuit::<Fr> {
+        root: Some(zero),
+        query_commitment: Some(zero),
+        low: Some(zero),
+        high: Some(zero),
+        expected_depth: depth,
+        low_path: vec![(Some(zero), Some(false)); depth],
+        high_path: vec![(Some(zero), Some(false)); depth],
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+pub fn prove_non_membership<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &NonMembershipInstance,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    Groth16::<Bn254>::create_random_proof_with_reduction(
+        build_non_membership_circuit(instance),
+        pk,
+        rng,
+    )
+}
+
+pub fn verify_non_membership(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &NonMembershipPublicInputs,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    let pvk = prepare_verifying_key(vk);
+    let inputs = vec![public_inputs.root, public_inputs.query_commitment];
+    Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_non_membership_circuit, build_non_membership_instance, build_sorted_tree,
+        prove_non_membership, setup_non_membership, verify_non_membership,
+        NonMembershipInstance,
+    };
+    use crate::{commitment_hash, poseidon_params};
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    fn satisfied(instance: &NonMembershipInstance) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        build_non_membership_circuit(instance)
+            .generate_constraints(cs.clone())
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn non_membership_covers_gaps_and_both_ends() {
+        let params = poseidon_params::<Fr>();
+        let members: Vec<Fr> = (0..4u64)
+            .map(|idx| commitment_hash(&params, Fr::from(300 + idx), Fr::from(400 + idx)))
+            .collect();
+        let tree = build_sorted_tree(&members, 3, &params).unwrap();
+        let sorted = tree.commitments().to_vec();
+        assert_eq!(sorted.len(), members.len() + 2);
+        assert!(build_sorted_tree(&[members[0], members[0]], 3, &params).is_err());
+
+        let outsider = commitment_hash(&params, Fr::from(999u64), Fr::from(998u64));
+        let below_smallest = Fr::from(1u64);
+        let above_largest = -Fr::from(2u64);
+        assert!(below_smallest < sorted[1] && above_largest > sorted[4]);
+
+        let mut rng = StdRng::seed_from_u64(101);
+        let pk = setup_non_membership(&mut rng, 3).unwrap();
+        for query in [outsider, below_smallest, above_largest] {
+            let instance = build_non_membership_instance(&tree, query).unwrap();
+            assert!(satisfied(&instance));
+            let proof = prove_non_membership(&pk, &instance, &mut rng).unwrap();
+            assert!(verify_non_membership(&pk.vk, &instance.public_inputs, &proof).unwrap());
+
+            let mut member = instance.public_inputs.clone();
+            member.query_commitment = members[0];
+            assert!(!verify_non_membership(&pk.vk, &member, &proof).unwrap());
+        }
+        assert!(build_non_membership_instance(&tree, members[2]).is_err());
+
+        // A member cannot be bracketed by its own neighbours.
+        let mut member = build_non_membership_instance(&tree, sorted[2] + Fr::from(1u64)).unwrap();
+        member.public_inputs.query_commitment = sorted[2];
+        assert!(!satisfied(&member));
+
+        // Leaves two apart bracket sorted[2] but are not adjacent.
+        let mut skip = build_non_membership_instance(&tree, sorted[2] + Fr::from(1u64)).unwrap();
+        skip.public_inputs.query_commitment = sorted[2];
+        skip.witness.low = sorted[1];
+        skip.witness.low_path = tree.path(1).unwrap();
+        assert!(!satisfied(&skip));
+    }
+}
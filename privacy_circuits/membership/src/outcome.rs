@@ -0,0 +1,102 @@
+use ark_bn254::Bn254;
+use ark_groth16::{Proof, VerifyingKey};
+use ark_relations::r1cs::SynthesisError;
+
+use crate::{
+    verify_membership, verify_membership_v2, MembershipPublicInputsBytes,
+    MembershipPublicInputsV1Bytes, MembershipPublicInputsV2Bytes,
+};
+
+// Uniform verification result shared by every statement crate, so a service can
+// count and route results without matching on per-crate error types.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerifyOutcome {
+    Valid,
+    InvalidProof,
+    SchemaError(String),
+}
+
+impl VerifyOutcome {
+    pub fn from_verification(result: Result<bool, SynthesisError>) -> Self {
+        match result {
+            Ok(true) => VerifyOutcome::Valid,
+            Ok(false) => VerifyOutcome::InvalidProof,
+            Err(err) => VerifyOutcome::SchemaError(err.to_string()),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        matches!(self, VerifyOutcome::Valid)
+    }
+}
+
+pub fn verify_membership_outcome(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: MembershipPublicInputsBytes,
+    proof: &Proof<Bn254>,
+) -> VerifyOutcome {
+    match public_inputs.into_public_inputs() {
+        Ok(inputs) => VerifyOutcome::from_verification(verify_membership(vk, &inputs, proof)),
+        Err(err) => VerifyOutcome::SchemaError(err.to_string()),
+    }
+}
+
+pub fn verify_membership_v1_outcome(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: MembershipPublicInputsV1Bytes,
+    proof: &Proof<Bn254>,
+) -> VerifyOutcome {
+    match public_inputs.into_public_inputs_with_depth() {
+        Ok((inputs, _)) => VerifyOutcome::from_verification(verify_membership(vk, &inputs, proof)),
+        Err(err) => VerifyOutcome::SchemaError(err.to_string()),
+    }
+}
+
+pub fn verify_membership_v2_outcome(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: MembershipPublicInputsV2Bytes,
+    proof: &Proof<Bn254>,
+) -> VerifyOutcome {
+    match public_inputs.into_public_inputs_with_depth() {
+        Ok((inputs, _)) => {
+            VerifyOutcome::from_verification(verify_membership_v2(vk, &inputs, proof))
+        }
+        Err(err) => VerifyOutcome::SchemaError(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_membership_v2_outcome, VerifyOutcome};
+    use crate::test_support::{public_inputs_v2_bytes, sample_instance_v2};
+    use crate::{prove_membership_v2, setup_membership_with_depth_v2};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn membership_v2_outcomes() {
+        let instance = sample_instance_v2(1);
+        let public_inputs = public_inputs_v2_bytes(&instance);
+        let mut rng = StdRng::seed_from_u64(63);
+        let pk = setup_membership_with_depth_v2(&mut rng, 1).unwrap();
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+
+        assert_eq!(
+            verify_membership_v2_outcome(&pk.vk, public_inputs.clone(), &proof),
+            VerifyOutcome::Valid
+        );
+
+        let mut tampered = proof.clone();
+        tampered.a = -tampered.a;
+        assert_eq!(
+            verify_membership_v2_outcome(&pk.vk, public_inputs.clone(), &tampered),
+            VerifyOutcome::InvalidProof
+        );
+
+        let mut bad_domain = public_inputs;
+        bad_domain.domain_sep[0] ^= 0x01;
+        assert!(matches!(
+            verify_membership_v2_outcome(&pk.vk, bad_domain, &proof),
+            VerifyOutcome::SchemaError(_)
+        ));
+    }
+}
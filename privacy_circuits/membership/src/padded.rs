@@ -0,0 +1,243 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::bits::boolean::Boolean;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_sponge::poseidon::PoseidonConfig;
+use ark_std::rand::RngCore;
+
+use crate::tree::{empty_subtree_hashes, MAX_TREE_DEPTH};
+use crate::{
+    node_hash, poseidon_hash_var, poseidon_params, verify_membership, MembershipInstance,
+    MembershipPublicInputs, DOMAIN_COMMITMENT, DOMAIN_LEAF, DOMAIN_NODE,
+};
+
+// Membership proof that always synthesizes `max_depth` levels so the verifier only
+// learns the maximum depth. Padding levels hash the current node (on the left)
+// with the empty-subtree constant, so the public root is the root of the same
+// tree zero-padded to `max_depth`, as built by PoseidonMerkleTree.
+#[derive(Clone, Debug, Default)]
+pub struct MembershipCircuitPadded<F: PrimeField> {
+    pub root: Option<F>,
+    pub commitment: Option<F>,
+    pub identity_scalar: Option<F>,
+    pub blinding: Option<F>,
+    pub max_depth: usize,
+    // Setup constants: empty_hashes[level] is the empty-subtree sibling at that level.
+    pub empty_hashes: Vec<F>,
+    // Each entry is (sibling, is_left, is_padding); padding entries ignore sibling/is_left.
+    pub merkle_path: Vec<(Option<F>, Option<bool>, Option<bool>)>,
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for MembershipCircuitPadded<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        if self.max_depth == 0
+            || self.merkle_path.len() != self.max_depth
+            || self.empty_hashes.len() != self.max_depth
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let params = poseidon_params::<F>();
+
+        let root =
+            FpVar::new_input(cs.clone(), || self.root.ok_or(SynthesisError::AssignmentMissing))?;
+        let commitment_input = FpVar::new_input(cs.clone(), || {
+            self.commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let identity_scalar = FpVar::new_witness(cs.clone(), || {
+            self.identity_scalar.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let domain_commitment = FpVar::constant(F::from(DOMAIN_COMMITMENT));
+        let domain_leaf = FpVar::constant(F::from(DOMAIN_LEAF));
+        let domain_node = FpVar::constant(F::from(DOMAIN_NODE));
+
+        let commitment = poseidon_hash_var(
+            cs.clone(),
+            &params,
+            &[domain_commitment, identity_scalar, blinding],
+        )?;
+        commitment.enforce_equal(&commitment_input)?;
+
+        let mut current =
+            poseidon_hash_var(cs.clone(), &params, &[domain_leaf, commitment, FpVar::zero()])?;
+        let mut previous_padding = Boolean::FALSE;
+        for ((sibling_value, is_left_value, is_padding_value), empty_hash) in
+            self.merkle_path.into_iter().zip(self.empty_hashes)
+        {
+            let sibling = FpVar::new_witness(cs.clone(), || {
+                sibling_value.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let is_left = Boolean::new_witness(cs.clone(), || {
+                is_left_value.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let is_padding = Boolean::new_witness(cs.clone(), || {
+                is_padding_value.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            // Padding only extends the top of the path; a real level cannot follow it.
+            previous_padding
+                .and(&is_padding.not())?
+                .enforce_equal(&Boolean::FALSE)?;
+
+            let sibling = is_padding.select(&FpVar::constant(empty_hash), &sibling)?;
+            let is_left = is_left.and(&is_padding.not())?;
+            let left = is_left.select(&sibling, &current)?;
+            let right = is_left.select(&current, &sibling)?;
+            current = poseidon_hash_var(cs.clone(), &params, &[domain_node.clone(), left, right])?;
+            previous_padding = is_padding;
+        }
+
+        current.enforce_equal(&root)?;
+        Ok(())
+    }
+}
+
+fn check_max_depth(depth: usize, max_depth: usize) -> Result<(), String> {
+    if max_depth == 0 || max_depth > MAX_TREE_DEPTH {
+        return Err(format!(
+            "max_depth must be between 1 and {}, got {}",
+            MAX_TREE_DEPTH, max_depth
+        ));
+    }
+    if depth == 0 || depth > max_depth {
+        return Err(format!(
+            "path depth must be between 1 and {}, got {}",
+            max_depth, depth
+        ));
+    }
+    Ok(())
+}
+
+// Lifts the root of a depth-`depth` tree to the root of the same tree padded with
+// empty subtrees up to `max_depth`.
+pub fn padded_root(
+    params: &PoseidonConfig<Fr>,
+    root: Fr,
+    depth: usize,
+    max_depth: usize,
+) -> Result<Fr, String> {
+    check_max_depth(depth, max_depth)?;
+    let empty_hashes = empty_subtree_hashes(params, max_depth);
+    Ok(empty_hashes[depth..]
+        .iter()
+        .fold(root, |current, empty| node_hash(params, current, *empty)))
+}
+
+pub fn setup_membership_padded<R: RngCore>(
+    rng: &mut R,
+    max_depth: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let params = poseidon_params::<Fr>();
+    let zero = Fr::from(0u64);
+    let circuit = MembershipCircuitPadded::<Fr> {
+        root: Some(zero),
+        commitment: Some(zero),
+        identity_scalar: Some(zero),
+        blinding: Some(zero),
+        max_depth,
+        empty_hashes: empty_subtree_hashes(&params, max_depth),
+        merkle_path: vec![(Some(zero), Some(false), Some(false)); max_depth],
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+// `instance.public_inputs.root` must already be the padded root (see `padded_root`).
+pub fn prove_membership_padded<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &MembershipInstance,
+    max_depth: usize,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, String> {
+    let depth = instance.witness.merkle_path.len();
+    check_max_depth(depth, max_depth)?;
+    let params = poseidon_params::<Fr>();
+    let zero = Fr::from(0u64);
+
+    let mut merkle_path: Vec<_> = instance
+        .witness
+        .merkle_path
+        .iter()
+        .map(|(sibling, is_left)| (Some(*sibling), Some(*is_left), Some(false)))
+        .collect();
+    merkle_path.resize(max_depth, (Some(zero), Some(false), Some(true)));
+
+    let circuit = MembershipCircuitPadded::<Fr> {
+        root: Some(instance.public_inputs.root),
+        commitment: Some(instance.public_inputs.commitment),
+        identity_scalar: Some(instance.witness.identity_scalar),
+        blinding: Some(instance.witness.blinding),
+        max_depth,
+        empty_hashes: empty_subtree_hashes(&params, max_depth),
+        merkle_path,
+    };
+    Groth16::<Bn254>::create_random_proof_with_reduction(circuit, pk, rng)
+        .map_err(|err| err.to_string())
+}
+
+pub fn verify_membership_padded(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &MembershipPublicInputs,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    verify_membership(vk, public_inputs, proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        padded_root, prove_membership_padded, setup_membership_padded,
+        verify_membership_padded,
+    };
+    use crate::tree::PoseidonMerkleTree;
+    use crate::{
+        commitment_hash, poseidon_params, MembershipInstance, MembershipPublicInputs,
+        MembershipWitness,
+    };
+    use ark_bn254::Fr;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn padded_circuit_hides_depth_up_to_max() {
+        let params = poseidon_params::<Fr>();
+        let max_depth = 8;
+        let identity = Fr::from(41u64);
+        let blinding = Fr::from(42u64);
+        let commitment = commitment_hash(&params, identity, blinding);
+
+        let mut rng = StdRng::seed_from_u64(66);
+        let pk = setup_membership_padded(&mut rng, max_depth).unwrap();
+
+        for depth in [2usize, 4] {
+            let mut commitments: Vec<Fr> = (0..3u64)
+                .map(|idx| commitment_hash(&params, Fr::from(idx), Fr::from(idx + 100)))
+                .collect();
+            commitments.push(commitment);
+            let tree = PoseidonMerkleTree::build(&commitments, depth, &params).unwrap();
+            let root = padded_root(&params, tree.root(), depth, max_depth).unwrap();
+            let padded_tree = PoseidonMerkleTree::build(&commitments, max_depth, &params).unwrap();
+            assert_eq!(root, padded_tree.root());
+
+            let instance = MembershipInstance {
+                public_inputs: MembershipPublicInputs { root, commitment },
+                witness: MembershipWitness {
+                    identity_scalar: identity,
+                    blinding,
+                    merkle_path: tree.path(3).unwrap(),
+                },
+            };
+            let proof = prove_membership_padded(&pk, &instance, max_depth, &mut rng).unwrap();
+            assert!(verify_membership_padded(&pk.vk, &instance.public_inputs, &proof).unwrap());
+
+            let unpadded = MembershipPublicInputs {
+                root: tree.root(),
+                commitment,
+            };
+            assert!(!verify_membership_padded(&pk.vk, &unpadded, &proof).unwrap());
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use ark_bn254::Bn254;
+use ark_groth16::{Proof, ProvingKey};
+use ark_relations::r1cs::SynthesisError;
+use ark_std::rand::{rngs::StdRng, SeedableRng};
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+
+use crate::{prove_membership, MembershipInstance};
+
+// Proves every instance in parallel on the global rayon pool; size it with
+// `RAYON_NUM_THREADS` or `rayon::ThreadPoolBuilder::build_global`, or call this
+// inside `ThreadPool::install` to use a dedicated pool. Task `i` draws from its own
+// StdRng seeded with SHA-256(seed || i), so the output depends only on `seed` and
+// the order of `instances`, not on scheduling or the thread count. Never reuse a
+// seed for a different batch: proofs sharing randomness can leak witnesses.
+pub fn prove_membership_many(
+    pk: &ProvingKey<Bn254>,
+    instances: &[MembershipInstance],
+    seed: u64,
+) -> Vec<Result<Proof<Bn254>, SynthesisError>> {
+    instances
+        .par_iter()
+        .enumerate()
+        .map(|(index, instance)| prove_membership(pk, instance, &mut task_rng(seed, index)))
+        .collect()
+}
+
+fn task_rng(seed: u64, index: usize) -> StdRng {
+    let mut hasher = Sha256::new();
+    hasher.update(seed.to_be_bytes());
+    hasher.update((index as u64).to_be_bytes());
+    StdRng::from_seed(hasher.finalize().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prove_membership_many, task_rng};
+    use crate::test_support::sample_instance;
+    use crate::{
+        prove_membership, setup_membership_with_depth, verify_membership, MembershipInstance,
+    };
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn parallel_proofs_are_deterministic_per_seed() {
+        let mut rng = StdRng::seed_from_u64(98);
+        let pk = setup_membership_with_depth(&mut rng, 1).unwrap();
+        let instances: Vec<MembershipInstance> = (1..=6).map(sample_instance).collect();
+
+        let proofs: Vec<_> = prove_membership_many(&pk, &instances, 7)
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        for (index, (instance, proof)) in instances.iter().zip(&proofs).enumerate() {
+            assert!(verify_membership(&pk.vk, &instance.public_inputs, proof).unwrap());
+            let sequential = prove_membership(&pk, instance, &mut task_rng(7, index)).unwrap();
+            assert_eq!(&sequential, proof);
+        }
+
+        let again = prove_membership_many(&pk, &instances, 7);
+        assert!(again.iter().zip(&proofs).all(|(a, b)| a.as_ref().unwrap() == b));
+        let reseeded = prove_membership_many(&pk, &instances, 8);
+        assert!(reseeded.iter().zip(&proofs).all(|(a, b)| a.as_ref().unwrap() != b));
+        assert!(prove_membership_many(&pk, &[], 7).is_empty());
+    }
+}
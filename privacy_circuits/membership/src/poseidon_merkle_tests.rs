@@ -1,5 +1,15 @@
-use super::{poseidon_hash_leaf, poseidon_hash_node, poseidon_params};
+use super::{
+    check_poseidon_params_hash, commitment_hash, poseidon_hash_leaf, poseidon_hash_native,
+    poseidon_hash_node, poseidon_hash_var, poseidon_params, poseidon_params_hash,
+    poseidon_params_from_spec, poseidon_params_with, PoseidonParamsSpec, POSEIDON_DERIVATIONS,
+    POSEIDON_PARAMS_HASH, POSEIDON_RATE,
+};
 use ark_bn254::Fr;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::R1CSVar;
+use ark_relations::r1cs::ConstraintSystem;
+use std::sync::atomic::Ordering;
 
 #[test]
 fn poseidon_merkle_leaf_node_domain_separation() {
@@ -22,3 +32,68 @@ fn poseidon_merkle_node_order_sensitive() {
 
     assert_ne!(hash_lr, hash_rl);
 }
+
+#[test]
+fn poseidon_params_hash_matches_pinned_value() {
+    assert_eq!(poseidon_params_hash(), POSEIDON_PARAMS_HASH);
+    assert!(check_poseidon_params_hash().is_ok());
+}
+
+#[test]
+fn poseidon_params_are_derived_once_for_bn254() {
+    poseidon_params::<Fr>();
+    let derivations = POSEIDON_DERIVATIONS.load(Ordering::SeqCst);
+    for idx in 0..100u64 {
+        let params = poseidon_params::<Fr>();
+        commitment_hash(&params, Fr::from(idx), Fr::from(idx + 1));
+    }
+    assert_eq!(POSEIDON_DERIVATIONS.load(Ordering::SeqCst), derivations);
+    assert_eq!(poseidon_params_hash(), POSEIDON_PARAMS_HASH);
+}
+
+#[test]
+fn poseidon_params_with_rate_is_self_consistent() {
+    let default = poseidon_params::<Fr>();
+    let same = poseidon_params_with::<Fr>(POSEIDON_RATE);
+    assert_eq!((same.ark, same.mds), (default.ark, default.mds));
+
+    let inputs: Vec<Fr> = (1..=8u64).map(Fr::from).collect();
+    let mut digests = Vec::new();
+    for rate in [3usize, 5] {
+        let params = poseidon_params_with::<Fr>(rate);
+        assert_eq!(params.rate, rate);
+        assert_eq!(params.mds.len(), rate + 1);
+        assert!(params.ark.iter().all(|row| row.len() == rate + 1));
+
+        let native = poseidon_hash_native(&params, &inputs);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let vars: Vec<FpVar<Fr>> = inputs
+            .iter()
+            .map(|value| FpVar::new_witness(cs.clone(), || Ok(*value)).unwrap())
+            .collect();
+        let digest = poseidon_hash_var(cs.clone(), &params, &vars).unwrap();
+        assert_eq!(digest.value().unwrap(), native);
+        assert!(cs.is_satisfied().unwrap());
+        digests.push(native);
+    }
+    assert_ne!(digests[0], digests[1]);
+}
+
+#[test]
+fn poseidon_spec_round_trips_and_default_matches_cached_params() {
+    let spec = PoseidonParamsSpec::DEFAULT_SPEC;
+    assert_eq!(PoseidonParamsSpec::from_bytes(&spec.to_bytes()).unwrap(), spec);
+    assert!(PoseidonParamsSpec::from_bytes(&[0u8; 32]).is_err());
+    assert!(PoseidonParamsSpec::from_bytes(&spec.to_bytes()[..31]).is_err());
+
+    let default = poseidon_params::<Fr>();
+    let from_spec = poseidon_params_from_spec::<Fr>(spec);
+    assert_eq!((from_spec.ark, from_spec.mds), (default.ark, default.mds));
+
+    let more_rounds = poseidon_params_from_spec::<Fr>(PoseidonParamsSpec {
+        partial_rounds: 57,
+        ..spec
+    });
+    assert_eq!(more_rounds.partial_rounds, 57);
+    assert_eq!(more_rounds.ark.len(), spec.full_rounds + 57);
+}
@@ -0,0 +1,164 @@
+use ark_bn254::Bn254;
+use ark_ec::short_weierstrass::{Affine, SWCurveConfig};
+use ark_groth16::{Proof, VerifyingKey};
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::CanonicalDeserialize;
+use std::fmt;
+
+use crate::io::{PROOF_SIZE_COMPRESSED, PROOF_SIZE_UNCOMPRESSED};
+use crate::{verify_membership_v2, MembershipPublicInputsV2};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofPoint {
+    A,
+    B,
+    C,
+}
+
+impl fmt::Display for ProofPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofPoint::A => f.write_str("A"),
+            ProofPoint::B => f.write_str("B"),
+            ProofPoint::C => f.write_str("C"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ProofError {
+    InvalidProofLength { expected: usize, got: usize },
+    Decode(String),
+    NotOnCurve(ProofPoint),
+    NotInSubgroup(ProofPoint),
+    Synthesis(SynthesisError),
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofError::InvalidProofLength { expected, got } => write!(
+                f,
+                "proof must be {expected} bytes ({PROOF_SIZE_COMPRESSED} compressed), got {got}"
+            ),
+            ProofError::Decode(err) => write!(f, "proof decode failed: {err}"),
+            ProofError::NotOnCurve(point) => write!(f, "proof point {point} is not on the curve"),
+            ProofError::NotInSubgroup(point) => {
+                write!(f, "proof point {point} is not in the prime-order subgroup")
+            }
+            ProofError::Synthesis(err) => write!(f, "verification failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ProofError {}
+
+fn check_point<P: SWCurveConfig>(point: &Affine<P>, label: ProofPoint) -> Result<(), ProofError> {
+    if !point.is_on_curve() {
+        return Err(ProofError::NotOnCurve(label));
+    }
+    if !point.is_in_correct_subgroup_assuming_on_curve() {
+        return Err(ProofError::NotInSubgroup(label));
+    }
+    Ok(())
+}
+
+// Proofs built in memory, or read with an unchecked deserializer, skip the curve
+// and subgroup checks that `deserialize_uncompressed` performs. G1 has cofactor
+// one, so the subgroup check only ever fails for B.
+pub fn validate_proof_points(proof: &Proof<Bn254>) -> Result<(), ProofError> {
+    check_point(&proof.a, ProofPoint::A)?;
+    check_point(&proof.b, ProofPoint::B)?;
+    check_point(&proof.c, ProofPoint::C)
+}
+
+// Entry point for untrusted proof bytes. Groth16 proofs over BN254 have a fixed
+// size, so anything else is rejected before arkworks sees it; the length also
+// picks the compressed or uncompressed decoder.
+pub fn proof_from_bytes(bytes: &[u8]) -> Result<Proof<Bn254>, ProofError> {
+    let decoded = match bytes.len() {
+        PROOF_SIZE_UNCOMPRESSED => Proof::<Bn254>::deserialize_uncompressed(bytes),
+        PROOF_SIZE_COMPRESSED => Proof::<Bn254>::deserialize_compressed(bytes),
+        got => {
+            return Err(ProofError::InvalidProofLength {
+                expected: PROOF_SIZE_UNCOMPRESSED,
+                got,
+            })
+        }
+    };
+    decoded.map_err(|err| ProofError::Decode(err.to_string()))
+}
+
+pub fn verify_membership_v2_strict(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &MembershipPublicInputsV2,
+    proof: &Proof<Bn254>,
+) -> Result<bool, ProofError> {
+    validate_proof_points(proof)?;
+    verify_membership_v2(vk, public_inputs, proof).map_err(ProofError::Synthesis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        proof_from_bytes, validate_proof_points, verify_membership_v2_strict, ProofError,
+        ProofPoint,
+    };
+    use crate::test_support::sample_instance_v2;
+    use crate::{prove_membership_v2, setup_membership_with_depth_v2};
+    use ark_bn254::{Fq, Fq2, G2Affine};
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn genuine_proof_passes_and_corrupted_points_are_named() {
+        let mut rng = StdRng::seed_from_u64(98);
+        let pk = setup_membership_with_depth_v2(&mut rng, 2).unwrap();
+        let instance = sample_instance_v2(2);
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+        assert!(validate_proof_points(&proof).is_ok());
+        assert!(verify_membership_v2_strict(&pk.vk, &instance.public_inputs, &proof).unwrap());
+
+        let mut off_curve = proof.clone();
+        off_curve.c.y += Fq::from(1u64);
+        let err = verify_membership_v2_strict(&pk.vk, &instance.public_inputs, &off_curve)
+            .unwrap_err();
+        assert!(matches!(err, ProofError::NotOnCurve(ProofPoint::C)));
+        assert_eq!(err.to_string(), "proof point C is not on the curve");
+
+        let outside_subgroup = (1u64..)
+            .filter_map(|x| G2Affine::get_point_from_x_unchecked(Fq2::from(x), false))
+            .find(|point| !point.is_in_correct_subgroup_assuming_on_curve())
+            .unwrap();
+        let mut wrong_subgroup = proof;
+        wrong_subgroup.b = outside_subgroup;
+        assert!(matches!(
+            validate_proof_points(&wrong_subgroup),
+            Err(ProofError::NotInSubgroup(ProofPoint::B))
+        ));
+    }
+
+    #[test]
+    fn truncated_proof_bytes_report_their_length() {
+        let mut rng = StdRng::seed_from_u64(61);
+        let pk = setup_membership_with_depth_v2(&mut rng, 1).unwrap();
+        let proof = prove_membership_v2(&pk, &sample_instance_v2(1), &mut rng).unwrap();
+        let mut uncompressed = Vec::new();
+        proof.serialize_uncompressed(&mut uncompressed).unwrap();
+        let mut compressed = Vec::new();
+        proof.serialize_compressed(&mut compressed).unwrap();
+        assert_eq!(proof_from_bytes(&uncompressed).unwrap(), proof);
+        assert_eq!(proof_from_bytes(&compressed).unwrap(), proof);
+
+        let err = proof_from_bytes(&uncompressed[..uncompressed.len() - 1]).unwrap_err();
+        assert!(matches!(
+            err,
+            ProofError::InvalidProofLength { expected: 256, got: 255 }
+        ));
+        assert_eq!(err.to_string(), "proof must be 256 bytes (128 compressed), got 255");
+        assert!(matches!(
+            proof_from_bytes(&[]),
+            Err(ProofError::InvalidProofLength { got: 0, .. })
+        ));
+    }
+}
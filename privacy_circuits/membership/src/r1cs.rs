@@ -0,0 +1,225 @@
+use ark_bn254::Fr;
+use ark_relations::r1cs::{
+    ConstraintMatrices, ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef,
+    OptimizationGoal, SynthesisError, SynthesisMode,
+};
+use std::fmt::Write;
+
+use crate::{
+    build_circuit, build_circuit_v2, placeholder_circuit, placeholder_circuit_v2,
+    MembershipInstance, MembershipInstanceV2, MERKLE_DEPTH,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MembershipSchema {
+    V0,
+    V1,
+    V2,
+}
+
+// Synthesizes the circuit with placeholder assignments, optimized and finalized
+// the same way Groth16 setup does, so the matrices match the ones behind the keys.
+// V0 is always the legacy depth and ignores `depth`.
+pub fn export_r1cs(
+    schema: MembershipSchema,
+    depth: usize,
+) -> Result<ConstraintSystemRef<Fr>, SynthesisError> {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    cs.set_optimization_goal(OptimizationGoal::Constraints);
+    synthesize_placeholder(cs.clone(), schema, depth)?;
+    cs.finalize();
+    Ok(cs)
+}
+
+fn synthesize_placeholder(
+    cs: ConstraintSystemRef<Fr>,
+    schema: MembershipSchema,
+    depth: usize,
+) -> Result<(), SynthesisError> {
+    match schema {
+        MembershipSchema::V0 => placeholder_circuit(MERKLE_DEPTH).generate_constraints(cs),
+        MembershipSchema::V1 => placeholder_circuit(depth).generate_constraints(cs),
+        MembershipSchema::V2 => placeholder_circuit_v2(depth).generate_constraints(cs),
+    }
+}
+
+fn count_constraints(schema: MembershipSchema, depth: usize) -> Result<usize, SynthesisError> {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    cs.set_mode(SynthesisMode::Setup);
+    synthesize_placeholder(cs.clone(), schema, depth)?;
+    Ok(cs.num_constraints())
+}
+
+// Ceiling applied by the setup binaries unless `--max-constraints` overrides it.
+pub const DEFAULT_MAX_CONSTRAINTS: usize = 1 << 22;
+
+// Every Merkle level adds the same gadget, so the count is affine in depth. It is
+// measured at depths 1 and 2 and extrapolated, which keeps the estimate cheap for
+// depths far too large to synthesize.
+pub fn estimate_constraints(
+    schema: MembershipSchema,
+    depth: usize,
+) -> Result<usize, SynthesisError> {
+    if schema == MembershipSchema::V0 || depth <= 2 {
+        return count_constraints(schema, depth);
+    }
+    let base = count_constraints(schema, 1)?;
+    let per_level = count_constraints(schema, 2)? - base;
+    Ok(base.saturating_add((depth - 1).saturating_mul(per_level)))
+}
+
+pub fn check_constraint_limit(
+    schema: MembershipSchema,
+    depth: usize,
+    max_constraints: usize,
+) -> Result<usize, String> {
+    let estimate = estimate_constraints(schema, depth).map_err(|err| err.to_string())?;
+    if estimate > max_constraints {
+        return Err(format!(
+            "{schema:?} at depth {depth} needs about {estimate} constraints, \
+             over the --max-constraints limit of {max_constraints}"
+        ));
+    }
+    Ok(estimate)
+}
+
+// Synthesizes the circuit for a concrete instance and names the first failing
+// constraint. The Groth16 prover does not check satisfiability in release builds,
+// so a bad witness otherwise surfaces only as a proof that fails to verify.
+// arkworks reports the constraint's trace path when a tracing `ConstraintLayer` is
+// installed and its index otherwise.
+pub fn debug_check_membership(instance: &MembershipInstance) -> Result<(), String> {
+    debug_check(build_circuit(instance))
+}
+
+pub fn debug_check_membership_v2(instance: &MembershipInstanceV2) -> Result<(), String> {
+    debug_check(build_circuit_v2(instance))
+}
+
+fn debug_check<C: ConstraintSynthesizer<Fr>>(circuit: C) -> Result<(), String> {
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    circuit
+        .generate_constraints(cs.clone())
+        .map_err(|err| format!("synthesis failed: {err}"))?;
+    if cs.is_satisfied().map_err(|err| err.to_string())? {
+        return Ok(());
+    }
+    let failing = cs
+        .which_is_unsatisfied()
+        .map_err(|err| err.to_string())?
+        .unwrap_or_else(|| "<unknown>".to_string());
+    Err(format!(
+        "unsatisfied constraint {failing} (of {})",
+        cs.num_constraints()
+    ))
+}
+
+// Plain-text dump: a header line `r1cs <instance vars> <witness vars> <constraints>`,
+// then sections `A`, `B`, `C` of `<row> <column> <value>` lines. Column 0 is the
+// constant one, followed by the instance and then the witness variables.
+pub fn matrices_to_text(matrices: &ConstraintMatrices<Fr>) -> String {
+    let mut out = format!(
+        "r1cs {} {} {}\n",
+        matrices.num_instance_variables, matrices.num_witness_variables, matrices.num_constraints
+    );
+    for (name, matrix) in [("A", &matrices.a), ("B", &matrices.b), ("C", &matrices.c)] {
+        out.push_str(name);
+        out.push('\n');
+        for (row, terms) in matrix.iter().enumerate() {
+            for (value, column) in terms {
+                let _ = writeln!(out, "{row} {column} {value}");
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        check_constraint_limit, debug_check_membership, debug_check_membership_v2,
+        estimate_constraints, export_r1cs, matrices_to_text, MembershipSchema,
+        DEFAULT_MAX_CONSTRAINTS,
+    };
+    use crate::{
+        build_circuit_v2, commitment_hash, leaf_hash, node_hash, poseidon_params,
+        MembershipInstance, MembershipPublicInputs, MembershipWitness,
+    };
+    use crate::test_support::sample_instance_v2;
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+
+    #[test]
+    fn exported_depth_one_system_matches_valid_assignment() {
+        let exported = export_r1cs(MembershipSchema::V2, 1).unwrap();
+        assert!(exported.num_constraints() > 0);
+        assert_eq!(exported.num_instance_variables(), 5);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        build_circuit_v2(&sample_instance_v2(1))
+            .generate_constraints(cs.clone())
+            .unwrap();
+        assert!(cs.is_satisfied().unwrap());
+        assert_eq!(cs.num_constraints(), exported.num_constraints());
+
+        let matrices = exported.to_matrices().unwrap();
+        let text = matrices_to_text(&matrices);
+        assert!(text.starts_with(&format!(
+            "r1cs 5 {} {}\nA\n",
+            matrices.num_witness_variables, matrices.num_constraints
+        )));
+
+        let v1 = export_r1cs(MembershipSchema::V1, 1).unwrap();
+        assert_eq!(v1.num_instance_variables(), 3);
+        assert!(export_r1cs(MembershipSchema::V1, 0).is_err());
+    }
+
+    #[test]
+    fn constraint_limit_accepts_modest_and_rejects_absurd_depth() {
+        let exact = export_r1cs(MembershipSchema::V2, 4).unwrap().num_constraints();
+        assert_eq!(estimate_constraints(MembershipSchema::V2, 4).unwrap(), exact);
+        assert!(estimate_constraints(MembershipSchema::V1, 0).is_err());
+
+        let modest = check_constraint_limit(MembershipSchema::V2, 20, DEFAULT_MAX_CONSTRAINTS);
+        assert!(modest.unwrap() <= DEFAULT_MAX_CONSTRAINTS);
+        let err = check_constraint_limit(MembershipSchema::V2, 1 << 20, DEFAULT_MAX_CONSTRAINTS)
+            .unwrap_err();
+        assert!(err.contains("--max-constraints"));
+        assert!(check_constraint_limit(MembershipSchema::V1, 4, exact / 2).is_err());
+    }
+
+    #[test]
+    fn debug_check_names_failing_constraint_of_tampered_instance() {
+        let mut instance = sample_instance_v2(2);
+        assert!(debug_check_membership_v2(&instance).is_ok());
+
+        instance.witness.merkle_path[1].0 += Fr::from(1u64);
+        let report = debug_check_membership_v2(&instance).unwrap_err();
+        assert!(report.starts_with("unsatisfied constraint "));
+        let failing = report
+            .trim_start_matches("unsatisfied constraint ")
+            .split(" (of ")
+            .next()
+            .unwrap();
+        assert!(!failing.is_empty());
+
+        let params = poseidon_params::<Fr>();
+        let (identity, blinding) = (Fr::from(31u64), Fr::from(32u64));
+        let commitment = commitment_hash(&params, identity, blinding);
+        let sibling = leaf_hash(&params, Fr::from(33u64));
+        let mut legacy = MembershipInstance {
+            public_inputs: MembershipPublicInputs {
+                root: node_hash(&params, leaf_hash(&params, commitment), sibling),
+                commitment,
+            },
+            witness: MembershipWitness {
+                identity_scalar: identity,
+                blinding,
+                merkle_path: vec![(sibling, false)],
+            },
+        };
+        assert!(debug_check_membership(&legacy).is_ok());
+        legacy.witness.blinding += Fr::from(1u64);
+        assert!(debug_check_membership(&legacy).is_err());
+    }
+}
@@ -0,0 +1,146 @@
+use ark_bn254::{Bn254, Fr};
+use ark_groth16::{Proof, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commitment_hash, fr_from_bytes, fr_to_fixed_bytes, membership_v2_domain_sep_fr,
+    poseidon_params, root_from_witness_v2, verify_membership_v2, MembershipInstanceV2,
+    MembershipPublicInputsV2, MembershipWitnessV2, MEMBERSHIP_STATEMENT_TYPE,
+};
+
+// Issued by a verifier to pin the parameters a proof must be produced under.
+// An empty `allowed_roots` accepts any root.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofRequest {
+    pub statement_type: u16,
+    pub ctx_hash: [u8; 32],
+    pub challenge: Option<[u8; 32]>,
+    pub allowed_roots: Vec<[u8; 32]>,
+}
+
+impl ProofRequest {
+    pub fn membership_v2(ctx_hash: [u8; 32], allowed_roots: Vec<[u8; 32]>) -> Self {
+        Self {
+            statement_type: MEMBERSHIP_STATEMENT_TYPE,
+            ctx_hash,
+            challenge: None,
+            allowed_roots,
+        }
+    }
+
+    // Membership V2 has no public input that could carry a challenge, and folding
+    // one into ctx_hash would change every leaf and therefore the root.
+    fn membership_v2_ctx_hash(&self) -> Result<Fr, String> {
+        if self.statement_type != MEMBERSHIP_STATEMENT_TYPE {
+            return Err(format!(
+                "request is for statement type {}, expected {}",
+                self.statement_type, MEMBERSHIP_STATEMENT_TYPE
+            ));
+        }
+        if self.challenge.is_some() {
+            return Err("membership V2 proofs cannot carry a challenge".to_string());
+        }
+        Ok(fr_from_bytes("ctx_hash", &self.ctx_hash)?)
+    }
+
+    fn allows_root(&self, root: &Fr) -> bool {
+        let root = fr_to_fixed_bytes(root);
+        self.allowed_roots.is_empty()
+            || self
+                .allowed_roots
+                .iter()
+                .any(|allowed| allowed[..] == root[..])
+    }
+}
+
+// Prover side: builds the V2 instance under the request's ctx_hash and refuses
+// to continue if the resulting root is not one the verifier will accept.
+pub fn membership_v2_instance_for_request(
+    request: &ProofRequest,
+    witness: MembershipWitnessV2,
+) -> Result<MembershipInstanceV2, String> {
+    let ctx_hash = request.membership_v2_ctx_hash()?;
+    let params = poseidon_params::<Fr>();
+    let domain_sep = membership_v2_domain_sep_fr();
+    let root = root_from_witness_v2(&params, &witness, domain_sep, ctx_hash);
+    if !request.allows_root(&root) {
+        return Err("root not in request allowlist".to_string());
+    }
+    Ok(MembershipInstanceV2 {
+        public_inputs: MembershipPublicInputsV2 {
+            root,
+            commitment: commitment_hash(&params, witness.identity_scalar, witness.blinding),
+            domain_sep,
+            ctx_hash,
+        },
+        witness,
+    })
+}
+
+// Verifier side: the proof's ctx_hash and root must match the request before the
+// pairing check runs.
+pub fn verify_membership_v2_for_request(
+    vk: &VerifyingKey<Bn254>,
+    request: &ProofRequest,
+    public_inputs: &MembershipPublicInputsV2,
+    proof: &Proof<Bn254>,
+) -> Result<bool, String> {
+    let ctx_hash = request.membership_v2_ctx_hash()?;
+    if public_inputs.ctx_hash != ctx_hash {
+        return Err("ctx_hash does not match the request".to_string());
+    }
+    if !request.allows_root(&public_inputs.root) {
+        return Err("root not in request allowlist".to_string());
+    }
+    verify_membership_v2(vk, public_inputs, proof).map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        membership_v2_instance_for_request, verify_membership_v2_for_request, ProofRequest,
+    };
+    use crate::test_support::{sample_instance_v2, sample_instance_v2_with_ctx};
+    use crate::{fr_to_fixed_bytes, prove_membership_v2, setup_membership_with_depth_v2};
+    use ark_bn254::Fr;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn request_ctx_hash_is_enforced_end_to_end() {
+        let ctx_hash = Fr::from(0x5151u64);
+        let reference = sample_instance_v2_with_ctx(2, ctx_hash);
+        let request = ProofRequest::membership_v2(
+            fr_to_fixed_bytes(&ctx_hash).try_into().unwrap(),
+            vec![fr_to_fixed_bytes(&reference.public_inputs.root).try_into().unwrap()],
+        );
+        let decoded: ProofRequest =
+            bincode::deserialize(&bincode::serialize(&request).unwrap()).unwrap();
+        assert_eq!(decoded, request);
+
+        let instance = membership_v2_instance_for_request(&request, reference.witness).unwrap();
+        assert_eq!(instance.public_inputs.root, reference.public_inputs.root);
+
+        let mut rng = StdRng::seed_from_u64(87);
+        let pk = setup_membership_with_depth_v2(&mut rng, 2).unwrap();
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+        assert!(
+            verify_membership_v2_for_request(&pk.vk, &request, &instance.public_inputs, &proof)
+                .unwrap()
+        );
+
+        let ignoring = sample_instance_v2(2);
+        let ignoring_proof = prove_membership_v2(&pk, &ignoring, &mut rng).unwrap();
+        let err = verify_membership_v2_for_request(
+            &pk.vk,
+            &request,
+            &ignoring.public_inputs,
+            &ignoring_proof,
+        )
+        .unwrap_err();
+        assert_eq!(err, "ctx_hash does not match the request");
+
+        let mut challenged = request.clone();
+        challenged.challenge = Some([1u8; 32]);
+        assert!(membership_v2_instance_for_request(&challenged, ignoring.witness).is_err());
+    }
+}
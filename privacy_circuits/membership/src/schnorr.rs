@@ -0,0 +1,302 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ec::{AffineRepr, CurveGroup, Group};
+use ark_ed_on_bn254::constraints::EdwardsVar;
+use ark_ed_on_bn254::{EdwardsAffine, EdwardsProjective, Fr as JubjubFr};
+use ark_ff::{BigInteger, PrimeField, UniformRand};
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_sponge::poseidon::PoseidonConfig;
+use ark_std::rand::RngCore;
+
+use crate::{
+    membership_v2_domain_sep_fr, merkle_root_var, poseidon_hash_native, poseidon_hash_var,
+    poseidon_params, MembershipInstanceV2, MembershipPublicInputsV2, DOMAIN_COMMITMENT,
+    DOMAIN_SCHNORR, MEMBERSHIP_V2_DOMAIN_SEP,
+};
+
+// Schnorr signatures on Baby Jubjub (ed-on-bn254), whose base field is the BN254
+// scalar field, so points are native circuit values. The secret key is the
+// credential's identity_scalar and the public key is identity_scalar * G. The
+// challenge is Poseidon(DOMAIN_SCHNORR, R.x, R.y, pk.x, pk.y, ctx_hash) and a
+// signature (R, s) is valid when s * G == R + challenge * pk.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SchnorrSignature {
+    pub r: EdwardsAffine,
+    pub s: JubjubFr,
+}
+
+fn to_jubjub_scalar(value: Fr) -> JubjubFr {
+    JubjubFr::from_le_bytes_mod_order(&value.into_bigint().to_bytes_le())
+}
+
+pub fn schnorr_public_key(identity_scalar: Fr) -> EdwardsAffine {
+    (EdwardsAffine::generator() * to_jubjub_scalar(identity_scalar)).into_affine()
+}
+
+pub fn schnorr_challenge(
+    params: &PoseidonConfig<Fr>,
+    r: &EdwardsAffine,
+    public_key: &EdwardsAffine,
+    ctx_hash: Fr,
+) -> Fr {
+    poseidon_hash_native(
+        params,
+        &[Fr::from(DOMAIN_SCHNORR), r.x, r.y, public_key.x, public_key.y, ctx_hash],
+    )
+}
+
+pub fn sign_ctx_hash<R: RngCore>(
+    identity_scalar: Fr,
+    ctx_hash: Fr,
+    rng: &mut R,
+) -> SchnorrSignature {
+    let params = poseidon_params::<Fr>();
+    let nonce = JubjubFr::rand(rng);
+    let r = (EdwardsAffine::generator() * nonce).into_affine();
+    let public_key = schnorr_public_key(identity_scalar);
+    let challenge = to_jubjub_scalar(schnorr_challenge(&params, &r, &public_key, ctx_hash));
+    SchnorrSignature {
+        r,
+        s: nonce + challenge * to_jubjub_scalar(identity_scalar),
+    }
+}
+
+pub fn verify_schnorr(
+    public_key: &EdwardsAffine,
+    ctx_hash: Fr,
+    signature: &SchnorrSignature,
+) -> bool {
+    let params = poseidon_params::<Fr>();
+    let challenge = schnorr_challenge(&params, &signature.r, public_key, ctx_hash);
+    EdwardsAffine::generator() * signature.s
+        == signature.r.into_group() + *public_key * to_jubjub_scalar(challenge)
+}
+
+// MembershipCircuitV2 plus a Schnorr signature over its ctx_hash by the key derived
+// from the committed identity_scalar, so only the credential holder can produce a
+// proof for a given request. Public inputs: [root, commitment, domain_sep,
+// ctx_hash, public_key.x, public_key.y]. Fixed to BN254 because the curve gadget
+// is.
+#[derive(Clone, Debug, Default)]
+pub struct MembershipCircuitV3 {
+    pub root: Option<Fr>,
+    pub commitment: Option<Fr>,
+    pub domain_sep: Option<Fr>,
+    pub ctx_hash: Option<Fr>,
+    pub public_key: Option<EdwardsAffine>,
+    pub identity_scalar: Option<Fr>,
+    pub blinding: Option<Fr>,
+    pub signature: Option<SchnorrSignature>,
+    pub expected_depth: usize,
+    pub merkle_path: Vec<(Option<Fr>, Option<bool>)>,
+}
+
+impl ConstraintSynthesizer<Fr> for MembershipCircuitV3 {
+    fn generate_constraints(self, cs: ConstraintSystemRef<Fr>) -> Result<(), SynthesisError> {
+        if self.expected_depth == 0 || self.merkle_path.len() != self.expected_depth {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let params = poseidon_params::<Fr>();
+
+        let root =
+            FpVar::new_input(cs.clone(), || self.root.ok_or(SynthesisError::AssignmentMissing))?;
+        let commitment_input = FpVar::new_input(cs.clone(), || {
+            self.commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let domain_sep = FpVar::new_input(cs.clone(), || {
+            self.domain_sep.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let ctx_hash =
+            FpVar::new_input(cs.clone(), || self.ctx_hash.ok_or(SynthesisError::AssignmentMissing))?;
+        let public_key_x = FpVar::new_input(cs.clone(), || {
+            self.public_key.map(|key| key.x).ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let public_key_y = FpVar::new_input(cs.clone(), || {
+            self.public_key.map(|key| key.y).ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let identity_scalar = FpVar::new_witness(cs.clone(), || {
+            self.identity_scalar.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let signature_r = EdwardsVar::new_witness(cs.clone(), || {
+            self.signature.map(|sig| sig.r).ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        // Jubjub scalars are smaller than the BN254 modulus, so s fits in one element.
+        let signature_s = FpVar::new_witness(cs.clone(), || {
+            self.signature
+                .map(|sig| Fr::from_le_bytes_mod_order(&sig.s.into_bigint().to_bytes_le()))
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let domain_commitment = FpVar::constant(Fr::from(DOMAIN_COMMITMENT));
+
+        let commitment = poseidon_hash_var(
+            cs.clone(),
+            &params,
+            &[domain_commitment, identity_scalar.clone(), blinding],
+        )?;
+        commitment.enforce_equal(&commitment_input)?;
+
+        let domain_sep_const =
+            FpVar::constant(Fr::from_be_bytes_mod_order(&MEMBERSHIP_V2_DOMAIN_SEP));
+        domain_sep.enforce_equal(&domain_sep_const)?;
+
+        let leaf =
+            poseidon_hash_var(cs.clone(), &params, &[domain_sep, ctx_hash.clone(), commitment])?;
+        let computed_root = merkle_root_var(cs.clone(), &params, leaf, self.merkle_path)?;
+        computed_root.enforce_equal(&root)?;
+
+        let generator = EdwardsVar::constant(EdwardsProjective::generator());
+        let public_key = generator.scalar_mul_le(identity_scalar.to_bits_le()?.iter())?;
+        public_key.x.enforce_equal(&public_key_x)?;
+        public_key.y.enforce_equal(&public_key_y)?;
+
+        let challenge = poseidon_hash_var(
+            cs,
+            &params,
+            &[
+                FpVar::constant(Fr::from(DOMAIN_SCHNORR)),
+                signature_r.x.clone(),
+                signature_r.y.clone(),
+                public_key_x,
+                public_key_y,
+                ctx_hash,
+            ],
+        )?;
+        let lhs = generator.scalar_mul_le(signature_s.to_bits_le()?.iter())?;
+        let rhs = signature_r + public_key.scalar_mul_le(challenge.to_bits_le()?.iter())?;
+        lhs.enforce_equal(&rhs)?;
+        Ok(())
+    }
+}
+
+pub fn build_circuit_schnorr(
+    instance: &MembershipInstanceV2,
+    signature: &SchnorrSignature,
+) -> MembershipCircuitV3 {
+    MembershipCircuitV3 {
+        root: Some(instance.public_inputs.root),
+        commitment: Some(instance.public_inputs.commitment),
+        domain_sep: Some(instance.public_inputs.domain_sep),
+        ctx_hash: Some(instance.public_inputs.ctx_hash),
+        public_key: Some(schnorr_public_key(instance.witness.identity_scalar)),
+        identity_scalar: Some(instance.witness.identity_scalar),
+        blinding: Some(instance.witness.blinding),
+        signature: Some(*signature),
+        expected_depth: instance.witness.merkle_path.len(),
+        merkle_path: instance
+            .witness
+            .merkle_path
+            .iter()
+            .map(|(sibling, is_left)| (Some(*sibling), Some(*is_left)))
+            .collect(),
+    }
+}
+
+pub fn setup_membership_schnorr<R: RngCore>(
+    rng: &mut R,
+    depth: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let zero = Fr::from(0u64);
+    let identity = EdwardsAffine::zero();
+    let circuit = MembershipCircuitV3 {
+        root: Some(zero),
+        commitment: Some(zero),
+        domain_sep: Some(membership_v2_domain_sep_fr()),
+        ctx_hash: Some(zero),
+        public_key: Some(identity),
+        identity_scalar: Some(zero),
+        blinding: Some(zero),
+        signature: Some(SchnorrSignature {
+            r: identity,
+            s: JubjubFr::from(0u64),
+        }),
+        expected_depth: depth,
+        merkle_path: vec![(Some(zero), Some(false)); depth],
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+pub fn prove_membership_schnorr<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &MembershipInstanceV2,
+    signature: &SchnorrSignature,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    Groth16::<Bn254>::create_random_proof_with_reduction(
+        build_circuit_schnorr(instance, signature),
+        pk,
+        rng,
+    )
+}
+
+// `public_key` is the key the verifier expects to have signed the request, e.g.
+// one registered alongside the credential.
+pub fn verify_membership_schnorr(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &MembershipPublicInputsV2,
+    public_key: &EdwardsAffine,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    let pvk = prepare_verifying_key(vk);
+    let inputs = vec![
+        public_inputs.root,
+        public_inputs.commitment,
+        public_inputs.domain_sep,
+        public_inputs.ctx_hash,
+        public_key.x,
+        public_key.y,
+    ];
+    Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_circuit_schnorr, prove_membership_schnorr, schnorr_public_key,
+        setup_membership_schnorr, sign_ctx_hash, verify_membership_schnorr, verify_schnorr,
+    };
+    use crate::test_support::sample_instance_v2;
+    use ark_bn254::Fr;
+    use ark_ed_on_bn254::Fr as JubjubFr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn schnorr_proof_requires_a_signature_by_the_committed_identity() {
+        let instance = sample_instance_v2(2);
+        let identity = instance.witness.identity_scalar;
+        let ctx_hash = instance.public_inputs.ctx_hash;
+        let public_key = schnorr_public_key(identity);
+        let mut rng = StdRng::seed_from_u64(102);
+
+        let signature = sign_ctx_hash(identity, ctx_hash, &mut rng);
+        assert!(verify_schnorr(&public_key, ctx_hash, &signature));
+        assert!(!verify_schnorr(&public_key, ctx_hash + Fr::from(1u64), &signature));
+
+        let pk = setup_membership_schnorr(&mut rng, 2).unwrap();
+        let proof = prove_membership_schnorr(&pk, &instance, &signature, &mut rng).unwrap();
+        let inputs = &instance.public_inputs;
+        assert!(verify_membership_schnorr(&pk.vk, inputs, &public_key, &proof).unwrap());
+        let other_key = schnorr_public_key(identity + Fr::from(1u64));
+        assert!(!verify_membership_schnorr(&pk.vk, inputs, &other_key, &proof).unwrap());
+
+        // Tampered s, and a valid signature by a different key, both leave the
+        // circuit unsatisfied.
+        let mut tampered = signature;
+        tampered.s += JubjubFr::from(1u64);
+        let foreign = sign_ctx_hash(identity + Fr::from(1u64), ctx_hash, &mut rng);
+        for bad in [tampered, foreign] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            build_circuit_schnorr(&instance, &bad)
+                .generate_constraints(cs.clone())
+                .unwrap();
+            assert!(!cs.is_satisfied().unwrap());
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use ark_bn254::Fr;
+use ark_ff::PrimeField;
+use zeroize::Zeroize;
+
+use crate::{
+    commitment_hash, fixed_bytes, fr_from_bytes, fr_to_array, fr_to_fixed_bytes,
+    membership_v2_domain_sep_fr, poseidon_params, root_from_witness_v2, MembershipInstanceV2Bytes,
+    MembershipPublicInputsV2Bytes, MembershipWitnessV2, MembershipWitnessV2Bytes,
+    MEMBERSHIP_INSTANCE_VERSION_V2, MEMBERSHIP_STATEMENT_TYPE, MEMBERSHIP_STATEMENT_VERSION_V2,
+    MEMBERSHIP_V2_DOMAIN_SEP,
+};
+
+// Rust-side copies of the secrets made while an instance is built. Byte buffers
+// are overwritten in place rather than cleared, so a wipe can be observed.
+#[derive(Default)]
+struct SecretScratch {
+    identity_bytes: Vec<u8>,
+    blinding_bytes: Vec<u8>,
+    identity: Fr,
+    blinding: Fr,
+}
+
+impl Zeroize for SecretScratch {
+    fn zeroize(&mut self) {
+        self.identity_bytes.as_mut_slice().zeroize();
+        self.blinding_bytes.as_mut_slice().zeroize();
+        self.identity.zeroize();
+        self.blinding.zeroize();
+    }
+}
+
+// Same output as `make_membership_instance_v2_bytes` in membership_py: the
+// bincode-encoded (instance, public_inputs) pair. The secret buffers passed in
+// and every value decoded from them are zeroized before returning, on error
+// paths too. The returned instance still holds identity and blinding, because
+// they are the witness, so callers must treat it as secret and discard it once
+// the proof is made.
+pub fn membership_instance_v2_bytes_zeroizing(
+    identity_scalar: Vec<u8>,
+    blinding: Vec<u8>,
+    merkle_siblings: &[Vec<u8>],
+    merkle_is_left: &[bool],
+    ctx_hash: [u8; 32],
+) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let mut scratch = SecretScratch {
+        identity_bytes: identity_scalar,
+        blinding_bytes: blinding,
+        ..SecretScratch::default()
+    };
+    build_and_wipe(&mut scratch, merkle_siblings, merkle_is_left, ctx_hash)
+}
+
+fn build_and_wipe(
+    scratch: &mut SecretScratch,
+    merkle_siblings: &[Vec<u8>],
+    merkle_is_left: &[bool],
+    ctx_hash: [u8; 32],
+) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let result = build_instance_v2_bytes(scratch, merkle_siblings, merkle_is_left, ctx_hash);
+    scratch.zeroize();
+    result
+}
+
+fn build_instance_v2_bytes(
+    scratch: &mut SecretScratch,
+    merkle_siblings: &[Vec<u8>],
+    merkle_is_left: &[bool],
+    ctx_hash: [u8; 32],
+) -> Result<(Vec<u8>, Vec<u8>), String> {
+    if merkle_siblings.len() != merkle_is_left.len() {
+        return Err("merkle_siblings and merkle_is_left length mismatch".to_string());
+    }
+    if merkle_siblings.is_empty() {
+        return Err("merkle_path must not be empty".to_string());
+    }
+    let depth = merkle_siblings.len() as u32;
+
+    scratch.identity = fr_from_bytes("identity_scalar", &scratch.identity_bytes)?;
+    scratch.blinding = fr_from_bytes("blinding", &scratch.blinding_bytes)?;
+    let merkle_path = merkle_siblings
+        .iter()
+        .zip(merkle_is_left)
+        .enumerate()
+        .map(|(idx, (sibling, is_left))| {
+            Ok((fr_from_bytes(&format!("merkle_siblings[{idx}]"), sibling)?, *is_left))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let params = poseidon_params::<Fr>();
+    let mut witness = MembershipWitnessV2 {
+        identity_scalar: scratch.identity,
+        blinding: scratch.blinding,
+        merkle_path,
+    };
+    let commitment = commitment_hash(&params, witness.identity_scalar, witness.blinding);
+    let ctx_hash = Fr::from_be_bytes_mod_order(&ctx_hash);
+    let root = root_from_witness_v2(&params, &witness, membership_v2_domain_sep_fr(), ctx_hash);
+    witness.identity_scalar.zeroize();
+    witness.blinding.zeroize();
+
+    let public_inputs = MembershipPublicInputsV2Bytes {
+        schema_version: MEMBERSHIP_INSTANCE_VERSION_V2,
+        statement_type: MEMBERSHIP_STATEMENT_TYPE,
+        statement_version: MEMBERSHIP_STATEMENT_VERSION_V2,
+        depth,
+        root: fixed_bytes("root", &fr_to_fixed_bytes(&root))?,
+        commitment: fixed_bytes("commitment", &fr_to_fixed_bytes(&commitment))?,
+        domain_sep: MEMBERSHIP_V2_DOMAIN_SEP,
+        ctx_hash: fr_to_array(&ctx_hash),
+    };
+    let mut instance = MembershipInstanceV2Bytes {
+        schema_version: MEMBERSHIP_INSTANCE_VERSION_V2,
+        public_inputs: public_inputs.clone(),
+        witness: MembershipWitnessV2Bytes {
+            schema_version: MEMBERSHIP_INSTANCE_VERSION_V2,
+            depth,
+            identity_scalar: scratch.identity_bytes.clone(),
+            blinding: scratch.blinding_bytes.clone(),
+            merkle_siblings: merkle_siblings.to_vec(),
+            merkle_directions: merkle_is_left.to_vec(),
+        },
+    };
+    let instance_bytes = bincode::serialize(&instance).map_err(|err| err.to_string());
+    instance.witness.identity_scalar.zeroize();
+    instance.witness.blinding.zeroize();
+
+    let public_inputs_bytes = bincode::serialize(&public_inputs).map_err(|err| err.to_string())?;
+    Ok((instance_bytes?, public_inputs_bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_and_wipe, SecretScratch};
+    use crate::fr_to_fixed_bytes;
+    use crate::test_support::{public_inputs_v2_bytes, sample_instance_v2};
+    use ark_bn254::Fr;
+
+    fn wiped(scratch: &SecretScratch) -> bool {
+        scratch.identity_bytes.iter().all(|byte| *byte == 0)
+            && scratch.blinding_bytes.iter().all(|byte| *byte == 0)
+            && scratch.identity == Fr::from(0u64)
+            && scratch.blinding == Fr::from(0u64)
+    }
+
+    #[test]
+    fn scratch_secrets_are_wiped_on_success_and_on_error() {
+        let instance = sample_instance_v2(2);
+        let witness = &instance.witness;
+        let siblings: Vec<Vec<u8>> = witness
+            .merkle_path
+            .iter()
+            .map(|(sibling, _)| fr_to_fixed_bytes(sibling))
+            .collect();
+        let is_left: Vec<bool> = witness.merkle_path.iter().map(|(_, is_left)| *is_left).collect();
+        let ctx_hash: [u8; 32] = fr_to_fixed_bytes(&instance.public_inputs.ctx_hash)
+            .try_into()
+            .unwrap();
+
+        let mut scratch = SecretScratch {
+            identity_bytes: fr_to_fixed_bytes(&witness.identity_scalar),
+            blinding_bytes: fr_to_fixed_bytes(&witness.blinding),
+            ..SecretScratch::default()
+        };
+        let (_, public_inputs) =
+            build_and_wipe(&mut scratch, &siblings, &is_left, ctx_hash).unwrap();
+        assert_eq!(scratch.identity_bytes.len(), 32);
+        assert!(wiped(&scratch));
+        assert_eq!(
+            public_inputs,
+            bincode::serialize(&public_inputs_v2_bytes(&instance)).unwrap()
+        );
+
+        let mut scratch = SecretScratch {
+            identity_bytes: fr_to_fixed_bytes(&witness.identity_scalar),
+            blinding_bytes: fr_to_fixed_bytes(&witness.blinding),
+            ..SecretScratch::default()
+        };
+        let bad_siblings = vec![siblings[0].clone(), Vec::new()];
+        assert!(build_and_wipe(&mut scratch, &bad_siblings, &is_left, ctx_hash).is_err());
+        assert!(wiped(&scratch));
+    }
+}
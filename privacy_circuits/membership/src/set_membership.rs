@@ -0,0 +1,327 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::rand::RngCore;
+use ark_std::{format, string::{String, ToString}, vec, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    commitment_hash, fr_from_bytes, fr_from_bytes_canonical, fr_to_array, poseidon_hash_var,
+    poseidon_params, MembershipError, DOMAIN_COMMITMENT,
+};
+
+pub const SET_MEMBERSHIP_INSTANCE_VERSION: u16 = 1;
+pub const SET_MEMBERSHIP_DOMAIN_SEP: [u8; 32] = *b"SNARK_SET_MEMBERSHIP_V1_________";
+
+fn set_membership_domain_sep_fr() -> Fr {
+    Fr::from_be_bytes_mod_order(&SET_MEMBERSHIP_DOMAIN_SEP)
+}
+
+// Proves commitment = H(DOMAIN_COMMITMENT, value, blinding) with value equal to
+// one entry of a public allowed set. The set size is fixed by the keys; the
+// witnessed index selects the entry through a one-hot multiplexer.
+// Public inputs: [commitment, allowed[0..n], domain_sep].
+#[derive(Clone, Debug, Default)]
+pub struct SetMembershipCircuit<F: PrimeField> {
+    pub commitment: Option<F>,
+    pub allowed: Vec<Option<F>>,
+    pub domain_sep: Option<F>,
+    pub value: Option<F>,
+    pub blinding: Option<F>,
+    pub index: Option<u64>,
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for SetMembershipCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        if self.allowed.is_empty() {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let params = poseidon_params::<F>();
+
+        let commitment_input = FpVar::new_input(cs.clone(), || {
+            self.commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let allowed = self
+            .allowed
+            .iter()
+            .map(|entry| {
+                FpVar::new_input(cs.clone(), || entry.ok_or(SynthesisError::AssignmentMissing))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let domain_sep = FpVar::new_input(cs.clone(), || {
+            self.domain_sep.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let value =
+            FpVar::new_witness(cs.clone(), || self.value.ok_or(SynthesisError::AssignmentMissing))?;
+        let blinding = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let index = FpVar::new_witness(cs.clone(), || {
+            self.index
+                .map(F::from)
+                .ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let domain_sep_const =
+            FpVar::constant(F::from_be_bytes_mod_order(&SET_MEMBERSHIP_DOMAIN_SEP));
+        domain_sep.enforce_equal(&domain_sep_const)?;
+
+        let domain_commitment = FpVar::constant(F::from(DOMAIN_COMMITMENT));
+        let commitment =
+            poseidon_hash_var(cs.clone(), &params, &[domain_commitment, value.clone(), blinding])?;
+        commitment.enforce_equal(&commitment_input)?;
+
+        let mut selected = FpVar::zero();
+        let mut selector_sum = FpVar::zero();
+        for (i, entry) in allowed.iter().enumerate() {
+            let selector: FpVar<F> = index.is_eq(&FpVar::constant(F::from(i as u64)))?.into();
+            selected += &selector * entry;
+            selector_sum += selector;
+        }
+        selector_sum.enforce_equal(&FpVar::one())?;
+        selected.enforce_equal(&value)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SetMembershipPublicInputs {
+    pub commitment: Fr,
+    pub allowed: Vec<Fr>,
+    pub domain_sep: Fr,
+}
+
+#[derive(Clone, Debug)]
+pub struct SetMembershipWitness {
+    pub value: Fr,
+    pub blinding: Fr,
+    pub index: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct SetMembershipInstance {
+    pub public_inputs: SetMembershipPublicInputs,
+    pub witness: SetMembershipWitness,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SetMembershipPublicInputsBytes {
+    pub schema_version: u16,
+    pub commitment: [u8; 32],
+    pub allowed: Vec<[u8; 32]>,
+    pub domain_sep: [u8; 32],
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SetMembershipInstanceBytes {
+    pub schema_version: u16,
+    pub public_inputs: SetMembershipPublicInputsBytes,
+    pub value: [u8; 32],
+    pub blinding: [u8; 32],
+    pub index: u32,
+}
+
+impl SetMembershipPublicInputsBytes {
+    pub fn into_public_inputs(self) -> Result<SetMembershipPublicInputs, MembershipError> {
+        if self.schema_version != SET_MEMBERSHIP_INSTANCE_VERSION {
+            return Err(MembershipError::VersionMismatch {
+                field: "public_inputs.schema_version".to_string(),
+                expected: SET_MEMBERSHIP_INSTANCE_VERSION,
+                got: self.schema_version,
+            });
+        }
+        if self.domain_sep != SET_MEMBERSHIP_DOMAIN_SEP {
+            return Err(MembershipError::DomainSepMismatch {
+                field: "public_inputs.domain_sep".to_string(),
+            });
+        }
+        if self.allowed.is_empty() {
+            return Err(MembershipError::EmptyField {
+                field: "public_inputs.allowed".to_string(),
+            });
+        }
+        let allowed = self
+            .allowed
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| fr_from_bytes_canonical(&format!("allowed[{idx}]"), entry))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(SetMembershipPublicInputs {
+            commitment: fr_from_bytes_canonical("commitment", &self.commitment)?,
+            allowed,
+            domain_sep: fr_from_bytes("domain_sep", &self.domain_sep)?,
+        })
+    }
+}
+
+impl SetMembershipInstanceBytes {
+    pub fn into_instance(self) -> Result<SetMembershipInstance, MembershipError> {
+        if self.schema_version != SET_MEMBERSHIP_INSTANCE_VERSION {
+            return Err(MembershipError::VersionMismatch {
+                field: "schema_version".to_string(),
+                expected: SET_MEMBERSHIP_INSTANCE_VERSION,
+                got: self.schema_version,
+            });
+        }
+        let public_inputs = self.public_inputs.into_public_inputs()?;
+        Ok(SetMembershipInstance {
+            public_inputs,
+            witness: SetMembershipWitness {
+                value: fr_from_bytes("value", &self.value)?,
+                blinding: fr_from_bytes("blinding", &self.blinding)?,
+                index: self.index as usize,
+            },
+        })
+    }
+}
+
+pub fn build_set_membership_instance(
+    value: Fr,
+    blinding: Fr,
+    allowed: &[Fr],
+) -> Result<SetMembershipInstanceBytes, MembershipError> {
+    let index = allowed
+        .iter()
+        .position(|entry| *entry == value)
+        .ok_or(MembershipError::ValueNotInSet)?;
+    let params = poseidon_params::<Fr>();
+    let commitment = commitment_hash(&params, value, blinding);
+    Ok(SetMembershipInstanceBytes {
+        schema_version: SET_MEMBERSHIP_INSTANCE_VERSION,
+        public_inputs: SetMembershipPublicInputsBytes {
+            schema_version: SET_MEMBERSHIP_INSTANCE_VERSION,
+            commitment: fr_to_array(&commitment),
+            allowed: allowed.iter().map(fr_to_array).collect(),
+            domain_sep: SET_MEMBERSHIP_DOMAIN_SEP,
+        },
+        value: fr_to_array(&value),
+        blinding: fr_to_array(&blinding),
+        index: index as u32,
+    })
+}
+
+pub fn set_membership_field_inputs(public_inputs: &SetMembershipPublicInputs) -> Vec<Fr> {
+    let mut inputs = Vec::with_capacity(public_inputs.allowed.len() + 2);
+    inputs.push(public_inputs.commitment);
+    inputs.extend_from_slice(&public_inputs.allowed);
+    inputs.push(public_inputs.domain_sep);
+    inputs
+}
+
+pub fn build_set_membership_circuit(instance: &SetMembershipInstance) -> SetMembershipCircuit<Fr> {
+    SetMembershipCircuit {
+        commitment: Some(instance.public_inputs.commitment),
+        allowed: instance.public_inputs.allowed.iter().copied().map(Some).collect(),
+        domain_sep: Some(instance.public_inputs.domain_sep),
+        value: Some(instance.witness.value),
+        blinding: Some(instance.witness.blinding),
+        index: Some(instance.witness.index as u64),
+    }
+}
+
+pub fn setup_set_membership<R: RngCore>(
+    rng: &mut R,
+    set_size: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let zero = Fr::from(0u64);
+    let circuit = SetMembershipCircuit::<Fr> {
+        commitment: Some(zero),
+        allowed: vec![Some(zero); set_size],
+        domain_sep: Some(set_membership_domain_sep_fr()),
+        value: Some(zero),
+        blinding: Some(zero),
+        index: Some(0),
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+pub fn prove_set_membership<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &SetMembershipInstance,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, String> {
+    let allowed = &instance.public_inputs.allowed;
+    let expected = pk.vk.gamma_abc_g1.len().saturating_sub(3);
+    if allowed.len() != expected {
+        return Err(format!(
+            "allowed set size mismatch: key expects {expected}, got {}",
+            allowed.len()
+        ));
+    }
+    match allowed.get(instance.witness.index) {
+        Some(entry) if *entry == instance.witness.value => {}
+        _ => return Err("value is not the allowed entry at the witnessed index".to_string()),
+    }
+
+    Groth16::<Bn254>::create_random_proof_with_reduction(
+        build_set_membership_circuit(instance),
+        pk,
+        rng,
+    )
+    .map_err(|err| err.to_string())
+}
+
+pub fn verify_set_membership(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &SetMembershipPublicInputs,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    let pvk = prepare_verifying_key(vk);
+    Groth16::<Bn254>::verify_proof(&pvk, proof, &set_membership_field_inputs(public_inputs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_set_membership_circuit, build_set_membership_instance, prove_set_membership,
+        setup_set_membership, verify_set_membership,
+    };
+    use crate::{commitment_hash, poseidon_params, MembershipError};
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn set_membership_accepts_allowed_value_and_rejects_outsider() {
+        let allowed: Vec<Fr> = [10u64, 20, 30, 40].into_iter().map(Fr::from).collect();
+        let mut rng = StdRng::seed_from_u64(81);
+        let pk = setup_set_membership(&mut rng, allowed.len()).unwrap();
+
+        let bytes = build_set_membership_instance(allowed[2], Fr::from(7u64), &allowed).unwrap();
+        assert_eq!(bytes.index, 2);
+        let instance = bytes.into_instance().unwrap();
+        let proof = prove_set_membership(&pk, &instance, &mut rng).unwrap();
+        assert!(verify_set_membership(&pk.vk, &instance.public_inputs, &proof).unwrap());
+
+        assert!(build_set_membership_instance(Fr::from(25u64), Fr::from(7u64), &allowed).is_err());
+
+        let mut outsider = instance.clone();
+        outsider.witness.value = Fr::from(25u64);
+        outsider.public_inputs.commitment =
+            commitment_hash(&poseidon_params::<Fr>(), Fr::from(25u64), Fr::from(7u64));
+        assert!(prove_set_membership(&pk, &outsider, &mut rng).is_err());
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        build_set_membership_circuit(&outsider)
+            .generate_constraints(cs.clone())
+            .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+
+        let mut shifted = instance.public_inputs.clone();
+        shifted.allowed[2] = Fr::from(25u64);
+        assert!(!verify_set_membership(&pk.vk, &shifted, &proof).unwrap());
+
+        // An allowed entry at or past the modulus is rejected, not reduced.
+        let mut non_canonical = build_set_membership_instance(allowed[2], Fr::from(7u64), &allowed)
+            .unwrap()
+            .public_inputs;
+        non_canonical.allowed[0] = [0xff; 32];
+        let err = non_canonical.into_public_inputs().unwrap_err();
+        assert!(matches!(
+            err,
+            MembershipError::FieldDecode(ref decode) if decode.field == "allowed[0]"
+        ));
+    }
+}
@@ -0,0 +1,178 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::rand::RngCore;
+
+use crate::{
+    merkle_root_var, poseidon_hash_var, poseidon_params, MembershipInstance,
+    MembershipPublicInputs, DOMAIN_COMMITMENT, DOMAIN_LEAF,
+};
+
+// MembershipCircuit plus a public `message_hash` (e.g. a transaction hash) that
+// is constrained equal to a witness copy, so the proof only verifies for the
+// message it was created over. Public inputs: [root, commitment, message_hash].
+#[derive(Clone, Debug, Default)]
+pub struct MembershipCircuitSigned<F: PrimeField> {
+    pub root: Option<F>,
+    pub commitment: Option<F>,
+    pub message_hash: Option<F>,
+    pub identity_scalar: Option<F>,
+    pub blinding: Option<F>,
+    pub expected_depth: usize,
+    pub merkle_path: Vec<(Option<F>, Option<bool>)>,
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for MembershipCircuitSigned<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        if self.expected_depth == 0 || self.merkle_path.len() != self.expected_depth {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let params = poseidon_params::<F>();
+
+        let root =
+            FpVar::new_input(cs.clone(), || self.root.ok_or(SynthesisError::AssignmentMissing))?;
+        let commitment_input = FpVar::new_input(cs.clone(), || {
+            self.commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let message_hash_input = FpVar::new_input(cs.clone(), || {
+            self.message_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let message_hash = FpVar::new_witness(cs.clone(), || {
+            self.message_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let identity_scalar = FpVar::new_witness(cs.clone(), || {
+            self.identity_scalar.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        message_hash.enforce_equal(&message_hash_input)?;
+
+        let domain_commitment = FpVar::constant(F::from(DOMAIN_COMMITMENT));
+        let domain_leaf = FpVar::constant(F::from(DOMAIN_LEAF));
+
+        let commitment = poseidon_hash_var(
+            cs.clone(),
+            &params,
+            &[domain_commitment, identity_scalar, blinding],
+        )?;
+        commitment.enforce_equal(&commitment_input)?;
+
+        let leaf =
+            poseidon_hash_var(cs.clone(), &params, &[domain_leaf, commitment, FpVar::zero()])?;
+        let computed_root = merkle_root_var(cs, &params, leaf, self.merkle_path)?;
+        computed_root.enforce_equal(&root)?;
+        Ok(())
+    }
+}
+
+pub fn build_circuit_signed(
+    instance: &MembershipInstance,
+    message_hash: Fr,
+) -> MembershipCircuitSigned<Fr> {
+    MembershipCircuitSigned {
+        root: Some(instance.public_inputs.root),
+        commitment: Some(instance.public_inputs.commitment),
+        message_hash: Some(message_hash),
+        identity_scalar: Some(instance.witness.identity_scalar),
+        blinding: Some(instance.witness.blinding),
+        expected_depth: instance.witness.merkle_path.len(),
+        merkle_path: instance
+            .witness
+            .merkle_path
+            .iter()
+            .map(|(sibling, is_left)| (Some(*sibling), Some(*is_left)))
+            .collect(),
+    }
+}
+
+pub fn setup_membership_signed<R: RngCore>(
+    rng: &mut R,
+    depth: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let zero = Fr::from(0u64);
+    let circuit = MembershipCircuitSigned::<Fr> {
+        root: Some(zero),
+        commitment: Some(zero),
+        message_hash: Some(zero),
+        identity_scalar: Some(zero),
+        blinding: Some(zero),
+        expected_depth: depth,
+        merkle_path: vec![(Some(zero), Some(false)); depth],
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+pub fn prove_membership_signed<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &MembershipInstance,
+    message_hash: Fr,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    Groth16::<Bn254>::create_random_proof_with_reduction(
+        build_circuit_signed(instance, message_hash),
+        pk,
+        rng,
+    )
+}
+
+// `message_hash` is the message the verifier is authorizing, not a value taken
+// from the prover.
+pub fn verify_membership_signed(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &MembershipPublicInputs,
+    message_hash: Fr,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    let pvk = prepare_verifying_key(vk);
+    let inputs = vec![public_inputs.root, public_inputs.commitment, message_hash];
+    Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{prove_membership_signed, setup_membership_signed, verify_membership_signed};
+    use crate::{
+        commitment_hash, leaf_hash, node_hash, poseidon_params, MembershipInstance,
+        MembershipPublicInputs, MembershipWitness,
+    };
+    use ark_bn254::Fr;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn signed_proof_binds_to_message() {
+        let params = poseidon_params::<Fr>();
+        let identity = Fr::from(41u64);
+        let blinding = Fr::from(42u64);
+        let commitment = commitment_hash(&params, identity, blinding);
+        let sibling = leaf_hash(&params, Fr::from(43u64));
+        let instance = MembershipInstance {
+            public_inputs: MembershipPublicInputs {
+                root: node_hash(&params, leaf_hash(&params, commitment), sibling),
+                commitment,
+            },
+            witness: MembershipWitness {
+                identity_scalar: identity,
+                blinding,
+                merkle_path: vec![(sibling, false)],
+            },
+        };
+        let message_a = Fr::from(0xaaaau64);
+        let message_b = Fr::from(0xbbbbu64);
+
+        let mut rng = StdRng::seed_from_u64(83);
+        let pk = setup_membership_signed(&mut rng, 1).unwrap();
+        let proof = prove_membership_signed(&pk, &instance, message_a, &mut rng).unwrap();
+
+        assert!(
+            verify_membership_signed(&pk.vk, &instance.public_inputs, message_a, &proof).unwrap()
+        );
+        assert!(
+            !verify_membership_signed(&pk.vk, &instance.public_inputs, message_b, &proof).unwrap()
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::bits::boolean::Boolean;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::rand::RngCore;
+
+use crate::tree::MAX_TREE_DEPTH;
+use crate::{
+    poseidon_hash_native, poseidon_hash_var, poseidon_params, verify_membership,
+    MembershipInstance, MembershipPublicInputs, DOMAIN_COMMITMENT, DOMAIN_LEAF, DOMAIN_NODE,
+    DOMAIN_SLOT,
+};
+
+// Leaf index bound to the identity: the low `depth` bits of H(DOMAIN_SLOT, id, 0).
+// Bit i is the direction at path level i (1 = current node is the right child).
+pub fn slot_for_identity(id: Fr, depth: usize) -> u64 {
+    let params = poseidon_params::<Fr>();
+    let slot_hash = poseidon_hash_native(&params, &[Fr::from(DOMAIN_SLOT), id, Fr::from(0u64)]);
+    let low = slot_hash.into_bigint().as_ref()[0];
+    if depth >= 64 {
+        low
+    } else {
+        low & ((1u64 << depth) - 1)
+    }
+}
+
+// Membership proof whose path directions must equal the bit decomposition of
+// `slot_for_identity(identity_scalar, depth)`, so a prover cannot pick an
+// arbitrary leaf position for their identity.
+#[derive(Clone, Debug, Default)]
+pub struct MembershipCircuitSlotted<F: PrimeField> {
+    pub root: Option<F>,
+    pub commitment: Option<F>,
+    pub identity_scalar: Option<F>,
+    pub blinding: Option<F>,
+    pub expected_depth: usize,
+    pub merkle_path: Vec<(Option<F>, Option<bool>)>,
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for MembershipCircuitSlotted<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        if self.expected_depth == 0 || self.merkle_path.len() != self.expected_depth {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let params = poseidon_params::<F>();
+
+        let root =
+            FpVar::new_input(cs.clone(), || self.root.ok_or(SynthesisError::AssignmentMissing))?;
+        let commitment_input = FpVar::new_input(cs.clone(), || {
+            self.commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let identity_scalar = FpVar::new_witness(cs.clone(), || {
+            self.identity_scalar.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let domain_commitment = FpVar::constant(F::from(DOMAIN_COMMITMENT));
+        let domain_leaf = FpVar::constant(F::from(DOMAIN_LEAF));
+        let domain_node = FpVar::constant(F::from(DOMAIN_NODE));
+        let domain_slot = FpVar::constant(F::from(DOMAIN_SLOT));
+
+        let commitment = poseidon_hash_var(
+            cs.clone(),
+            &params,
+            &[domain_commitment, identity_scalar.clone(), blinding],
+        )?;
+        commitment.enforce_equal(&commitment_input)?;
+
+        let slot_hash =
+            poseidon_hash_var(cs.clone(), &params, &[domain_slot, identity_scalar, FpVar::zero()])?;
+        let slot_bits = slot_hash.to_bits_le()?;
+
+        let mut current =
+            poseidon_hash_var(cs.clone(), &params, &[domain_leaf, commitment, FpVar::zero()])?;
+        for ((sibling_value, is_left_value), slot_bit) in
+            self.merkle_path.into_iter().zip(slot_bits)
+        {
+            let sibling = FpVar::new_witness(cs.clone(), || {
+                sibling_value.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let is_left = Boolean::new_witness(cs.clone(), || {
+                is_left_value.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            is_left.enforce_equal(&slot_bit)?;
+
+            let left = is_left.select(&sibling, &current)?;
+            let right = is_left.select(&current, &sibling)?;
+            current = poseidon_hash_var(cs.clone(), &params, &[domain_node.clone(), left, right])?;
+        }
+
+        current.enforce_equal(&root)?;
+        Ok(())
+    }
+}
+
+fn check_depth(depth: usize) -> Result<(), String> {
+    if depth == 0 || depth > MAX_TREE_DEPTH {
+        return Err(format!(
+            "depth must be between 1 and {}, got {}",
+            MAX_TREE_DEPTH, depth
+        ));
+    }
+    Ok(())
+}
+
+pub fn build_circuit_slotted(instance: &MembershipInstance) -> MembershipCircuitSlotted<Fr> {
+    MembershipCircuitSlotted {
+        root: Some(instance.public_inputs.root),
+        commitment: Some(instance.public_inputs.commitment),
+        identity_scalar: Some(instance.witness.identity_scalar),
+        blinding: Some(instance.witness.blinding),
+        expected_depth: instance.witness.merkle_path.len(),
+        merkle_path: instance
+            .witness
+            .merkle_path
+            .iter()
+            .map(|(sibling, is_left)| (Some(*sibling), Some(*is_left)))
+            .collect(),
+    }
+}
+
+pub fn setup_membership_slotted<R: RngCore>(
+    rng: &mut R,
+    depth: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let zero = Fr::from(0u64);
+    let circuit = MembershipCircuitSlotted::<Fr> {
+        root: Some(zero),
+        commitment: Some(zero),
+        identity_scalar: Some(zero),
+        blinding: Some(zero),
+        expected_depth: depth,
+        merkle_path: vec![(Some(zero), Some(false)); depth],
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+pub fn prove_membership_slotted<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &MembershipInstance,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, String> {
+    let depth = instance.witness.merkle_path.len();
+    check_depth(depth)?;
+    let slot = slot_for_identity(instance.witness.identity_scalar, depth);
+    let path_slot = instance
+        .witness
+        .merkle_path
+        .iter()
+        .enumerate()
+        .fold(0u64, |acc, (level, (_, is_left))| acc | ((*is_left as u64) << level));
+    if path_slot != slot {
+        return Err(format!(
+            "merkle path leads to slot {path_slot}, identity is bound to slot {slot}"
+        ));
+    }
+
+    Groth16::<Bn254>::create_random_proof_with_reduction(build_circuit_slotted(instance), pk, rng)
+        .map_err(|err| err.to_string())
+}
+
+pub fn verify_membership_slotted(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &MembershipPublicInputs,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    verify_membership(vk, public_inputs, proof)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_circuit_slotted, prove_membership_slotted, setup_membership_slotted,
+        slot_for_identity, verify_membership_slotted,
+    };
+    use crate::tree::PoseidonMerkleTree;
+    use crate::{
+        commitment_hash, poseidon_params, MembershipInstance, MembershipPublicInputs,
+        MembershipWitness,
+    };
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    fn instance_at_slot(identity: Fr, blinding: Fr, depth: usize, slot: usize) -> MembershipInstance {
+        let params = poseidon_params::<Fr>();
+        let commitment = commitment_hash(&params, identity, blinding);
+        let mut commitments = vec![Fr::from(0u64); 1 << depth];
+        commitments[slot] = commitment;
+        let tree = PoseidonMerkleTree::build(&commitments, depth, &params).unwrap();
+        MembershipInstance {
+            public_inputs: MembershipPublicInputs {
+                root: tree.root(),
+                commitment,
+            },
+            witness: MembershipWitness {
+                identity_scalar: identity,
+                blinding,
+                merkle_path: tree.path(slot).unwrap(),
+            },
+        }
+    }
+
+    #[test]
+    fn slotted_circuit_binds_identity_to_slot() {
+        let depth = 4;
+        let identity = Fr::from(51u64);
+        let blinding = Fr::from(52u64);
+        let slot = slot_for_identity(identity, depth) as usize;
+        assert!(slot < 1 << depth);
+
+        let mut rng = StdRng::seed_from_u64(68);
+        let pk = setup_membership_slotted(&mut rng, depth).unwrap();
+
+        let instance = instance_at_slot(identity, blinding, depth, slot);
+        let proof = prove_membership_slotted(&pk, &instance, &mut rng).unwrap();
+        assert!(verify_membership_slotted(&pk.vk, &instance.public_inputs, &proof).unwrap());
+
+        let wrong = instance_at_slot(identity, blinding, depth, slot ^ 1);
+        let err = prove_membership_slotted(&pk, &wrong, &mut rng).unwrap_err();
+        assert!(err.contains("identity is bound to slot"));
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        build_circuit_slotted(&wrong)
+            .generate_constraints(cs.clone())
+            .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,272 @@
+use ark_bn254::{Bn254, Fq, Fq2, G1Affine, G2Affine};
+use ark_ff::{BigInteger, PrimeField};
+use ark_groth16::{Proof, VerifyingKey};
+use std::fmt::Write;
+
+use crate::vk_fingerprint_hex;
+
+// A statement's name and its public inputs in the order the Rust verifier passes
+// them to Groth16; the generated `verifyProof` takes `input` in the same order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SolidityStatement {
+    pub name: &'static str,
+    pub inputs: &'static [&'static str],
+}
+
+// Membership V0 and V1 share the V1 input layout.
+pub const SOLIDITY_STATEMENTS: [SolidityStatement; 5] = [
+    SolidityStatement {
+        name: "membership-v1",
+        inputs: &["root", "commitment"],
+    },
+    SolidityStatement {
+        name: "membership-v2",
+        inputs: &["root", "commitment", "domain_sep", "ctx_hash"],
+    },
+    SolidityStatement {
+        name: "continuity-v1",
+        inputs: &["c1_hash", "c2_hash", "domain_sep"],
+    },
+    SolidityStatement {
+        name: "continuity-v2",
+        inputs: &["c1_hash", "c2_hash", "domain_sep", "ctx_hash"],
+    },
+    SolidityStatement {
+        name: "unlinkability-v2",
+        inputs: &["tag", "domain_sep", "ctx_hash"],
+    },
+];
+
+pub fn solidity_statement(name: &str) -> Option<SolidityStatement> {
+    SOLIDITY_STATEMENTS
+        .iter()
+        .find(|statement| statement.name == name)
+        .copied()
+}
+
+// Renders a standalone Groth16 verifier contract with `vk` baked in. It uses the
+// EVM's BN254 add (0x06), mul (0x07) and pairing (0x08) precompiles and checks
+// e(-A, B) * e(alpha, beta) * e(vk_x, gamma) * e(C, delta) == 1. G2 points use the
+// precompile's (imaginary, real) coordinate order; `solidity_proof_words` lays a
+// proof out the same way.
+pub fn solidity_verifier(
+    vk: &VerifyingKey<Bn254>,
+    statement: &SolidityStatement,
+) -> Result<String, String> {
+    let count = statement.inputs.len();
+    if vk.gamma_abc_g1.len() != count + 1 {
+        return Err(format!(
+            "verifying key takes {} public inputs but {} has {count}",
+            vk.gamma_abc_g1.len().saturating_sub(1),
+            statement.name
+        ));
+    }
+
+    let mut out = String::new();
+    let contract = contract_name(statement.name);
+    out.push_str("// SPDX-License-Identifier: MIT\npragma solidity ^0.8.0;\n\n");
+    let _ = writeln!(out, "// Groth16 verifier for {}.", statement.name);
+    let _ = writeln!(out, "// Public inputs, in order: {}.", statement.inputs.join(", "));
+    let _ = writeln!(out, "// VK fingerprint (sha256): {}", vk_fingerprint_hex(vk));
+    let _ = writeln!(out, "contract {contract} {{");
+    out.push_str(
+        "    uint256 constant SNARK_SCALAR_FIELD =\n        \
+         21888242871839275222246405745257275088548364400416034343698204186575808495617;\n    \
+         uint256 constant PRIME_Q =\n        \
+         21888242871839275222246405745257275088696311157297823662689037894645226208583;\n\n",
+    );
+    g1_constants(&mut out, "ALPHA", &vk.alpha_g1);
+    g2_constants(&mut out, "BETA", &vk.beta_g2);
+    g2_constants(&mut out, "GAMMA", &vk.gamma_g2);
+    g2_constants(&mut out, "DELTA", &vk.delta_g2);
+    for (index, point) in vk.gamma_abc_g1.iter().enumerate() {
+        g1_constants(&mut out, &format!("IC{index}"), point);
+    }
+
+    let _ = writeln!(
+        out,
+        "\n    function verifyProof(\n        \
+         uint256[2] calldata a,\n        \
+         uint256[2][2] calldata b,\n        \
+         uint256[2] calldata c,\n        \
+         uint256[{count}] calldata input\n    \
+         ) public view returns (bool) {{"
+    );
+    let _ = writeln!(out, "        uint256[2] memory vkX = [IC0_X, IC0_Y];");
+    for index in 0..count {
+        let ic = index + 1;
+        let _ = writeln!(
+            out,
+            "        require(input[{index}] < SNARK_SCALAR_FIELD, \"input out of field\");"
+        );
+        let _ = writeln!(
+            out,
+            "        vkX = ecAdd(vkX, ecMul([IC{ic}_X, IC{ic}_Y], input[{index}]));"
+        );
+    }
+    out.push_str(PAIRING_BODY);
+    out.push_str("}\n");
+    Ok(out)
+}
+
+// The proof as the eight `uint256` words `verifyProof` takes: a.x, a.y, then b as
+// [[x.c1, x.c0], [y.c1, y.c0]], then c.x, c.y; each 0x-prefixed hex.
+pub fn solidity_proof_words(proof: &Proof<Bn254>) -> [String; 8] {
+    let (b_x, b_y) = (proof.b.x, proof.b.y);
+    [
+        proof.a.x, proof.a.y, b_x.c1, b_x.c0, b_y.c1, b_y.c0, proof.c.x, proof.c.y,
+    ]
+    .map(|value| fq_hex(&value))
+}
+
+fn contract_name(statement: &str) -> String {
+    let mut name: String = statement
+        .split('-')
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        })
+        .collect();
+    name.push_str("Verifier");
+    name
+}
+
+fn fq_hex(value: &Fq) -> String {
+    let bytes = value.into_bigint().to_bytes_be();
+    let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("0x{hex}")
+}
+
+fn g1_constants(out: &mut String, label: &str, point: &G1Affine) {
+    let _ = writeln!(out, "    uint256 constant {label}_X = {};", fq_hex(&point.x));
+    let _ = writeln!(out, "    uint256 constant {label}_Y = {};", fq_hex(&point.y));
+}
+
+fn g2_constants(out: &mut String, label: &str, point: &G2Affine) {
+    let coordinates: [(&str, &Fq2); 2] = [("X", &point.x), ("Y", &point.y)];
+    for (axis, value) in coordinates {
+        let _ = writeln!(out, "    uint256 constant {label}_{axis}1 = {};", fq_hex(&value.c1));
+        let _ = writeln!(out, "    uint256 constant {label}_{axis}0 = {};", fq_hex(&value.c0));
+    }
+}
+
+const PAIRING_BODY: &str = r#"
+        uint256[24] memory p;
+        p[0] = a[0];
+        p[1] = (PRIME_Q - (a[1] % PRIME_Q)) % PRIME_Q;
+        p[2] = b[0][0];
+        p[3] = b[0][1];
+        p[4] = b[1][0];
+        p[5] = b[1][1];
+        p[6] = ALPHA_X;
+        p[7] = ALPHA_Y;
+        p[8] = BETA_X1;
+        p[9] = BETA_X0;
+        p[10] = BETA_Y1;
+        p[11] = BETA_Y0;
+        p[12] = vkX[0];
+        p[13] = vkX[1];
+        p[14] = GAMMA_X1;
+        p[15] = GAMMA_X0;
+        p[16] = GAMMA_Y1;
+        p[17] = GAMMA_Y0;
+        p[18] = c[0];
+        p[19] = c[1];
+        p[20] = DELTA_X1;
+        p[21] = DELTA_X0;
+        p[22] = DELTA_Y1;
+        p[23] = DELTA_Y0;
+
+        uint256[1] memory result;
+        bool success;
+        assembly {
+            success := staticcall(gas(), 8, p, 768, result, 0x20)
+        }
+        require(success, "pairing precompile failed");
+        return result[0] == 1;
+    }
+
+    function ecAdd(uint256[2] memory p1, uint256[2] memory p2)
+        internal
+        view
+        returns (uint256[2] memory r)
+    {
+        uint256[4] memory input = [p1[0], p1[1], p2[0], p2[1]];
+        bool success;
+        assembly {
+            success := staticcall(gas(), 6, input, 0x80, r, 0x40)
+        }
+        require(success, "ecAdd precompile failed");
+    }
+
+    function ecMul(uint256[2] memory p1, uint256 s)
+        internal
+        view
+        returns (uint256[2] memory r)
+    {
+        uint256[3] memory input = [p1[0], p1[1], s];
+        bool success;
+        assembly {
+            success := staticcall(gas(), 7, input, 0x60, r, 0x40)
+        }
+        require(success, "ecMul precompile failed");
+    }
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        fq_hex, solidity_proof_words, solidity_statement, solidity_verifier, SOLIDITY_STATEMENTS,
+    };
+    use crate::test_support::sample_instance_v2;
+    use crate::{prove_membership_v2, setup_membership_with_depth_v2};
+    use ark_bn254::{Fq, Fq2, G2Affine};
+    use ark_ff::PrimeField;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    fn parse_word(word: &str) -> Fq {
+        let hex = word.strip_prefix("0x").unwrap();
+        let bytes: Vec<u8> = (0..hex.len())
+            .step_by(2)
+            .map(|idx| u8::from_str_radix(&hex[idx..idx + 2], 16).unwrap())
+            .collect();
+        Fq::from_be_bytes_mod_order(&bytes)
+    }
+
+    #[test]
+    fn membership_v2_verifier_bakes_in_the_vk_and_input_order() {
+        let instance = sample_instance_v2(2);
+        let mut rng = StdRng::seed_from_u64(99);
+        let pk = setup_membership_with_depth_v2(&mut rng, 2).unwrap();
+        let statement = solidity_statement("membership-v2").unwrap();
+
+        let source = solidity_verifier(&pk.vk, &statement).unwrap();
+        assert!(source.contains("contract MembershipV2Verifier {"));
+        assert!(source.contains("uint256[4] calldata input"));
+        assert!(source.contains("in order: root, commitment, domain_sep, ctx_hash."));
+        assert!(source.contains("input[3]") && !source.contains("input[4]"));
+        assert!(source.contains("uint256 constant IC4_Y") && !source.contains("IC5_X"));
+        let beta_x1 = format!("BETA_X1 = {};", fq_hex(&pk.vk.beta_g2.x.c1));
+        assert!(source.contains(&beta_x1));
+        assert_eq!(source.matches('{').count(), source.matches('}').count());
+
+        for other in SOLIDITY_STATEMENTS {
+            let result = solidity_verifier(&pk.vk, &other);
+            assert_eq!(result.is_ok(), other.inputs.len() == 4, "{}", other.name);
+        }
+        assert!(solidity_statement("membership-v3").is_none());
+
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+        let words = solidity_proof_words(&proof);
+        assert_eq!((parse_word(&words[0]), parse_word(&words[1])), (proof.a.x, proof.a.y));
+        let b = G2Affine::new(
+            Fq2::new(parse_word(&words[3]), parse_word(&words[2])),
+            Fq2::new(parse_word(&words[5]), parse_word(&words[4])),
+        );
+        assert_eq!(b, proof.b);
+        assert_eq!((parse_word(&words[6]), parse_word(&words[7])), (proof.c.x, proof.c.y));
+    }
+}
@@ -0,0 +1,103 @@
+use ark_bn254::Fr;
+
+use crate::{
+    commitment_hash, fr_to_fixed_bytes, leaf_hash, membership_v2_domain_sep_fr, node_hash,
+    poseidon_hash_leaf_v2, poseidon_params, MembershipInstance, MembershipInstanceV2,
+    MembershipInstanceV2Bytes, MembershipPublicInputs, MembershipPublicInputsV2,
+    MembershipPublicInputsV2Bytes, MembershipWitness, MembershipWitnessV2,
+    MembershipWitnessV2Bytes, MEMBERSHIP_INSTANCE_VERSION_V2, MEMBERSHIP_STATEMENT_TYPE,
+    MEMBERSHIP_STATEMENT_VERSION_V2, MEMBERSHIP_V2_DOMAIN_SEP,
+};
+
+// Depth-one V1 instance; distinct identities give distinct commitments and roots.
+pub fn sample_instance(identity: u64) -> MembershipInstance {
+    let params = poseidon_params::<Fr>();
+    let identity_scalar = Fr::from(identity);
+    let blinding = Fr::from(identity + 1);
+    let commitment = commitment_hash(&params, identity_scalar, blinding);
+    let sibling = leaf_hash(&params, Fr::from(9u64));
+    MembershipInstance {
+        public_inputs: MembershipPublicInputs {
+            root: node_hash(&params, leaf_hash(&params, commitment), sibling),
+            commitment,
+        },
+        witness: MembershipWitness {
+            identity_scalar,
+            blinding,
+            merkle_path: vec![(sibling, false)],
+        },
+    }
+}
+
+pub fn sample_instance_v2(depth: usize) -> MembershipInstanceV2 {
+    sample_instance_v2_with_ctx(depth, Fr::from(23u64))
+}
+
+pub fn sample_instance_v2_with_ctx(depth: usize, ctx_hash: Fr) -> MembershipInstanceV2 {
+    let params = poseidon_params::<Fr>();
+    let identity = Fr::from(21u64);
+    let blinding = Fr::from(22u64);
+    let commitment = commitment_hash(&params, identity, blinding);
+    let domain_sep = membership_v2_domain_sep_fr();
+    let mut current = poseidon_hash_leaf_v2(&params, domain_sep, ctx_hash, commitment);
+    let mut merkle_path = Vec::with_capacity(depth);
+    for idx in 0..depth {
+        let sibling = node_hash(&params, commitment, Fr::from(100 + idx as u64));
+        let is_left = idx % 2 == 1;
+        current = if is_left {
+            node_hash(&params, sibling, current)
+        } else {
+            node_hash(&params, current, sibling)
+        };
+        merkle_path.push((sibling, is_left));
+    }
+    MembershipInstanceV2 {
+        public_inputs: MembershipPublicInputsV2 {
+            root: current,
+            commitment,
+            domain_sep,
+            ctx_hash,
+        },
+        witness: MembershipWitnessV2 {
+            identity_scalar: identity,
+            blinding,
+            merkle_path,
+        },
+    }
+}
+
+pub fn public_inputs_v2_bytes(
+    instance: &MembershipInstanceV2,
+) -> MembershipPublicInputsV2Bytes {
+    let inputs = &instance.public_inputs;
+    MembershipPublicInputsV2Bytes {
+        schema_version: MEMBERSHIP_INSTANCE_VERSION_V2,
+        statement_type: MEMBERSHIP_STATEMENT_TYPE,
+        statement_version: MEMBERSHIP_STATEMENT_VERSION_V2,
+        depth: instance.witness.merkle_path.len() as u32,
+        root: fr_to_fixed_bytes(&inputs.root).try_into().unwrap(),
+        commitment: fr_to_fixed_bytes(&inputs.commitment).try_into().unwrap(),
+        domain_sep: MEMBERSHIP_V2_DOMAIN_SEP,
+        ctx_hash: fr_to_fixed_bytes(&inputs.ctx_hash).try_into().unwrap(),
+    }
+}
+
+pub fn instance_v2_bytes(instance: &MembershipInstanceV2) -> MembershipInstanceV2Bytes {
+    let witness = &instance.witness;
+    MembershipInstanceV2Bytes {
+        schema_version: MEMBERSHIP_INSTANCE_VERSION_V2,
+        public_inputs: public_inputs_v2_bytes(instance),
+        witness: MembershipWitnessV2Bytes {
+            schema_version: MEMBERSHIP_INSTANCE_VERSION_V2,
+            depth: witness.merkle_path.len() as u32,
+            identity_scalar: fr_to_fixed_bytes(&witness.identity_scalar),
+            blinding: fr_to_fixed_bytes(&witness.blinding),
+            merkle_siblings: witness
+                .merkle_path
+                .iter()
+                .map(|(sibling, _)| fr_to_fixed_bytes(sibling))
+                .collect(),
+            merkle_directions: witness.merkle_path.iter().map(|(_, left)| *left).collect(),
+        },
+    }
+}
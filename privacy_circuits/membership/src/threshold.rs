@@ -0,0 +1,306 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::bits::boolean::Boolean;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_std::rand::RngCore;
+
+use crate::compare::enforce_in_range;
+use crate::{merkle_root_var, poseidon_hash_var, poseidon_params, DOMAIN_COMMITMENT, DOMAIN_LEAF};
+
+// One identity is a member of at least `threshold` of `roots.len()` trees.
+// Public inputs: [root_0, ..., root_{n-1}]. Every branch opens its own commitment
+// to the shared identity scalar and walks its own path; a witnessed selector
+// decides whether that branch's root check is enforced, and the selectors must
+// sum to at least the threshold. Unselected branches carry dummy witnesses, so
+// the proof does not reveal which groups the identity belongs to. The threshold
+// is a constant of the circuit: keys are specific to (k, n, depth).
+#[derive(Clone, Debug, Default)]
+pub struct ThresholdMembershipCircuit<F: PrimeField> {
+    pub roots: Vec<Option<F>>,
+    pub threshold: usize,
+    pub identity_scalar: Option<F>,
+    pub blindings: Vec<Option<F>>,
+    pub selectors: Vec<Option<bool>>,
+    pub expected_depth: usize,
+    pub merkle_paths: Vec<Vec<(Option<F>, Option<bool>)>>,
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for ThresholdMembershipCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let count = self.roots.len();
+        if self.threshold == 0
+            || self.threshold > count
+            || self.blindings.len() != count
+            || self.selectors.len() != count
+            || self.merkle_paths.len() != count
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        if self.expected_depth == 0
+            || self
+                .merkle_paths
+                .iter()
+                .any(|path| path.len() != self.expected_depth)
+        {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let params = poseidon_params::<F>();
+
+        let roots = self
+            .roots
+            .iter()
+            .map(|root| {
+                FpVar::new_input(cs.clone(), || root.ok_or(SynthesisError::AssignmentMissing))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let identity_scalar = FpVar::new_witness(cs.clone(), || {
+            self.identity_scalar.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let domain_commitment = FpVar::constant(F::from(DOMAIN_COMMITMENT));
+        let domain_leaf = FpVar::constant(F::from(DOMAIN_LEAF));
+
+        let mut selected = FpVar::zero();
+        let branches = self
+            .blindings
+            .into_iter()
+            .zip(self.selectors)
+            .zip(self.merkle_paths)
+            .zip(roots);
+        for (((blinding, selector), merkle_path), root) in branches {
+            let blinding = FpVar::new_witness(cs.clone(), || {
+                blinding.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+            let selector = Boolean::new_witness(cs.clone(), || {
+                selector.ok_or(SynthesisError::AssignmentMissing)
+            })?;
+
+            let commitment = poseidon_hash_var(
+                cs.clone(),
+                &params,
+                &[domain_commitment.clone(), identity_scalar.clone(), blinding],
+            )?;
+            let leaf = poseidon_hash_var(
+                cs.clone(),
+                &params,
+                &[domain_leaf.clone(), commitment, FpVar::zero()],
+            )?;
+            let computed_root = merkle_root_var(cs.clone(), &params, leaf, merkle_path)?;
+            computed_root.conditional_enforce_equal(&root, &selector)?;
+            selected += FpVar::from(selector);
+        }
+
+        // `selected` is at most n, so `selected - k` is negative exactly when it
+        // wraps to a value far outside the bits needed to write n.
+        let bit_width = (usize::BITS - count.leading_zeros()) as usize;
+        let surplus = selected - FpVar::constant(F::from(self.threshold as u64));
+        enforce_in_range(cs, &surplus, bit_width)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ThresholdMembershipPublicInputs {
+    pub roots: Vec<Fr>,
+}
+
+// The opening of the identity's commitment in one tree and its path there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ThresholdBranchWitness {
+    pub blinding: Fr,
+    pub merkle_path: Vec<(Fr, bool)>,
+}
+
+// `branches[i]` answers for `roots[i]`; `None` marks a tree the identity is not
+// claimed to be in and is filled with a dummy witness.
+#[derive(Clone, Debug)]
+pub struct ThresholdMembershipWitness {
+    pub identity_scalar: Fr,
+    pub branches: Vec<Option<ThresholdBranchWitness>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ThresholdMembershipInstance {
+    pub public_inputs: ThresholdMembershipPublicInputs,
+    pub witness: ThresholdMembershipWitness,
+}
+
+pub fn threshold_membership_field_inputs(
+    public_inputs: &ThresholdMembershipPublicInputs,
+) -> Vec<Fr> {
+    public_inputs.roots.clone()
+}
+
+pub fn build_threshold_circuit(
+    instance: &ThresholdMembershipInstance,
+    threshold: usize,
+    depth: usize,
+) -> ThresholdMembershipCircuit<Fr> {
+    let zero = Fr::from(0u64);
+    let branches = &instance.witness.branches;
+    ThresholdMembershipCircuit {
+        roots: instance.public_inputs.roots.iter().copied().map(Some).collect(),
+        threshold,
+        identity_scalar: Some(instance.witness.identity_scalar),
+        blindings: branches
+            .iter()
+            .map(|branch| Some(branch.as_ref().map_or(zero, |branch| branch.blinding)))
+            .collect(),
+        selectors: branches.iter().map(|branch| Some(branch.is_some())).collect(),
+        expected_depth: depth,
+        merkle_paths: branches
+            .iter()
+            .map(|branch| match branch {
+                Some(branch) => branch
+                    .merkle_path
+                    .iter()
+                    .map(|(sibling, is_left)| (Some(*sibling), Some(*is_left)))
+                    .collect(),
+                None => vec![(Some(zero), Some(false)); depth],
+            })
+            .collect(),
+    }
+}
+
+pub fn setup_threshold_membership<R: RngCore>(
+    rng: &mut R,
+    threshold: usize,
+    count: usize,
+    depth: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let zero = Fr::from(0u64);
+    let circuit = ThresholdMembershipCircuit::<Fr> {
+        roots: vec![Some(zero); count],
+        threshold,
+        identity_scalar: Some(zero),
+        blindings: vec![Some(zero); count],
+        selectors: vec![Some(false); count],
+        expected_depth: depth,
+        merkle_paths: vec![vec![(Some(zero), Some(false)); depth]; count],
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+// `threshold` and `depth` must be the values the key was set up with. Fewer than
+// `threshold` claimed branches is rejected here rather than yielding a proof that
+// cannot verify.
+pub fn prove_threshold_membership<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &ThresholdMembershipInstance,
+    threshold: usize,
+    depth: usize,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    let claimed = instance.witness.branches.iter().flatten().count();
+    if claimed < threshold {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    Groth16::<Bn254>::create_random_proof_with_reduction(
+        build_threshold_circuit(instance, threshold, depth),
+        pk,
+        rng,
+    )
+}
+
+pub fn verify_threshold_membership(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &ThresholdMembershipPublicInputs,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    let pvk = prepare_verifying_key(vk);
+    let inputs = threshold_membership_field_inputs(public_inputs);
+    Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_threshold_circuit, prove_threshold_membership, setup_threshold_membership,
+        verify_threshold_membership, ThresholdBranchWitness, ThresholdMembershipInstance,
+        ThresholdMembershipPublicInputs, ThresholdMembershipWitness,
+    };
+    use crate::tree::PoseidonMerkleTree;
+    use crate::{commitment_hash, poseidon_params};
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem, SynthesisError};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    const DEPTH: usize = 2;
+
+    fn satisfied(instance: &ThresholdMembershipInstance, threshold: usize) -> bool {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        build_threshold_circuit(instance, threshold, DEPTH)
+            .generate_constraints(cs.clone())
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn two_of_three_groups_prove_and_one_does_not() {
+        let params = poseidon_params::<Fr>();
+        let identity = Fr::from(42u64);
+        // Three groups, each holding the identity under its own blinding at a
+        // different slot among unrelated members.
+        let mut roots = Vec::new();
+        let mut branches = Vec::new();
+        for group in 0..3u64 {
+            let blinding = Fr::from(500 + group);
+            let mut commitments: Vec<Fr> = (0..4u64)
+                .map(|idx| {
+                    commitment_hash(&params, Fr::from(1000 * group + idx), Fr::from(7u64))
+                })
+                .collect();
+            let slot = group as usize;
+            commitments[slot] = commitment_hash(&params, identity, blinding);
+            let tree = PoseidonMerkleTree::build(&commitments, DEPTH, &params).unwrap();
+            roots.push(tree.root());
+            branches.push(Some(ThresholdBranchWitness {
+                blinding,
+                merkle_path: tree.path(slot).unwrap(),
+            }));
+        }
+        let public_inputs = ThresholdMembershipPublicInputs { roots };
+
+        let mut two_of_three = ThresholdMembershipInstance {
+            public_inputs: public_inputs.clone(),
+            witness: ThresholdMembershipWitness {
+                identity_scalar: identity,
+                branches,
+            },
+        };
+        two_of_three.witness.branches[1] = None;
+        assert!(satisfied(&two_of_three, 2));
+
+        let mut rng = StdRng::seed_from_u64(107);
+        let pk = setup_threshold_membership(&mut rng, 2, 3, DEPTH).unwrap();
+        assert_eq!(pk.vk.gamma_abc_g1.len(), 4);
+        let proof = prove_threshold_membership(&pk, &two_of_three, 2, DEPTH, &mut rng).unwrap();
+        assert!(verify_threshold_membership(&pk.vk, &public_inputs, &proof).unwrap());
+
+        let mut other_roots = public_inputs.clone();
+        other_roots.roots.swap(0, 2);
+        assert!(!verify_threshold_membership(&pk.vk, &other_roots, &proof).unwrap());
+
+        // k - 1 real memberships: the count falls short of the threshold.
+        let mut one_of_three = two_of_three.clone();
+        one_of_three.witness.branches[2] = None;
+        assert!(!satisfied(&one_of_three, 2));
+        assert!(matches!(
+            prove_threshold_membership(&pk, &one_of_three, 2, DEPTH, &mut rng),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+
+        // Claiming a group with a path from another tree fails its root check.
+        let mut borrowed_path = two_of_three.clone();
+        let path = borrowed_path.witness.branches[0].clone();
+        borrowed_path.witness.branches[2] = path;
+        assert!(!satisfied(&borrowed_path, 2));
+
+        // A different identity cannot reuse the openings.
+        let mut other_identity = two_of_three;
+        other_identity.witness.identity_scalar = Fr::from(43u64);
+        assert!(!satisfied(&other_identity, 2));
+    }
+}
@@ -0,0 +1,649 @@
+use ark_bn254::Fr;
+use ark_sponge::poseidon::PoseidonConfig;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::{
+    commitment_hash, fr_from_bytes, fr_to_array, fr_to_fixed_bytes, leaf_hash, node_hash,
+    poseidon_params, root_from_leaf,
+};
+
+pub const MAX_TREE_DEPTH: usize = 32;
+
+// Fixed-depth binary Poseidon Merkle tree. Unused slots are filled with the
+// leaf hash of a zero commitment so roots are deterministic for any fill level.
+#[derive(Clone, Debug)]
+pub struct PoseidonMerkleTree {
+    depth: usize,
+    commitments: Vec<Fr>,
+    // levels[0] holds the hashed leaves, levels[depth] holds the root.
+    levels: Vec<Vec<Fr>>,
+}
+
+// On-disk form of a tree: its depth and the commitments in leaf order. Inner
+// levels are rebuilt on load.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TreeSnapshotBytes {
+    pub depth: u32,
+    pub commitments: Vec<Vec<u8>>,
+}
+
+fn check_shape(commitments: &[Fr], depth: usize) -> Result<(), String> {
+    if depth == 0 || depth > MAX_TREE_DEPTH {
+        return Err(format!(
+            "tree depth must be between 1 and {}, got {}",
+            MAX_TREE_DEPTH, depth
+        ));
+    }
+    let capacity = 1usize << depth;
+    if commitments.len() > capacity {
+        return Err(format!(
+            "too many leaves for depth {}: capacity {}, got {}",
+            depth,
+            capacity,
+            commitments.len()
+        ));
+    }
+    Ok(())
+}
+
+fn padded_leaves(commitments: &[Fr], depth: usize, params: &PoseidonConfig<Fr>) -> Vec<Fr> {
+    let capacity = 1usize << depth;
+    let empty_leaf = leaf_hash(params, Fr::from(0u64));
+    let mut leaves = Vec::with_capacity(capacity);
+    leaves.extend(commitments.iter().map(|commitment| leaf_hash(params, *commitment)));
+    leaves.resize(capacity, empty_leaf);
+    leaves
+}
+
+// empty[level] is the root of an all-empty subtree whose leaves sit at `level`,
+// i.e. the sibling a path sees at that level when the neighbouring slots are unused.
+pub fn empty_subtree_hashes(params: &PoseidonConfig<Fr>, depth: usize) -> Vec<Fr> {
+    let mut hashes = Vec::with_capacity(depth);
+    let mut current = leaf_hash(params, Fr::from(0u64));
+    for _ in 0..depth {
+        hashes.push(current);
+        current = node_hash(params, current, current);
+    }
+    hashes
+}
+
+impl PoseidonMerkleTree {
+    pub fn build(
+        commitments: &[Fr],
+        depth: usize,
+        params: &PoseidonConfig<Fr>,
+    ) -> Result<Self, String> {
+        Self::build_with_progress(commitments, depth, params, |_, _| {})
+    }
+
+    // Same as `build`, calling `on_progress(completed_levels, total_levels)` after
+    // each level is hashed: once for the leaves, then once per node level up to
+    // the root, so it fires `depth + 1` times.
+    pub fn build_with_progress(
+        commitments: &[Fr],
+        depth: usize,
+        params: &PoseidonConfig<Fr>,
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> Result<Self, String> {
+        check_shape(commitments, depth)?;
+
+        let total_levels = depth + 1;
+        let mut levels = Vec::with_capacity(total_levels);
+        levels.push(padded_leaves(commitments, depth, params));
+        on_progress(1, total_levels);
+        for level in 0..depth {
+            let next = levels[level]
+                .chunks(2)
+                .map(|pair| node_hash(params, pair[0], pair[1]))
+                .collect();
+            levels.push(next);
+            on_progress(level + 2, total_levels);
+        }
+
+        Ok(Self {
+            depth,
+            commitments: commitments.to_vec(),
+            levels,
+        })
+    }
+
+    #[cfg(feature = "rayon")]
+    pub fn build_parallel(
+        commitments: &[Fr],
+        depth: usize,
+        params: &PoseidonConfig<Fr>,
+    ) -> Result<Self, String> {
+        check_shape(commitments, depth)?;
+
+        let capacity = 1usize << depth;
+        let empty_leaf = leaf_hash(params, Fr::from(0u64));
+        let mut leaves: Vec<Fr> = commitments
+            .par_iter()
+            .map(|commitment| leaf_hash(params, *commitment))
+            .collect();
+        leaves.resize(capacity, empty_leaf);
+
+        let mut levels = Vec::with_capacity(depth + 1);
+        levels.push(leaves);
+        for level in 0..depth {
+            let next = levels[level]
+                .par_chunks(2)
+                .map(|pair| node_hash(params, pair[0], pair[1]))
+                .collect();
+            levels.push(next);
+        }
+
+        Ok(Self {
+            depth,
+            commitments: commitments.to_vec(),
+            levels,
+        })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn root(&self) -> Fr {
+        self.levels[self.depth][0]
+    }
+
+    pub fn commitments(&self) -> &[Fr] {
+        &self.commitments
+    }
+
+    pub fn len(&self) -> usize {
+        self.commitments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commitments.is_empty()
+    }
+
+    pub fn path(&self, index: usize) -> Result<Vec<(Fr, bool)>, String> {
+        if index >= self.commitments.len() {
+            return Err(format!(
+                "leaf index {} out of range ({} leaves)",
+                index,
+                self.commitments.len()
+            ));
+        }
+
+        let mut path = Vec::with_capacity(self.depth);
+        let mut position = index;
+        for level in 0..self.depth {
+            let is_left = position % 2 == 1;
+            let sibling = self.levels[level][position ^ 1];
+            path.push((sibling, is_left));
+            position /= 2;
+        }
+        Ok(path)
+    }
+
+    pub fn to_snapshot(&self) -> TreeSnapshotBytes {
+        TreeSnapshotBytes {
+            depth: self.depth as u32,
+            commitments: self.commitments.iter().map(fr_to_fixed_bytes).collect(),
+        }
+    }
+
+    pub fn from_snapshot(
+        snapshot: &TreeSnapshotBytes,
+        params: &PoseidonConfig<Fr>,
+    ) -> Result<Self, String> {
+        let commitments = snapshot
+            .commitments
+            .iter()
+            .enumerate()
+            .map(|(idx, bytes)| fr_from_bytes(&format!("commitment[{idx}]"), bytes))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::build(&commitments, snapshot.depth as usize, params)
+    }
+}
+
+// Tree sized to its contents: the depth is the smallest that holds every
+// commitment (at least 1), and the remaining slots are padded with the leaf hash
+// of a zero commitment exactly as in `PoseidonMerkleTree`. Paths from
+// `generate_path` plug straight into `MembershipWitness::merkle_path`.
+#[derive(Clone, Debug)]
+pub struct MerkleTree {
+    inner: PoseidonMerkleTree,
+}
+
+impl MerkleTree {
+    pub fn new(commitments: Vec<Fr>, params: &PoseidonConfig<Fr>) -> Result<Self, String> {
+        let depth = commitments.len().max(2).next_power_of_two().trailing_zeros() as usize;
+        let inner = PoseidonMerkleTree::build(&commitments, depth, params)?;
+        Ok(Self { inner })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.inner.depth()
+    }
+
+    pub fn root(&self) -> Fr {
+        self.inner.root()
+    }
+
+    pub fn generate_path(&self, index: usize) -> Result<Vec<(Fr, bool)>, String> {
+        self.inner.path(index)
+    }
+
+    pub fn as_fixed_depth(&self) -> &PoseidonMerkleTree {
+        &self.inner
+    }
+}
+
+// Append-only tree of fixed depth for registries that grow one member at a time.
+// Only the frontier is kept: for each level, the latest left-hand node still
+// waiting for its right sibling. `append` is O(depth), and `root()` always equals
+// `PoseidonMerkleTree::build` over the same commitments at the same depth.
+// Paths are not available; rebuild a `PoseidonMerkleTree` to hand out witnesses.
+#[derive(Clone, Debug)]
+pub struct IncrementalMerkleTree {
+    depth: usize,
+    len: usize,
+    frontier: Vec<Fr>,
+    empty: Vec<Fr>,
+    root: Fr,
+    params: PoseidonConfig<Fr>,
+}
+
+impl IncrementalMerkleTree {
+    pub fn new(depth: usize, params: &PoseidonConfig<Fr>) -> Result<Self, String> {
+        check_shape(&[], depth)?;
+        let empty = empty_subtree_hashes(params, depth);
+        let root = node_hash(params, empty[depth - 1], empty[depth - 1]);
+        Ok(Self {
+            depth,
+            len: 0,
+            frontier: empty.clone(),
+            empty,
+            root,
+            params: params.clone(),
+        })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn root(&self) -> Fr {
+        self.root
+    }
+
+    // Adds `commitment` at the next free slot and returns its index. Everything to
+    // the right of the new leaf is still empty, so the path to the root only needs
+    // the frontier on the left and the empty-subtree hashes on the right.
+    pub fn append(&mut self, commitment: Fr) -> Result<usize, String> {
+        let capacity = 1usize << self.depth;
+        if self.len == capacity {
+            return Err(format!("tree of depth {} is full ({capacity} leaves)", self.depth));
+        }
+
+        let index = self.len;
+        let mut node = leaf_hash(&self.params, commitment);
+        let mut position = index;
+        for level in 0..self.depth {
+            node = if position & 1 == 0 {
+                self.frontier[level] = node;
+                node_hash(&self.params, node, self.empty[level])
+            } else {
+                node_hash(&self.params, self.frontier[level], node)
+            };
+            position /= 2;
+        }
+        self.root = node;
+        self.len += 1;
+        Ok(index)
+    }
+}
+
+// Non-ZK inclusion proof: the member's commitment (the leaf preimage), its
+// authentication path and the root it opens to. Checked with the same Poseidon
+// leaf/node hashing as the circuits, so roots are interchangeable.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlainInclusionProof {
+    pub commitment: Fr,
+    pub path: Vec<(Fr, bool)>,
+    pub root: Fr,
+}
+
+pub fn plain_inclusion_proof(
+    tree: &PoseidonMerkleTree,
+    index: usize,
+) -> Result<PlainInclusionProof, String> {
+    let path = tree.path(index)?;
+    Ok(PlainInclusionProof {
+        commitment: tree.commitments[index],
+        path,
+        root: tree.root(),
+    })
+}
+
+pub fn verify_plain_inclusion(params: &PoseidonConfig<Fr>, proof: &PlainInclusionProof) -> bool {
+    root_from_leaf(params, leaf_hash(params, proof.commitment), &proof.path) == proof.root
+}
+
+// Appends b's leaves after a's and rebuilds at the shared depth. Leaf order is
+// preserved, so merging the same inputs always yields the same root.
+pub fn merge_trees(
+    a: &PoseidonMerkleTree,
+    b: &PoseidonMerkleTree,
+) -> Result<PoseidonMerkleTree, String> {
+    if a.depth != b.depth {
+        return Err(format!(
+            "tree depths differ: {} vs {}",
+            a.depth, b.depth
+        ));
+    }
+
+    let mut seen: HashSet<Fr> = a.commitments.iter().copied().collect();
+    if seen.len() != a.commitments.len() {
+        return Err("first tree contains duplicate commitments".to_string());
+    }
+    let mut commitments = a.commitments.clone();
+    for (idx, commitment) in b.commitments.iter().enumerate() {
+        if !seen.insert(*commitment) {
+            return Err(format!("duplicate commitment at index {idx} of second tree"));
+        }
+        commitments.push(*commitment);
+    }
+
+    PoseidonMerkleTree::build(&commitments, a.depth, &poseidon_params::<Fr>())
+}
+
+// Public side of one registry member, as published in an allowlist. Built from
+// the secrets once; nothing here lets the witness be recovered.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegistryEntry {
+    pub commitment: [u8; 32],
+    pub leaf: [u8; 32],
+}
+
+pub fn registry_entry(identity: Fr, blinding: Fr) -> RegistryEntry {
+    registry_entry_with_params(&poseidon_params::<Fr>(), identity, blinding)
+}
+
+pub fn registry_entries(secrets: &[(Fr, Fr)]) -> Vec<RegistryEntry> {
+    let params = poseidon_params::<Fr>();
+    secrets
+        .iter()
+        .map(|(identity, blinding)| registry_entry_with_params(&params, *identity, *blinding))
+        .collect()
+}
+
+fn registry_entry_with_params(
+    params: &PoseidonConfig<Fr>,
+    identity: Fr,
+    blinding: Fr,
+) -> RegistryEntry {
+    let commitment = commitment_hash(params, identity, blinding);
+    RegistryEntry {
+        commitment: fr_to_array(&commitment),
+        leaf: fr_to_array(&leaf_hash(params, commitment)),
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SetDiff {
+    pub added: Vec<[u8; 32]>,
+    pub removed: Vec<[u8; 32]>,
+}
+
+fn commitment_bytes(tree: &PoseidonMerkleTree) -> Vec<[u8; 32]> {
+    tree.commitments.iter().map(fr_to_array).collect()
+}
+
+// Membership changes between two registry snapshots, computed over their known
+// commitments (roots alone cannot be diffed). `added` is in b but not a,
+// `removed` is in a but not b; both keep leaf order.
+pub fn root_set_diff(a: &PoseidonMerkleTree, b: &PoseidonMerkleTree) -> SetDiff {
+    let a_members = commitment_bytes(a);
+    let b_members = commitment_bytes(b);
+    let a_set: HashSet<&[u8; 32]> = a_members.iter().collect();
+    let b_set: HashSet<&[u8; 32]> = b_members.iter().collect();
+    SetDiff {
+        added: b_members
+            .iter()
+            .filter(|member| !a_set.contains(member))
+            .copied()
+            .collect(),
+        removed: a_members
+            .iter()
+            .filter(|member| !b_set.contains(member))
+            .copied()
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        merge_trees, plain_inclusion_proof, registry_entries, registry_entry, root_set_diff,
+        verify_plain_inclusion, IncrementalMerkleTree, MerkleTree, PoseidonMerkleTree,
+    };
+    use crate::{
+        commitment_hash, fr_to_fixed_bytes, leaf_hash, node_hash, poseidon_params,
+        root_from_witness, MembershipWitness,
+    };
+    use ark_bn254::Fr;
+
+    fn sample_commitments(count: u64) -> Vec<Fr> {
+        let params = poseidon_params::<Fr>();
+        (0..count)
+            .map(|idx| commitment_hash(&params, Fr::from(idx + 1), Fr::from(idx + 1000)))
+            .collect()
+    }
+
+    #[test]
+    fn build_with_progress_reports_each_level() {
+        let params = poseidon_params::<Fr>();
+        let commitments = sample_commitments(5);
+        let mut calls = Vec::new();
+        let tree =
+            PoseidonMerkleTree::build_with_progress(&commitments, 3, &params, |done, total| {
+                calls.push((done, total))
+            })
+            .unwrap();
+        assert_eq!(calls, vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
+        assert_eq!(
+            tree.root(),
+            PoseidonMerkleTree::build(&commitments, 3, &params)
+                .unwrap()
+                .root()
+        );
+
+        let mut called = false;
+        let overfull =
+            PoseidonMerkleTree::build_with_progress(&commitments, 2, &params, |_, _| called = true);
+        assert!(overfull.is_err());
+        assert!(!called);
+    }
+
+    #[test]
+    fn tree_path_recomputes_root() {
+        let params = poseidon_params::<Fr>();
+        let commitments = sample_commitments(5);
+        let tree = PoseidonMerkleTree::build(&commitments, 3, &params).unwrap();
+
+        for (index, commitment) in commitments.iter().enumerate() {
+            let mut current = leaf_hash(&params, *commitment);
+            for (sibling, is_left) in tree.path(index).unwrap() {
+                current = if is_left {
+                    node_hash(&params, sibling, current)
+                } else {
+                    node_hash(&params, current, sibling)
+                };
+            }
+            assert_eq!(current, tree.root());
+        }
+    }
+
+    #[test]
+    fn tree_rejects_overfull_and_out_of_range() {
+        let params = poseidon_params::<Fr>();
+        let commitments = sample_commitments(5);
+        assert!(PoseidonMerkleTree::build(&commitments, 2, &params).is_err());
+
+        let tree = PoseidonMerkleTree::build(&commitments, 3, &params).unwrap();
+        assert!(tree.path(5).is_err());
+    }
+
+    #[test]
+    fn plain_inclusion_proof_detects_tampered_sibling() {
+        let params = poseidon_params::<Fr>();
+        let commitments = sample_commitments(5);
+        let tree = PoseidonMerkleTree::build(&commitments, 3, &params).unwrap();
+
+        let proof = plain_inclusion_proof(&tree, 4).unwrap();
+        assert_eq!(proof.commitment, commitments[4]);
+        assert!(verify_plain_inclusion(&params, &proof));
+
+        let mut tampered = proof.clone();
+        tampered.path[1].0 += Fr::from(1u64);
+        assert!(!verify_plain_inclusion(&params, &tampered));
+        assert!(plain_inclusion_proof(&tree, 5).is_err());
+    }
+
+    #[test]
+    fn merged_tree_keeps_every_member_provable() {
+        let params = poseidon_params::<Fr>();
+        let commitments = sample_commitments(7);
+        let a = PoseidonMerkleTree::build(&commitments[..3], 3, &params).unwrap();
+        let b = PoseidonMerkleTree::build(&commitments[3..], 3, &params).unwrap();
+        let merged = merge_trees(&a, &b).unwrap();
+        assert_eq!(merged.commitments(), commitments.as_slice());
+
+        for (index, commitment) in commitments.iter().enumerate() {
+            let mut current = leaf_hash(&params, *commitment);
+            for (sibling, is_left) in merged.path(index).unwrap() {
+                current = if is_left {
+                    node_hash(&params, sibling, current)
+                } else {
+                    node_hash(&params, current, sibling)
+                };
+            }
+            assert_eq!(current, merged.root());
+        }
+
+        let restored =
+            PoseidonMerkleTree::from_snapshot(&merged.to_snapshot(), &params).unwrap();
+        assert_eq!(restored.root(), merged.root());
+
+        assert!(merge_trees(&a, &a).unwrap_err().contains("duplicate commitment"));
+        let shallow = PoseidonMerkleTree::build(&commitments[3..], 2, &params).unwrap();
+        assert!(merge_trees(&a, &shallow).unwrap_err().contains("depths differ"));
+        let full = PoseidonMerkleTree::build(&sample_commitments(12)[4..], 3, &params).unwrap();
+        assert!(merge_trees(&a, &full).unwrap_err().contains("too many leaves"));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn tree_parallel_build_matches_sequential() {
+        let params = poseidon_params::<Fr>();
+        let commitments = sample_commitments(3000);
+        let sequential = PoseidonMerkleTree::build(&commitments, 12, &params).unwrap();
+        let parallel = PoseidonMerkleTree::build_parallel(&commitments, 12, &params).unwrap();
+
+        assert_eq!(sequential.root(), parallel.root());
+        for index in [0, 1, 1499, 2999] {
+            assert_eq!(sequential.path(index).unwrap(), parallel.path(index).unwrap());
+        }
+    }
+
+    #[test]
+    fn set_diff_reports_added_member() {
+        let params = poseidon_params::<Fr>();
+        let commitments = sample_commitments(4);
+        let before = PoseidonMerkleTree::build(&commitments[..3], 3, &params).unwrap();
+        let after = PoseidonMerkleTree::build(&commitments, 3, &params).unwrap();
+
+        let diff = root_set_diff(&before, &after);
+        assert_eq!(
+            diff.added,
+            vec![<[u8; 32]>::try_from(fr_to_fixed_bytes(&commitments[3])).unwrap()]
+        );
+        assert!(diff.removed.is_empty());
+
+        let reverse = root_set_diff(&after, &before);
+        assert_eq!(reverse.removed, diff.added);
+        assert!(reverse.added.is_empty());
+        assert_eq!(root_set_diff(&after, &after), Default::default());
+    }
+
+    #[test]
+    fn merkle_tree_pads_to_the_next_power_of_two_and_yields_witness_paths() {
+        let params = poseidon_params::<Fr>();
+        let identity = Fr::from(7u64);
+        let blinding = Fr::from(8u64);
+        let mut commitments = sample_commitments(2);
+        commitments.push(commitment_hash(&params, identity, blinding));
+
+        let tree = MerkleTree::new(commitments.clone(), &params).unwrap();
+        assert_eq!(tree.depth(), 2);
+        assert_eq!(
+            tree.root(),
+            PoseidonMerkleTree::build(&commitments, 2, &params)
+                .unwrap()
+                .root()
+        );
+
+        let witness = MembershipWitness {
+            identity_scalar: identity,
+            blinding,
+            merkle_path: tree.generate_path(2).unwrap(),
+        };
+        assert_eq!(root_from_witness(&params, &witness), tree.root());
+        assert!(tree.generate_path(3).is_err());
+
+        let single = MerkleTree::new(vec![commitments[0]], &params).unwrap();
+        assert_eq!(single.depth(), 1);
+    }
+
+    #[test]
+    fn registry_entries_match_the_leaves_the_tree_hashes() {
+        let secrets: Vec<(Fr, Fr)> = (0..3u64)
+            .map(|idx| (Fr::from(idx + 1), Fr::from(idx + 1000)))
+            .collect();
+        let entries = registry_entries(&secrets);
+        assert_eq!(entries[1], registry_entry(secrets[1].0, secrets[1].1));
+
+        let params = poseidon_params::<Fr>();
+        let tree = PoseidonMerkleTree::build(&sample_commitments(3), 2, &params).unwrap();
+        for (idx, entry) in entries.iter().enumerate() {
+            assert_eq!(entry.commitment[..], fr_to_fixed_bytes(&tree.commitments()[idx])[..]);
+            assert_eq!(entry.leaf[..], fr_to_fixed_bytes(&tree.levels[0][idx])[..]);
+        }
+    }
+
+    #[test]
+    fn incremental_root_matches_a_full_rebuild_after_each_append() {
+        let params = poseidon_params::<Fr>();
+        let commitments = sample_commitments(8);
+        let mut tree = IncrementalMerkleTree::new(3, &params).unwrap();
+        assert!(tree.is_empty());
+        assert_eq!(tree.root(), PoseidonMerkleTree::build(&[], 3, &params).unwrap().root());
+
+        for (idx, commitment) in commitments.iter().enumerate() {
+            assert_eq!(tree.append(*commitment).unwrap(), idx);
+            let rebuilt = PoseidonMerkleTree::build(&commitments[..=idx], 3, &params).unwrap();
+            assert_eq!(tree.root(), rebuilt.root(), "after {} appends", idx + 1);
+        }
+        assert_eq!(tree.len(), 8);
+        assert_eq!(tree.root(), MerkleTree::new(commitments, &params).unwrap().root());
+        assert!(tree.append(Fr::from(1u64)).is_err());
+        assert!(IncrementalMerkleTree::new(0, &params).is_err());
+    }
+}
@@ -0,0 +1,63 @@
+use ark_bn254::Bn254;
+use ark_groth16::VerifyingKey;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VkComponent {
+    Alpha,
+    Beta,
+    Gamma,
+    Delta,
+    Ic(usize),
+}
+
+// Lists the components that differ between two verifying keys. IC entries present
+// in only one of the keys are reported as differing at their index.
+pub fn diff_vks(a: &VerifyingKey<Bn254>, b: &VerifyingKey<Bn254>) -> Vec<VkComponent> {
+    let mut diff = Vec::new();
+    if a.alpha_g1 != b.alpha_g1 {
+        diff.push(VkComponent::Alpha);
+    }
+    if a.beta_g2 != b.beta_g2 {
+        diff.push(VkComponent::Beta);
+    }
+    if a.gamma_g2 != b.gamma_g2 {
+        diff.push(VkComponent::Gamma);
+    }
+    if a.delta_g2 != b.delta_g2 {
+        diff.push(VkComponent::Delta);
+    }
+    let ic_len = a.gamma_abc_g1.len().max(b.gamma_abc_g1.len());
+    for index in 0..ic_len {
+        if a.gamma_abc_g1.get(index) != b.gamma_abc_g1.get(index) {
+            diff.push(VkComponent::Ic(index));
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_vks, VkComponent};
+    use crate::{setup_membership_with_depth, setup_membership_with_depth_v2};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn diff_reports_differing_components() {
+        let vk_a = setup_membership_with_depth(&mut StdRng::seed_from_u64(84), 1)
+            .unwrap()
+            .vk;
+        let vk_b = setup_membership_with_depth(&mut StdRng::seed_from_u64(85), 1)
+            .unwrap()
+            .vk;
+        assert!(diff_vks(&vk_a, &vk_a).is_empty());
+
+        let diff = diff_vks(&vk_a, &vk_b);
+        assert!(diff.contains(&VkComponent::Alpha));
+        assert!(diff.contains(&VkComponent::Ic(2)));
+
+        let vk_v2 = setup_membership_with_depth_v2(&mut StdRng::seed_from_u64(84), 1)
+            .unwrap()
+            .vk;
+        assert!(diff_vks(&vk_a, &vk_v2).contains(&VkComponent::Ic(4)));
+    }
+}
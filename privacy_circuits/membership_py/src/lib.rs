@@ -1,23 +1,38 @@
 use ark_bn254::{Bn254, Fr};
 use ark_ff::PrimeField;
-use ark_groth16::{Proof, VerifyingKey};
-use ark_serialize::CanonicalDeserialize;
+use ark_groth16::{Proof, ProvingKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::rngs::OsRng;
+use membership::io::{
+    bincode_deserialize_capped, decode_canonical_auto, read_file_capped,
+    DEFAULT_MAX_INSTANCE_BYTES, DEFAULT_MAX_PROOF_BYTES, DEFAULT_MAX_VK_BYTES,
+};
 use membership::{
-    commitment_hash, fr_to_fixed_bytes, leaf_hash, node_hash, poseidon_hash_leaf,
-    poseidon_hash_leaf_v2, poseidon_hash_node, poseidon_params,
-    verify_membership as verify_membership_inner, verify_membership_v2 as verify_membership_v2_inner,
+    commitment_hash, commitment_hash_domain as commitment_hash_domain_inner,
+    expected_public_inputs, fr_from_u128 as fr_from_u128_inner,
+    fr_from_u64_pair as fr_from_u64_pair_inner, fr_to_array, fr_to_fixed_bytes,
+    membership_instance_v2_bytes_zeroizing, node_hash,
+    plain_inclusion_proof as plain_inclusion_proof_inner, poseidon_params, proof_from_bytes,
+    prove_membership as prove_membership_inner,
+    registry_entries as registry_entries_inner, registry_entry as registry_entry_inner,
+    root_from_witness as root_from_witness_inner, root_from_witness_v2,
+    setup_membership_with_depth,
+    verify_opening as verify_opening_inner, verify_plain_inclusion as verify_plain_inclusion_inner,
+    verify_membership as verify_membership_inner,
+    verify_membership_timed as verify_membership_timed_inner,
+    verify_membership_v2 as verify_membership_v2_inner,
+    verify_membership_v2_root_allowlist as verify_membership_v2_root_allowlist_inner,
+    vk_fingerprint as vk_fingerprint_inner,
     MembershipInstanceBytes, MembershipInstanceV1Bytes, MembershipInstanceV2Bytes,
     MembershipPublicInputsBytes, MembershipPublicInputsV1Bytes, MembershipPublicInputsV2Bytes,
     MembershipWitnessBytes, MembershipWitnessV1Bytes, MembershipWitnessV2Bytes, MerklePathNodeBytes,
-    MEMBERSHIP_INSTANCE_VERSION_V1, MEMBERSHIP_INSTANCE_VERSION_V2, MEMBERSHIP_STATEMENT_TYPE,
-    MEMBERSHIP_STATEMENT_VERSION_V2, MEMBERSHIP_V2_DOMAIN_SEP, MERKLE_DEPTH,
+    PlainInclusionProof, PoseidonMerkleTree, MEMBERSHIP_INSTANCE_VERSION_V1, MEMBERSHIP_INSTANCE_VERSION_V2, MEMBERSHIP_STATEMENT_TYPE,
+    MEMBERSHIP_STATEMENT_VERSION_V2, MEMBERSHIP_V2_DOMAIN_SEP,
 };
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::types::PyBytes;
-use std::fs;
-use std::fs::File;
-use std::io::BufReader;
+use serde::de::DeserializeOwned;
 
 #[pyfunction]
 fn verify_membership(
@@ -27,31 +42,96 @@ fn verify_membership(
 ) -> PyResult<bool> {
     let vk = read_verifying_key(vk_path)?;
     let public_inputs_bytes = read_public_inputs(public_inputs_path)?;
-    let public_inputs = public_inputs_bytes.into_public_inputs().map_err(PyValueError::new_err)?;
+    let public_inputs = public_inputs_bytes.into_public_inputs()
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
     let proof = read_proof(proof_path)?;
 
     verify_membership_inner(&vk, &public_inputs, &proof)
         .map_err(|err| PyValueError::new_err(err.to_string()))
 }
 
+// Decoding is cheap and its errors become Python exceptions, so it runs under the
+// GIL. The pairing check only touches the owned, decoded values and takes
+// milliseconds, so the GIL is released for it and other Python threads (e.g.
+// concurrent requests in a server) keep running.
 #[pyfunction]
 fn verify_membership_bytes(
+    py: Python<'_>,
     vk_bytes: Vec<u8>,
     public_inputs_bytes: Vec<u8>,
     proof_bytes: Vec<u8>,
 ) -> PyResult<bool> {
     let vk = deserialize_verifying_key(&vk_bytes)?;
-    let public_inputs: MembershipPublicInputsBytes =
-        bincode::deserialize(&public_inputs_bytes).map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let public_inputs: MembershipPublicInputsBytes = decode_bincode(&public_inputs_bytes)?;
     let inputs = public_inputs
         .into_public_inputs()
         .map_err(|err| PyValueError::new_err(err.to_string()))?;
     let proof = deserialize_proof(&proof_bytes)?;
 
-    verify_membership_inner(&vk, &inputs, &proof)
+    py.allow_threads(|| verify_membership_inner(&vk, &inputs, &proof))
         .map_err(|err| PyValueError::new_err(err.to_string()))
 }
 
+// Like verify_membership_bytes, also returning the seconds spent in the pairing
+// check so services can report verification latency. Decoding is not counted.
+#[pyfunction]
+fn verify_membership_timed_bytes(
+    py: Python<'_>,
+    vk_bytes: Vec<u8>,
+    public_inputs_bytes: Vec<u8>,
+    proof_bytes: Vec<u8>,
+) -> PyResult<(bool, f64)> {
+    let vk = deserialize_verifying_key(&vk_bytes)?;
+    let public_inputs: MembershipPublicInputsBytes = decode_bincode(&public_inputs_bytes)?;
+    let inputs = public_inputs
+        .into_public_inputs()
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let proof = deserialize_proof(&proof_bytes)?;
+
+    let (valid, elapsed) = py
+        .allow_threads(|| verify_membership_timed_inner(&vk, &inputs, &proof))
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok((valid, elapsed.as_secs_f64()))
+}
+
+// Key generation and proving for a pure-Python pipeline. Keys and proofs use the
+// same uncompressed encoding as the setup/prove binaries, so the bytes can be
+// passed straight to verify_membership_bytes or written to disk for the CLI.
+#[pyfunction]
+fn setup_membership_bytes(py: Python<'_>, depth: usize) -> PyResult<(Py<PyBytes>, Py<PyBytes>)> {
+    if depth == 0 {
+        return Err(PyValueError::new_err("depth must be greater than zero"));
+    }
+    let mut rng = OsRng;
+    let pk = setup_membership_with_depth(&mut rng, depth)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let pk_bytes = serialize_uncompressed(&pk)?;
+    let vk_bytes = serialize_uncompressed(&pk.vk)?;
+
+    Ok((
+        PyBytes::new(py, &pk_bytes).into(),
+        PyBytes::new(py, &vk_bytes).into(),
+    ))
+}
+
+#[pyfunction]
+fn prove_membership_bytes(
+    py: Python<'_>,
+    pk_bytes: Vec<u8>,
+    instance_bytes: Vec<u8>,
+) -> PyResult<Py<PyBytes>> {
+    let pk = deserialize_proving_key(&pk_bytes)?;
+    let instance: MembershipInstanceBytes = decode_bincode(&instance_bytes)?;
+    let instance = instance
+        .into_instance()
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+
+    let mut rng = OsRng;
+    let proof = prove_membership_inner(&pk, &instance, &mut rng)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok(PyBytes::new(py, &serialize_uncompressed(&proof)?).into())
+}
+
 #[pyfunction]
 fn make_membership_instance_bytes(
     py: Python<'_>,
@@ -65,13 +145,6 @@ fn make_membership_instance_bytes(
             "merkle_siblings and merkle_is_left length mismatch",
         ));
     }
-    if merkle_siblings.len() != MERKLE_DEPTH {
-        return Err(PyValueError::new_err(format!(
-            "merkle_path length mismatch: expected {}, got {}",
-            MERKLE_DEPTH,
-            merkle_siblings.len()
-        )));
-    }
 
     let merkle_path: Vec<MerklePathNodeBytes> = merkle_siblings
         .into_iter()
@@ -87,19 +160,11 @@ fn make_membership_instance_bytes(
     let witness = witness_bytes
         .clone()
         .into_witness()
-        .map_err(PyValueError::new_err)?;
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
 
     let params = poseidon_params::<Fr>();
     let commitment = commitment_hash(&params, witness.identity_scalar, witness.blinding);
-    let mut current = leaf_hash(&params, commitment);
-    for (sibling, is_left) in witness.merkle_path.iter() {
-        let (left, right) = if *is_left {
-            (*sibling, current)
-        } else {
-            (current, *sibling)
-        };
-        current = node_hash(&params, left, right);
-    }
+    let current = root_from_witness_inner(&params, &witness);
 
     let public_inputs = MembershipPublicInputsBytes {
         root: fr_to_fixed_bytes(&current),
@@ -160,19 +225,11 @@ fn make_membership_instance_v1_bytes(
     let witness = witness_bytes
         .clone()
         .into_witness(depth as usize)
-        .map_err(PyValueError::new_err)?;
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
 
     let params = poseidon_params::<Fr>();
     let commitment = commitment_hash(&params, witness.identity_scalar, witness.blinding);
-    let mut current = poseidon_hash_leaf(&params, commitment);
-    for (sibling, is_left) in witness.merkle_path.iter() {
-        let (left, right) = if *is_left {
-            (*sibling, current)
-        } else {
-            (current, *sibling)
-        };
-        current = poseidon_hash_node(&params, left, right);
-    }
+    let current = root_from_witness_inner(&params, &witness);
 
     let public_inputs = MembershipPublicInputsV1Bytes {
         version: MEMBERSHIP_INSTANCE_VERSION_V1,
@@ -207,7 +264,7 @@ fn verify_membership_v1(
     let public_inputs_bytes = read_public_inputs_v1(public_inputs_path)?;
     let (public_inputs, _depth) = public_inputs_bytes
         .into_public_inputs_with_depth()
-        .map_err(PyValueError::new_err)?;
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
     let proof = read_proof(proof_path)?;
 
     verify_membership_inner(&vk, &public_inputs, &proof)
@@ -221,8 +278,7 @@ fn verify_membership_v1_bytes(
     proof_bytes: Vec<u8>,
 ) -> PyResult<bool> {
     let vk = deserialize_verifying_key(&vk_bytes)?;
-    let public_inputs: MembershipPublicInputsV1Bytes =
-        bincode::deserialize(&public_inputs_bytes).map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let public_inputs: MembershipPublicInputsV1Bytes = decode_bincode(&public_inputs_bytes)?;
     let (inputs, _depth) = public_inputs
         .into_public_inputs_with_depth()
         .map_err(|err| PyValueError::new_err(err.to_string()))?;
@@ -265,21 +321,13 @@ fn make_membership_instance_v2_bytes(
     let witness = witness_bytes
         .clone()
         .into_witness(depth as usize)
-        .map_err(PyValueError::new_err)?;
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
 
     let params = poseidon_params::<Fr>();
     let commitment = commitment_hash(&params, witness.identity_scalar, witness.blinding);
     let domain_sep_fr = Fr::from_be_bytes_mod_order(&domain_sep_fixed);
     let ctx_hash_fr = Fr::from_be_bytes_mod_order(&ctx_hash_fixed);
-    let mut current = poseidon_hash_leaf_v2(&params, domain_sep_fr, ctx_hash_fr, commitment);
-    for (sibling, is_left) in witness.merkle_path.iter() {
-        let (left, right) = if *is_left {
-            (*sibling, current)
-        } else {
-            (current, *sibling)
-        };
-        current = poseidon_hash_node(&params, left, right);
-    }
+    let current = root_from_witness_v2(&params, &witness, domain_sep_fr, ctx_hash_fr);
 
     let public_inputs = MembershipPublicInputsV2Bytes {
         schema_version: MEMBERSHIP_INSTANCE_VERSION_V2,
@@ -289,7 +337,7 @@ fn make_membership_instance_v2_bytes(
         root: fixed_bytes32("root", &fr_to_fixed_bytes(&current))?,
         commitment: fixed_bytes32("commitment", &fr_to_fixed_bytes(&commitment))?,
         domain_sep: domain_sep_fixed,
-        ctx_hash: ctx_hash_fixed,
+        ctx_hash: fr_to_array(&ctx_hash_fr),
     };
     let instance = MembershipInstanceV2Bytes {
         schema_version: MEMBERSHIP_INSTANCE_VERSION_V2,
@@ -308,6 +356,35 @@ fn make_membership_instance_v2_bytes(
     ))
 }
 
+// Like make_membership_instance_v2_bytes, but the Rust-side copies of
+// identity_scalar and blinding are zeroized before returning. The Python bytes
+// objects passed in are not touched, and the returned instance still contains
+// both secrets (it is the witness): keep it out of logs and drop it after proving.
+#[pyfunction]
+fn make_membership_instance_v2_bytes_zeroizing(
+    py: Python<'_>,
+    identity_scalar: Vec<u8>,
+    blinding: Vec<u8>,
+    merkle_siblings: Vec<Vec<u8>>,
+    merkle_is_left: Vec<bool>,
+    ctx_hash: Vec<u8>,
+) -> PyResult<(Py<PyBytes>, Py<PyBytes>)> {
+    let ctx_hash = fixed_bytes32("ctx_hash", &ctx_hash)?;
+    let (instance_bytes, public_inputs_bytes) = membership_instance_v2_bytes_zeroizing(
+        identity_scalar,
+        blinding,
+        &merkle_siblings,
+        &merkle_is_left,
+        ctx_hash,
+    )
+    .map_err(PyValueError::new_err)?;
+
+    Ok((
+        PyBytes::new(py, &instance_bytes).into(),
+        PyBytes::new(py, &public_inputs_bytes).into(),
+    ))
+}
+
 #[pyfunction]
 fn verify_membership_v2(
     vk_path: &str,
@@ -318,7 +395,7 @@ fn verify_membership_v2(
     let public_inputs_bytes = read_public_inputs_v2(public_inputs_path)?;
     let (public_inputs, _depth) = public_inputs_bytes
         .into_public_inputs_with_depth()
-        .map_err(PyValueError::new_err)?;
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
     let proof = read_proof(proof_path)?;
 
     verify_membership_v2_inner(&vk, &public_inputs, &proof)
@@ -332,8 +409,7 @@ fn verify_membership_v2_bytes(
     proof_bytes: Vec<u8>,
 ) -> PyResult<bool> {
     let vk = deserialize_verifying_key(&vk_bytes)?;
-    let public_inputs: MembershipPublicInputsV2Bytes =
-        bincode::deserialize(&public_inputs_bytes).map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let public_inputs: MembershipPublicInputsV2Bytes = decode_bincode(&public_inputs_bytes)?;
     let (inputs, _depth) = public_inputs
         .into_public_inputs_with_depth()
         .map_err(|err| PyValueError::new_err(err.to_string()))?;
@@ -343,17 +419,262 @@ fn verify_membership_v2_bytes(
         .map_err(|err| PyValueError::new_err(err.to_string()))
 }
 
+#[pyfunction]
+fn verify_membership_v2_root_allowlist(
+    vk_bytes: Vec<u8>,
+    public_inputs_bytes: Vec<u8>,
+    proof_bytes: Vec<u8>,
+    allowed_roots: Vec<Vec<u8>>,
+) -> PyResult<bool> {
+    let vk = deserialize_verifying_key(&vk_bytes)?;
+    let proof = deserialize_proof(&proof_bytes)?;
+    let allowed_roots = allowed_roots
+        .iter()
+        .map(|root| fixed_bytes32("allowed_root", root))
+        .collect::<PyResult<Vec<_>>>()?;
+
+    verify_membership_v2_root_allowlist_inner(&vk, &public_inputs_bytes, &proof, &allowed_roots)
+        .map_err(PyValueError::new_err)
+}
+
+#[pyfunction]
+fn vk_accepts_input_count(vk_bytes: Vec<u8>, count: usize) -> PyResult<bool> {
+    let vk = deserialize_verifying_key(&vk_bytes)?;
+    Ok(expected_public_inputs(&vk) == count)
+}
+
+#[pyfunction]
+fn commitment_hash_domain(
+    py: Python<'_>,
+    domain: Vec<u8>,
+    id: Vec<u8>,
+    blinding: Vec<u8>,
+) -> PyResult<Py<PyBytes>> {
+    let domain = Fr::from_be_bytes_mod_order(&fixed_bytes32("domain", &domain)?);
+    let id = Fr::from_be_bytes_mod_order(&fixed_bytes32("id", &id)?);
+    let blinding = Fr::from_be_bytes_mod_order(&fixed_bytes32("blinding", &blinding)?);
+
+    let params = poseidon_params::<Fr>();
+    let commitment = commitment_hash_domain_inner(&params, domain, id, blinding);
+    Ok(PyBytes::new(py, &fr_to_fixed_bytes(&commitment)).into())
+}
+
+// Poseidon helpers so Python services can build commitments, leaves and roots
+// that match the circuits. Inputs are big-endian, at most 32 bytes, reduced mod r;
+// outputs are 32 fixed bytes.
+#[pyfunction]
+fn commitment_hash_bytes(
+    py: Python<'_>,
+    identity: Vec<u8>,
+    blinding: Vec<u8>,
+) -> PyResult<Py<PyBytes>> {
+    let identity = Fr::from_be_bytes_mod_order(&fixed_bytes32("identity", &identity)?);
+    let blinding = Fr::from_be_bytes_mod_order(&fixed_bytes32("blinding", &blinding)?);
+
+    let commitment = commitment_hash(&poseidon_params::<Fr>(), identity, blinding);
+    Ok(PyBytes::new(py, &fr_to_fixed_bytes(&commitment)).into())
+}
+
+#[pyfunction]
+fn merkle_node_hash_bytes(py: Python<'_>, left: Vec<u8>, right: Vec<u8>) -> PyResult<Py<PyBytes>> {
+    let left = Fr::from_be_bytes_mod_order(&fixed_bytes32("left", &left)?);
+    let right = Fr::from_be_bytes_mod_order(&fixed_bytes32("right", &right)?);
+
+    let node = node_hash(&poseidon_params::<Fr>(), left, right);
+    Ok(PyBytes::new(py, &fr_to_fixed_bytes(&node)).into())
+}
+
+// Out-of-band opening check: no proof, the verifier sees identity and blinding.
+#[pyfunction]
+fn verify_opening(identity: Vec<u8>, blinding: Vec<u8>, commitment: Vec<u8>) -> PyResult<bool> {
+    let identity = Fr::from_be_bytes_mod_order(&fixed_bytes32("identity", &identity)?);
+    let blinding = Fr::from_be_bytes_mod_order(&fixed_bytes32("blinding", &blinding)?);
+    let commitment = fixed_bytes32("commitment", &commitment)?;
+    Ok(verify_opening_inner(identity, blinding, commitment))
+}
+
+type RegistryEntryTuple = (Py<PyBytes>, Py<PyBytes>);
+
+// Returns (commitment, leaf) for publishing; the secrets are not needed again.
+#[pyfunction]
+fn registry_entry(
+    py: Python<'_>,
+    identity: Vec<u8>,
+    blinding: Vec<u8>,
+) -> PyResult<RegistryEntryTuple> {
+    let identity = Fr::from_be_bytes_mod_order(&fixed_bytes32("identity", &identity)?);
+    let blinding = Fr::from_be_bytes_mod_order(&fixed_bytes32("blinding", &blinding)?);
+    let entry = registry_entry_inner(identity, blinding);
+    Ok((
+        PyBytes::new(py, &entry.commitment).into(),
+        PyBytes::new(py, &entry.leaf).into(),
+    ))
+}
+
+#[pyfunction]
+fn registry_entries(
+    py: Python<'_>,
+    secrets: Vec<(Vec<u8>, Vec<u8>)>,
+) -> PyResult<Vec<RegistryEntryTuple>> {
+    let secrets = secrets
+        .iter()
+        .map(|(identity, blinding)| {
+            Ok((
+                Fr::from_be_bytes_mod_order(&fixed_bytes32("identity", identity)?),
+                Fr::from_be_bytes_mod_order(&fixed_bytes32("blinding", blinding)?),
+            ))
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    Ok(registry_entries_inner(&secrets)
+        .iter()
+        .map(|entry| {
+            (
+                PyBytes::new(py, &entry.commitment).into(),
+                PyBytes::new(py, &entry.leaf).into(),
+            )
+        })
+        .collect())
+}
+
+#[pyfunction]
+fn root_from_witness(
+    py: Python<'_>,
+    identity_scalar: Vec<u8>,
+    blinding: Vec<u8>,
+    merkle_siblings: Vec<Vec<u8>>,
+    merkle_is_left: Vec<bool>,
+) -> PyResult<Py<PyBytes>> {
+    let depth = merkle_siblings.len();
+    let witness_bytes = MembershipWitnessV1Bytes {
+        version: MEMBERSHIP_INSTANCE_VERSION_V1,
+        depth: depth as u32,
+        identity_scalar,
+        blinding,
+        merkle_siblings,
+        merkle_directions: merkle_is_left,
+    };
+    let witness = witness_bytes
+        .into_witness(depth)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+
+    let params = poseidon_params::<Fr>();
+    let root = root_from_witness_inner(&params, &witness);
+    Ok(PyBytes::new(py, &fr_to_fixed_bytes(&root)).into())
+}
+
+// Numeric identities (account ids) as 32-byte big-endian field elements.
+#[pyfunction]
+fn fr_from_u128(py: Python<'_>, value: u128) -> Py<PyBytes> {
+    PyBytes::new(py, &fr_to_fixed_bytes(&fr_from_u128_inner(value))).into()
+}
+
+#[pyfunction]
+fn fr_from_u64_pair(py: Python<'_>, hi: u64, lo: u64) -> Py<PyBytes> {
+    PyBytes::new(py, &fr_to_fixed_bytes(&fr_from_u64_pair_inner(hi, lo))).into()
+}
+
+// (commitment, merkle_siblings, merkle_is_left, root)
+type PlainInclusionTuple = (Py<PyBytes>, Vec<Py<PyBytes>>, Vec<bool>, Py<PyBytes>);
+
+#[pyfunction]
+fn plain_inclusion_proof(
+    py: Python<'_>,
+    commitments: Vec<Vec<u8>>,
+    depth: usize,
+    index: usize,
+) -> PyResult<PlainInclusionTuple> {
+    let commitments = commitments
+        .iter()
+        .map(|bytes| Ok(Fr::from_be_bytes_mod_order(&fixed_bytes32("commitment", bytes)?)))
+        .collect::<PyResult<Vec<_>>>()?;
+    let params = poseidon_params::<Fr>();
+    let tree =
+        PoseidonMerkleTree::build(&commitments, depth, &params).map_err(PyValueError::new_err)?;
+    let proof = plain_inclusion_proof_inner(&tree, index).map_err(PyValueError::new_err)?;
+
+    let siblings = proof
+        .path
+        .iter()
+        .map(|(sibling, _)| PyBytes::new(py, &fr_to_fixed_bytes(sibling)).into())
+        .collect();
+    let is_left = proof.path.iter().map(|(_, is_left)| *is_left).collect();
+    Ok((
+        PyBytes::new(py, &fr_to_fixed_bytes(&proof.commitment)).into(),
+        siblings,
+        is_left,
+        PyBytes::new(py, &fr_to_fixed_bytes(&proof.root)).into(),
+    ))
+}
+
+#[pyfunction]
+fn verify_plain_inclusion(
+    commitment: Vec<u8>,
+    merkle_siblings: Vec<Vec<u8>>,
+    merkle_is_left: Vec<bool>,
+    root: Vec<u8>,
+) -> PyResult<bool> {
+    if merkle_siblings.len() != merkle_is_left.len() {
+        return Err(PyValueError::new_err(
+            "merkle_siblings and merkle_is_left must have the same length",
+        ));
+    }
+    let path = merkle_siblings
+        .iter()
+        .zip(merkle_is_left)
+        .map(|(sibling, is_left)| {
+            Ok((
+                Fr::from_be_bytes_mod_order(&fixed_bytes32("sibling", sibling)?),
+                is_left,
+            ))
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+    let proof = PlainInclusionProof {
+        commitment: Fr::from_be_bytes_mod_order(&fixed_bytes32("commitment", &commitment)?),
+        path,
+        root: Fr::from_be_bytes_mod_order(&fixed_bytes32("root", &root)?),
+    };
+    Ok(verify_plain_inclusion_inner(&poseidon_params::<Fr>(), &proof))
+}
+
+// SHA-256 of the uncompressed VK encoding; matches `sha256sum` of the setup's
+// vk file, so a failing proof can be checked against the key it was made for.
+#[pyfunction]
+fn vk_fingerprint(py: Python<'_>, vk_bytes: Vec<u8>) -> PyResult<Py<PyBytes>> {
+    let vk = deserialize_verifying_key(&vk_bytes)?;
+    Ok(PyBytes::new(py, &vk_fingerprint_inner(&vk)).into())
+}
+
 #[pymodule]
 fn membership_py(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(verify_membership, m)?)?;
     m.add_function(wrap_pyfunction!(verify_membership_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_membership_timed_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(setup_membership_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(prove_membership_bytes, m)?)?;
     m.add_function(wrap_pyfunction!(make_membership_instance_bytes, m)?)?;
     m.add_function(wrap_pyfunction!(make_membership_instance_v1_bytes, m)?)?;
     m.add_function(wrap_pyfunction!(make_membership_instance_v2_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(make_membership_instance_v2_bytes_zeroizing, m)?)?;
     m.add_function(wrap_pyfunction!(verify_membership_v1, m)?)?;
     m.add_function(wrap_pyfunction!(verify_membership_v1_bytes, m)?)?;
     m.add_function(wrap_pyfunction!(verify_membership_v2, m)?)?;
     m.add_function(wrap_pyfunction!(verify_membership_v2_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_membership_v2_root_allowlist, m)?)?;
+    m.add_function(wrap_pyfunction!(vk_accepts_input_count, m)?)?;
+    m.add_function(wrap_pyfunction!(vk_fingerprint, m)?)?;
+    m.add_function(wrap_pyfunction!(commitment_hash_domain, m)?)?;
+    m.add_function(wrap_pyfunction!(commitment_hash_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(merkle_node_hash_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_opening, m)?)?;
+    m.add_function(wrap_pyfunction!(registry_entry, m)?)?;
+    m.add_function(wrap_pyfunction!(registry_entries, m)?)?;
+    m.add_function(wrap_pyfunction!(root_from_witness, m)?)?;
+    m.add_function(wrap_pyfunction!(plain_inclusion_proof, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_plain_inclusion, m)?)?;
+    m.add_function(wrap_pyfunction!(fr_from_u128, m)?)?;
+    m.add_function(wrap_pyfunction!(fr_from_u64_pair, m)?)?;
+    m.add("PROOF_SIZE_COMPRESSED", membership::io::PROOF_SIZE_COMPRESSED)?;
+    m.add("PROOF_SIZE_UNCOMPRESSED", membership::io::PROOF_SIZE_UNCOMPRESSED)?;
     Ok(())
 }
 
@@ -372,45 +693,123 @@ fn fixed_bytes32(label: &str, data: &[u8]) -> PyResult<[u8; 32]> {
 }
 
 fn read_verifying_key(path: &str) -> PyResult<VerifyingKey<Bn254>> {
-    let file = File::open(path).map_err(PyValueError::new_err)?;
-    let mut reader = BufReader::new(file);
-    VerifyingKey::<Bn254>::deserialize_uncompressed(&mut reader)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    let data = read_file_capped(path, DEFAULT_MAX_VK_BYTES).map_err(PyValueError::new_err)?;
+    deserialize_verifying_key(&data)
 }
 
 fn read_public_inputs(path: &str) -> PyResult<MembershipPublicInputsBytes> {
-    let data = fs::read(path).map_err(PyValueError::new_err)?;
-    bincode::deserialize::<MembershipPublicInputsBytes>(&data)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    read_bincode(path)
 }
 
 fn read_public_inputs_v1(path: &str) -> PyResult<MembershipPublicInputsV1Bytes> {
-    let data = fs::read(path).map_err(PyValueError::new_err)?;
-    bincode::deserialize::<MembershipPublicInputsV1Bytes>(&data)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    read_bincode(path)
 }
 
 fn read_public_inputs_v2(path: &str) -> PyResult<MembershipPublicInputsV2Bytes> {
-    let data = fs::read(path).map_err(PyValueError::new_err)?;
-    bincode::deserialize::<MembershipPublicInputsV2Bytes>(&data)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    read_bincode(path)
 }
 
 fn read_proof(path: &str) -> PyResult<Proof<Bn254>> {
-    let file = File::open(path).map_err(PyValueError::new_err)?;
-    let mut reader = BufReader::new(file);
-    Proof::<Bn254>::deserialize_uncompressed(&mut reader)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    let data = read_file_capped(path, DEFAULT_MAX_PROOF_BYTES).map_err(PyValueError::new_err)?;
+    decode_canonical_auto(&data).map_err(PyValueError::new_err)
 }
 
-fn deserialize_verifying_key(bytes: &[u8]) -> PyResult<VerifyingKey<Bn254>> {
+// Instance and public-input bytes may come from untrusted clients, so decoding is
+// capped at DEFAULT_MAX_INSTANCE_BYTES and the variable-length fields are checked
+// before anything else touches them.
+const MAX_SIBLINGS: usize = 256;
+const MAX_FIELD_BYTES: usize = 32;
+
+trait BoundedInput {
+    fn check_bounds(&self) -> PyResult<()>;
+}
+
+fn check_field_bytes(label: &str, bytes: &[u8]) -> PyResult<()> {
+    if bytes.len() > MAX_FIELD_BYTES {
+        return Err(PyValueError::new_err(format!(
+            "{label} is {} bytes, at most {MAX_FIELD_BYTES} allowed",
+            bytes.len()
+        )));
+    }
+    Ok(())
+}
+
+fn check_sibling_count(count: usize) -> PyResult<()> {
+    if count > MAX_SIBLINGS {
+        return Err(PyValueError::new_err(format!(
+            "merkle path has {count} siblings, at most {MAX_SIBLINGS} allowed"
+        )));
+    }
+    Ok(())
+}
+
+impl BoundedInput for MembershipPublicInputsBytes {
+    fn check_bounds(&self) -> PyResult<()> {
+        check_field_bytes("root", &self.root)?;
+        check_field_bytes("commitment", &self.commitment)
+    }
+}
+
+impl BoundedInput for MembershipPublicInputsV1Bytes {
+    fn check_bounds(&self) -> PyResult<()> {
+        check_field_bytes("root", &self.root)?;
+        check_field_bytes("commitment", &self.commitment)
+    }
+}
+
+// Every field is a fixed 32-byte array.
+impl BoundedInput for MembershipPublicInputsV2Bytes {
+    fn check_bounds(&self) -> PyResult<()> {
+        Ok(())
+    }
+}
+
+impl BoundedInput for MembershipInstanceBytes {
+    fn check_bounds(&self) -> PyResult<()> {
+        self.public_inputs.check_bounds()?;
+        let witness = &self.witness;
+        check_field_bytes("identity_scalar", &witness.identity_scalar)?;
+        check_field_bytes("blinding", &witness.blinding)?;
+        check_sibling_count(witness.merkle_path.len())?;
+        for node in &witness.merkle_path {
+            check_field_bytes("merkle sibling", &node.sibling)?;
+        }
+        Ok(())
+    }
+}
+
+fn decode_bincode<T: DeserializeOwned + BoundedInput>(data: &[u8]) -> PyResult<T> {
+    let value: T = bincode_deserialize_capped(data, DEFAULT_MAX_INSTANCE_BYTES)
+        .map_err(PyValueError::new_err)?;
+    value.check_bounds()?;
+    Ok(value)
+}
+
+fn read_bincode<T: DeserializeOwned + BoundedInput>(path: &str) -> PyResult<T> {
+    let data =
+        read_file_capped(path, DEFAULT_MAX_INSTANCE_BYTES).map_err(PyValueError::new_err)?;
+    decode_bincode(&data)
+}
+
+fn deserialize_proving_key(bytes: &[u8]) -> PyResult<ProvingKey<Bn254>> {
     let mut reader = std::io::Cursor::new(bytes);
-    VerifyingKey::<Bn254>::deserialize_uncompressed(&mut reader)
+    ProvingKey::<Bn254>::deserialize_uncompressed(&mut reader)
         .map_err(|err| PyValueError::new_err(err.to_string()))
 }
 
+// Keys from either setup mode load; only bytes that decode in neither are refused.
+fn deserialize_verifying_key(bytes: &[u8]) -> PyResult<VerifyingKey<Bn254>> {
+    decode_canonical_auto(bytes).map_err(PyValueError::new_err)
+}
+
 fn deserialize_proof(bytes: &[u8]) -> PyResult<Proof<Bn254>> {
-    let mut reader = std::io::Cursor::new(bytes);
-    Proof::<Bn254>::deserialize_uncompressed(&mut reader)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    proof_from_bytes(bytes).map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+fn serialize_uncompressed<T: CanonicalSerialize>(value: &T) -> PyResult<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(value.uncompressed_size());
+    value
+        .serialize_uncompressed(&mut bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok(bytes)
 }
@@ -0,0 +1,109 @@
+use ark_bn254::Bn254;
+use ark_groth16::{Proof, VerifyingKey};
+use membership::io::{decode_canonical, decode_public_inputs};
+use membership::{proof_from_bytes, MembershipPublicInputsV2, MembershipPublicInputsV2Bytes};
+use wasm_bindgen::prelude::*;
+
+// Browser entry point. Takes the same bytes the CLIs and Python bindings use: an
+// arkworks verifying key and proof (compressed or uncompressed) and bincode V2
+// public inputs. Malformed input becomes a JS exception, never a panic; a proof
+// that simply does not verify returns false.
+#[wasm_bindgen]
+pub fn verify_membership_v2(
+    vk_bytes: &[u8],
+    public_inputs_bytes: &[u8],
+    proof_bytes: &[u8],
+) -> Result<bool, JsError> {
+    verify_membership_v2_bytes(vk_bytes, public_inputs_bytes, proof_bytes)
+        .map_err(|err| JsError::new(&err))
+}
+
+// JsError can only be built on a wasm target, so the logic lives here where
+// native tests can reach it.
+fn verify_membership_v2_bytes(
+    vk_bytes: &[u8],
+    public_inputs_bytes: &[u8],
+    proof_bytes: &[u8],
+) -> Result<bool, String> {
+    let vk = decode_verifying_key(vk_bytes)?;
+    let public_inputs: MembershipPublicInputsV2 = decode_public_inputs(
+        public_inputs_bytes,
+        |bytes: MembershipPublicInputsV2Bytes| {
+            bytes
+                .into_public_inputs_with_depth()
+                .map(|(public_inputs, _)| public_inputs)
+        },
+    )
+    .map_err(|err| format!("invalid public inputs: {err}"))?;
+    let proof: Proof<Bn254> =
+        proof_from_bytes(proof_bytes).map_err(|err| format!("invalid proof: {err}"))?;
+
+    membership::verify_membership_v2(&vk, &public_inputs, &proof)
+        .map_err(|err| format!("verification failed: {err}"))
+}
+
+fn decode_verifying_key(bytes: &[u8]) -> Result<VerifyingKey<Bn254>, String> {
+    decode_canonical(bytes, false)
+        .or_else(|_| decode_canonical(bytes, true))
+        .map_err(|err| format!("invalid verifying key: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify_membership_v2_bytes;
+    use ark_bn254::Fr;
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use ark_std::UniformRand;
+    use membership::{
+        fr_to_fixed_bytes, membership_instance_v2_bytes_zeroizing, prove_membership_v2,
+        setup_membership_with_depth_v2, MembershipInstanceV2Bytes, MEMBERSHIP_V2_DEFAULT_CTX_HASH,
+    };
+
+    #[test]
+    fn verifies_v2_bytes_and_reports_malformed_input() {
+        let mut rng = StdRng::seed_from_u64(100);
+        let siblings: Vec<Vec<u8>> = (0..2)
+            .map(|_| fr_to_fixed_bytes(&Fr::rand(&mut rng)))
+            .collect();
+        let (instance_bytes, public_inputs) = membership_instance_v2_bytes_zeroizing(
+            fr_to_fixed_bytes(&Fr::from(5u64)),
+            fr_to_fixed_bytes(&Fr::from(6u64)),
+            &siblings,
+            &[false, true],
+            MEMBERSHIP_V2_DEFAULT_CTX_HASH,
+        )
+        .unwrap();
+        let instance: MembershipInstanceV2Bytes = bincode::deserialize(&instance_bytes).unwrap();
+        let (instance, _) = instance.into_instance_with_depth().unwrap();
+        let pk = setup_membership_with_depth_v2(&mut rng, 2).unwrap();
+        let proof = prove_membership_v2(&pk, &instance, &mut rng).unwrap();
+
+        let mut vk = Vec::new();
+        pk.vk.serialize_uncompressed(&mut vk).unwrap();
+        let mut proof_bytes = Vec::new();
+        proof.serialize_compressed(&mut proof_bytes).unwrap();
+        assert!(verify_membership_v2_bytes(&vk, &public_inputs, &proof_bytes).unwrap());
+
+        let mut other = Vec::new();
+        prove_membership_v2(&pk, &instance, &mut rng)
+            .unwrap()
+            .serialize_uncompressed(&mut other)
+            .unwrap();
+        other[0] ^= 1;
+        assert!(verify_membership_v2_bytes(&vk, &public_inputs, &other).is_err());
+
+        let mut wrong_root: membership::MembershipPublicInputsV2Bytes =
+            bincode::deserialize(&public_inputs).unwrap();
+        wrong_root.root = wrong_root.commitment;
+        let wrong_root = bincode::serialize(&wrong_root).unwrap();
+        assert!(!verify_membership_v2_bytes(&vk, &wrong_root, &proof_bytes).unwrap());
+
+        let err = verify_membership_v2_bytes(&vk[..vk.len() - 1], &public_inputs, &proof_bytes);
+        assert!(err.unwrap_err().starts_with("invalid verifying key"));
+        let err = verify_membership_v2_bytes(&vk, &proof_bytes, &public_inputs);
+        assert!(err.unwrap_err().starts_with("invalid public inputs"));
+        let err = verify_membership_v2_bytes(&vk, &public_inputs, &proof_bytes[..10]);
+        assert!(err.unwrap_err().starts_with("invalid proof"));
+    }
+}
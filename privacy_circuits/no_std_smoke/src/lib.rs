@@ -0,0 +1,143 @@
+#![cfg_attr(not(test), no_std)]
+
+use ark_bn254::Bn254;
+use ark_groth16::{Proof, VerifyingKey};
+use continuity::{verify_continuity_v2, ContinuityError, ContinuityPublicInputsV2};
+use membership::{verify_membership, MembershipError, MembershipPublicInputsBytes};
+use unlinkability::{verify_unlinkability_v2, UnlinkabilityError, UnlinkabilityPublicInputsV2};
+
+#[derive(Debug)]
+pub enum SmokeError {
+    Schema(MembershipError),
+    ContinuitySchema(ContinuityError),
+    UnlinkabilitySchema(UnlinkabilityError),
+    Verify,
+}
+
+// The path an embedded verifier takes: parse the V0 public-inputs schema, then
+// run the Groth16 check.
+pub fn verify_membership_bytes(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: MembershipPublicInputsBytes,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SmokeError> {
+    let public_inputs = public_inputs.into_public_inputs().map_err(SmokeError::Schema)?;
+    verify_membership(vk, &public_inputs, proof).map_err(|_| SmokeError::Verify)
+}
+
+pub fn verify_continuity_v2_bytes(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: ContinuityPublicInputsV2,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SmokeError> {
+    let public_inputs = public_inputs
+        .into_public_inputs()
+        .map_err(SmokeError::ContinuitySchema)?;
+    verify_continuity_v2(vk, &public_inputs, proof).map_err(|_| SmokeError::Verify)
+}
+
+pub fn verify_unlinkability_v2_bytes(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: UnlinkabilityPublicInputsV2,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SmokeError> {
+    let public_inputs = public_inputs
+        .into_public_inputs()
+        .map_err(SmokeError::UnlinkabilitySchema)?;
+    verify_unlinkability_v2(vk, &public_inputs, proof).map_err(|_| SmokeError::Verify)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        verify_continuity_v2_bytes, verify_membership_bytes, verify_unlinkability_v2_bytes,
+        SmokeError,
+    };
+    use ark_bn254::Fr;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use membership::{
+        commitment_hash, fr_to_fixed_bytes, node_hash, poseidon_params, prove_membership,
+        root_from_witness, setup_membership_with_seed, MembershipInstance,
+        MembershipPublicInputs, MembershipPublicInputsBytes, MembershipWitness,
+    };
+
+    #[test]
+    fn verifies_membership_without_std() {
+        let params = poseidon_params::<Fr>();
+        let identity = Fr::from(21u64);
+        let blinding = Fr::from(22u64);
+        let commitment = commitment_hash(&params, identity, blinding);
+        let witness = MembershipWitness {
+            identity_scalar: identity,
+            blinding,
+            merkle_path: vec![(node_hash(&params, commitment, Fr::from(100u64)), false)],
+        };
+        let root = root_from_witness(&params, &witness);
+        let instance = MembershipInstance {
+            public_inputs: MembershipPublicInputs { root, commitment },
+            witness,
+        };
+
+        let pk = setup_membership_with_seed([7u8; 32], 1).unwrap();
+        let mut rng = StdRng::seed_from_u64(105);
+        let proof = prove_membership(&pk, &instance, &mut rng).unwrap();
+        let bytes = MembershipPublicInputsBytes {
+            root: fr_to_fixed_bytes(&root),
+            commitment: fr_to_fixed_bytes(&commitment),
+        };
+        assert!(verify_membership_bytes(&pk.vk, bytes.clone(), &proof).unwrap());
+
+        let tampered = MembershipPublicInputsBytes {
+            commitment: fr_to_fixed_bytes(&(commitment + Fr::from(1u64))),
+            ..bytes
+        };
+        assert!(!verify_membership_bytes(&pk.vk, tampered, &proof).unwrap());
+
+        let empty = MembershipPublicInputsBytes {
+            root: vec![],
+            commitment: vec![],
+        };
+        assert!(matches!(
+            verify_membership_bytes(&pk.vk, empty, &proof),
+            Err(SmokeError::Schema(_))
+        ));
+    }
+
+    #[test]
+    fn verifies_continuity_and_unlinkability_without_std() {
+        let id = Fr::from(31u64);
+        let ctx_hash = Fr::from(33u64);
+        let mut rng = StdRng::seed_from_u64(106);
+
+        let (instance, public_inputs) =
+            continuity::schema::build_instance_v2(id, Fr::from(32u64), Fr::from(34u64), ctx_hash);
+        let pk = continuity::setup_continuity_v2_with_seed([8u8; 32]).unwrap();
+        let proof =
+            continuity::prove_continuity_v2(&pk, &instance.into_instance().unwrap(), &mut rng)
+                .unwrap();
+        assert!(verify_continuity_v2_bytes(&pk.vk, public_inputs.clone(), &proof).unwrap());
+        let mut bad = public_inputs;
+        bad.domain_sep[0] ^= 1;
+        assert!(matches!(
+            verify_continuity_v2_bytes(&pk.vk, bad, &proof),
+            Err(SmokeError::ContinuitySchema(_))
+        ));
+
+        let (instance, public_inputs) =
+            unlinkability::build_instance_v2(id, Fr::from(35u64), ctx_hash);
+        let pk = unlinkability::setup_unlinkability_v2_with_seed([9u8; 32]).unwrap();
+        let proof = unlinkability::prove_unlinkability_v2(
+            &pk,
+            &instance.into_instance().unwrap(),
+            &mut rng,
+        )
+        .unwrap();
+        assert!(verify_unlinkability_v2_bytes(&pk.vk, public_inputs.clone(), &proof).unwrap());
+        let mut bad = public_inputs;
+        bad.domain_sep[0] ^= 1;
+        assert!(matches!(
+            verify_unlinkability_v2_bytes(&pk.vk, bad, &proof),
+            Err(SmokeError::UnlinkabilitySchema(_))
+        ));
+    }
+}
@@ -0,0 +1,36 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use membership::{poseidon_hash_var, poseidon_params};
+
+// Proves nullifier = H(domain_app, ctx_hash, id) for a private id. Public inputs:
+// [nullifier, ctx_hash]. `domain_app` is a circuit constant rather than an input,
+// so it is fixed when the keys are generated and a key pair only accepts
+// nullifiers of its own application.
+#[derive(Clone, Debug, Default)]
+pub struct NullifierCircuit<F: PrimeField> {
+    pub domain_app: F,
+    pub nullifier: Option<F>,
+    pub ctx_hash: Option<F>,
+    pub id: Option<F>,
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for NullifierCircuit<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let params = poseidon_params::<F>();
+
+        let nullifier = FpVar::new_input(cs.clone(), || {
+            self.nullifier.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let ctx_hash =
+            FpVar::new_input(cs.clone(), || self.ctx_hash.ok_or(SynthesisError::AssignmentMissing))?;
+        let id =
+            FpVar::new_witness(cs.clone(), || self.id.ok_or(SynthesisError::AssignmentMissing))?;
+
+        let domain_app = FpVar::constant(self.domain_app);
+        let computed = poseidon_hash_var(cs, &params, &[domain_app, ctx_hash, id])?;
+        computed.enforce_equal(&nullifier)?;
+        Ok(())
+    }
+}
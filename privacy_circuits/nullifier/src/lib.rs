@@ -0,0 +1,149 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_relations::r1cs::SynthesisError;
+use ark_sponge::poseidon::{PoseidonConfig, PoseidonSponge};
+use ark_sponge::CryptographicSponge;
+use ark_std::rand::RngCore;
+
+pub mod circuit;
+pub mod membership_v2;
+
+pub use circuit::NullifierCircuit;
+pub use membership_v2::{
+    build_membership_nullifier_circuit_v2, build_membership_nullifier_instance_v2,
+    membership_nullifier_v2, nullifier_domain_sep_v2_fr, prove_membership_nullifier_v2,
+    setup_membership_nullifier_v2, verify_membership_nullifier_v2, MembershipNullifierCircuitV2,
+    MembershipNullifierInstanceV2, MembershipNullifierPublicInputsV2,
+    NULLIFIER_V2_DEFAULT_CTX_HASH, NULLIFIER_V2_DOMAIN_SEP,
+};
+pub use membership::{
+    fixed_bytes, fixed_bytes_allow_empty, fr_to_fixed_bytes, poseidon_params, proof_from_bytes,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NullifierPublicInputs {
+    pub nullifier: Fr,
+    pub ctx_hash: Fr,
+}
+
+#[derive(Clone, Debug)]
+pub struct NullifierInstance {
+    pub public_inputs: NullifierPublicInputs,
+    pub id: Fr,
+}
+
+// Application domains are 32-byte labels, read big-endian like the V2 domain
+// separators.
+pub fn app_domain_fr(domain_app: &[u8; 32]) -> Fr {
+    Fr::from_be_bytes_mod_order(domain_app)
+}
+
+pub fn nullifier_for(params: &PoseidonConfig<Fr>, domain_app: Fr, ctx_hash: Fr, id: Fr) -> Fr {
+    let mut sponge = PoseidonSponge::<Fr>::new(params);
+    sponge.absorb(&vec![domain_app, ctx_hash, id]);
+    sponge.squeeze_field_elements(1)[0]
+}
+
+pub fn build_instance(domain_app: Fr, ctx_hash: Fr, id: Fr) -> NullifierInstance {
+    let params = poseidon_params::<Fr>();
+    NullifierInstance {
+        public_inputs: NullifierPublicInputs {
+            nullifier: nullifier_for(&params, domain_app, ctx_hash, id),
+            ctx_hash,
+        },
+        id,
+    }
+}
+
+pub fn build_circuit(domain_app: Fr, instance: &NullifierInstance) -> NullifierCircuit<Fr> {
+    NullifierCircuit {
+        domain_app,
+        nullifier: Some(instance.public_inputs.nullifier),
+        ctx_hash: Some(instance.public_inputs.ctx_hash),
+        id: Some(instance.id),
+    }
+}
+
+pub fn setup_nullifier<R: RngCore>(
+    rng: &mut R,
+    domain_app: Fr,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let zero = Fr::from(0u64);
+    let circuit = NullifierCircuit::<Fr> {
+        domain_app,
+        nullifier: Some(zero),
+        ctx_hash: Some(zero),
+        id: Some(zero),
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+// `domain_app` must be the one the proving key was generated for.
+pub fn prove_nullifier<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    domain_app: Fr,
+    instance: &NullifierInstance,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    Groth16::<Bn254>::create_random_proof_with_reduction(
+        build_circuit(domain_app, instance),
+        pk,
+        rng,
+    )
+}
+
+pub fn verify_nullifier(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &NullifierPublicInputs,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    let pvk = prepare_verifying_key(vk);
+    let inputs = vec![public_inputs.nullifier, public_inputs.ctx_hash];
+    Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        app_domain_fr, build_circuit, build_instance, nullifier_for, poseidon_params,
+        prove_nullifier, setup_nullifier, verify_nullifier,
+    };
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn app_domains_separate_nullifiers_and_keys() {
+        let params = poseidon_params::<Fr>();
+        let domain_a = app_domain_fr(b"NULLIFIER_APP_A_________________");
+        let domain_b = app_domain_fr(b"NULLIFIER_APP_B_________________");
+        let id = Fr::from(71u64);
+        let ctx_hash = Fr::from(72u64);
+
+        let nullifier_a = nullifier_for(&params, domain_a, ctx_hash, id);
+        let nullifier_b = nullifier_for(&params, domain_b, ctx_hash, id);
+        assert_ne!(nullifier_a, nullifier_b);
+        assert_eq!(nullifier_a, nullifier_for(&params, domain_a, ctx_hash, id));
+
+        let instance = build_instance(domain_a, ctx_hash, id);
+        assert_eq!(instance.public_inputs.nullifier, nullifier_a);
+        // An app-A nullifier is not derivable under app B's domain.
+        for (domain, expected) in [(domain_a, true), (domain_b, false)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            build_circuit(domain, &instance)
+                .generate_constraints(cs.clone())
+                .unwrap();
+            assert_eq!(cs.is_satisfied().unwrap(), expected);
+        }
+
+        // Identically seeded setups: the domain constant is all that differs.
+        let pk_a = setup_nullifier(&mut StdRng::seed_from_u64(94), domain_a).unwrap();
+        let pk_b = setup_nullifier(&mut StdRng::seed_from_u64(94), domain_b).unwrap();
+        let mut rng = StdRng::seed_from_u64(95);
+        let proof = prove_nullifier(&pk_a, domain_a, &instance, &mut rng).unwrap();
+
+        assert!(verify_nullifier(&pk_a.vk, &instance.public_inputs, &proof).unwrap());
+        assert!(!verify_nullifier(&pk_b.vk, &instance.public_inputs, &proof).unwrap());
+    }
+}
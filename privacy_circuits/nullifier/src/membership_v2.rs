@@ -0,0 +1,279 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use ark_sponge::poseidon::PoseidonConfig;
+use ark_std::rand::RngCore;
+use membership::{
+    commitment_hash, merkle_root_var, poseidon_hash_var, poseidon_params, root_from_witness_v2,
+    MembershipWitnessV2, DOMAIN_COMMITMENT, MEMBERSHIP_V2_DOMAIN_SEP,
+};
+
+use crate::nullifier_for;
+
+pub const NULLIFIER_V2_DOMAIN_SEP: [u8; 32] = *b"SNARK_NULLIFIER_V2______________";
+pub const NULLIFIER_V2_DEFAULT_CTX_HASH: [u8; 32] = *b"NULLIFIER_CTX_V2________________";
+
+// Proves the hidden commitment H(1, identity, blinding) sits in a membership V2
+// tree and reveals nullifier = H(domain_sep, external_nullifier, identity).
+// Public inputs: [root, nullifier, external_nullifier, domain_sep, ctx_hash].
+// Leaves are hashed exactly as in MembershipCircuitV2, so the same tree serves
+// both statements; the commitment itself stays private. One identity yields one
+// nullifier per external_nullifier whatever blinding or tree it proves against.
+#[derive(Clone, Debug, Default)]
+pub struct MembershipNullifierCircuitV2<F: PrimeField> {
+    pub root: Option<F>,
+    pub nullifier: Option<F>,
+    pub external_nullifier: Option<F>,
+    pub domain_sep: Option<F>,
+    pub ctx_hash: Option<F>,
+    pub identity_scalar: Option<F>,
+    pub blinding: Option<F>,
+    pub expected_depth: usize,
+    pub merkle_path: Vec<(Option<F>, Option<bool>)>,
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for MembershipNullifierCircuitV2<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        if self.expected_depth == 0 || self.merkle_path.len() != self.expected_depth {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let params = poseidon_params::<F>();
+
+        let root =
+            FpVar::new_input(cs.clone(), || self.root.ok_or(SynthesisError::AssignmentMissing))?;
+        let nullifier = FpVar::new_input(cs.clone(), || {
+            self.nullifier.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let external_nullifier = FpVar::new_input(cs.clone(), || {
+            self.external_nullifier.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let domain_sep = FpVar::new_input(cs.clone(), || {
+            self.domain_sep.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let ctx_hash =
+            FpVar::new_input(cs.clone(), || self.ctx_hash.ok_or(SynthesisError::AssignmentMissing))?;
+
+        let identity_scalar = FpVar::new_witness(cs.clone(), || {
+            self.identity_scalar.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let domain_sep_const = FpVar::constant(F::from_be_bytes_mod_order(
+            &NULLIFIER_V2_DOMAIN_SEP,
+        ));
+        domain_sep.enforce_equal(&domain_sep_const)?;
+
+        let computed_nullifier = poseidon_hash_var(
+            cs.clone(),
+            &params,
+            &[domain_sep, external_nullifier, identity_scalar.clone()],
+        )?;
+        computed_nullifier.enforce_equal(&nullifier)?;
+
+        let domain_commitment = FpVar::constant(F::from(DOMAIN_COMMITMENT));
+        let commitment = poseidon_hash_var(
+            cs.clone(),
+            &params,
+            &[domain_commitment, identity_scalar, blinding],
+        )?;
+
+        let membership_domain_sep = FpVar::constant(F::from_be_bytes_mod_order(
+            &MEMBERSHIP_V2_DOMAIN_SEP,
+        ));
+        let leaf =
+            poseidon_hash_var(cs.clone(), &params, &[membership_domain_sep, ctx_hash, commitment])?;
+        let computed_root = merkle_root_var(cs, &params, leaf, self.merkle_path)?;
+        computed_root.enforce_equal(&root)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MembershipNullifierPublicInputsV2 {
+    pub root: Fr,
+    pub nullifier: Fr,
+    pub external_nullifier: Fr,
+    pub domain_sep: Fr,
+    pub ctx_hash: Fr,
+}
+
+#[derive(Clone, Debug)]
+pub struct MembershipNullifierInstanceV2 {
+    pub public_inputs: MembershipNullifierPublicInputsV2,
+    pub witness: MembershipWitnessV2,
+}
+
+pub fn nullifier_domain_sep_v2_fr() -> Fr {
+    Fr::from_be_bytes_mod_order(&NULLIFIER_V2_DOMAIN_SEP)
+}
+
+pub fn membership_nullifier_v2(
+    params: &PoseidonConfig<Fr>,
+    external_nullifier: Fr,
+    identity_scalar: Fr,
+) -> Fr {
+    nullifier_for(params, nullifier_domain_sep_v2_fr(), external_nullifier, identity_scalar)
+}
+
+// Derives the root from the witness path, so the result only verifies if the
+// caller's tree was built with the membership V2 leaf hash under `ctx_hash`.
+pub fn build_membership_nullifier_instance_v2(
+    witness: MembershipWitnessV2,
+    external_nullifier: Fr,
+    ctx_hash: Fr,
+) -> MembershipNullifierInstanceV2 {
+    let params = poseidon_params::<Fr>();
+    let membership_domain_sep = Fr::from_be_bytes_mod_order(&MEMBERSHIP_V2_DOMAIN_SEP);
+    MembershipNullifierInstanceV2 {
+        public_inputs: MembershipNullifierPublicInputsV2 {
+            root: root_from_witness_v2(&params, &witness, membership_domain_sep, ctx_hash),
+            nullifier: membership_nullifier_v2(
+                &params,
+                external_nullifier,
+                witness.identity_scalar,
+            ),
+            external_nullifier,
+            domain_sep: nullifier_domain_sep_v2_fr(),
+            ctx_hash,
+        },
+        witness,
+    }
+}
+
+pub fn build_membership_nullifier_circuit_v2(
+    instance: &MembershipNullifierInstanceV2,
+) -> MembershipNullifierCircuitV2<Fr> {
+    MembershipNullifierCircuitV2::<Fr> {
+        root: Some(instance.public_inputs.root),
+        nullifier: Some(instance.public_inputs.nullifier),
+        external_nullifier: Some(instance.public_inputs.external_nullifier),
+        domain_sep: Some(instance.public_inputs.domain_sep),
+        ctx_hash: Some(instance.public_inputs.ctx_hash),
+        identity_scalar: Some(instance.witness.identity_scalar),
+        blinding: Some(instance.witness.blinding),
+        expected_depth: instance.witness.merkle_path.len(),
+        merkle_path: instance
+            .witness
+            .merkle_path
+            .iter()
+            .map(|(sibling, is_left)| (Some(*sibling), Some(*is_left)))
+            .collect(),
+    }
+}
+
+pub fn setup_membership_nullifier_v2<R: RngCore>(
+    rng: &mut R,
+    depth: usize,
+) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let params = poseidon_params::<Fr>();
+    let zero = Fr::from(0u64);
+    let commitment = commitment_hash(&params, zero, zero);
+    let circuit = MembershipNullifierCircuitV2::<Fr> {
+        root: Some(zero),
+        nullifier: Some(zero),
+        external_nullifier: Some(zero),
+        domain_sep: Some(nullifier_domain_sep_v2_fr()),
+        ctx_hash: Some(zero),
+        identity_scalar: Some(zero),
+        blinding: Some(zero),
+        expected_depth: depth,
+        merkle_path: vec![(Some(commitment), Some(false)); depth],
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+pub fn prove_membership_nullifier_v2<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &MembershipNullifierInstanceV2,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    let circuit = build_membership_nullifier_circuit_v2(instance);
+    Groth16::<Bn254>::create_random_proof_with_reduction(circuit, pk, rng)
+}
+
+pub fn verify_membership_nullifier_v2(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &MembershipNullifierPublicInputsV2,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    let pvk = prepare_verifying_key(vk);
+    let inputs = vec![
+        public_inputs.root,
+        public_inputs.nullifier,
+        public_inputs.external_nullifier,
+        public_inputs.domain_sep,
+        public_inputs.ctx_hash,
+    ];
+    Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_membership_nullifier_circuit_v2, build_membership_nullifier_instance_v2,
+        membership_nullifier_v2, prove_membership_nullifier_v2, setup_membership_nullifier_v2,
+        verify_membership_nullifier_v2,
+    };
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use membership::{node_hash, poseidon_params, MembershipWitnessV2};
+
+    fn witness(blinding: u64) -> MembershipWitnessV2 {
+        let params = poseidon_params::<Fr>();
+        MembershipWitnessV2 {
+            identity_scalar: Fr::from(31u64),
+            blinding: Fr::from(blinding),
+            merkle_path: vec![
+                (node_hash(&params, Fr::from(1u64), Fr::from(2u64)), false),
+                (node_hash(&params, Fr::from(3u64), Fr::from(4u64)), true),
+            ],
+        }
+    }
+
+    #[test]
+    fn nullifier_is_bound_to_member_and_external_nullifier() {
+        let params = poseidon_params::<Fr>();
+        let ctx_hash = Fr::from(40u64);
+        let vote_a = Fr::from(41u64);
+        let vote_b = Fr::from(42u64);
+
+        let instance = build_membership_nullifier_instance_v2(witness(32), vote_a, ctx_hash);
+        assert_eq!(
+            instance.public_inputs.nullifier,
+            membership_nullifier_v2(&params, vote_a, Fr::from(31u64))
+        );
+        // Re-blinding does not give a member a second nullifier for the same vote.
+        let reblinded = build_membership_nullifier_instance_v2(witness(33), vote_a, ctx_hash);
+        assert_eq!(reblinded.public_inputs.nullifier, instance.public_inputs.nullifier);
+        let other_vote = build_membership_nullifier_instance_v2(witness(32), vote_b, ctx_hash);
+        assert_ne!(other_vote.public_inputs.nullifier, instance.public_inputs.nullifier);
+
+        let mut rng = StdRng::seed_from_u64(67);
+        let pk = setup_membership_nullifier_v2(&mut rng, 2).unwrap();
+        let proof = prove_membership_nullifier_v2(&pk, &instance, &mut rng).unwrap();
+        assert!(verify_membership_nullifier_v2(&pk.vk, &instance.public_inputs, &proof).unwrap());
+
+        let mut moved = instance.public_inputs.clone();
+        moved.external_nullifier = vote_b;
+        moved.nullifier = other_vote.public_inputs.nullifier;
+        assert!(!verify_membership_nullifier_v2(&pk.vk, &moved, &proof).unwrap());
+
+        let mut other_ctx = instance.public_inputs.clone();
+        other_ctx.ctx_hash = Fr::from(43u64);
+        assert!(!verify_membership_nullifier_v2(&pk.vk, &other_ctx, &proof).unwrap());
+
+        let mut forged = instance.clone();
+        forged.public_inputs.nullifier = Fr::from(44u64);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        build_membership_nullifier_circuit_v2(&forged)
+            .generate_constraints(cs.clone())
+            .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+}
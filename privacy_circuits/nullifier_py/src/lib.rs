@@ -0,0 +1,65 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::VerifyingKey;
+use ark_serialize::CanonicalDeserialize;
+use nullifier::{
+    fr_to_fixed_bytes, nullifier_for as nullifier_for_inner, poseidon_params, proof_from_bytes,
+    verify_nullifier as verify_nullifier_inner, NullifierPublicInputs,
+};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+#[pyfunction]
+fn nullifier_for(
+    py: Python<'_>,
+    domain_app: Vec<u8>,
+    ctx_hash: Vec<u8>,
+    id: Vec<u8>,
+) -> PyResult<Py<PyBytes>> {
+    let domain_app = Fr::from_be_bytes_mod_order(&fixed_bytes("domain_app", domain_app)?);
+    let ctx_hash = Fr::from_be_bytes_mod_order(&fixed_bytes_allow_empty("ctx_hash", ctx_hash)?);
+    let id = Fr::from_be_bytes_mod_order(&fixed_bytes("id", id)?);
+
+    let params = poseidon_params::<Fr>();
+    let nullifier = nullifier_for_inner(&params, domain_app, ctx_hash, id);
+    Ok(PyBytes::new(py, &fr_to_fixed_bytes(&nullifier)).into())
+}
+
+#[pyfunction]
+fn verify_nullifier_bytes(
+    vk_bytes: Vec<u8>,
+    nullifier: Vec<u8>,
+    ctx_hash: Vec<u8>,
+    proof_bytes: Vec<u8>,
+) -> PyResult<bool> {
+    let vk = VerifyingKey::<Bn254>::deserialize_uncompressed(&mut std::io::Cursor::new(vk_bytes))
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let proof =
+        proof_from_bytes(&proof_bytes).map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let public_inputs = NullifierPublicInputs {
+        nullifier: Fr::from_be_bytes_mod_order(&fixed_bytes("nullifier", nullifier)?),
+        ctx_hash: Fr::from_be_bytes_mod_order(&fixed_bytes_allow_empty("ctx_hash", ctx_hash)?),
+    };
+
+    verify_nullifier_inner(&vk, &public_inputs, &proof)
+        .map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+#[pymodule]
+fn nullifier_py(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(nullifier_for, m)?)?;
+    m.add_function(wrap_pyfunction!(verify_nullifier_bytes, m)?)?;
+    Ok(())
+}
+
+// domain_app and id must be supplied explicitly; b"" for ctx_hash means "no context".
+fn fixed_bytes(label: &str, bytes: Vec<u8>) -> PyResult<[u8; 32]> {
+    nullifier::fixed_bytes(label, &bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+fn fixed_bytes_allow_empty(label: &str, bytes: Vec<u8>) -> PyResult<[u8; 32]> {
+    nullifier::fixed_bytes_allow_empty(label, &bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))
+}
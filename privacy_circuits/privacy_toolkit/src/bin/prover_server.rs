@@ -0,0 +1,98 @@
+use privacy_toolkit::server::{error_response, VerifierSet, MAX_REQUEST_BYTES};
+use std::env;
+use std::io::Read;
+use tiny_http::{Header, Response, Server};
+
+const DEFAULT_ADDR: &str = "127.0.0.1:8080";
+
+fn main() {
+    let args = match parse_args() {
+        Some(args) => args,
+        None => {
+            eprintln!(
+                "Usage: prover_server --membership-vk <path> --continuity-vk <path> --unlinkability-vk <path> [--addr <host:port>] [--compressed]"
+            );
+            std::process::exit(1);
+        }
+    };
+
+    // Keys are read and prepared once; every request reuses them.
+    let verifiers = match VerifierSet::load(
+        &args.membership_vk,
+        &args.continuity_vk,
+        &args.unlinkability_vk,
+        args.compressed,
+    ) {
+        Ok(verifiers) => verifiers,
+        Err(err) => {
+            eprintln!("failed to load verifying keys: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    let server = match Server::http(&args.addr) {
+        Ok(server) => server,
+        Err(err) => {
+            eprintln!("failed to listen on {}: {err}", args.addr);
+            std::process::exit(1);
+        }
+    };
+    eprintln!("listening on {}", args.addr);
+
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+        .expect("static header is valid");
+    // Requests are handled one at a time on this thread.
+    for mut request in server.incoming_requests() {
+        let mut body = Vec::new();
+        let read = request
+            .as_reader()
+            .take(MAX_REQUEST_BYTES + 1)
+            .read_to_end(&mut body);
+        let (status, json) = match read {
+            Ok(_) => verifiers.handle(request.method().as_str(), request.url(), &body),
+            Err(err) => error_response(400, format!("failed to read body: {err}")),
+        };
+        let response = Response::from_string(json)
+            .with_status_code(status)
+            .with_header(content_type.clone());
+        if let Err(err) = request.respond(response) {
+            eprintln!("failed to send response: {err}");
+        }
+    }
+}
+
+struct Args {
+    membership_vk: String,
+    continuity_vk: String,
+    unlinkability_vk: String,
+    addr: String,
+    compressed: bool,
+}
+
+fn parse_args() -> Option<Args> {
+    let mut membership_vk = None;
+    let mut continuity_vk = None;
+    let mut unlinkability_vk = None;
+    let mut addr = DEFAULT_ADDR.to_string();
+    let mut compressed = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--membership-vk" => membership_vk = args.next(),
+            "--continuity-vk" => continuity_vk = args.next(),
+            "--unlinkability-vk" => unlinkability_vk = args.next(),
+            "--addr" => addr = args.next()?,
+            "--compressed" => compressed = true,
+            _ => return None,
+        }
+    }
+
+    Some(Args {
+        membership_vk: membership_vk?,
+        continuity_vk: continuity_vk?,
+        unlinkability_vk: unlinkability_vk?,
+        addr,
+        compressed,
+    })
+}
@@ -0,0 +1,204 @@
+use ::continuity::schema::build_instance_v2 as build_continuity_instance_v2;
+use ::continuity::{prove_continuity_v2, setup_continuity_v2, CONTINUITY_V2_DEFAULT_CTX_HASH};
+use ::membership::io::{write_owner_only, write_uncompressed};
+use ::membership::tree::MAX_TREE_DEPTH;
+use ::membership::{
+    fr_to_fixed_bytes, membership_instance_v2_bytes_zeroizing, prove_membership_v2,
+    setup_membership_with_depth_v2, MembershipInstanceV2Bytes, MEMBERSHIP_V2_DEFAULT_CTX_HASH,
+};
+use ::unlinkability::{
+    build_instance_v2 as build_unlinkability_instance_v2, prove_unlinkability_v2,
+    setup_unlinkability_v2, UNLINKABILITY_V2_DEFAULT_CTX_HASH,
+};
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{Proof, VerifyingKey};
+use ark_std::rand::{rngs::StdRng, Rng, SeedableRng};
+use ark_std::UniformRand;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+pub const DEMO_STATEMENTS: [&str; 3] = ["membership", "continuity", "unlinkability"];
+
+// Writes `<statement>_{vk,instance,public_inputs,proof}.bin` for each of the three
+// V2 statements plus a README.txt into `out_dir`. Keys, witnesses and proofs all
+// come from one StdRng seeded with `seed`, so the same arguments reproduce the
+// same bytes. One identity and blinding back all three statements: the
+// membership commitment, the unlinkability commitment and the first continuity
+// opening belong to the same credential. Proving keys are not written.
+pub fn generate_demo(out_dir: &str, depth: usize, seed: u64) -> Result<(), String> {
+    if depth == 0 || depth > MAX_TREE_DEPTH {
+        return Err(format!("depth must be in 1..={MAX_TREE_DEPTH}, got {depth}"));
+    }
+    fs::create_dir_all(out_dir).map_err(|err| format!("failed to create {out_dir}: {err}"))?;
+    let dir = Path::new(out_dir);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let identity = Fr::rand(&mut rng);
+    let blinding = Fr::rand(&mut rng);
+
+    let siblings: Vec<Vec<u8>> = (0..depth)
+        .map(|_| fr_to_fixed_bytes(&Fr::rand(&mut rng)))
+        .collect();
+    let is_left: Vec<bool> = (0..depth).map(|_| rng.gen()).collect();
+    let (instance_bytes, public_inputs_bytes) = membership_instance_v2_bytes_zeroizing(
+        fr_to_fixed_bytes(&identity),
+        fr_to_fixed_bytes(&blinding),
+        &siblings,
+        &is_left,
+        MEMBERSHIP_V2_DEFAULT_CTX_HASH,
+    )?;
+    let instance: MembershipInstanceV2Bytes =
+        bincode::deserialize(&instance_bytes).map_err(|err| err.to_string())?;
+    let (instance, _) = instance
+        .into_instance_with_depth()
+        .map_err(|err| err.to_string())?;
+    let pk = setup_membership_with_depth_v2(&mut rng, depth).map_err(|err| err.to_string())?;
+    let proof = prove_membership_v2(&pk, &instance, &mut rng).map_err(|err| err.to_string())?;
+    write_instance(dir, "membership", &instance_bytes)?;
+    write_file(dir, "membership_public_inputs", &public_inputs_bytes)?;
+    write_artifacts(dir, "membership", &pk.vk, &proof)?;
+
+    let r2 = Fr::rand(&mut rng);
+    let ctx_hash = Fr::from_be_bytes_mod_order(&CONTINUITY_V2_DEFAULT_CTX_HASH);
+    let (instance, public_inputs) = build_continuity_instance_v2(identity, blinding, r2, ctx_hash);
+    let parsed = instance.clone().into_instance().map_err(|err| err.to_string())?;
+    let pk = setup_continuity_v2(&mut rng).map_err(|err| err.to_string())?;
+    let proof = prove_continuity_v2(&pk, &parsed, &mut rng).map_err(|err| err.to_string())?;
+    write_instance(dir, "continuity", &to_bincode(&instance)?)?;
+    write_bincode(dir, "continuity_public_inputs", &public_inputs)?;
+    write_artifacts(dir, "continuity", &pk.vk, &proof)?;
+
+    let ctx_hash = Fr::from_be_bytes_mod_order(&UNLINKABILITY_V2_DEFAULT_CTX_HASH);
+    let (instance, public_inputs) = build_unlinkability_instance_v2(identity, blinding, ctx_hash);
+    let parsed = instance.clone().into_instance().map_err(|err| err.to_string())?;
+    let pk = setup_unlinkability_v2(&mut rng).map_err(|err| err.to_string())?;
+    let proof = prove_unlinkability_v2(&pk, &parsed, &mut rng).map_err(|err| err.to_string())?;
+    write_instance(dir, "unlinkability", &to_bincode(&instance)?)?;
+    write_bincode(dir, "unlinkability_public_inputs", &public_inputs)?;
+    write_artifacts(dir, "unlinkability", &pk.vk, &proof)?;
+
+    let readme = dir.join("README.txt");
+    fs::write(&readme, demo_readme(depth, seed))
+        .map_err(|err| format!("failed to write {}: {err}", readme.display()))
+}
+
+fn demo_readme(depth: usize, seed: u64) -> String {
+    let mut out = format!(
+        "Demo dataset generated with seed {seed}; the membership tree has depth {depth}.\n\
+         All statements use schema V2 and share one identity and blinding.\n\n"
+    );
+    for statement in DEMO_STATEMENTS {
+        out.push_str(&format!(
+            "{statement}_vk.bin             verifying key (arkworks, uncompressed)\n\
+             {statement}_instance.bin       full instance incl. witness (bincode); keep private\n\
+             {statement}_public_inputs.bin  public inputs (bincode)\n\
+             {statement}_proof.bin          Groth16 proof (arkworks, uncompressed)\n\n"
+        ));
+    }
+    out.push_str(
+        "Verify with e.g.\n  verify_membership --schema v2 --vk membership_vk.bin \
+         --public-inputs membership_public_inputs.bin --proof membership_proof.bin\n",
+    );
+    out
+}
+
+fn write_artifacts(
+    dir: &Path,
+    statement: &str,
+    vk: &VerifyingKey<Bn254>,
+    proof: &Proof<Bn254>,
+) -> Result<(), String> {
+    write_uncompressed(&artifact_path(dir, &format!("{statement}_vk")), vk)?;
+    write_uncompressed(&artifact_path(dir, &format!("{statement}_proof")), proof)
+}
+
+// The instance files carry the identity and blinding, so they are owner-only.
+fn write_instance(dir: &Path, statement: &str, bytes: &[u8]) -> Result<(), String> {
+    let path = artifact_path(dir, &format!("{statement}_instance"));
+    write_owner_only(&path, bytes).map_err(|err| format!("failed to write {path}: {err}"))
+}
+
+fn write_bincode<T: Serialize>(dir: &Path, name: &str, value: &T) -> Result<(), String> {
+    write_file(dir, name, &to_bincode(value)?)
+}
+
+fn to_bincode<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    bincode::serialize(value).map_err(|err| err.to_string())
+}
+
+fn write_file(dir: &Path, name: &str, bytes: &[u8]) -> Result<(), String> {
+    let path = artifact_path(dir, name);
+    fs::write(&path, bytes).map_err(|err| format!("failed to write {path}: {err}"))
+}
+
+fn artifact_path(dir: &Path, name: &str) -> String {
+    dir.join(format!("{name}.bin")).to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_demo, DEMO_STATEMENTS};
+    use ::continuity::{verify_continuity_v2, ContinuityPublicInputsV2};
+    use ::membership::io::{read_proof_capped, read_verifying_key_capped};
+    use ::membership::{verify_membership_v2, MembershipPublicInputsV2Bytes};
+    use ::unlinkability::{verify_unlinkability_v2, UnlinkabilityPublicInputsV2};
+    use std::fs;
+
+    const CAP: u64 = 1 << 20;
+
+    #[test]
+    fn every_demo_proof_verifies_against_its_vk() {
+        let base = std::env::temp_dir().join(format!("toolkit-demo-{}", std::process::id()));
+        let out = base.join("a").to_string_lossy().into_owned();
+        generate_demo(&out, 3, 7).unwrap();
+
+        let path = |name: &str| format!("{out}/{name}");
+        let read = |name: &str| fs::read(path(name)).unwrap();
+        let vk = |statement: &str| {
+            read_verifying_key_capped(&path(&format!("{statement}_vk.bin")), CAP).unwrap()
+        };
+        let proof = |statement: &str| {
+            read_proof_capped(&path(&format!("{statement}_proof.bin")), CAP).unwrap()
+        };
+
+        let inputs: MembershipPublicInputsV2Bytes =
+            bincode::deserialize(&read("membership_public_inputs.bin")).unwrap();
+        let (inputs, depth) = inputs.into_public_inputs_with_depth().unwrap();
+        assert_eq!(depth, 3);
+        assert!(verify_membership_v2(&vk("membership"), &inputs, &proof("membership")).unwrap());
+
+        let inputs: ContinuityPublicInputsV2 =
+            bincode::deserialize(&read("continuity_public_inputs.bin")).unwrap();
+        let inputs = inputs.into_public_inputs().unwrap();
+        assert!(verify_continuity_v2(&vk("continuity"), &inputs, &proof("continuity")).unwrap());
+
+        let inputs: UnlinkabilityPublicInputsV2 =
+            bincode::deserialize(&read("unlinkability_public_inputs.bin")).unwrap();
+        let inputs = inputs.into_public_inputs().unwrap();
+        let unlinkability_vk = vk("unlinkability");
+        let unlinkability_proof = proof("unlinkability");
+        assert!(verify_unlinkability_v2(&unlinkability_vk, &inputs, &unlinkability_proof).unwrap());
+
+        let readme = String::from_utf8(read("README.txt")).unwrap();
+        for statement in DEMO_STATEMENTS {
+            assert!(readme.contains(&format!("{statement}_proof.bin")));
+        }
+        #[cfg(unix)]
+        for statement in DEMO_STATEMENTS {
+            use std::os::unix::fs::PermissionsExt;
+            let metadata = fs::metadata(path(&format!("{statement}_instance.bin"))).unwrap();
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        }
+
+        // Same seed, same bytes.
+        let again = base.join("b").to_string_lossy().into_owned();
+        generate_demo(&again, 3, 7).unwrap();
+        for statement in DEMO_STATEMENTS {
+            let name = format!("{statement}_proof.bin");
+            assert_eq!(read(&name), fs::read(format!("{again}/{name}")).unwrap());
+        }
+        assert!(generate_demo(&again, 0, 7).is_err());
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
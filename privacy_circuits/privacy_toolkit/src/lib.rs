@@ -0,0 +1,228 @@
+//! Single dependency for the three statements. Each statement crate is available
+//! under its own module and the shared Poseidon primitives live once under
+//! [`poseidon`], so there is one unambiguous path for every item.
+//!
+//! ```
+//! use ark_bn254::Fr;
+//! use ark_std::rand::{rngs::StdRng, SeedableRng};
+//! use privacy_toolkit::membership::{
+//!     prove_membership, setup_membership_with_depth, verify_membership, MembershipInstance,
+//!     MembershipPublicInputs, MembershipWitness, PoseidonMerkleTree,
+//! };
+//! use privacy_toolkit::poseidon::{commitment_hash, poseidon_params};
+//!
+//! let params = poseidon_params::<Fr>();
+//! let (identity, blinding) = (Fr::from(5u64), Fr::from(6u64));
+//! let commitment = commitment_hash(&params, identity, blinding);
+//! let tree = PoseidonMerkleTree::build(&[commitment], 1, &params).unwrap();
+//!
+//! let instance = MembershipInstance {
+//!     public_inputs: MembershipPublicInputs { root: tree.root(), commitment },
+//!     witness: MembershipWitness {
+//!         identity_scalar: identity,
+//!         blinding,
+//!         merkle_path: tree.path(0).unwrap(),
+//!     },
+//! };
+//! let mut rng = StdRng::seed_from_u64(80);
+//! let pk = setup_membership_with_depth(&mut rng, 1).unwrap();
+//! let proof = prove_membership(&pk, &instance, &mut rng).unwrap();
+//! assert!(verify_membership(&pk.vk, &instance.public_inputs, &proof).unwrap());
+//! ```
+
+pub use ::continuity::validate_constants as validate_continuity_constants;
+pub use ::membership::validate_constants as validate_membership_constants;
+pub use ::unlinkability::validate_constants as validate_unlinkability_constants;
+
+use ::continuity::CONTINUITY_STATEMENT_TYPE;
+use ::membership::{expected_public_inputs, MEMBERSHIP_STATEMENT_TYPE};
+use ::unlinkability::UNLINKABILITY_STATEMENT_TYPE;
+use ark_bn254::Bn254;
+use ark_groth16::VerifyingKey;
+
+pub mod demo;
+pub mod linkage;
+#[cfg(feature = "server")]
+pub mod server;
+
+pub use demo::generate_demo;
+pub use linkage::same_commitment_continuity_membership;
+
+pub mod membership {
+    pub use ::membership::*;
+}
+
+pub mod continuity {
+    pub use ::continuity::*;
+}
+
+pub mod unlinkability {
+    pub use ::unlinkability::*;
+}
+
+// Hashing and encoding shared by every statement. The statement crates re-export
+// some of these too; prefer this path.
+pub mod poseidon {
+    pub use ::membership::{
+        check_poseidon_params_hash, commitment_hash, commitment_hash_domain, fixed_bytes,
+        fixed_bytes_allow_empty, fr_from_u128, fr_from_u64_pair, fr_to_fixed_bytes, leaf_hash,
+        node_hash, poseidon_params, poseidon_params_hash, POSEIDON_PARAMS_HASH,
+    };
+}
+
+#[cfg(feature = "inject-bad-constant")]
+const INJECTED_DOMAIN_SEP: [u8; 32] = *b"INJECTED_snark_V0_______________";
+
+// Startup self-test over every domain separator, statement type and the pinned
+// Poseidon parameters across the statement crates, including the nullifier and
+// range crates. Domain separators must also stay distinct once reduced into the
+// field; see DOMAIN_SEP_CANONICAL_BYTES. This is the only place that sees every
+// crate's separators, so the distinctness check lives here.
+pub fn validate_crate_constants() -> Result<(), String> {
+    validate_membership_constants()?;
+    validate_continuity_constants()?;
+    validate_unlinkability_constants()?;
+    ::range::validate_constants()?;
+    ::membership::check_domain_seps_distinct(&[
+        ("MEMBERSHIP_V2_DOMAIN_SEP", &::membership::MEMBERSHIP_V2_DOMAIN_SEP),
+        ("SET_MEMBERSHIP_DOMAIN_SEP", &::membership::SET_MEMBERSHIP_DOMAIN_SEP),
+        ("CONTINUITY_V1_DOMAIN_SEP", &::continuity::CONTINUITY_V1_DOMAIN_SEP),
+        ("CONTINUITY_V2_DOMAIN_SEP", &::continuity::CONTINUITY_V2_DOMAIN_SEP),
+        ("UNLINKABILITY_V2_DOMAIN_SEP", &::unlinkability::UNLINKABILITY_V2_DOMAIN_SEP),
+        ("NULLIFIER_V2_DOMAIN_SEP", &::nullifier::NULLIFIER_V2_DOMAIN_SEP),
+        ("RANGE_V2_DOMAIN_SEP", &::range::RANGE_V2_DOMAIN_SEP),
+    ])?;
+
+    #[cfg(feature = "inject-bad-constant")]
+    ::membership::check_constant_form("INJECTED_DOMAIN_SEP", &INJECTED_DOMAIN_SEP, "INJECTED_")?;
+
+    let statement_types = [
+        ("membership", MEMBERSHIP_STATEMENT_TYPE),
+        ("unlinkability", UNLINKABILITY_STATEMENT_TYPE),
+        ("continuity", CONTINUITY_STATEMENT_TYPE),
+    ];
+    for (idx, (name, value)) in statement_types.iter().enumerate() {
+        if let Some((other, _)) = statement_types[..idx]
+            .iter()
+            .find(|(_, existing)| existing == value)
+        {
+            return Err(format!(
+                "statement type {value} is shared by {other} and {name}"
+            ));
+        }
+    }
+    Ok(())
+}
+
+// A circuit shape the crates can produce, tagged with its public-input count.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KnownStatement {
+    Membership(usize),
+    Unlinkability(usize),
+    Continuity(usize),
+}
+
+impl KnownStatement {
+    pub fn statement_type(&self) -> u16 {
+        match self {
+            KnownStatement::Membership(_) => MEMBERSHIP_STATEMENT_TYPE,
+            KnownStatement::Unlinkability(_) => UNLINKABILITY_STATEMENT_TYPE,
+            KnownStatement::Continuity(_) => CONTINUITY_STATEMENT_TYPE,
+        }
+    }
+}
+
+// Every known circuit with this VK's public-input count: membership V0/V1 take 2,
+// unlinkability V2 and continuity V1 take 3, membership V2 and continuity V2 take 4.
+pub fn candidate_statements(vk: &VerifyingKey<Bn254>) -> Vec<KnownStatement> {
+    match expected_public_inputs(vk) {
+        2 => vec![KnownStatement::Membership(2)],
+        3 => vec![KnownStatement::Unlinkability(3), KnownStatement::Continuity(3)],
+        4 => vec![KnownStatement::Membership(4), KnownStatement::Continuity(4)],
+        _ => Vec::new(),
+    }
+}
+
+// Identifies a VK from its shape alone. Counts shared by more than one circuit
+// return None; use `identify_vk_with_statement_type` to disambiguate.
+pub fn identify_vk(vk: &VerifyingKey<Bn254>) -> Option<KnownStatement> {
+    match candidate_statements(vk).as_slice() {
+        [only] => Some(*only),
+        _ => None,
+    }
+}
+
+pub fn identify_vk_with_statement_type(
+    vk: &VerifyingKey<Bn254>,
+    statement_type: u16,
+) -> Option<KnownStatement> {
+    candidate_statements(vk)
+        .into_iter()
+        .find(|candidate| candidate.statement_type() == statement_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn identify_vk_distinguishes_known_shapes() {
+        let mut rng = StdRng::seed_from_u64(73);
+        let membership_v1 = membership::setup_membership_with_depth(&mut rng, 1).unwrap().vk;
+        let membership_v2 = membership::setup_membership_with_depth_v2(&mut rng, 1).unwrap().vk;
+        let unlinkability = unlinkability::setup_unlinkability_v2(&mut rng).unwrap().vk;
+
+        assert_eq!(identify_vk(&membership_v1), Some(KnownStatement::Membership(2)));
+
+        // Three and four inputs are each shared by two circuits.
+        assert_eq!(identify_vk(&unlinkability), None);
+        assert_eq!(identify_vk(&membership_v2), None);
+        assert_eq!(candidate_statements(&unlinkability).len(), 2);
+
+        assert_eq!(
+            identify_vk_with_statement_type(&membership_v2, MEMBERSHIP_STATEMENT_TYPE),
+            Some(KnownStatement::Membership(4))
+        );
+        assert_eq!(
+            identify_vk_with_statement_type(&unlinkability, UNLINKABILITY_STATEMENT_TYPE),
+            Some(KnownStatement::Unlinkability(3))
+        );
+        assert_eq!(
+            identify_vk_with_statement_type(&membership_v2, UNLINKABILITY_STATEMENT_TYPE),
+            None
+        );
+    }
+
+    #[cfg(not(feature = "inject-bad-constant"))]
+    #[test]
+    fn crate_constants_are_valid() {
+        validate_crate_constants().unwrap();
+    }
+
+    #[cfg(feature = "inject-bad-constant")]
+    #[test]
+    fn injected_bad_constant_is_reported() {
+        let err = validate_crate_constants().unwrap_err();
+        assert_eq!(err, "INJECTED_DOMAIN_SEP has invalid byte 0x73 at offset 9");
+    }
+
+    #[test]
+    fn constant_form_rejects_malformed_values() {
+        assert!(membership::check_constant_form("X", b"SHORT", "SHORT").is_err());
+        let err = membership::check_constant_form(
+            "X",
+            b"MEMBERSHIP_CTX_V2_______________",
+            "SNARK_MEMBERSHIP",
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "X must start with SNARK_MEMBERSHIP");
+        let err = membership::check_constant_form(
+            "X",
+            b"MEMBERSHIP_CTX_V2______________Z",
+            "MEMBERSHIP",
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "X must be right-padded with '_'");
+    }
+}
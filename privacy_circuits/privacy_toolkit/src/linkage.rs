@@ -0,0 +1,45 @@
+use ::continuity::schema::ContinuityPublicInputs;
+use ::membership::{fr_to_fixed_bytes, MembershipPublicInputs};
+
+// Checks that a continuity proof's c1 and a membership proof's commitment are the
+// same credential, compared in canonical byte form. This is a linkage the
+// verifier performs over the two sets of public inputs after verifying each
+// proof; neither proof attests to it in zero knowledge.
+pub fn same_commitment_continuity_membership(
+    cont: &ContinuityPublicInputs,
+    memb: &MembershipPublicInputs,
+) -> bool {
+    fr_to_fixed_bytes(&cont.c1_hash) == fr_to_fixed_bytes(&memb.commitment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::same_commitment_continuity_membership;
+    use ::continuity::schema::ContinuityPublicInputs;
+    use ::membership::{commitment_hash, poseidon_params, MembershipPublicInputs};
+    use ark_bn254::Fr;
+
+    #[test]
+    fn linkage_compares_c1_with_membership_commitment() {
+        let params = poseidon_params::<Fr>();
+        let c1 = commitment_hash(&params, Fr::from(71u64), Fr::from(72u64));
+        let c2 = commitment_hash(&params, Fr::from(71u64), Fr::from(73u64));
+        let cont = ContinuityPublicInputs {
+            c1_hash: c1,
+            c2_hash: c2,
+            domain_sep: Fr::from(0u64),
+        };
+
+        let matching = MembershipPublicInputs {
+            root: Fr::from(1u64),
+            commitment: c1,
+        };
+        assert!(same_commitment_continuity_membership(&cont, &matching));
+
+        let other = MembershipPublicInputs {
+            root: Fr::from(1u64),
+            commitment: c2,
+        };
+        assert!(!same_commitment_continuity_membership(&cont, &other));
+    }
+}
@@ -0,0 +1,204 @@
+use ::continuity::{ContinuityPublicInputsV2, ContinuityVerifier};
+use ::membership::io::{
+    check_params_spec, decode_canonical, decode_public_inputs, read_verifying_key_as,
+    DEFAULT_MAX_PROOF_BYTES, DEFAULT_MAX_VK_BYTES,
+};
+use ::membership::{MembershipPublicInputsV2Bytes, MembershipVerifier, PoseidonParamsSpec};
+use ::unlinkability::{UnlinkabilityPublicInputsV2, UnlinkabilityVerifier};
+use ark_bn254::Bn254;
+use ark_groth16::{Proof, VerifyingKey};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+// A request carries one proof and one bincode public-inputs file, base64 encoded;
+// anything much larger than that is not a verify request.
+pub const MAX_REQUEST_BYTES: u64 = 1 << 16;
+
+// Body of `POST /verify/<statement>`. `public_inputs` is the V2 public-inputs file
+// the verify binaries read and `proof` the arkworks proof, both base64 (standard
+// alphabet, padded).
+#[derive(Clone, Debug, Deserialize)]
+pub struct VerifyRequest {
+    pub public_inputs: String,
+    pub proof: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum VerifyResponse {
+    Verified { verified: bool },
+    Error { error: String },
+}
+
+// Prepared verifying keys for the three V2 statements, built once at startup so
+// each request only pays for the pairing check.
+#[derive(Clone, Debug)]
+pub struct VerifierSet {
+    membership: MembershipVerifier,
+    continuity: ContinuityVerifier,
+    unlinkability: UnlinkabilityVerifier,
+    compressed: bool,
+}
+
+impl VerifierSet {
+    pub fn new(
+        membership_vk: &VerifyingKey<Bn254>,
+        continuity_vk: &VerifyingKey<Bn254>,
+        unlinkability_vk: &VerifyingKey<Bn254>,
+        compressed: bool,
+    ) -> Self {
+        Self {
+            membership: MembershipVerifier::new(membership_vk),
+            continuity: ContinuityVerifier::new(continuity_vk),
+            unlinkability: UnlinkabilityVerifier::new(unlinkability_vk),
+            compressed,
+        }
+    }
+
+    // Reads each key in the encoding `compressed` names and checks its Poseidon
+    // spec sidecar, as the verify binaries do.
+    pub fn load(
+        membership_vk: &str,
+        continuity_vk: &str,
+        unlinkability_vk: &str,
+        compressed: bool,
+    ) -> Result<Self, String> {
+        let read = |path: &str| {
+            let vk = read_verifying_key_as(path, DEFAULT_MAX_VK_BYTES, compressed)?;
+            check_params_spec(path, &PoseidonParamsSpec::DEFAULT_SPEC)?;
+            Ok::<_, String>(vk)
+        };
+        Ok(Self::new(
+            &read(membership_vk)?,
+            &read(continuity_vk)?,
+            &read(unlinkability_vk)?,
+            compressed,
+        ))
+    }
+
+    // `statement` is the last path segment: membership, continuity or
+    // unlinkability. Errors are malformed requests; a well-formed proof that does
+    // not verify is `Ok(false)`.
+    pub fn verify(&self, statement: &str, request: &VerifyRequest) -> Result<bool, String> {
+        let inputs = STANDARD
+            .decode(&request.public_inputs)
+            .map_err(|err| format!("public_inputs: {err}"))?;
+        let proof = STANDARD
+            .decode(&request.proof)
+            .map_err(|err| format!("proof: {err}"))?;
+        if proof.len() as u64 > DEFAULT_MAX_PROOF_BYTES {
+            return Err(format!("proof is {} bytes", proof.len()));
+        }
+        let proof: Proof<Bn254> =
+            decode_canonical(&proof, self.compressed).map_err(|err| format!("proof: {err}"))?;
+        let verified = match statement {
+            "membership" => {
+                let (inputs, _depth) =
+                    decode_public_inputs(&inputs, |bytes: MembershipPublicInputsV2Bytes| {
+                        bytes.into_public_inputs_with_depth()
+                    })?;
+                self.membership.verify_v2(&inputs, &proof)
+            }
+            "continuity" => {
+                let inputs = decode_public_inputs(&inputs, |bytes: ContinuityPublicInputsV2| {
+                    bytes.into_public_inputs()
+                })?;
+                self.continuity.verify_v2(&inputs, &proof)
+            }
+            "unlinkability" => {
+                let inputs = decode_public_inputs(&inputs, |bytes: UnlinkabilityPublicInputsV2| {
+                    bytes.into_public_inputs()
+                })?;
+                self.unlinkability.verify(&inputs, &proof)
+            }
+            other => return Err(format!("unknown statement {other}")),
+        };
+        verified.map_err(|err| err.to_string())
+    }
+
+    // Routes one request and returns the HTTP status and JSON body. Transport is
+    // left to the caller so this can be exercised without a socket.
+    pub fn handle(&self, method: &str, url: &str, body: &[u8]) -> (u16, String) {
+        let statement = match url.strip_prefix("/verify/") {
+            Some(statement @ ("membership" | "continuity" | "unlinkability")) => statement,
+            _ => return error_response(404, format!("no route for {url}")),
+        };
+        if method != "POST" {
+            return error_response(405, format!("{url} only accepts POST"));
+        }
+        if body.len() as u64 > MAX_REQUEST_BYTES {
+            return error_response(413, format!("request body is {} bytes", body.len()));
+        }
+        let request: VerifyRequest = match serde_json::from_slice(body) {
+            Ok(request) => request,
+            Err(err) => return error_response(400, format!("invalid request: {err}")),
+        };
+        match self.verify(statement, &request) {
+            Ok(verified) => json_response(200, &VerifyResponse::Verified { verified }),
+            Err(err) => error_response(400, err),
+        }
+    }
+}
+
+pub fn error_response(status: u16, error: String) -> (u16, String) {
+    json_response(status, &VerifyResponse::Error { error })
+}
+
+fn json_response(status: u16, response: &VerifyResponse) -> (u16, String) {
+    let body = serde_json::to_string(response).expect("response serializes");
+    (status, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{VerifierSet, VerifyResponse};
+    use crate::demo::{generate_demo, DEMO_STATEMENTS};
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use std::fs;
+
+    fn request(dir: &str, statement: &str) -> String {
+        let read = |name: &str| STANDARD.encode(fs::read(format!("{dir}/{name}.bin")).unwrap());
+        serde_json::json!({
+            "public_inputs": read(&format!("{statement}_public_inputs")),
+            "proof": read(&format!("{statement}_proof")),
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn server_verifies_each_statement_and_rejects_bad_requests() {
+        let dir = std::env::temp_dir().join(format!("toolkit-server-{}", std::process::id()));
+        let dir = dir.to_string_lossy().into_owned();
+        generate_demo(&dir, 2, 11).unwrap();
+        let vk = |statement: &str| format!("{dir}/{statement}_vk.bin");
+        let verifiers =
+            VerifierSet::load(&vk("membership"), &vk("continuity"), &vk("unlinkability"), false)
+                .unwrap();
+
+        let verified = |(status, body): (u16, String)| {
+            (status, serde_json::from_str::<VerifyResponse>(&body).unwrap())
+        };
+        for statement in DEMO_STATEMENTS {
+            let body = request(&dir, statement);
+            let url = format!("/verify/{statement}");
+            assert_eq!(
+                verified(verifiers.handle("POST", &url, body.as_bytes())),
+                (200, VerifyResponse::Verified { verified: true })
+            );
+        }
+
+        // A continuity proof on the membership route either fails to decode or fails
+        // to verify, depending on how its public inputs happen to parse.
+        let body = request(&dir, "continuity");
+        let (status, response) =
+            verified(verifiers.handle("POST", "/verify/membership", body.as_bytes()));
+        assert!(status == 400 || response == VerifyResponse::Verified { verified: false });
+
+        assert_eq!(verifiers.handle("POST", "/verify/other", b"{}").0, 404);
+        assert_eq!(verifiers.handle("GET", "/verify/membership", b"").0, 405);
+        assert_eq!(verifiers.handle("POST", "/verify/membership", b"not json").0, 400);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,70 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use membership::{enforce_in_range, CircuitHash, Poseidon};
+
+use crate::{DOMAIN_RANGE_COMMITMENT, RANGE_ATTRIBUTE_BITS, RANGE_V2_DOMAIN_SEP};
+
+// Proves commitment = H(DOMAIN_RANGE_COMMITMENT, attribute, blinding, ctx_hash) and
+// min <= attribute <= max for a private attribute. Public inputs: [commitment, min,
+// max, domain_sep, ctx_hash]. attribute, min, max, attribute - min and
+// max - attribute are each decomposed into RANGE_ATTRIBUTE_BITS bits; with every
+// value far below the field modulus the two differences cannot wrap, so the
+// comparison holds over the integers.
+#[derive(Clone, Debug, Default)]
+pub struct RangeCircuitV2<F: PrimeField> {
+    pub commitment: Option<F>,
+    pub min: Option<F>,
+    pub max: Option<F>,
+    pub domain_sep: Option<F>,
+    pub ctx_hash: Option<F>,
+    pub attribute: Option<F>,
+    pub blinding: Option<F>,
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for RangeCircuitV2<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let params = Poseidon::params::<F>();
+
+        let commitment = FpVar::new_input(cs.clone(), || {
+            self.commitment.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let min = FpVar::new_input(cs.clone(), || {
+            self.min.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let max = FpVar::new_input(cs.clone(), || {
+            self.max.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let domain_sep = FpVar::new_input(cs.clone(), || {
+            self.domain_sep.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let ctx_hash = FpVar::new_input(cs.clone(), || {
+            self.ctx_hash.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let attribute = FpVar::new_witness(cs.clone(), || {
+            self.attribute.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        let blinding = FpVar::new_witness(cs.clone(), || {
+            self.blinding.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+
+        let domain_sep_const = FpVar::constant(F::from_be_bytes_mod_order(&RANGE_V2_DOMAIN_SEP));
+        domain_sep.enforce_equal(&domain_sep_const)?;
+
+        let domain_commitment = FpVar::constant(F::from(DOMAIN_RANGE_COMMITMENT));
+        let computed = Poseidon::hash_var(
+            cs.clone(),
+            &params,
+            &[domain_commitment, attribute.clone(), blinding, ctx_hash],
+        )?;
+        computed.enforce_equal(&commitment)?;
+
+        enforce_in_range(cs.clone(), &attribute, RANGE_ATTRIBUTE_BITS)?;
+        enforce_in_range(cs.clone(), &min, RANGE_ATTRIBUTE_BITS)?;
+        enforce_in_range(cs.clone(), &max, RANGE_ATTRIBUTE_BITS)?;
+        enforce_in_range(cs.clone(), &(&attribute - &min), RANGE_ATTRIBUTE_BITS)?;
+        enforce_in_range(cs, &(&max - &attribute), RANGE_ATTRIBUTE_BITS)
+    }
+}
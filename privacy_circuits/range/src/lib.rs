@@ -0,0 +1,225 @@
+use ark_bn254::{Bn254, Fr};
+use ark_ff::PrimeField;
+use ark_groth16::{prepare_verifying_key, Groth16, Proof, ProvingKey, VerifyingKey};
+use ark_relations::r1cs::SynthesisError;
+use ark_std::rand::RngCore;
+
+pub mod circuit;
+
+pub use circuit::RangeCircuitV2;
+pub use membership::{
+    check_constant_form, fixed_bytes, fr_to_fixed_bytes, poseidon_params, proof_from_bytes,
+    ConstantError,
+};
+use membership::{CircuitHash, Poseidon};
+
+pub const RANGE_V2_DOMAIN_SEP: [u8; 32] = *b"SNARK_RANGE_V2__________________";
+pub const RANGE_V2_DEFAULT_CTX_HASH: [u8; 32] = *b"RANGE_CTX_V2____________________";
+
+// Width of the attribute and of both bounds. Wide enough for ages, dates and
+// balances in minor units; a key pair is fixed to this width.
+pub const RANGE_ATTRIBUTE_BITS: usize = 64;
+
+// Follows membership's commitment, leaf, node, slot, Schnorr and attribute tags
+// (1 to 6), so a commitment made for another statement never opens here.
+const DOMAIN_RANGE_COMMITMENT: u64 = 7;
+
+pub fn validate_constants() -> Result<(), ConstantError> {
+    check_constant_form(
+        "RANGE_V2_DOMAIN_SEP",
+        &RANGE_V2_DOMAIN_SEP,
+        "SNARK_RANGE_V2",
+    )?;
+    check_constant_form(
+        "RANGE_V2_DEFAULT_CTX_HASH",
+        &RANGE_V2_DEFAULT_CTX_HASH,
+        "RANGE_CTX_V2",
+    )
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RangePublicInputsV2 {
+    pub commitment: Fr,
+    pub min: u64,
+    pub max: u64,
+    pub domain_sep: Fr,
+    pub ctx_hash: Fr,
+}
+
+#[derive(Clone, Debug)]
+pub struct RangeInstanceV2 {
+    pub public_inputs: RangePublicInputsV2,
+    pub attribute: u64,
+    pub blinding: Fr,
+}
+
+pub fn range_domain_sep_v2_fr() -> Fr {
+    Fr::from_be_bytes_mod_order(&RANGE_V2_DOMAIN_SEP)
+}
+
+// Binds the attribute commitment to ctx_hash, so a range proof made for one
+// context cannot be presented under another.
+pub fn commitment_hash_v2(
+    params: &<Poseidon as CircuitHash>::Params<Fr>,
+    attribute: Fr,
+    blinding: Fr,
+    ctx_hash: Fr,
+) -> Fr {
+    let domain = Fr::from(DOMAIN_RANGE_COMMITMENT);
+    Poseidon::hash(params, &[domain, attribute, blinding, ctx_hash])
+}
+
+pub fn build_range_instance_v2(
+    attribute: u64,
+    blinding: Fr,
+    min: u64,
+    max: u64,
+    ctx_hash: Fr,
+) -> Result<RangeInstanceV2, String> {
+    if min > max {
+        return Err(format!("empty range: min {min} > max {max}"));
+    }
+    if attribute < min || attribute > max {
+        return Err(format!("attribute is outside [{min}, {max}]"));
+    }
+    let params = poseidon_params::<Fr>();
+    Ok(RangeInstanceV2 {
+        public_inputs: RangePublicInputsV2 {
+            commitment: commitment_hash_v2(&params, Fr::from(attribute), blinding, ctx_hash),
+            min,
+            max,
+            domain_sep: range_domain_sep_v2_fr(),
+            ctx_hash,
+        },
+        attribute,
+        blinding,
+    })
+}
+
+pub fn build_range_circuit_v2(instance: &RangeInstanceV2) -> RangeCircuitV2<Fr> {
+    let public_inputs = &instance.public_inputs;
+    RangeCircuitV2::<Fr> {
+        commitment: Some(public_inputs.commitment),
+        min: Some(Fr::from(public_inputs.min)),
+        max: Some(Fr::from(public_inputs.max)),
+        domain_sep: Some(public_inputs.domain_sep),
+        ctx_hash: Some(public_inputs.ctx_hash),
+        attribute: Some(Fr::from(instance.attribute)),
+        blinding: Some(instance.blinding),
+    }
+}
+
+pub fn setup_range_v2<R: RngCore>(rng: &mut R) -> Result<ProvingKey<Bn254>, SynthesisError> {
+    let zero = Fr::from(0u64);
+    let circuit = RangeCircuitV2::<Fr> {
+        commitment: Some(zero),
+        min: Some(zero),
+        max: Some(zero),
+        domain_sep: Some(range_domain_sep_v2_fr()),
+        ctx_hash: Some(zero),
+        attribute: Some(zero),
+        blinding: Some(zero),
+    };
+    Groth16::<Bn254>::generate_random_parameters_with_reduction(circuit, rng)
+}
+
+pub fn prove_range_v2<R: RngCore>(
+    pk: &ProvingKey<Bn254>,
+    instance: &RangeInstanceV2,
+    rng: &mut R,
+) -> Result<Proof<Bn254>, SynthesisError> {
+    Groth16::<Bn254>::create_random_proof_with_reduction(build_range_circuit_v2(instance), pk, rng)
+}
+
+pub fn verify_range_v2(
+    vk: &VerifyingKey<Bn254>,
+    public_inputs: &RangePublicInputsV2,
+    proof: &Proof<Bn254>,
+) -> Result<bool, SynthesisError> {
+    let pvk = prepare_verifying_key(vk);
+    let inputs = vec![
+        public_inputs.commitment,
+        Fr::from(public_inputs.min),
+        Fr::from(public_inputs.max),
+        public_inputs.domain_sep,
+        public_inputs.ctx_hash,
+    ];
+    Groth16::<Bn254>::verify_proof(&pvk, proof, &inputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        build_range_circuit_v2, build_range_instance_v2, commitment_hash_v2, poseidon_params,
+        prove_range_v2, setup_range_v2, validate_constants, verify_range_v2, RangeCircuitV2,
+    };
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use membership::commitment_hash;
+
+    fn satisfied(attribute: u64, min: u64, max: u64) -> bool {
+        let instance = build_range_instance_v2(min, Fr::from(7u64), min, max, Fr::from(9u64));
+        let mut instance = instance.unwrap();
+        instance.attribute = attribute;
+        instance.public_inputs.commitment = commitment_hash_v2(
+            &poseidon_params::<Fr>(),
+            Fr::from(attribute),
+            instance.blinding,
+            instance.public_inputs.ctx_hash,
+        );
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        build_range_circuit_v2(&instance)
+            .generate_constraints(cs.clone())
+            .unwrap();
+        cs.is_satisfied().unwrap()
+    }
+
+    #[test]
+    fn range_circuit_accepts_only_attributes_within_bounds() {
+        validate_constants().unwrap();
+        assert!(satisfied(18, 18, 120));
+        assert!(satisfied(120, 18, 120));
+        assert!(satisfied(u64::MAX, 0, u64::MAX));
+        assert!(!satisfied(17, 18, 120));
+        assert!(!satisfied(121, 18, 120));
+        assert!(!satisfied(0, 1, u64::MAX));
+        assert!(build_range_instance_v2(17, Fr::from(7u64), 18, 120, Fr::from(9u64)).is_err());
+        assert!(build_range_instance_v2(5, Fr::from(7u64), 6, 4, Fr::from(9u64)).is_err());
+
+        let mut rng = StdRng::seed_from_u64(100);
+        let pk = setup_range_v2(&mut rng).unwrap();
+        let instance = build_range_instance_v2(42, Fr::from(7u64), 18, 120, Fr::from(9u64));
+        let instance = instance.unwrap();
+        let proof = prove_range_v2(&pk, &instance, &mut rng).unwrap();
+        assert!(verify_range_v2(&pk.vk, &instance.public_inputs, &proof).unwrap());
+
+        let mut wrong_ctx = instance.public_inputs.clone();
+        wrong_ctx.ctx_hash += Fr::from(1u64);
+        assert!(!verify_range_v2(&pk.vk, &wrong_ctx, &proof).unwrap());
+        let mut wider = instance.public_inputs.clone();
+        wider.max = 200;
+        assert!(!verify_range_v2(&pk.vk, &wider, &proof).unwrap());
+    }
+
+    #[test]
+    fn range_commitment_is_bound_to_ctx_hash() {
+        let params = poseidon_params::<Fr>();
+        let instance = build_range_instance_v2(42, Fr::from(7u64), 18, 120, Fr::from(9u64));
+        let instance = instance.unwrap();
+        let unsatisfied = |circuit: RangeCircuitV2<Fr>| {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.generate_constraints(cs.clone()).unwrap();
+            !cs.is_satisfied().unwrap()
+        };
+
+        let mut other_ctx = build_range_circuit_v2(&instance);
+        other_ctx.ctx_hash = Some(Fr::from(10u64));
+        assert!(unsatisfied(other_ctx));
+
+        // The plain membership commitment to the same opening does not open here.
+        let mut plain = build_range_circuit_v2(&instance);
+        plain.commitment = Some(commitment_hash(&params, Fr::from(42u64), Fr::from(7u64)));
+        assert!(unsatisfied(plain));
+    }
+}
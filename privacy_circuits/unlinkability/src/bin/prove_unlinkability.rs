@@ -1,31 +1,29 @@
-use ark_bn254::Bn254;
-use ark_groth16::ProvingKey;
-use ark_serialize::CanonicalDeserialize;
-use ark_serialize::CanonicalSerialize;
 use ark_std::rand::rngs::OsRng;
+use membership::io::{read_proving_key_as, write_canonical, DEFAULT_MAX_PK_BYTES};
+use membership::{write_bundle, ProofBundle};
 use std::env;
 use std::fs;
-use std::fs::File;
-use std::io::{BufReader, BufWriter, Write};
-use unlinkability::{prove_unlinkability_v2, UnlinkabilityInstanceV2};
+use unlinkability::{prove_unlinkability_v2, UnlinkabilityInstanceV2, UnlinkabilityPublicInputsV2};
 
 fn main() {
-    let (pk_path, instance_path, proof_out, schema) = match parse_args() {
-        Some(paths) => paths,
+    let args = match parse_args() {
+        Some(args) => args,
         None => {
             eprintln!(
-                "Usage: prove_unlinkability --pk <path> --instance <path> --proof-out <path> [--schema <v2>]"
+                "Usage: prove_unlinkability --pk <path> --instance <path> --proof-out <path> [--bundle-out <path>] [--max-pk-bytes <n>] [--schema <v2>] [--compressed]"
             );
             std::process::exit(1);
         }
     };
+    let Args { pk_path, instance_path, proof_out, bundle_out, schema, max_pk_bytes, compressed } =
+        args;
 
     if !matches!(schema, Schema::V2) {
         eprintln!("only schema v2 is supported");
         std::process::exit(1);
     }
 
-    let pk = match read_proving_key(&pk_path) {
+    let pk = match read_proving_key_as(&pk_path, max_pk_bytes, compressed) {
         Ok(pk) => pk,
         Err(err) => {
             eprintln!("failed to read proving key: {err}");
@@ -41,6 +39,14 @@ fn main() {
         }
     };
 
+    let public_inputs = UnlinkabilityPublicInputsV2 {
+        schema_version: instance_bytes.schema_version,
+        statement_type: instance_bytes.statement_type,
+        statement_version: instance_bytes.statement_version,
+        tag: instance_bytes.tag,
+        domain_sep: instance_bytes.domain_sep,
+        ctx_hash: instance_bytes.ctx_hash,
+    };
     let instance = match instance_bytes.into_instance() {
         Ok(instance) => instance,
         Err(err) => {
@@ -58,23 +64,52 @@ fn main() {
         }
     };
 
-    if let Err(err) = write_serialized(&proof_out, &proof) {
+    if let Err(err) = write_canonical(&proof_out, &proof, compressed) {
         eprintln!("failed to write proof: {err}");
         std::process::exit(1);
     }
+
+    if let Some(path) = bundle_out {
+        let result = bincode::serialize(&public_inputs)
+            .map_err(|err| err.to_string())
+            .and_then(|inputs| {
+                ProofBundle::new("unlinkability-v2", inputs, &proof, &pk.vk, compressed)
+            })
+            .and_then(|bundle| write_bundle(&path, &bundle));
+        if let Err(err) = result {
+            eprintln!("failed to write bundle: {err}");
+            std::process::exit(1);
+        }
+    }
 }
 
-fn parse_args() -> Option<(String, String, String, Schema)> {
+struct Args {
+    pk_path: String,
+    instance_path: String,
+    proof_out: String,
+    bundle_out: Option<String>,
+    schema: Schema,
+    max_pk_bytes: u64,
+    compressed: bool,
+}
+
+fn parse_args() -> Option<Args> {
     let mut pk_path = None;
     let mut instance_path = None;
     let mut proof_out = None;
+    let mut bundle_out = None;
+    let mut max_pk_bytes = DEFAULT_MAX_PK_BYTES;
     let mut schema = Schema::V2;
+    let mut compressed = false;
     let mut args = env::args().skip(1);
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--pk" => pk_path = args.next(),
             "--instance" => instance_path = args.next(),
             "--proof-out" => proof_out = args.next(),
+            "--bundle-out" => bundle_out = Some(args.next()?),
+            "--max-pk-bytes" => max_pk_bytes = args.next()?.parse().ok()?,
+            "--compressed" => compressed = true,
             "--schema" => {
                 schema = match args.next()?.as_str() {
                     "v2" => Schema::V2,
@@ -85,7 +120,15 @@ fn parse_args() -> Option<(String, String, String, Schema)> {
         }
     }
     match (pk_path, instance_path, proof_out) {
-        (Some(pk), Some(instance), Some(proof)) => Some((pk, instance, proof, schema)),
+        (Some(pk_path), Some(instance_path), Some(proof_out)) => Some(Args {
+            pk_path,
+            instance_path,
+            proof_out,
+            bundle_out,
+            schema,
+            max_pk_bytes,
+            compressed,
+        }),
         _ => None,
     }
 }
@@ -95,22 +138,7 @@ enum Schema {
     V2,
 }
 
-fn read_proving_key(path: &str) -> Result<ProvingKey<Bn254>, String> {
-    let file = File::open(path).map_err(|err| err.to_string())?;
-    let mut reader = BufReader::new(file);
-    ProvingKey::<Bn254>::deserialize_uncompressed(&mut reader).map_err(|err| err.to_string())
-}
-
 fn read_instance_v2(path: &str) -> Result<UnlinkabilityInstanceV2, String> {
     let data = fs::read(path).map_err(|err| err.to_string())?;
     bincode::deserialize::<UnlinkabilityInstanceV2>(&data).map_err(|err| err.to_string())
 }
-
-fn write_serialized<T: CanonicalSerialize>(path: &str, value: &T) -> Result<(), String> {
-    let file = File::create(path).map_err(|err| err.to_string())?;
-    let mut writer = BufWriter::new(file);
-    value
-        .serialize_uncompressed(&mut writer)
-        .map_err(|err| err.to_string())?;
-    writer.flush().map_err(|err| err.to_string())
-}
@@ -1,21 +1,29 @@
-use ark_serialize::CanonicalSerialize;
 use ark_std::rand::rngs::OsRng;
+use membership::io::{key_output_paths, parse_seed_hex, write_canonical, write_params_spec};
+use membership::PoseidonParamsSpec;
 use std::env;
-use std::fs::File;
-use std::io::{BufWriter, Write};
-use unlinkability::setup_unlinkability_v2;
+use unlinkability::{setup_unlinkability_v2, setup_unlinkability_v2_with_seed};
 
 fn main() {
-    let (pk_out, vk_out) = match parse_args() {
+    let (pk_out, vk_out, compressed, seed) = match parse_args() {
         Some(paths) => paths,
         None => {
-            eprintln!("Usage: setup_unlinkability --pk-out <path> --vk-out <path>");
+            eprintln!(
+                "Usage: setup_unlinkability (--pk-out <path> --vk-out <path> | --out-dir <dir> --name <prefix>) [--compressed] [--seed <hex32>]"
+            );
             std::process::exit(1);
         }
     };
 
-    let mut rng = OsRng;
-    let pk = match setup_unlinkability_v2(&mut rng) {
+    if seed.is_some() {
+        // The seed recreates the setup trapdoor; seeded keys are for tests and CI.
+        eprintln!("warning: anyone with the --seed value can regenerate these keys; do not deploy");
+    }
+    let pk = match seed {
+        Some(seed) => setup_unlinkability_v2_with_seed(seed),
+        None => setup_unlinkability_v2(&mut OsRng),
+    };
+    let pk = match pk {
         Ok(pk) => pk,
         Err(err) => {
             eprintln!("setup failed: {err}");
@@ -24,38 +32,54 @@ fn main() {
     };
     let vk = pk.vk.clone();
 
-    if let Err(err) = write_serialized(&pk_out, &pk) {
+    if let Err(err) = write_canonical(&pk_out, &pk, compressed) {
         eprintln!("failed to write proving key: {err}");
         std::process::exit(1);
     }
-    if let Err(err) = write_serialized(&vk_out, &vk) {
+    if let Err(err) = write_canonical(&vk_out, &vk, compressed) {
         eprintln!("failed to write verifying key: {err}");
         std::process::exit(1);
     }
+    if let Err(err) = write_params_spec(&vk_out, &PoseidonParamsSpec::DEFAULT_SPEC) {
+        eprintln!("failed to write Poseidon parameter spec: {err}");
+        std::process::exit(1);
+    }
 }
 
-fn parse_args() -> Option<(String, String)> {
+fn parse_args() -> Option<(String, String, bool, Option<[u8; 32]>)> {
     let mut pk_out = None;
     let mut vk_out = None;
+    let mut out_dir = None;
+    let mut name = None;
+    let mut compressed = false;
+    let mut seed = None;
     let mut args = env::args().skip(1);
     while let Some(arg) = args.next() {
         match arg.as_str() {
             "--pk-out" => pk_out = args.next(),
             "--vk-out" => vk_out = args.next(),
+            "--out-dir" => out_dir = args.next(),
+            "--name" => name = args.next(),
+            "--compressed" => compressed = true,
+            "--seed" => match parse_seed_hex(&args.next()?) {
+                Ok(bytes) => seed = Some(bytes),
+                Err(err) => {
+                    eprintln!("{err}");
+                    std::process::exit(1);
+                }
+            },
             _ => return None,
         }
     }
-    match (pk_out, vk_out) {
-        (Some(pk), Some(vk)) => Some((pk, vk)),
+    match (pk_out, vk_out, out_dir, name) {
+        (Some(pk), Some(vk), None, None) => Some((pk, vk, compressed, seed)),
+        (None, None, Some(dir), Some(name)) => match key_output_paths(&dir, &name) {
+            Ok((pk, vk)) => Some((pk, vk, compressed, seed)),
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        },
         _ => None,
     }
 }
-
-fn write_serialized<T: CanonicalSerialize>(path: &str, value: &T) -> Result<(), String> {
-    let file = File::create(path).map_err(|err| err.to_string())?;
-    let mut writer = BufWriter::new(file);
-    value
-        .serialize_uncompressed(&mut writer)
-        .map_err(|err| err.to_string())?;
-    writer.flush().map_err(|err| err.to_string())
-}
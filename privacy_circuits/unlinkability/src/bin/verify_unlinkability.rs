@@ -1,18 +1,18 @@
-use ark_bn254::Bn254;
-use ark_groth16::{Proof, VerifyingKey};
-use ark_serialize::CanonicalDeserialize;
+use membership::io::{
+    check_params_spec, decode_public_inputs, read_public_inputs_then_proof,
+    read_verifying_key_auto, DEFAULT_MAX_VK_BYTES,
+};
+use membership::{read_bundle, PoseidonParamsSpec, DEFAULT_MAX_BUNDLE_BYTES};
 use std::env;
-use std::fs;
-use std::fs::File;
-use std::io::BufReader;
-use unlinkability::{verify_unlinkability_v2, UnlinkabilityPublicInputsV2};
+use unlinkability::schema::UnlinkabilityPublicInputsV2Data;
+use unlinkability::{verify_unlinkability_v2, vk_fingerprint_hex, UnlinkabilityPublicInputsV2};
 
 fn main() {
-    let (vk_path, inputs_path, proof_path, schema) = match parse_args() {
+    let (vk_path, source, schema) = match parse_args() {
         Some(paths) => paths,
         None => {
             eprintln!(
-                "Usage: verify_unlinkability --vk <path> --public-inputs <path> --proof <path> [--schema <v2>]"
+                "Usage: verify_unlinkability --vk <path> (--public-inputs <path> --proof <path> | --bundle <path>) [--schema <v2>]"
             );
             std::process::exit(1);
         }
@@ -23,35 +23,48 @@ fn main() {
         std::process::exit(1);
     }
 
-    let vk = match read_verifying_key(&vk_path) {
+    let vk = match read_verifying_key_auto(&vk_path, DEFAULT_MAX_VK_BYTES) {
         Ok(vk) => vk,
         Err(err) => {
             eprintln!("failed to read verifying key: {err}");
             std::process::exit(1);
         }
     };
+    // Keys from a setup run with different Poseidon parameters cannot verify anything; say so
+    // up front instead of reporting every proof as invalid.
+    if let Err(err) = check_params_spec(&vk_path, &PoseidonParamsSpec::DEFAULT_SPEC) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
 
-    let proof = match read_proof(&proof_path) {
-        Ok(proof) => proof,
-        Err(err) => {
-            eprintln!("failed to read proof: {err}");
-            std::process::exit(1);
-        }
-    };
-
-    let inputs_bytes = match read_public_inputs_v2(&inputs_path) {
-        Ok(inputs) => inputs,
-        Err(err) => {
-            eprintln!("failed to read public inputs: {err}");
-            std::process::exit(1);
+    let (public_inputs, proof) = match source {
+        Source::Files {
+            inputs_path,
+            proof_path,
+        } => {
+            match read_public_inputs_then_proof(&inputs_path, &proof_path, decode_public_inputs_v2)
+            {
+                Ok(loaded) => loaded,
+                Err(err) => {
+                    eprintln!("{err}");
+                    std::process::exit(1);
+                }
+            }
         }
-    };
-
-    let public_inputs = match inputs_bytes.into_public_inputs() {
-        Ok(inputs) => inputs,
-        Err(err) => {
-            eprintln!("invalid public inputs: {err}");
-            std::process::exit(1);
+        // Checked against the VK fingerprint before anything in it is decoded.
+        Source::Bundle(path) => {
+            let opened = read_bundle(&path, DEFAULT_MAX_BUNDLE_BYTES).and_then(|bundle| {
+                let (data, proof) = bundle.open("unlinkability-v2", &vk)?;
+                Ok((decode_public_inputs_v2(data)?, proof))
+            });
+            match opened {
+                Ok(opened) => opened,
+                Err(err) => {
+                    eprintln!("failed to read bundle: {err}");
+                    eprintln!("vk fingerprint: {}", vk_fingerprint_hex(&vk));
+                    std::process::exit(1);
+                }
+            }
         }
     };
 
@@ -59,6 +72,7 @@ fn main() {
         Ok(result) => result,
         Err(err) => {
             eprintln!("verification failed: {err}");
+            eprintln!("vk fingerprint: {}", vk_fingerprint_hex(&vk));
             std::process::exit(1);
         }
     };
@@ -69,13 +83,20 @@ fn main() {
     }
 
     eprintln!("verification failed");
+    eprintln!("vk fingerprint: {}", vk_fingerprint_hex(&vk));
     std::process::exit(2);
 }
 
-fn parse_args() -> Option<(String, String, String, Schema)> {
+enum Source {
+    Files { inputs_path: String, proof_path: String },
+    Bundle(String),
+}
+
+fn parse_args() -> Option<(String, Source, Schema)> {
     let mut vk_path = None;
     let mut inputs_path = None;
     let mut proof_path = None;
+    let mut bundle = None;
     let mut schema = Schema::V2;
     let mut args = env::args().skip(1);
     while let Some(arg) = args.next() {
@@ -83,6 +104,9 @@ fn parse_args() -> Option<(String, String, String, Schema)> {
             "--vk" => vk_path = args.next(),
             "--public-inputs" => inputs_path = args.next(),
             "--proof" => proof_path = args.next(),
+            "--bundle" => bundle = args.next(),
+            // Kept for old invocations; encodings are detected on read.
+            "--compressed" => {}
             "--schema" => {
                 schema = match args.next()?.as_str() {
                     "v2" => Schema::V2,
@@ -92,10 +116,15 @@ fn parse_args() -> Option<(String, String, String, Schema)> {
             _ => return None,
         }
     }
-    match (vk_path, inputs_path, proof_path) {
-        (Some(vk), Some(inputs), Some(proof)) => Some((vk, inputs, proof, schema)),
-        _ => None,
-    }
+    let source = match (inputs_path, proof_path, bundle) {
+        (Some(inputs_path), Some(proof_path), None) => Source::Files {
+            inputs_path,
+            proof_path,
+        },
+        (None, None, Some(bundle)) => Source::Bundle(bundle),
+        _ => return None,
+    };
+    Some((vk_path?, source, schema))
 }
 
 #[derive(Clone, Copy)]
@@ -103,19 +132,8 @@ enum Schema {
     V2,
 }
 
-fn read_verifying_key(path: &str) -> Result<VerifyingKey<Bn254>, String> {
-    let file = File::open(path).map_err(|err| err.to_string())?;
-    let mut reader = BufReader::new(file);
-    VerifyingKey::<Bn254>::deserialize_uncompressed(&mut reader).map_err(|err| err.to_string())
-}
-
-fn read_public_inputs_v2(path: &str) -> Result<UnlinkabilityPublicInputsV2, String> {
-    let data = fs::read(path).map_err(|err| err.to_string())?;
-    bincode::deserialize::<UnlinkabilityPublicInputsV2>(&data).map_err(|err| err.to_string())
-}
-
-fn read_proof(path: &str) -> Result<Proof<Bn254>, String> {
-    let file = File::open(path).map_err(|err| err.to_string())?;
-    let mut reader = BufReader::new(file);
-    Proof::<Bn254>::deserialize_uncompressed(&mut reader).map_err(|err| err.to_string())
+fn decode_public_inputs_v2(data: &[u8]) -> Result<UnlinkabilityPublicInputsV2Data, String> {
+    decode_public_inputs(data, |bytes: UnlinkabilityPublicInputsV2| {
+        bytes.into_public_inputs()
+    })
 }
@@ -2,9 +2,8 @@ use ark_ff::PrimeField;
 use ark_r1cs_std::fields::fp::FpVar;
 use ark_r1cs_std::prelude::*;
 use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
-use ark_sponge::constraints::CryptographicSpongeVar;
-use ark_sponge::poseidon::constraints::PoseidonSpongeVar;
-use membership::poseidon_params;
+use core::marker::PhantomData;
+use membership::{CircuitHash, Poseidon};
 
 use crate::schema::UNLINKABILITY_V2_DOMAIN_SEP;
 
@@ -19,20 +18,42 @@ pub struct UnlinkabilityCircuitV2<F: PrimeField> {
     pub blinding: Option<F>,
 }
 
-fn poseidon_hash_var<F: PrimeField>(
-    cs: ConstraintSystemRef<F>,
-    params: &ark_sponge::poseidon::PoseidonConfig<F>,
-    inputs: &[FpVar<F>],
-) -> Result<FpVar<F>, SynthesisError> {
-    let mut sponge = PoseidonSpongeVar::<F>::new(cs, params);
-    sponge.absorb(&inputs)?;
-    let mut output = sponge.squeeze_field_elements(1)?;
-    Ok(output.remove(0))
+// `UnlinkabilityCircuitV2` synthesized with the hash `H`. `UnlinkabilityCircuitV2`
+// itself is this circuit with `H = Poseidon`.
+#[derive(Clone, Debug, Default)]
+pub struct UnlinkabilityCircuitV2WithHash<F: PrimeField, H: CircuitHash = Poseidon> {
+    circuit: UnlinkabilityCircuitV2<F>,
+    hash: PhantomData<H>,
 }
 
-impl<F: PrimeField> ConstraintSynthesizer<F> for UnlinkabilityCircuitV2<F> {
-    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
-        let params = poseidon_params::<F>();
+impl<F: PrimeField, H: CircuitHash> UnlinkabilityCircuitV2WithHash<F, H> {
+    pub fn new(circuit: UnlinkabilityCircuitV2<F>) -> Self {
+        Self {
+            circuit,
+            hash: PhantomData,
+        }
+    }
+}
+
+// Opt-in variant that additionally requires ctx_hash != 0, proven by supplying its
+// inverse. Keys are not interchangeable with UnlinkabilityCircuitV2, which keeps
+// accepting a zero context for backward compatibility.
+#[derive(Clone, Debug, Default)]
+pub struct UnlinkabilityCircuitV2Strict<F: PrimeField> {
+    pub tag: Option<F>,
+    pub domain_sep: Option<F>,
+    pub ctx_hash: Option<F>,
+    pub ctx_hash_inv: Option<F>,
+    pub id: Option<F>,
+    pub blinding: Option<F>,
+}
+
+impl<F: PrimeField> UnlinkabilityCircuitV2<F> {
+    fn synthesize<H: CircuitHash>(
+        self,
+        cs: ConstraintSystemRef<F>,
+    ) -> Result<FpVar<F>, SynthesisError> {
+        let params = H::params::<F>();
 
         let tag =
             FpVar::new_input(cs.clone(), || self.tag.ok_or(SynthesisError::AssignmentMissing))?;
@@ -49,11 +70,10 @@ impl<F: PrimeField> ConstraintSynthesizer<F> for UnlinkabilityCircuitV2<F> {
         })?;
 
         let domain_commitment = FpVar::constant(F::from(DOMAIN_COMMITMENT));
-        let commitment =
-            poseidon_hash_var(cs.clone(), &params, &[domain_commitment, id, blinding])?;
+        let commitment = H::hash_var(cs.clone(), &params, &[domain_commitment, id, blinding])?;
 
         let computed_tag =
-            poseidon_hash_var(cs.clone(), &params, &[domain_sep.clone(), ctx_hash, commitment])?;
+            H::hash_var(cs.clone(), &params, &[domain_sep.clone(), ctx_hash.clone(), commitment])?;
         computed_tag.enforce_equal(&tag)?;
 
         let domain_sep_const = FpVar::constant(F::from_be_bytes_mod_order(
@@ -61,6 +81,40 @@ impl<F: PrimeField> ConstraintSynthesizer<F> for UnlinkabilityCircuitV2<F> {
         ));
         domain_sep.enforce_equal(&domain_sep_const)?;
 
+        Ok(ctx_hash)
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for UnlinkabilityCircuitV2<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        self.synthesize::<Poseidon>(cs).map(|_| ())
+    }
+}
+
+impl<F: PrimeField, H: CircuitHash> ConstraintSynthesizer<F>
+    for UnlinkabilityCircuitV2WithHash<F, H>
+{
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        self.circuit.synthesize::<H>(cs).map(|_| ())
+    }
+}
+
+impl<F: PrimeField> ConstraintSynthesizer<F> for UnlinkabilityCircuitV2Strict<F> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let base = UnlinkabilityCircuitV2::<F> {
+            tag: self.tag,
+            domain_sep: self.domain_sep,
+            ctx_hash: self.ctx_hash,
+            id: self.id,
+            blinding: self.blinding,
+        };
+        let ctx_hash = base.synthesize::<Poseidon>(cs.clone())?;
+
+        let ctx_hash_inv = FpVar::new_witness(cs, || {
+            self.ctx_hash_inv.ok_or(SynthesisError::AssignmentMissing)
+        })?;
+        (ctx_hash * ctx_hash_inv).enforce_equal(&FpVar::one())?;
+
         Ok(())
     }
 }
@@ -0,0 +1,72 @@
+use ark_std::string::{String, ToString};
+use core::fmt;
+use membership::FieldDecodeError;
+
+// Failures from the unlinkability schema parsers. Display keeps the wording of the former
+// string errors.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UnlinkabilityError {
+    VersionMismatch {
+        field: String,
+        expected: u16,
+        got: u16,
+    },
+    StatementMismatch {
+        field: &'static str,
+        expected: u16,
+        got: u16,
+    },
+    DomainSepMismatch {
+        field: String,
+    },
+    CommitmentMismatch {
+        field: &'static str,
+    },
+    FieldDecode(FieldDecodeError),
+}
+
+impl fmt::Display for UnlinkabilityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnlinkabilityError::VersionMismatch {
+                field,
+                expected,
+                got,
+            } => write!(f, "{field}: schema_version mismatch (expected {expected}, got {got})"),
+            UnlinkabilityError::StatementMismatch {
+                field,
+                expected,
+                got,
+            } => write!(f, "{field} mismatch (expected {expected}, got {got})"),
+            UnlinkabilityError::DomainSepMismatch { field } => {
+                write!(f, "{field}: domain_sep mismatch")
+            }
+            UnlinkabilityError::CommitmentMismatch { field } => {
+                write!(f, "{field} does not match computed value")
+            }
+            UnlinkabilityError::FieldDecode(err) => err.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnlinkabilityError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UnlinkabilityError::FieldDecode(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<FieldDecodeError> for UnlinkabilityError {
+    fn from(err: FieldDecodeError) -> Self {
+        UnlinkabilityError::FieldDecode(err)
+    }
+}
+
+impl From<UnlinkabilityError> for String {
+    fn from(err: UnlinkabilityError) -> Self {
+        err.to_string()
+    }
+}
@@ -0,0 +1,166 @@
+use ark_bn254::Bn254;
+use ark_groth16::{Proof, VerifyingKey};
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use crate::schema::UnlinkabilityPublicInputsV2;
+use crate::{fr_to_array, verify_unlinkability_v2};
+
+// The V2 tag is deterministic per (commitment, ctx_hash), so its canonical bytes
+// serve as the nullifier recorded by a store.
+pub trait NullifierStore: Send {
+    fn contains(&self, nullifier: &[u8; 32]) -> bool;
+    // Returns false if the nullifier was already present.
+    fn insert(&mut self, nullifier: [u8; 32]) -> bool;
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct InMemoryNullifierStore {
+    spent: HashSet<[u8; 32]>,
+}
+
+impl InMemoryNullifierStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.spent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spent.is_empty()
+    }
+}
+
+impl NullifierStore for InMemoryNullifierStore {
+    fn contains(&self, nullifier: &[u8; 32]) -> bool {
+        self.spent.contains(nullifier)
+    }
+
+    fn insert(&mut self, nullifier: [u8; 32]) -> bool {
+        self.spent.insert(nullifier)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpendOutcome {
+    Spent,
+    AlreadySpent,
+    Invalid,
+}
+
+// Verifies an unlinkability V2 proof and records its tag as spent in one step.
+// The store stays locked across the lookup, the pairing check and the insert, so
+// concurrent submissions of the same tag yield exactly one `Spent`. Schema errors
+// are returned before the lock is taken.
+pub fn verify_and_spend(
+    vk: &VerifyingKey<Bn254>,
+    inputs_bytes: &[u8],
+    proof: &Proof<Bn254>,
+    store: &Mutex<dyn NullifierStore>,
+) -> Result<SpendOutcome, String> {
+    let inputs: UnlinkabilityPublicInputsV2 = bincode::deserialize(inputs_bytes)
+        .map_err(|err| format!("failed to decode public inputs: {err}"))?;
+    let public_inputs = inputs.into_public_inputs()?;
+    // Keyed on the element the proof is checked against, not on the submitted
+    // bytes, so no second encoding of one tag can be spent again.
+    let nullifier = fr_to_array(&public_inputs.tag);
+
+    let mut store = store
+        .lock()
+        .map_err(|_| "nullifier store lock poisoned".to_string())?;
+    if store.contains(&nullifier) {
+        return Ok(SpendOutcome::AlreadySpent);
+    }
+    if !verify_unlinkability_v2(vk, &public_inputs, proof).map_err(|err| err.to_string())? {
+        return Ok(SpendOutcome::Invalid);
+    }
+    store.insert(nullifier);
+    Ok(SpendOutcome::Spent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_and_spend, InMemoryNullifierStore, NullifierStore, SpendOutcome};
+    use crate::{build_instance_v2, prove_unlinkability_v2, setup_unlinkability_v2};
+    use ark_bn254::Fr;
+    use ark_ff::{BigInteger, PrimeField};
+    use ark_std::rand::{rngs::StdRng, SeedableRng};
+    use std::sync::Mutex;
+    use std::thread;
+
+    #[test]
+    fn concurrent_spends_of_one_proof_succeed_once() {
+        let mut rng = StdRng::seed_from_u64(88);
+        let pk = setup_unlinkability_v2(&mut rng).unwrap();
+        let (instance, public_inputs) =
+            build_instance_v2(Fr::from(91u64), Fr::from(92u64), Fr::from(93u64));
+        let instance = instance.into_instance().unwrap();
+        let proof = prove_unlinkability_v2(&pk, &instance, &mut rng).unwrap();
+        let inputs_bytes = bincode::serialize(&public_inputs).unwrap();
+
+        let store = Mutex::new(InMemoryNullifierStore::new());
+        let outcomes: Vec<SpendOutcome> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| verify_and_spend(&pk.vk, &inputs_bytes, &proof, &store)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap().unwrap())
+                .collect()
+        });
+        let spent = outcomes
+            .iter()
+            .filter(|outcome| **outcome == SpendOutcome::Spent)
+            .count();
+        assert_eq!(spent, 1);
+        assert_eq!(
+            outcomes
+                .iter()
+                .filter(|outcome| **outcome == SpendOutcome::AlreadySpent)
+                .count(),
+            3
+        );
+
+        let (_, other_inputs) =
+            build_instance_v2(Fr::from(94u64), Fr::from(95u64), Fr::from(93u64));
+        let other_bytes = bincode::serialize(&other_inputs).unwrap();
+        assert_eq!(
+            verify_and_spend(&pk.vk, &other_bytes, &proof, &store).unwrap(),
+            SpendOutcome::Invalid
+        );
+        let store = store.into_inner().unwrap();
+        assert_eq!(store.len(), 1);
+        assert!(!store.contains(&other_inputs.tag));
+    }
+    #[test]
+    fn tag_plus_modulus_cannot_spend_again() {
+        let mut rng = StdRng::seed_from_u64(89);
+        let pk = setup_unlinkability_v2(&mut rng).unwrap();
+        let (instance, public_inputs) =
+            build_instance_v2(Fr::from(96u64), Fr::from(97u64), Fr::from(98u64));
+        let instance = instance.into_instance().unwrap();
+        let proof = prove_unlinkability_v2(&pk, &instance, &mut rng).unwrap();
+        let store = Mutex::new(InMemoryNullifierStore::new());
+
+        let inputs_bytes = bincode::serialize(&public_inputs).unwrap();
+        assert_eq!(
+            verify_and_spend(&pk.vk, &inputs_bytes, &proof, &store).unwrap(),
+            SpendOutcome::Spent
+        );
+
+        // tag + p fits in 32 bytes and reduces to the same tag.
+        let mut unreduced = instance.public_inputs.tag.into_bigint();
+        assert!(!unreduced.add_with_carry(&Fr::MODULUS));
+        let mut replay = public_inputs.clone();
+        replay.tag.copy_from_slice(&unreduced.to_bytes_be());
+        assert_ne!(replay.tag, public_inputs.tag);
+        let replay_bytes = bincode::serialize(&replay).unwrap();
+        assert!(!matches!(
+            verify_and_spend(&pk.vk, &replay_bytes, &proof, &store),
+            Ok(SpendOutcome::Spent)
+        ));
+        assert_eq!(store.into_inner().unwrap().len(), 1);
+    }
+}
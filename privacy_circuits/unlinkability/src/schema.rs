@@ -2,9 +2,11 @@ use ark_bn254::Fr;
 use ark_ff::PrimeField;
 use ark_sponge::poseidon::PoseidonSponge;
 use ark_sponge::CryptographicSponge;
+use ark_std::{string::ToString, vec::Vec};
 use serde::{Deserialize, Serialize};
 
-use crate::{fr_from_fixed_bytes, fr_to_fixed_bytes};
+use crate::error::UnlinkabilityError;
+use crate::{fr_from_fixed_bytes, fr_from_fixed_bytes_canonical, fr_to_fixed_bytes};
 use membership::{commitment_hash, poseidon_params};
 
 pub const UNLINKABILITY_INSTANCE_VERSION_V2: u16 = 2;
@@ -15,25 +17,33 @@ pub const UNLINKABILITY_V2_DOMAIN_SEP: [u8; 32] =
 pub const UNLINKABILITY_V2_DEFAULT_CTX_HASH: [u8; 32] =
     *b"UNLINKABILITY_CTX_V2____________";
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UnlinkabilityInstanceV2 {
     pub schema_version: u16,
     pub statement_type: u16,
     pub statement_version: u16,
+    #[serde(with = "membership::hex_serde::array")]
     pub id: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub blinding: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub tag: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub domain_sep: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub ctx_hash: [u8; 32],
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UnlinkabilityPublicInputsV2 {
     pub schema_version: u16,
     pub statement_type: u16,
     pub statement_version: u16,
+    #[serde(with = "membership::hex_serde::array")]
     pub tag: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub domain_sep: [u8; 32],
+    #[serde(with = "membership::hex_serde::array")]
     pub ctx_hash: [u8; 32],
 }
 
@@ -43,7 +53,7 @@ pub struct UnlinkabilityWitnessV2 {
     pub blinding: Fr,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct UnlinkabilityPublicInputsV2Data {
     pub tag: Fr,
     pub domain_sep: Fr,
@@ -60,69 +70,77 @@ pub fn domain_sep_v2_fr() -> Fr {
     Fr::from_be_bytes_mod_order(&UNLINKABILITY_V2_DOMAIN_SEP)
 }
 
-fn ensure_version_v2(label: &str, version: u16) -> Result<(), String> {
+fn ensure_version_v2(label: &str, version: u16) -> Result<(), UnlinkabilityError> {
     if version != UNLINKABILITY_INSTANCE_VERSION_V2 {
-        return Err(format!(
-            "{label}: schema_version mismatch (expected {}, got {})",
-            UNLINKABILITY_INSTANCE_VERSION_V2, version
-        ));
+        return Err(UnlinkabilityError::VersionMismatch {
+            field: label.to_string(),
+            expected: UNLINKABILITY_INSTANCE_VERSION_V2,
+            got: version,
+        });
     }
     Ok(())
 }
 
-fn ensure_statement_type_version(statement_type: u16, statement_version: u16) -> Result<(), String> {
+fn ensure_statement_type_version(
+    statement_type: u16,
+    statement_version: u16,
+) -> Result<(), UnlinkabilityError> {
     if statement_type != UNLINKABILITY_STATEMENT_TYPE {
-        return Err(format!(
-            "statement_type mismatch (expected {}, got {})",
-            UNLINKABILITY_STATEMENT_TYPE, statement_type
-        ));
+        return Err(UnlinkabilityError::StatementMismatch {
+            field: "statement_type",
+            expected: UNLINKABILITY_STATEMENT_TYPE,
+            got: statement_type,
+        });
     }
     if statement_version != UNLINKABILITY_STATEMENT_VERSION_V2 {
-        return Err(format!(
-            "statement_version mismatch (expected {}, got {})",
-            UNLINKABILITY_STATEMENT_VERSION_V2, statement_version
-        ));
+        return Err(UnlinkabilityError::StatementMismatch {
+            field: "statement_version",
+            expected: UNLINKABILITY_STATEMENT_VERSION_V2,
+            got: statement_version,
+        });
     }
     Ok(())
 }
 
-fn ensure_domain_sep_v2(label: &str, value: &[u8; 32]) -> Result<(), String> {
+fn ensure_domain_sep_v2(label: &str, value: &[u8; 32]) -> Result<(), UnlinkabilityError> {
     if value != &UNLINKABILITY_V2_DOMAIN_SEP {
-        return Err(format!("{label}: domain_sep mismatch"));
+        return Err(UnlinkabilityError::DomainSepMismatch {
+            field: label.to_string(),
+        });
     }
     Ok(())
 }
 
 impl UnlinkabilityPublicInputsV2 {
-    pub fn into_public_inputs(self) -> Result<UnlinkabilityPublicInputsV2Data, String> {
+    pub fn into_public_inputs(self) -> Result<UnlinkabilityPublicInputsV2Data, UnlinkabilityError> {
         ensure_version_v2("public_inputs.schema_version", self.schema_version)?;
         ensure_statement_type_version(self.statement_type, self.statement_version)?;
         ensure_domain_sep_v2("public_inputs.domain_sep", &self.domain_sep)?;
 
         Ok(UnlinkabilityPublicInputsV2Data {
-            tag: fr_from_fixed_bytes("tag", &self.tag)?,
+            tag: fr_from_fixed_bytes_canonical("tag", &self.tag)?,
             domain_sep: fr_from_fixed_bytes("domain_sep", &self.domain_sep)?,
-            ctx_hash: fr_from_fixed_bytes("ctx_hash", &self.ctx_hash)?,
+            ctx_hash: fr_from_fixed_bytes_canonical("ctx_hash", &self.ctx_hash)?,
         })
     }
 }
 
 impl UnlinkabilityInstanceV2 {
-    pub fn into_instance(self) -> Result<UnlinkabilityInstanceV2Data, String> {
+    pub fn into_instance(self) -> Result<UnlinkabilityInstanceV2Data, UnlinkabilityError> {
         ensure_version_v2("instance.schema_version", self.schema_version)?;
         ensure_statement_type_version(self.statement_type, self.statement_version)?;
         ensure_domain_sep_v2("instance.domain_sep", &self.domain_sep)?;
 
         let id = fr_from_fixed_bytes("id", &self.id)?;
         let blinding = fr_from_fixed_bytes("blinding", &self.blinding)?;
-        let tag = fr_from_fixed_bytes("tag", &self.tag)?;
-        let ctx_hash = fr_from_fixed_bytes("ctx_hash", &self.ctx_hash)?;
+        let tag = fr_from_fixed_bytes_canonical("tag", &self.tag)?;
+        let ctx_hash = fr_from_fixed_bytes_canonical("ctx_hash", &self.ctx_hash)?;
 
         let params = poseidon_params::<Fr>();
         let commitment = commitment_hash(&params, id, blinding);
         let expected_tag = tag_hash(&params, domain_sep_v2_fr(), ctx_hash, commitment);
         if expected_tag != tag {
-            return Err("tag does not match computed value".to_string());
+            return Err(UnlinkabilityError::CommitmentMismatch { field: "tag" });
         }
 
         let public_inputs = UnlinkabilityPublicInputsV2Data {
@@ -137,6 +155,17 @@ impl UnlinkabilityInstanceV2 {
             witness,
         })
     }
+
+    // Pretty JSON with every byte field as a `0x`-prefixed hex string.
+    #[cfg(feature = "std")]
+    pub fn to_json(&self) -> Result<String, String> {
+        membership::hex_serde::to_json(self)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        membership::hex_serde::from_json(json)
+    }
 }
 
 pub fn build_instance_v2(
@@ -144,9 +173,30 @@ pub fn build_instance_v2(
     blinding: Fr,
     ctx_hash: Fr,
 ) -> (UnlinkabilityInstanceV2, UnlinkabilityPublicInputsV2) {
+    build_instance_v2_with_params(&poseidon_params::<Fr>(), id, blinding, ctx_hash)
+}
+
+// Builds one instance per (id, blinding) pair under a shared ctx_hash, deriving
+// the Poseidon parameters once for the whole batch.
+pub fn build_instances_v2_batch(
+    items: &[(Fr, Fr)],
+    ctx_hash: Fr,
+) -> Vec<(UnlinkabilityInstanceV2, UnlinkabilityPublicInputsV2)> {
     let params = poseidon_params::<Fr>();
-    let commitment = commitment_hash(&params, id, blinding);
-    let tag = tag_hash(&params, domain_sep_v2_fr(), ctx_hash, commitment);
+    items
+        .iter()
+        .map(|(id, blinding)| build_instance_v2_with_params(&params, *id, *blinding, ctx_hash))
+        .collect()
+}
+
+fn build_instance_v2_with_params(
+    params: &ark_sponge::poseidon::PoseidonConfig<Fr>,
+    id: Fr,
+    blinding: Fr,
+    ctx_hash: Fr,
+) -> (UnlinkabilityInstanceV2, UnlinkabilityPublicInputsV2) {
+    let commitment = commitment_hash(params, id, blinding);
+    let tag = tag_hash(params, domain_sep_v2_fr(), ctx_hash, commitment);
 
     let public_inputs = UnlinkabilityPublicInputsV2 {
         schema_version: UNLINKABILITY_INSTANCE_VERSION_V2,
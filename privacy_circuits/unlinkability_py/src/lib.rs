@@ -1,18 +1,23 @@
 use ark_bn254::{Bn254, Fr};
-use ark_groth16::{Proof, VerifyingKey};
-use ark_serialize::CanonicalDeserialize;
+use ark_groth16::{Proof, ProvingKey, VerifyingKey};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::rand::rngs::OsRng;
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::types::PyBytes;
-use std::fs;
-use std::fs::File;
-use std::io::BufReader;
+use serde::de::DeserializeOwned;
 use unlinkability::{
-    commitment_hash, domain_sep_v2_fr, fr_from_fixed_bytes, fr_to_fixed_bytes, poseidon_params,
-    tag_hash, verify_unlinkability_v2 as verify_unlinkability_v2_inner, UnlinkabilityInstanceV2,
+    build_instances_v2_batch, commitment_hash, domain_sep_v2_fr, fr_from_fixed_bytes, fr_to_fixed_bytes, poseidon_params,
+    proof_from_bytes, prove_unlinkability_v2 as prove_unlinkability_v2_inner,
+    setup_unlinkability_v2, tag_hash, verify_unlinkability_v2 as verify_unlinkability_v2_inner,
+    UnlinkabilityInstanceV2,
     UnlinkabilityPublicInputsV2, UNLINKABILITY_INSTANCE_VERSION_V2,
     UNLINKABILITY_STATEMENT_TYPE, UNLINKABILITY_STATEMENT_VERSION_V2, UNLINKABILITY_V2_DOMAIN_SEP,
 };
+use unlinkability::{
+    bincode_deserialize_capped, decode_canonical_auto, read_file_capped,
+    DEFAULT_MAX_INSTANCE_BYTES, DEFAULT_MAX_PROOF_BYTES, DEFAULT_MAX_VK_BYTES,
+};
 
 #[pyfunction]
 fn make_unlinkability_instance_v2_bytes(
@@ -23,17 +28,22 @@ fn make_unlinkability_instance_v2_bytes(
 ) -> PyResult<(Py<PyBytes>, Py<PyBytes>)> {
     let id_bytes = fixed_bytes("id", id)?;
     let blinding_bytes = fixed_bytes("blinding", blinding)?;
-    let ctx_bytes = fixed_bytes("ctx_hash", ctx_hash)?;
+    let ctx_bytes = fixed_bytes_allow_empty("ctx_hash", ctx_hash)?;
 
-    let id_fr = fr_from_fixed_bytes("id", &id_bytes).map_err(PyValueError::new_err)?;
-    let blinding_fr = fr_from_fixed_bytes("blinding", &blinding_bytes).map_err(PyValueError::new_err)?;
-    let ctx_fr = fr_from_fixed_bytes("ctx_hash", &ctx_bytes).map_err(PyValueError::new_err)?;
+    let id_fr = fr_from_fixed_bytes("id", &id_bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let blinding_fr = fr_from_fixed_bytes("blinding", &blinding_bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let ctx_fr = fr_from_fixed_bytes("ctx_hash", &ctx_bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
 
     let params = poseidon_params::<Fr>();
     let commitment = commitment_hash(&params, id_fr, blinding_fr);
     let tag = tag_hash(&params, domain_sep_v2_fr(), ctx_fr, commitment);
 
     let tag_bytes = fixed_bytes_from_vec("tag", fr_to_fixed_bytes(&tag))?;
+    // ctx_hash may be any 32 bytes; the instance carries the reduced element.
+    let ctx_bytes = fixed_bytes_from_vec("ctx_hash", fr_to_fixed_bytes(&ctx_fr))?;
 
     let public_inputs = UnlinkabilityPublicInputsV2 {
         schema_version: UNLINKABILITY_INSTANCE_VERSION_V2,
@@ -65,6 +75,32 @@ fn make_unlinkability_instance_v2_bytes(
     ))
 }
 
+// Tag for a commitment exactly as the circuit computes it, so a Python service can
+// precompute or check tags. Inputs are big-endian and at most 32 bytes; an empty
+// ctx_hash means "no context". Returns 32 fixed bytes.
+#[pyfunction]
+fn tag_hash_bytes(
+    py: Python<'_>,
+    domain_sep: Vec<u8>,
+    ctx_hash: Vec<u8>,
+    commitment: Vec<u8>,
+) -> PyResult<Py<PyBytes>> {
+    let domain_sep = fixed_bytes("domain_sep", domain_sep)?;
+    let ctx_hash = fixed_bytes_allow_empty("ctx_hash", ctx_hash)?;
+    let commitment = fixed_bytes("commitment", commitment)?;
+    let field = |label: &str, bytes: &[u8; 32]| {
+        fr_from_fixed_bytes(label, bytes).map_err(|err| PyValueError::new_err(err.to_string()))
+    };
+
+    let tag = tag_hash(
+        &poseidon_params::<Fr>(),
+        field("domain_sep", &domain_sep)?,
+        field("ctx_hash", &ctx_hash)?,
+        field("commitment", &commitment)?,
+    );
+    Ok(PyBytes::new(py, &fr_to_fixed_bytes(&tag)).into())
+}
+
 #[pyfunction]
 fn verify_unlinkability_v2(
     vk_path: &str,
@@ -73,55 +109,141 @@ fn verify_unlinkability_v2(
 ) -> PyResult<bool> {
     let vk = read_verifying_key(vk_path)?;
     let public_inputs_bytes = read_public_inputs_v2(public_inputs_path)?;
-    let public_inputs = public_inputs_bytes.into_public_inputs().map_err(PyValueError::new_err)?;
+    let public_inputs = public_inputs_bytes.into_public_inputs()
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
     let proof = read_proof(proof_path)?;
 
     verify_unlinkability_v2_inner(&vk, &public_inputs, &proof)
         .map_err(|err| PyValueError::new_err(err.to_string()))
 }
 
+// Decoding stays under the GIL so its errors surface as exceptions; the pairing
+// check runs on owned values with the GIL released.
 #[pyfunction]
 fn verify_unlinkability_v2_bytes(
+    py: Python<'_>,
     vk_bytes: Vec<u8>,
     public_inputs_bytes: Vec<u8>,
     proof_bytes: Vec<u8>,
 ) -> PyResult<bool> {
     let vk = deserialize_verifying_key(&vk_bytes)?;
-    let public_inputs: UnlinkabilityPublicInputsV2 =
-        bincode::deserialize(&public_inputs_bytes)
-            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let public_inputs: UnlinkabilityPublicInputsV2 = decode_bincode(&public_inputs_bytes)?;
     let inputs = public_inputs
         .into_public_inputs()
         .map_err(|err| PyValueError::new_err(err.to_string()))?;
     let proof = deserialize_proof(&proof_bytes)?;
 
-    verify_unlinkability_v2_inner(&vk, &inputs, &proof)
+    py.allow_threads(|| verify_unlinkability_v2_inner(&vk, &inputs, &proof))
         .map_err(|err| PyValueError::new_err(err.to_string()))
 }
 
+#[pyfunction]
+fn make_unlinkability_instances_v2_batch(
+    py: Python<'_>,
+    items: Vec<(Vec<u8>, Vec<u8>)>,
+    ctx_hash: Vec<u8>,
+) -> PyResult<Vec<(Py<PyBytes>, Py<PyBytes>)>> {
+    let ctx_bytes = fixed_bytes_allow_empty("ctx_hash", ctx_hash)?;
+    let ctx_fr = fr_from_fixed_bytes("ctx_hash", &ctx_bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let items = items
+        .into_iter()
+        .map(|(id, blinding)| {
+            let id = fixed_bytes("id", id)?;
+            let blinding = fixed_bytes("blinding", blinding)?;
+            Ok((
+                fr_from_fixed_bytes("id", &id)
+                    .map_err(|err| PyValueError::new_err(err.to_string()))?,
+                fr_from_fixed_bytes("blinding", &blinding)
+                    .map_err(|err| PyValueError::new_err(err.to_string()))?,
+            ))
+        })
+        .collect::<PyResult<Vec<_>>>()?;
+
+    build_instances_v2_batch(&items, ctx_fr)
+        .into_iter()
+        .map(|(instance, public_inputs)| {
+            let instance_bytes = bincode::serialize(&instance)
+                .map_err(|err| PyValueError::new_err(err.to_string()))?;
+            let public_inputs_bytes = bincode::serialize(&public_inputs)
+                .map_err(|err| PyValueError::new_err(err.to_string()))?;
+            Ok((
+                PyBytes::new(py, &instance_bytes).into(),
+                PyBytes::new(py, &public_inputs_bytes).into(),
+            ))
+        })
+        .collect()
+}
+
+// Key generation and proving for a pure-Python pipeline. Keys and proofs use the
+// uncompressed encoding the setup/prove binaries write, so the bytes interoperate
+// with verify_unlinkability_v2_bytes and the CLI.
+#[pyfunction]
+fn setup_unlinkability_v2_bytes(py: Python<'_>) -> PyResult<(Py<PyBytes>, Py<PyBytes>)> {
+    let mut rng = OsRng;
+    let pk = setup_unlinkability_v2(&mut rng)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    let pk_bytes = serialize_uncompressed(&pk)?;
+    let vk_bytes = serialize_uncompressed(&pk.vk)?;
+
+    Ok((
+        PyBytes::new(py, &pk_bytes).into(),
+        PyBytes::new(py, &vk_bytes).into(),
+    ))
+}
+
+// `instance_bytes` is the bincode instance from make_unlinkability_instance_v2_bytes.
+#[pyfunction]
+fn prove_unlinkability_v2_bytes(
+    py: Python<'_>,
+    pk_bytes: Vec<u8>,
+    instance_bytes: Vec<u8>,
+) -> PyResult<Py<PyBytes>> {
+    let pk = deserialize_proving_key(&pk_bytes)?;
+    let instance: UnlinkabilityInstanceV2 = decode_bincode(&instance_bytes)?;
+    let instance = instance
+        .into_instance()
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+
+    let mut rng = OsRng;
+    let proof = prove_unlinkability_v2_inner(&pk, &instance, &mut rng)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok(PyBytes::new(py, &serialize_uncompressed(&proof)?).into())
+}
+
+// SHA-256 of the uncompressed VK encoding; matches `sha256sum` of the setup's
+// vk file, so a failing proof can be checked against the key it was made for.
+#[pyfunction]
+fn vk_fingerprint(py: Python<'_>, vk_bytes: Vec<u8>) -> PyResult<Py<PyBytes>> {
+    let vk = deserialize_verifying_key(&vk_bytes)?;
+    Ok(PyBytes::new(py, &unlinkability::vk_fingerprint(&vk)).into())
+}
+
 #[pymodule]
 fn unlinkability_py(_py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(make_unlinkability_instance_v2_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(make_unlinkability_instances_v2_batch, m)?)?;
     m.add_function(wrap_pyfunction!(verify_unlinkability_v2, m)?)?;
     m.add_function(wrap_pyfunction!(verify_unlinkability_v2_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(setup_unlinkability_v2_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(prove_unlinkability_v2_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(tag_hash_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(vk_fingerprint, m)?)?;
+    m.add("PROOF_SIZE_COMPRESSED", unlinkability::PROOF_SIZE_COMPRESSED)?;
+    m.add("PROOF_SIZE_UNCOMPRESSED", unlinkability::PROOF_SIZE_UNCOMPRESSED)?;
     Ok(())
 }
 
-fn fixed_bytes(label: &str, mut bytes: Vec<u8>) -> PyResult<[u8; 32]> {
-    if bytes.is_empty() {
-        return Err(PyValueError::new_err(format!("{label} must not be empty")));
-    }
-    if bytes.len() > 32 {
-        return Err(PyValueError::new_err(format!("{label} must be <= 32 bytes")));
-    }
-    if bytes.len() < 32 {
-        let mut padded = vec![0u8; 32 - bytes.len()];
-        padded.append(&mut bytes);
-        bytes = padded;
-    }
-    Ok(bytes
-        .try_into()
-        .map_err(|_| PyValueError::new_err("invalid length"))?)
+// id, r1/r2 and blinding must be supplied explicitly (b"" is an error, b"\x00" is
+// zero). ctx_hash goes through fixed_bytes_allow_empty so b"" means "no context".
+fn fixed_bytes(label: &str, bytes: Vec<u8>) -> PyResult<[u8; 32]> {
+    unlinkability::fixed_bytes(label, &bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+fn fixed_bytes_allow_empty(label: &str, bytes: Vec<u8>) -> PyResult<[u8; 32]> {
+    unlinkability::fixed_bytes_allow_empty(label, &bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))
 }
 
 fn fixed_bytes_from_vec(label: &str, bytes: Vec<u8>) -> PyResult<[u8; 32]> {
@@ -129,33 +251,46 @@ fn fixed_bytes_from_vec(label: &str, bytes: Vec<u8>) -> PyResult<[u8; 32]> {
 }
 
 fn read_verifying_key(path: &str) -> PyResult<VerifyingKey<Bn254>> {
-    let file = File::open(path).map_err(PyValueError::new_err)?;
-    let mut reader = BufReader::new(file);
-    VerifyingKey::<Bn254>::deserialize_uncompressed(&mut reader)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    let data = read_file_capped(path, DEFAULT_MAX_VK_BYTES).map_err(PyValueError::new_err)?;
+    deserialize_verifying_key(&data)
 }
 
 fn read_public_inputs_v2(path: &str) -> PyResult<UnlinkabilityPublicInputsV2> {
-    let data = fs::read(path).map_err(PyValueError::new_err)?;
-    bincode::deserialize::<UnlinkabilityPublicInputsV2>(&data)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    let data =
+        read_file_capped(path, DEFAULT_MAX_INSTANCE_BYTES).map_err(PyValueError::new_err)?;
+    decode_bincode(&data)
 }
 
 fn read_proof(path: &str) -> PyResult<Proof<Bn254>> {
-    let file = File::open(path).map_err(PyValueError::new_err)?;
-    let mut reader = BufReader::new(file);
-    Proof::<Bn254>::deserialize_uncompressed(&mut reader)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    let data = read_file_capped(path, DEFAULT_MAX_PROOF_BYTES).map_err(PyValueError::new_err)?;
+    decode_canonical_auto(&data).map_err(PyValueError::new_err)
 }
 
-fn deserialize_verifying_key(bytes: &[u8]) -> PyResult<VerifyingKey<Bn254>> {
+// Public-input files are fixed-size, but a caller may hand over anything; the cap
+// keeps decoding from reading or allocating past DEFAULT_MAX_INSTANCE_BYTES.
+fn decode_bincode<T: DeserializeOwned>(data: &[u8]) -> PyResult<T> {
+    bincode_deserialize_capped(data, DEFAULT_MAX_INSTANCE_BYTES).map_err(PyValueError::new_err)
+}
+
+fn deserialize_proving_key(bytes: &[u8]) -> PyResult<ProvingKey<Bn254>> {
     let mut reader = std::io::Cursor::new(bytes);
-    VerifyingKey::<Bn254>::deserialize_uncompressed(&mut reader)
+    ProvingKey::<Bn254>::deserialize_uncompressed(&mut reader)
         .map_err(|err| PyValueError::new_err(err.to_string()))
 }
 
+// Keys from either setup mode load; only bytes that decode in neither are refused.
+fn deserialize_verifying_key(bytes: &[u8]) -> PyResult<VerifyingKey<Bn254>> {
+    decode_canonical_auto(bytes).map_err(PyValueError::new_err)
+}
+
 fn deserialize_proof(bytes: &[u8]) -> PyResult<Proof<Bn254>> {
-    let mut reader = std::io::Cursor::new(bytes);
-    Proof::<Bn254>::deserialize_uncompressed(&mut reader)
-        .map_err(|err| PyValueError::new_err(err.to_string()))
+    proof_from_bytes(bytes).map_err(|err| PyValueError::new_err(err.to_string()))
+}
+
+fn serialize_uncompressed<T: CanonicalSerialize>(value: &T) -> PyResult<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(value.uncompressed_size());
+    value
+        .serialize_uncompressed(&mut bytes)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+    Ok(bytes)
 }